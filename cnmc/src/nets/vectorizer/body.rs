@@ -82,15 +82,13 @@ impl WordVectorizer {
     }
 
     fn set_char_one_hot(&self, inputs: &mut [f32], ch: char) {
-        match self
+        if let Some(pos) = self
             .alphabet
             .char_indices()
             .position(|(_pos, char)| char == ch)
         {
-            Some(pos) => inputs[pos] = 1.0,
-
-            None => {}
-        };
+            inputs[pos] = 1.0;
+        }
     }
 
     fn convolve_one(
@@ -1,4 +1,6 @@
 //! Useful imports for the vectorizer.
 
 pub use super::body::*;
+// the trainer is still a stub, with nothing to export yet
+#[allow(unused_imports)]
 pub use super::train::*;
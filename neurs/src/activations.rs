@@ -1,6 +1,70 @@
 //! Functions which are meant to be used as activation functions by neural
 //! networks' layers. See [super::neuralnet::NeuralLayer].
 
+use crate::neuralnet::NNActivation;
+
+/// Every activation function in this module, along with its name.
+pub const ALL: &[(&str, NNActivation)] = &[
+    ("relu", relu),
+    ("identity", identity),
+    ("fast_sigmoid_signed", fast_sigmoid_signed),
+    ("fast_sigmoid", fast_sigmoid),
+    ("sigmoid", sigmoid),
+    ("silu", silu),
+    ("fast_silu", fast_silu),
+    ("softplus", softplus),
+];
+
+/// Looks up an activation function of this module by its name, e.g.
+/// `"fast_sigmoid"`.
+pub fn from_name(name: &str) -> Option<NNActivation> {
+    ALL.iter().find(|(n, _)| *n == name).map(|(_, f)| *f)
+}
+
+/// Finds the name of an activation function of this module, if it is one.
+///
+/// Function pointers are not reliably unique (these functions are inlined),
+/// so functions are told apart by their outputs on a few probe values instead.
+pub fn name_of(activation: NNActivation) -> Option<&'static str> {
+    const PROBES: [f32; 5] = [-2.0, -0.5, 0.0, 0.5, 3.0];
+
+    ALL.iter()
+        .find(|(_, f)| PROBES.iter().all(|x| f(*x) == activation(*x)))
+        .map(|(n, _)| *n)
+}
+
+/// (De)serializes a boxed activation function by its name. See [from_name]
+/// and [name_of].
+#[cfg(feature = "serde")]
+pub(crate) mod serde_by_name {
+    use super::{from_name, name_of};
+    use crate::neuralnet::NNActivation;
+    use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        activation: &NNActivation,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let name = name_of(*activation).ok_or_else(|| {
+            S::Error::custom(
+                "Only the activation functions in neurs::activations can be serialized",
+            )
+        })?;
+
+        serializer.serialize_str(name)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Box<NNActivation>, D::Error> {
+        let name = String::deserialize(deserializer)?;
+
+        from_name(&name)
+            .map(Box::from)
+            .ok_or_else(|| D::Error::custom(format!("Unknown activation function {:?}", name)))
+    }
+}
+
 /// The ReLu activation function; returns x, unless it is negative, in which
 /// case 0 is returned instead.
 #[inline(always)]
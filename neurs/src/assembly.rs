@@ -12,4 +12,9 @@ pub trait Assembly {
     /// Get mutable references to the neural networks used by this
     /// assembly.
     fn get_networks_mut(&mut self) -> Vec<&mut SimpleNeuralNetwork>;
+
+    /// Called by training strategies after they change the weights of this
+    /// assembly's networks, e.g. to keep tied weights in sync (see
+    /// [crate::tied::TiedAssembly]). Does nothing by default.
+    fn after_update(&mut self) {}
 }
@@ -0,0 +1,208 @@
+/*!
+ * Scaled dot-product attention over sequences of vectors.
+ *
+ * Each position of a sequence computes a query, a key and a value vector;
+ * the output at a position is the average of all values, weighted by how
+ * well that position's query matches each key. Unlike a fixed-order
+ * convolution, this lets every position draw from any other, however far.
+ *
+ * Sequences are passed as flat slices of their vectors, one after the other,
+ * like the outputs of [Embedding::embed].
+ */
+use rand::prelude::*;
+
+use crate::prelude::*;
+
+/**
+ * Computes scaled dot-product attention, from `seq_len` queries and keys of
+ * `key_dim` values each, and as many values of `value_dim` values each.
+ *
+ * If given, `mask` holds `seq_len * seq_len` flags, where `mask[i * seq_len +
+ * j]` tells whether position `i` may attend to position `j`. A position which
+ * may attend to none gets an output of zeroes.
+ */
+pub fn dot_product_attention(
+    queries: &[f32],
+    keys: &[f32],
+    values: &[f32],
+    key_dim: usize,
+    value_dim: usize,
+    mask: Option<&[bool]>,
+    outputs: &mut [f32],
+) -> Result<(), String> {
+    let seq_len = queries
+        .len()
+        .checked_div(key_dim)
+        .ok_or("Queries and keys must have at least one value")?;
+
+    if queries.len() != seq_len * key_dim
+        || keys.len() != seq_len * key_dim
+        || values.len() != seq_len * value_dim
+        || outputs.len() != seq_len * value_dim
+    {
+        return Err(
+            "Queries, keys, values and outputs must be of the same sequence length".to_owned(),
+        );
+    }
+
+    if mask.is_some_and(|mask| mask.len() != seq_len * seq_len) {
+        return Err(format!(
+            "The mask of a sequence of {} vectors must have {} flags",
+            seq_len,
+            seq_len * seq_len
+        ));
+    }
+
+    let scale = 1.0 / (key_dim as f32).sqrt();
+    let mut weights = vec![0.0_f32; seq_len];
+
+    for (i, (query, output)) in queries
+        .chunks_exact(key_dim)
+        .zip(outputs.chunks_mut(value_dim.max(1)))
+        .enumerate()
+    {
+        let allowed = |j: usize| mask.is_none_or(|mask| mask[i * seq_len + j]);
+
+        for (j, (weight, key)) in weights
+            .iter_mut()
+            .zip(keys.chunks_exact(key_dim))
+            .enumerate()
+        {
+            *weight = if allowed(j) {
+                scale * query.iter().zip(key).map(|(q, k)| q * k).sum::<f32>()
+            } else {
+                f32::NEG_INFINITY
+            };
+        }
+
+        output.fill(0.0);
+
+        if weights.iter().all(|weight| *weight == f32::NEG_INFINITY) {
+            continue;
+        }
+
+        softmax(&mut weights);
+
+        for (weight, value) in weights.iter().zip(values.chunks(value_dim.max(1))) {
+            for (out, v) in output.iter_mut().zip(value) {
+                *out += weight * v;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A mask for [dot_product_attention] which only lets each position attend
+/// to itself and the positions before it.
+pub fn causal_mask(seq_len: usize) -> Vec<bool> {
+    (0..seq_len * seq_len)
+        .map(|idx| idx % seq_len <= idx / seq_len)
+        .collect()
+}
+
+/// An attention layer, with learned projections of its input vectors into
+/// queries, keys and values.
+#[derive(Clone)]
+pub struct Attention {
+    /// Projects each input vector into a query.
+    pub query: SimpleNeuralNetwork,
+
+    /// Projects each input vector into a key. Must have the same input and
+    /// output sizes as [Self::query].
+    pub key: SimpleNeuralNetwork,
+
+    /// Projects each input vector into a value, which is also the size of each
+    /// output vector.
+    pub value: SimpleNeuralNetwork,
+}
+
+impl Attention {
+    /// Creates an attention layer with random linear projections.
+    pub fn new(input_dim: usize, key_dim: usize, value_dim: usize) -> Self {
+        Self::new_with_rng(input_dim, key_dim, value_dim, &mut thread_rng())
+    }
+
+    /// Like [Self::new], but draws the random projections from the given
+    /// random number generator.
+    pub fn new_with_rng<R: Rng>(
+        input_dim: usize,
+        key_dim: usize,
+        value_dim: usize,
+        rng: &mut R,
+    ) -> Self {
+        let mut projection = |output_dim| {
+            SimpleNeuralNetwork::new_simple_with_rng(
+                &[input_dim, output_dim],
+                &[Some(activations::identity)],
+                rng,
+            )
+        };
+
+        Self {
+            query: projection(key_dim),
+            key: projection(key_dim),
+            value: projection(value_dim),
+        }
+    }
+
+    fn project(
+        network: &SimpleNeuralNetwork,
+        inputs: &[f32],
+        input_dim: usize,
+    ) -> Result<Vec<f32>, String> {
+        let output_dim = network.output_size()?;
+        let mut outputs = vec![0.0_f32; inputs.len() / input_dim * output_dim];
+
+        for (input, output) in inputs
+            .chunks_exact(input_dim)
+            .zip(outputs.chunks_exact_mut(output_dim))
+        {
+            network.compute_values(input, output)?;
+        }
+
+        Ok(outputs)
+    }
+
+    /**
+     * Transforms a sequence of input vectors into as many output vectors.
+     *
+     * `inputs` must hold a whole number of input vectors, and `outputs` as
+     * many output vectors. See [dot_product_attention] for `mask`.
+     */
+    pub fn compute_sequence(
+        &self,
+        inputs: &[f32],
+        mask: Option<&[bool]>,
+        outputs: &mut [f32],
+    ) -> Result<(), String> {
+        let input_dim = self.query.input_size()?;
+
+        if input_dim == 0 || !inputs.len().is_multiple_of(input_dim) {
+            return Err(format!(
+                "The inputs must be a sequence of vectors of {} values",
+                input_dim
+            ));
+        }
+
+        dot_product_attention(
+            &Self::project(&self.query, inputs, input_dim)?,
+            &Self::project(&self.key, inputs, input_dim)?,
+            &Self::project(&self.value, inputs, input_dim)?,
+            self.query.output_size()?,
+            self.value.output_size()?,
+            mask,
+            outputs,
+        )
+    }
+}
+
+impl Assembly for Attention {
+    fn get_network_refs(&self) -> Vec<&SimpleNeuralNetwork> {
+        vec![&self.query, &self.key, &self.value]
+    }
+
+    fn get_networks_mut(&mut self) -> Vec<&mut SimpleNeuralNetwork> {
+        vec![&mut self.query, &mut self.key, &mut self.value]
+    }
+}
@@ -0,0 +1,315 @@
+/*!
+ * A compact binary format for networks and assemblies.
+ *
+ * Unlike the `serde` support, this needs no extra dependencies, and produces
+ * small files suited for shipping trained models along with e.g. game mods.
+ *
+ * Every file starts with the [BINARY_MAGIC] bytes, followed by the format
+ * version (a little-endian `u16`) and the kind of content (`0` for a single
+ * network, `1` for an assembly), and ends with a CRC-32 checksum of all
+ * preceding bytes, so that truncated or corrupted files are detected rather
+ * than loaded. Files written by a newer version of the format are rejected.
+ *
+ * All numbers are little-endian. Each network is stored as its number of
+ * layers (`u32`), then, for each layer, its input and output sizes (`u32`),
+ * the name of its activation (a `u8` length and as many UTF-8 bytes; see
+ * [crate::activations::ALL]), its weights, then its biases (`f32`). A network
+ * has at least one layer, and each layer takes as many inputs as the one
+ * before it outputs.
+ */
+use std::fs;
+use std::path::Path;
+
+use crate::activations;
+use crate::prelude::*;
+
+/// The bytes every file in this format starts with.
+pub const BINARY_MAGIC: [u8; 4] = *b"NEUR";
+
+/// The version of the format written by this version of neurs.
+pub const BINARY_FORMAT_VERSION: u16 = 1;
+
+const KIND_NETWORK: u8 = 0;
+const KIND_ASSEMBLY: u8 = 1;
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+
+    for byte in bytes {
+        crc ^= *byte as u32;
+
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (!(crc & 1)).wrapping_add(1));
+        }
+    }
+
+    !crc
+}
+
+fn write_u32(out: &mut Vec<u8>, value: usize) -> Result<(), String> {
+    let value = u32::try_from(value).map_err(|_| format!("{} is too large to store", value))?;
+    out.extend(value.to_le_bytes());
+
+    Ok(())
+}
+
+fn write_network(out: &mut Vec<u8>, network: &SimpleNeuralNetwork) -> Result<(), String> {
+    write_u32(out, network.layers.len())?;
+
+    for (i, layer) in network.layers.iter().enumerate() {
+        let activation = activations::name_of(*layer.activation).ok_or_else(|| {
+            format!(
+                "The activation of layer {} is not one of neurs::activations",
+                i
+            )
+        })?;
+
+        write_u32(out, layer.input_size)?;
+        write_u32(out, layer.output_size)?;
+
+        out.push(activation.len() as u8);
+        out.extend(activation.as_bytes());
+
+        for value in layer.weights.iter().chain(&layer.biases) {
+            out.extend(value.to_le_bytes());
+        }
+    }
+
+    Ok(())
+}
+
+fn write_file(kind: u8, networks: &[&SimpleNeuralNetwork]) -> Result<Vec<u8>, String> {
+    let mut out = BINARY_MAGIC.to_vec();
+    out.extend(BINARY_FORMAT_VERSION.to_le_bytes());
+    out.push(kind);
+
+    if kind == KIND_ASSEMBLY {
+        write_u32(&mut out, networks.len())?;
+    }
+
+    for network in networks {
+        write_network(&mut out, network)?;
+    }
+
+    out.extend(crc32(&out).to_le_bytes());
+
+    Ok(out)
+}
+
+/// Reads values off a byte slice, failing instead of panicking when it runs
+/// out.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if len > self.bytes.len() {
+            return Err("Unexpected end of data".to_owned());
+        }
+
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        Ok(taken)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<usize, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()) as usize)
+    }
+
+    fn read_f32s(&mut self, count: usize) -> Result<Vec<f32>, String> {
+        let len = count
+            .checked_mul(4)
+            .ok_or_else(|| "Layer size is too large".to_owned())?;
+
+        Ok(self
+            .take(len)?
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect())
+    }
+
+    fn read_network(&mut self) -> Result<SimpleNeuralNetwork, String> {
+        let num_layers = self.read_u32()?;
+
+        if num_layers == 0 {
+            return Err("A network needs at least one layer".to_owned());
+        }
+
+        let mut layers: Vec<NeuralLayer> = vec![];
+
+        for _ in 0..num_layers {
+            let input_size = self.read_u32()?;
+            let output_size = self.read_u32()?;
+
+            let name_len = self.read_u8()? as usize;
+            let name = std::str::from_utf8(self.take(name_len)?)
+                .map_err(|_| "Activation name is not valid UTF-8".to_owned())?;
+            let activation = activations::from_name(name)
+                .ok_or_else(|| format!("Unknown activation function {:?}", name))?;
+
+            let area = input_size
+                .checked_mul(output_size)
+                .ok_or_else(|| "Layer size is too large".to_owned())?;
+
+            if let Some(previous) = layers.last() {
+                if previous.output_size != input_size {
+                    return Err(format!(
+                        "Layer {} outputs {} values, but layer {} takes {} inputs",
+                        layers.len() - 1,
+                        previous.output_size,
+                        layers.len(),
+                        input_size
+                    ));
+                }
+            }
+
+            layers.push(NeuralLayer {
+                activation: Box::from(activation),
+                weights: self.read_f32s(area)?,
+                biases: self.read_f32s(output_size)?,
+                input_size,
+                output_size,
+                area: area as u32,
+            });
+        }
+
+        Ok(SimpleNeuralNetwork { layers })
+    }
+}
+
+/// Checks the header and checksum of a file, returning a reader over its
+/// contents.
+fn read_file(bytes: &[u8], kind: u8) -> Result<Reader<'_>, String> {
+    let mut header = Reader { bytes };
+
+    if header.take(4).ok() != Some(&BINARY_MAGIC[..]) {
+        return Err("Not a neurs binary file".to_owned());
+    }
+
+    let version = u16::from_le_bytes(header.take(2)?.try_into().unwrap());
+
+    if version > BINARY_FORMAT_VERSION {
+        return Err(format!(
+            "This file uses version {} of the format, but only up to version {} is supported; try a newer version of neurs",
+            version, BINARY_FORMAT_VERSION
+        ));
+    }
+
+    if bytes.len() < 11 {
+        return Err("Unexpected end of data".to_owned());
+    }
+
+    let (contents, checksum) = bytes.split_at(bytes.len() - 4);
+
+    if crc32(contents) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+        return Err("Checksum mismatch; the file is corrupted or truncated".to_owned());
+    }
+
+    let mut reader = Reader {
+        bytes: &contents[6..],
+    };
+
+    let found_kind = reader.read_u8()?;
+
+    if found_kind != kind {
+        return Err(match found_kind {
+            KIND_NETWORK => "This file contains a single network, not an assembly".to_owned(),
+            KIND_ASSEMBLY => "This file contains an assembly, not a single network".to_owned(),
+            _ => format!("Unknown content kind {}", found_kind),
+        });
+    }
+
+    Ok(reader)
+}
+
+impl SimpleNeuralNetwork {
+    /// Encodes this network in the compact binary format. See the [module
+    /// documentation](self).
+    pub fn to_binary(&self) -> Result<Vec<u8>, String> {
+        write_file(KIND_NETWORK, &[self])
+    }
+
+    /// Decodes a network from the compact binary format, as written by
+    /// [Self::to_binary].
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = read_file(bytes, KIND_NETWORK)?;
+        let network = reader.read_network()?;
+
+        if !reader.bytes.is_empty() {
+            return Err("Unexpected trailing data after the network".to_owned());
+        }
+
+        Ok(network)
+    }
+
+    /// Writes this network to a file in the compact binary format.
+    pub fn save_binary<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        fs::write(path, self.to_binary()?).map_err(|err| err.to_string())
+    }
+
+    /// Reads a network from a file in the compact binary format.
+    pub fn load_binary<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        Self::from_binary(&fs::read(path).map_err(|err| err.to_string())?)
+    }
+}
+
+/// Encodes every network of an assembly, in order, in the compact binary
+/// format.
+pub fn assembly_to_binary<A: Assembly + ?Sized>(assembly: &A) -> Result<Vec<u8>, String> {
+    write_file(KIND_ASSEMBLY, &assembly.get_network_refs())
+}
+
+/// Decodes the networks of an assembly, as written by [assembly_to_binary],
+/// and loads them into `assembly`.
+///
+/// The file must have as many networks as the assembly, each with the same
+/// input and output sizes as the network it replaces; otherwise, the assembly
+/// is left untouched.
+pub fn assembly_from_binary<A: Assembly + ?Sized>(
+    assembly: &mut A,
+    bytes: &[u8],
+) -> Result<(), String> {
+    let mut reader = read_file(bytes, KIND_ASSEMBLY)?;
+    let num_networks = reader.read_u32()?;
+
+    let mut networks = assembly.get_networks_mut();
+
+    if num_networks != networks.len() {
+        return Err(format!(
+            "The file has {} networks, but the assembly has {}",
+            num_networks,
+            networks.len()
+        ));
+    }
+
+    let loaded = (0..num_networks)
+        .map(|_| reader.read_network())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if !reader.bytes.is_empty() {
+        return Err("Unexpected trailing data after the assembly".to_owned());
+    }
+
+    for (i, (network, new)) in networks.iter().zip(&loaded).enumerate() {
+        if network.input_size().ok() != new.input_size().ok()
+            || network.output_size().ok() != new.output_size().ok()
+        {
+            return Err(format!(
+                "Network {} of the file does not match the shape of the assembly's",
+                i
+            ));
+        }
+    }
+
+    for (network, new) in networks.iter_mut().zip(loaded) {
+        **network = new;
+    }
+
+    Ok(())
+}
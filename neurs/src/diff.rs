@@ -0,0 +1,141 @@
+/*!
+ * Utilities to compare two neural networks of the same shape.
+ *
+ * Useful for understanding what an epoch of training, or the transition
+ * between two checkpoints, actually changed in a network.
+ */
+use crate::prelude::*;
+
+/// How much a single layer changed between two networks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayerDiff {
+    /// The Euclidean (L2) distance between the weights of both layers.
+    pub weights_l2: f32,
+
+    /// The largest absolute difference (L∞ distance) between any weight of
+    /// both layers.
+    pub weights_linf: f32,
+
+    /// The Euclidean (L2) distance between the biases of both layers.
+    pub biases_l2: f32,
+
+    /// The largest absolute difference (L∞ distance) between any bias of both
+    /// layers.
+    pub biases_linf: f32,
+
+    /// How much each neuron (output) of the layer changed, as the L2 distance
+    /// between its incoming weights and bias; indexed by neuron.
+    pub neuron_changes: Vec<f32>,
+}
+
+/// How much a network changed, layer by layer. See [diff].
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkDiff {
+    /// The differences of each layer, in order.
+    pub layers: Vec<LayerDiff>,
+}
+
+impl NetworkDiff {
+    /// The L2 distance between all parameters of both networks.
+    pub fn l2(&self) -> f32 {
+        self.layers
+            .iter()
+            .map(|layer| layer.weights_l2.powi(2) + layer.biases_l2.powi(2))
+            .sum::<f32>()
+            .sqrt()
+    }
+
+    /// The largest absolute difference between any parameter of both
+    /// networks.
+    pub fn linf(&self) -> f32 {
+        self.layers
+            .iter()
+            .map(|layer| layer.weights_linf.max(layer.biases_linf))
+            .fold(0.0, f32::max)
+    }
+
+    /// Lists the `count` neurons which changed the most, across all layers,
+    /// as tuples of (layer index, neuron index, change); most changed first.
+    pub fn most_changed_neurons(&self, count: usize) -> Vec<(usize, usize, f32)> {
+        let mut neurons: Vec<(usize, usize, f32)> = self
+            .layers
+            .iter()
+            .enumerate()
+            .flat_map(|(layer_idx, layer)| {
+                layer
+                    .neuron_changes
+                    .iter()
+                    .enumerate()
+                    .map(move |(neuron_idx, change)| (layer_idx, neuron_idx, *change))
+            })
+            .collect();
+
+        neurons.sort_by(|a, b| b.2.total_cmp(&a.2));
+        neurons.truncate(count);
+
+        neurons
+    }
+}
+
+fn distances(from: &[f32], to: &[f32]) -> (f32, f32) {
+    let (sum_sq, max) = from
+        .iter()
+        .zip(to)
+        .map(|(a, b)| (b - a).abs())
+        .fold((0.0_f32, 0.0_f32), |(sum_sq, max), d| {
+            (sum_sq + d * d, max.max(d))
+        });
+
+    (sum_sq.sqrt(), max)
+}
+
+/// Compares two layers of the same shape.
+pub fn diff_layers(from: &NeuralLayer, to: &NeuralLayer) -> Result<LayerDiff, String> {
+    if from.input_size != to.input_size || from.output_size != to.output_size {
+        return Err(format!(
+            "Cannot compare layers of different shapes ({}x{} and {}x{})",
+            from.input_size, from.output_size, to.input_size, to.output_size
+        ));
+    }
+
+    let (weights_l2, weights_linf) = distances(&from.weights, &to.weights);
+    let (biases_l2, biases_linf) = distances(&from.biases, &to.biases);
+
+    let neuron_changes = (0..from.output_size)
+        .map(|i| {
+            let range = i * from.input_size..(i + 1) * from.input_size;
+            let (weights_dist, _) = distances(&from.weights[range.clone()], &to.weights[range]);
+            let bias_dist = to.biases[i] - from.biases[i];
+
+            (weights_dist.powi(2) + bias_dist.powi(2)).sqrt()
+        })
+        .collect();
+
+    Ok(LayerDiff {
+        weights_l2,
+        weights_linf,
+        biases_l2,
+        biases_linf,
+        neuron_changes,
+    })
+}
+
+/// Compares two networks of the same shape, layer by layer.
+pub fn diff(from: &SimpleNeuralNetwork, to: &SimpleNeuralNetwork) -> Result<NetworkDiff, String> {
+    if from.layers.len() != to.layers.len() {
+        return Err(format!(
+            "Cannot compare networks with different numbers of layers ({} and {})",
+            from.layers.len(),
+            to.layers.len()
+        ));
+    }
+
+    Ok(NetworkDiff {
+        layers: from
+            .layers
+            .iter()
+            .zip(&to.layers)
+            .map(|(a, b)| diff_layers(a, b))
+            .collect::<Result<_, _>>()?,
+    })
+}
@@ -0,0 +1,166 @@
+/*!
+ * Embeddings; learned dense vectors for categorical inputs, such as tokens or
+ * characters.
+ *
+ * Rather than feeding a network a one-hot vector per token, which needs a
+ * weight per possible token for every neuron of the first layer, an
+ * [Embedding] looks up a short vector per token, and the network is fed
+ * those instead.
+ *
+ * The embedding table is stored as the weights of a single layer, so that
+ * training strategies can adjust it like any other network. That layer has
+ * one neuron per token, whose incoming weights are that token's vector; run
+ * as a layer, it thus scores how close a vector is to each token's.
+ */
+use rand::prelude::*;
+
+use crate::prelude::*;
+
+/// A table of learned vectors, one per token id. See the
+/// [module documentation](self).
+#[derive(Clone)]
+pub struct Embedding {
+    /// A network of a single layer, whose input size is the size of each
+    /// vector, and whose output size is the number of tokens. Its biases are
+    /// not used by lookups.
+    pub table: SimpleNeuralNetwork,
+}
+
+impl Embedding {
+    /// Creates an embedding of `vocab_size` tokens, each mapped to a random
+    /// vector of `dim` values.
+    pub fn new(vocab_size: usize, dim: usize) -> Self {
+        Self::new_with_rng(vocab_size, dim, &mut thread_rng())
+    }
+
+    /// Like [Self::new], but draws the random vectors from the given random
+    /// number generator.
+    pub fn new_with_rng<R: Rng>(vocab_size: usize, dim: usize, rng: &mut R) -> Self {
+        Self {
+            table: SimpleNeuralNetwork {
+                layers: vec![NeuralLayer::new_with_rng(
+                    dim,
+                    vocab_size,
+                    Some(activations::identity),
+                    rng,
+                )],
+            },
+        }
+    }
+
+    fn layer(&self) -> Result<&NeuralLayer, String> {
+        match self.table.layers.as_slice() {
+            [layer] => Ok(layer),
+            _ => Err("An embedding table must have exactly one layer".to_owned()),
+        }
+    }
+
+    /// The number of distinct tokens.
+    pub fn vocab_size(&self) -> Result<usize, String> {
+        Ok(self.layer()?.output_size)
+    }
+
+    /// The size of each token's vector.
+    pub fn dim(&self) -> Result<usize, String> {
+        Ok(self.layer()?.input_size)
+    }
+
+    /// The vector of a token.
+    pub fn lookup(&self, token: usize) -> Result<&[f32], String> {
+        let layer = self.layer()?;
+
+        if token >= layer.output_size {
+            return Err(format!(
+                "Token {} is out of bounds for an embedding of {} tokens",
+                token, layer.output_size
+            ));
+        }
+
+        Ok(&layer.weights[token * layer.input_size..(token + 1) * layer.input_size])
+    }
+
+    /// Writes the vectors of a sequence of tokens, one after the other, into
+    /// `outputs`, which must hold exactly `tokens.len() * dim` values.
+    pub fn embed(&self, tokens: &[usize], outputs: &mut [f32]) -> Result<(), String> {
+        let dim = self.dim()?;
+
+        if outputs.len() != tokens.len() * dim {
+            return Err(format!(
+                "Embedding {} tokens needs {} outputs, not {}",
+                tokens.len(),
+                tokens.len() * dim,
+                outputs.len()
+            ));
+        }
+
+        for (token, dest) in tokens.iter().zip(outputs.chunks_exact_mut(dim.max(1))) {
+            dest.copy_from_slice(self.lookup(*token)?);
+        }
+
+        Ok(())
+    }
+}
+
+impl Assembly for Embedding {
+    fn get_network_refs(&self) -> Vec<&SimpleNeuralNetwork> {
+        vec![&self.table]
+    }
+
+    fn get_networks_mut(&mut self) -> Vec<&mut SimpleNeuralNetwork> {
+        vec![&mut self.table]
+    }
+}
+
+/// A network fed with the embeddings of a fixed number of tokens, trained
+/// along with them.
+#[derive(Clone)]
+pub struct EmbeddedNetwork {
+    /// The embedding of the input tokens.
+    pub embedding: Embedding,
+
+    /// The network fed with the embedded tokens. Its input size must be a
+    /// multiple of the embedding's vector size.
+    pub network: SimpleNeuralNetwork,
+}
+
+impl EmbeddedNetwork {
+    /// How many tokens this network takes.
+    pub fn num_tokens(&self) -> Result<usize, String> {
+        let (input_size, dim) = (self.network.input_size()?, self.embedding.dim()?);
+
+        if dim == 0 || input_size % dim != 0 {
+            return Err(format!(
+                "The network's input size ({}) is not a multiple of the embedding size ({})",
+                input_size, dim
+            ));
+        }
+
+        Ok(input_size / dim)
+    }
+
+    /// Computes the outputs of the network for a sequence of token ids.
+    pub fn compute_tokens(&self, tokens: &[usize], outputs: &mut [f32]) -> Result<(), String> {
+        if tokens.len() != self.num_tokens()? {
+            return Err(format!(
+                "This network takes {} tokens, not {}",
+                self.num_tokens()?,
+                tokens.len()
+            ));
+        }
+
+        let mut embedded = vec![0.0_f32; self.network.input_size()?];
+
+        self.embedding.embed(tokens, &mut embedded)?;
+        self.network.compute_values(&embedded, outputs)
+    }
+}
+
+impl Assembly for EmbeddedNetwork {
+    fn get_network_refs(&self) -> Vec<&SimpleNeuralNetwork> {
+        vec![&self.embedding.table, &self.network]
+    }
+
+    fn get_networks_mut(&mut self) -> Vec<&mut SimpleNeuralNetwork> {
+        vec![&mut self.embedding.table, &mut self.network]
+    }
+}
@@ -7,6 +7,7 @@
  */
 
 use crate::prelude::*;
+use std::hash::Hasher;
 use std::marker::PhantomData;
 
 /// Parameters and specifics for how an Assembly is used and trained.
@@ -20,6 +21,17 @@ where
     /// Poll whether a slot for another run is available.
     fn can_run(&self) -> bool;
 
+    /// If training runs of the same assembly always yield the same fitness,
+    /// a key for everything else that fitness depends on, such as a hash of
+    /// this frame's cases and settings. It must change whenever they do.
+    ///
+    /// Training strategies may use it to avoid re-evaluating assemblies that
+    /// have not changed since a run under the same key. Defaults to None,
+    /// for frames whose fitness may vary between runs.
+    fn fitness_key(&self) -> Option<u64> {
+        None
+    }
+
     /// Performs a training run.
     /// Returns a handle.
     fn start_train_run(
@@ -51,11 +63,34 @@ where
         assembly: AssemblyType,
     ) -> Result<(AssemblyType, Result<f32, String>), (AssemblyType, String)>;
 
+    /// Run this frame for an <Assembly>, as part of training.
+    ///
+    /// Defaults to [Self::run]. Override this if training runs should behave
+    /// differently from production runs, e.g. by corrupting the inputs.
+    fn train_run(
+        &mut self,
+        assembly: AssemblyType,
+    ) -> Result<(AssemblyType, Result<f32, String>), (AssemblyType, String)> {
+        self.run(assembly)
+    }
+
+    /// A key for what the fitness of training runs depends on besides the
+    /// assembly, if it does not vary between runs. See [Frame::fitness_key].
+    fn fitness_key(&self) -> Option<u64> {
+        None
+    }
+
     fn _run_to_result(
         &mut self,
         assembly: AssemblyType,
+        training: bool,
     ) -> Result<SimpleFrameHandle<AssemblyType>, (AssemblyType, String)> {
-        let (assembly, result) = self.run(assembly)?;
+        let (assembly, result) = if training {
+            self.train_run(assembly)?
+        } else {
+            self.run(assembly)?
+        };
+
         Ok(SimpleFrameHandle { assembly, result })
     }
 }
@@ -81,15 +116,14 @@ impl<AssemblyType: Assembly> FrameHandle<AssemblyType> for SimpleFrameHandle<Ass
     fn poll_state(&mut self) -> FrameRunState {
         use FrameRunState::*;
 
-        if self.result.is_ok() {
-            Done
-        } else {
-            Error(self.result.unwrap_err())
+        match &self.result {
+            Ok(_) => Done,
+            Err(err) => Error(err.clone()),
         }
     }
 
     fn get_fitness(&self) -> f32 {
-        self.result.unwrap_or(0.0)
+        *self.result.as_ref().unwrap_or(&0.0)
     }
 }
 
@@ -105,18 +139,22 @@ where
         true
     }
 
+    fn fitness_key(&self) -> Option<u64> {
+        SimpleFrame::fitness_key(self)
+    }
+
     fn start_train_run(
         &mut self,
         assembly: AssemblyType,
     ) -> Result<SimpleFrameHandle<AssemblyType>, (AssemblyType, String)> {
-        self._run_to_result(assembly)
+        self._run_to_result(assembly, true)
     }
 
     fn start_run(
         &mut self,
         assembly: AssemblyType,
     ) -> Result<SimpleFrameHandle<AssemblyType>, (AssemblyType, String)> {
-        self._run_to_result(assembly)
+        self._run_to_result(assembly, false)
     }
 }
 
@@ -169,13 +207,19 @@ where
     _phantom: PhantomData<AA>,
 }
 
+/// The state of a handle in a [HandlePool], as of the last poll.
 pub struct HandleResult<AssemblyType>
 where
     AssemblyType: Assembly,
 {
-    state: FrameRunState,
-    fitness: f32,
-    returned_assembly: Option<AssemblyType>,
+    /// The state the handle was in.
+    pub state: FrameRunState,
+
+    /// The fitness of the run, if it is done; 0 otherwise.
+    pub fitness: f32,
+
+    /// The assembly of the run, once it is done or has failed.
+    pub returned_assembly: Option<AssemblyType>,
 }
 
 impl<AssemblyType> Default for HandleResult<AssemblyType>
@@ -196,38 +240,57 @@ where
     AA: Assembly,
     HandleType: FrameHandle<AA>,
 {
-    fn add_handle(&mut self, handle: HandleType) -> () {
+    /// Adds a handle to this pool.
+    pub fn add_handle(&mut self, handle: HandleType) {
         self.handles.push(handle);
     }
 
-    fn poll_all(&mut self) -> Vec<HandleResult<AA>> {
-        let res: Vec<HandleResult<AA>> = vec![];
-
-        for _ in 0..self.handles.len() {
-            res.push(HandleResult::default());
+    /// Polls every handle in this pool, in the order they were added.
+    ///
+    /// Handles which are done, or have failed, are finished and removed
+    /// from the pool, returning their assemblies.
+    pub fn poll_all(&mut self) -> Vec<HandleResult<AA>> {
+        let mut res: Vec<HandleResult<AA>> = Vec::with_capacity(self.handles.len());
+        let mut pending = Vec::with_capacity(self.handles.len());
+
+        for mut handle in self.handles.drain(..) {
+            let state = handle.poll_state();
+
+            if !state.is_done() {
+                res.push(HandleResult {
+                    state,
+                    ..Default::default()
+                });
+                pending.push(handle);
+                continue;
+            }
+
+            let fitness = if matches!(state, FrameRunState::Done) {
+                handle.get_fitness()
+            } else {
+                0.0
+            };
+
+            res.push(HandleResult {
+                state,
+                fitness,
+                returned_assembly: Some(handle.finish()),
+            });
         }
 
-        self.handles
-            .iter()
-            .zip(res.iter_mut())
-            .for_each(|(&handle, &mut res)| {
-                let state = handle.poll_state();
-                res.state = state;
+        self.handles = pending;
 
-                if matches!(state, FrameRunState::Done) {
-                    res.fitness = handle.get_fitness();
-                }
-            });
-
-        self.handles.iter().zip(res.iter_mut()).for_each(
-            |(handle, item): (&HandleType, &mut HandleResult<AA>)| {
-                item.returned_assembly = Some(handle.finish());
-            },
-        );
+        res
+    }
+}
 
-        self.handles.retain(|h| !h.poll_state().is_done());
+/// Feeds a list of floats to a hasher, by their bits; useful to implement
+/// [Frame::fitness_key].
+pub fn hash_floats<H: Hasher>(values: &[f32], state: &mut H) {
+    state.write_usize(values.len());
 
-        res
+    for value in values {
+        state.write_u32(value.to_bits());
     }
 }
 
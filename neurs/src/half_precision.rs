@@ -0,0 +1,168 @@
+/*!
+ * Networks stored in half precision (16-bit floats).
+ *
+ * Only available with the `half` feature. A [HalfNeuralNetwork] takes half
+ * the memory of a [SimpleNeuralNetwork], which matters for large networks,
+ * such as image autoencoders. Values are converted to 32-bit floats as they
+ * are used, so computations themselves are still done in full precision.
+ *
+ * Half precision is meant for storing and running trained networks; train
+ * a [SimpleNeuralNetwork], then convert it with [HalfNeuralNetwork::from].
+ */
+use half::f16;
+
+use crate::prelude::*;
+
+fn to_half(values: &[f32]) -> Vec<f16> {
+    values.iter().copied().map(f16::from_f32).collect()
+}
+
+fn to_full(values: &[f16]) -> Vec<f32> {
+    values.iter().copied().map(f16::to_f32).collect()
+}
+
+/// A dense layer, stored in half precision. See [NeuralLayer].
+#[derive(Clone)]
+pub struct HalfNeuralLayer {
+    /// The activation function of the layer.
+    pub activation: Box<NNActivation>,
+
+    /// The weights of the layer.
+    pub weights: Vec<f16>,
+
+    /// The biases of the layer.
+    pub biases: Vec<f16>,
+
+    /// The input size of the layer.
+    pub input_size: usize,
+
+    /// The output size of the layer.
+    pub output_size: usize,
+}
+
+impl HalfNeuralLayer {
+    /// Transforms a vector of values through this dense layer of neurons.
+    pub fn compute(&self, inputs: &[f32], outputs: &mut [f32]) -> Result<(), String> {
+        if inputs.len() < self.input_size {
+            return Err("Source slice is smaller than the input size of this layer".to_owned());
+        }
+
+        if outputs.len() < self.output_size {
+            return Err(
+                "Destination slice is smaller than the output size of this layer".to_owned(),
+            );
+        }
+
+        let inputs = &inputs[0..self.input_size];
+
+        for (i, out) in outputs[0..self.output_size].iter_mut().enumerate() {
+            let weights = &self.weights[i * self.input_size..(i + 1) * self.input_size];
+
+            *out = (self.activation)(
+                self.biases[i].to_f32()
+                    + inputs
+                        .iter()
+                        .zip(weights)
+                        .map(|(inp, w)| inp * w.to_f32())
+                        .sum::<f32>(),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&NeuralLayer> for HalfNeuralLayer {
+    fn from(layer: &NeuralLayer) -> Self {
+        Self {
+            activation: layer.activation.clone(),
+            weights: to_half(&layer.weights),
+            biases: to_half(&layer.biases),
+            input_size: layer.input_size,
+            output_size: layer.output_size,
+        }
+    }
+}
+
+impl From<&HalfNeuralLayer> for NeuralLayer {
+    fn from(layer: &HalfNeuralLayer) -> Self {
+        Self {
+            activation: layer.activation.clone(),
+            weights: to_full(&layer.weights),
+            biases: to_full(&layer.biases),
+            input_size: layer.input_size,
+            output_size: layer.output_size,
+            area: (layer.input_size * layer.output_size) as u32,
+        }
+    }
+}
+
+/// A feed-forward neural network, stored in half precision. See
+/// [SimpleNeuralNetwork].
+#[derive(Clone)]
+pub struct HalfNeuralNetwork {
+    /// A list of layers in this network. The last one is the output layer.
+    pub layers: Vec<HalfNeuralLayer>,
+}
+
+impl HalfNeuralNetwork {
+    /// Returns the input size of this network, as determined by its first
+    /// layer.
+    pub fn input_size(&self) -> Result<usize, String> {
+        self.layers
+            .first()
+            .map(|layer| layer.input_size)
+            .ok_or_else(|| "There are no layers in this network".to_owned())
+    }
+
+    /// Returns the output size of this network, as determined by its last
+    /// layer.
+    pub fn output_size(&self) -> Result<usize, String> {
+        self.layers
+            .last()
+            .map(|layer| layer.output_size)
+            .ok_or_else(|| "There are no layers in this network".to_owned())
+    }
+
+    /// Computes a list of floats and saves the result in an output buffer.
+    pub fn compute_values(&self, inputs: &[f32], outputs: &mut [f32]) -> Result<(), String> {
+        if outputs.len() != self.output_size()? {
+            return Err(
+                "The size of the destination array does not match the output size of this network"
+                    .to_owned(),
+            );
+        }
+
+        let mut in_values = inputs.to_vec();
+
+        for layer in &self.layers {
+            let mut dest = vec![0.0; layer.output_size];
+
+            layer.compute(&in_values, &mut dest)?;
+
+            in_values = dest;
+        }
+
+        outputs.copy_from_slice(&in_values);
+
+        Ok(())
+    }
+}
+
+impl From<&SimpleNeuralNetwork> for HalfNeuralNetwork {
+    fn from(network: &SimpleNeuralNetwork) -> Self {
+        Self {
+            layers: network.layers.iter().map(HalfNeuralLayer::from).collect(),
+        }
+    }
+}
+
+impl From<&HalfNeuralNetwork> for SimpleNeuralNetwork {
+    /// Converts back to full precision, e.g. to resume training. Precision
+    /// lost when converting to half precision is not recovered.
+    fn from(network: &HalfNeuralNetwork) -> Self {
+        Self {
+            layers: network.layers.iter().map(NeuralLayer::from).collect(),
+        }
+    }
+}
@@ -3,17 +3,40 @@
  */
 pub mod activations;
 pub mod assembly;
+pub mod attention;
+pub mod binary;
+pub mod diff;
+pub mod embedding;
 pub mod frame;
 pub mod neuralnet;
+pub mod residual;
+pub mod tied;
 pub mod train;
 
+#[cfg(feature = "half")]
+pub mod half_precision;
+
+#[cfg(feature = "safetensors")]
+pub mod safetensors;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 pub mod prelude {
     /*!
      * A set of useful imports to always have.
      */
     pub use super::activations;
     pub use super::assembly::*;
+    pub use super::attention::*;
+    pub use super::binary::*;
+    pub use super::diff::*;
+    #[cfg(feature = "half")]
+    pub use super::half_precision::*;
+    pub use super::embedding::*;
     pub use super::frame::prelude::*;
     pub use super::neuralnet::*;
+    pub use super::residual::*;
+    pub use super::tied::*;
     pub use super::train::prelude::*;
 }
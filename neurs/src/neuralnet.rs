@@ -17,10 +17,15 @@ pub type NNActivation = fn(f32) -> f32;
 
 /**
  * A simple dense layer.
+ *
+ * With the `serde` feature, layers can be serialized; the activation function
+ * is stored by name, so it must be one of [crate::activations::ALL].
  */
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NeuralLayer {
     /// The activation function of the layer.
+    #[cfg_attr(feature = "serde", serde(with = "crate::activations::serde_by_name"))]
     pub activation: Box<NNActivation>,
 
     /// The weights of the layer.
@@ -48,17 +53,29 @@ impl NeuralLayer {
         input_size: usize,
         output_size: usize,
         activation: Option<NNActivation>,
+    ) -> NeuralLayer {
+        Self::new_with_rng(input_size, output_size, activation, &mut thread_rng())
+    }
+
+    /// Like [Self::new], but draws the random weights and biases from the given
+    /// random number generator.
+    ///
+    /// Useful for reproducible initialization, and for targets where
+    /// [thread_rng] is not available.
+    pub fn new_with_rng<R: Rng>(
+        input_size: usize,
+        output_size: usize,
+        activation: Option<NNActivation>,
+        rng: &mut R,
     ) -> NeuralLayer {
         let activation = activation.unwrap_or(relu);
 
         let area: u32 = input_size as u32 * output_size as u32;
 
         let mut weights: Vec<f32> = vec![0.0; area as usize];
-        let mut biases: Vec<f32> = vec![0.0; output_size as usize];
+        let mut biases: Vec<f32> = vec![0.0; output_size];
 
-        let mut random_distrib = Normal::<f32>::new(0.0, 1.0)
-            .unwrap()
-            .sample_iter(thread_rng());
+        let mut random_distrib = Normal::<f32>::new(0.0, 1.0).unwrap().sample_iter(rng);
 
         weights
             .as_mut_slice()
@@ -81,12 +98,12 @@ impl NeuralLayer {
 
     /// Transforms a vector of values through this dense layer of neurons.
     pub fn compute(&self, mut inputs: &[f32], mut outputs: &mut [f32]) -> Result<(), String> {
-        if cfg!(debug) || cfg!(tests) {
-            if inputs.len() < self.input_size as usize {
+        if cfg!(debug_assertions) {
+            if inputs.len() < self.input_size {
                 return Err("Source slice is smaller than the input size of this layer".to_owned());
             }
 
-            if outputs.len() < self.output_size as usize {
+            if outputs.len() < self.output_size {
                 return Err(
                     "Destination slice is smaller than the output size of this layer".to_owned(),
                 );
@@ -97,7 +114,7 @@ impl NeuralLayer {
         outputs = &mut outputs[0..self.output_size];
 
         for (i, out) in outputs.iter_mut().enumerate() {
-            let idx_base: usize = (i * self.input_size) as usize;
+            let idx_base: usize = i * self.input_size;
 
             let value = (self.activation)(
                 self.biases[i]
@@ -119,6 +136,7 @@ impl NeuralLayer {
  * A simple feed-forward neural network.
  */
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimpleNeuralNetwork {
     /// A list of layers in this network. The last one is the output layer.
     pub layers: Vec<NeuralLayer>,
@@ -135,6 +153,18 @@ impl SimpleNeuralNetwork {
      * every layer, see [Self::new_simple_with_activation].
      */
     pub fn new_simple(layer_sizes: &[usize], activations: &[Option<NNActivation>]) -> Self {
+        Self::new_simple_with_rng(layer_sizes, activations, &mut thread_rng())
+    }
+
+    /**
+     * Like [Self::new_simple], but draws the random weights and biases from
+     * the given random number generator.
+     */
+    pub fn new_simple_with_rng<R: Rng>(
+        layer_sizes: &[usize],
+        activations: &[Option<NNActivation>],
+        rng: &mut R,
+    ) -> Self {
         SimpleNeuralNetwork {
             layers: layer_sizes
                 .iter()
@@ -143,7 +173,7 @@ impl SimpleNeuralNetwork {
                 .enumerate()
                 .map(|item| {
                     let (i, (a, b)) = item;
-                    NeuralLayer::new(*a, *b, activations[i])
+                    NeuralLayer::new_with_rng(*a, *b, activations[i], rng)
                 })
                 .collect(),
         }
@@ -163,6 +193,80 @@ impl SimpleNeuralNetwork {
         Self::new_simple(layer_sizes, vec![activation; layer_sizes.len()].as_slice())
     }
 
+    /**
+     * Copies the weights and biases of layers of another network into this
+     * one, and reinitializes every other layer with random values; e.g. to
+     * retrain a new classifier head on top of a pre-trained feature
+     * extractor.
+     *
+     * `layer_map` lists pairs of (layer index in `other`, layer index in
+     * `self`). A layer is only copied if both layers have the same shape;
+     * activations are left untouched. Returns the indices of the layers of
+     * `self` which were copied into.
+     */
+    pub fn transplant_from(
+        &mut self,
+        other: &SimpleNeuralNetwork,
+        layer_map: &[(usize, usize)],
+    ) -> Result<Vec<usize>, String> {
+        self.transplant_from_with_rng(other, layer_map, &mut thread_rng())
+    }
+
+    /**
+     * Like [Self::transplant_from], but draws the random weights and biases
+     * of reinitialized layers from the given random number generator.
+     */
+    pub fn transplant_from_with_rng<R: Rng>(
+        &mut self,
+        other: &SimpleNeuralNetwork,
+        layer_map: &[(usize, usize)],
+        rng: &mut R,
+    ) -> Result<Vec<usize>, String> {
+        for (from, to) in layer_map {
+            if *from >= other.layers.len() || *to >= self.layers.len() {
+                return Err(format!(
+                    "Layer mapping {} -> {} is out of bounds ({} -> {} layers)",
+                    from,
+                    to,
+                    other.layers.len(),
+                    self.layers.len()
+                ));
+            }
+        }
+
+        let mut transplanted = vec![];
+
+        for (i, layer) in self.layers.iter_mut().enumerate() {
+            let source = layer_map
+                .iter()
+                .filter(|(_, to)| *to == i)
+                .map(|(from, _)| &other.layers[*from])
+                .find(|source| {
+                    source.input_size == layer.input_size && source.output_size == layer.output_size
+                });
+
+            match source {
+                Some(source) => {
+                    layer.weights.clone_from(&source.weights);
+                    layer.biases.clone_from(&source.biases);
+
+                    transplanted.push(i);
+                }
+
+                None => {
+                    *layer = NeuralLayer::new_with_rng(
+                        layer.input_size,
+                        layer.output_size,
+                        Some(*layer.activation),
+                        rng,
+                    );
+                }
+            }
+        }
+
+        Ok(transplanted)
+    }
+
     /// Returns the input size of this network, as determined by its first
     /// layer.
     pub fn input_size(&self) -> Result<usize, String> {
@@ -189,19 +293,19 @@ impl SimpleNeuralNetwork {
 
     /// Computes a list of floats and saves the result in an output buffer.
     pub fn compute_values(&self, inputs: &[f32], outputs: &mut [f32]) -> Result<(), String> {
-        if cfg!(debug) || cfg!(tests) {
+        if cfg!(debug_assertions) {
             if self.layers.is_empty() {
                 return Err("There are no layers in this network".to_owned());
             }
 
-            if inputs.len() != self.input_size().unwrap() as usize {
+            if inputs.len() != self.input_size().unwrap() {
                 return Err(
                     "The number of input values does not match the input size of this network"
                         .to_owned(),
                 );
             }
 
-            if outputs.len() != self.output_size().unwrap() as usize {
+            if outputs.len() != self.output_size().unwrap() {
                 return Err("The size of the destination array does not match the output size of this network".to_owned());
             }
         }
@@ -209,7 +313,7 @@ impl SimpleNeuralNetwork {
         let mut in_values = inputs.to_vec();
 
         for layer in &self.layers {
-            let mut dest = vec![0.0; layer.output_size as usize];
+            let mut dest = vec![0.0; layer.output_size];
 
             layer.compute(&in_values, &mut dest)?;
 
@@ -0,0 +1,175 @@
+/*!
+ * Residual (skip) connections.
+ *
+ * A [ResidualBlock] wraps a few layers and adds its input to their output,
+ * so that the layers only need to learn a correction to their input. This
+ * keeps deeper networks trainable, since information can always flow past a
+ * block, rather than being scrambled by every layer in turn.
+ *
+ * Blocks are chained into a [BlockNetwork], which is an [Assembly] of all of
+ * its blocks' networks, and can thus be trained like any other assembly.
+ */
+use crate::prelude::*;
+
+/// A stack of layers whose input is added to their output.
+#[derive(Clone)]
+pub struct ResidualBlock {
+    /// The wrapped layers. Their input and output sizes must match.
+    pub inner: SimpleNeuralNetwork,
+}
+
+impl ResidualBlock {
+    /// Wraps a network into a residual block. Fails if its input and output
+    /// sizes do not match.
+    pub fn new(inner: SimpleNeuralNetwork) -> Result<Self, String> {
+        let (input_size, output_size) = (inner.input_size()?, inner.output_size()?);
+
+        if input_size != output_size {
+            return Err(format!(
+                "A residual block needs matching input and output sizes, not {} and {}",
+                input_size, output_size
+            ));
+        }
+
+        Ok(Self { inner })
+    }
+
+    /// The input (and output) size of this block.
+    pub fn size(&self) -> Result<usize, String> {
+        self.inner.input_size()
+    }
+
+    /// Computes the wrapped layers' outputs, plus the inputs.
+    pub fn compute_values(&self, inputs: &[f32], outputs: &mut [f32]) -> Result<(), String> {
+        self.inner.compute_values(inputs, outputs)?;
+
+        for (output, input) in outputs.iter_mut().zip(inputs) {
+            *output += input;
+        }
+
+        Ok(())
+    }
+}
+
+/// A part of a [BlockNetwork].
+#[derive(Clone)]
+pub enum Block {
+    /// Plain layers, without a skip connection.
+    Plain(SimpleNeuralNetwork),
+
+    /// Layers with a skip connection around them.
+    Residual(ResidualBlock),
+}
+
+impl Block {
+    /// The network holding the layers of this block.
+    pub fn network(&self) -> &SimpleNeuralNetwork {
+        match self {
+            Block::Plain(network) => network,
+            Block::Residual(block) => &block.inner,
+        }
+    }
+
+    /// Mutably references the network holding the layers of this block.
+    pub fn network_mut(&mut self) -> &mut SimpleNeuralNetwork {
+        match self {
+            Block::Plain(network) => network,
+            Block::Residual(block) => &mut block.inner,
+        }
+    }
+
+    /// Transforms values through this block.
+    pub fn compute_values(&self, inputs: &[f32], outputs: &mut [f32]) -> Result<(), String> {
+        match self {
+            Block::Plain(network) => network.compute_values(inputs, outputs),
+            Block::Residual(block) => block.compute_values(inputs, outputs),
+        }
+    }
+}
+
+/// A feed-forward network made of a sequence of blocks, some of which may
+/// have skip connections.
+#[derive(Clone, Default)]
+pub struct BlockNetwork {
+    /// The blocks of this network, in order.
+    pub blocks: Vec<Block>,
+}
+
+impl BlockNetwork {
+    /// Constructs a network from its blocks, checking that the output size of
+    /// every block matches the input size of the next.
+    pub fn new(blocks: Vec<Block>) -> Result<Self, String> {
+        for (i, pair) in blocks.windows(2).enumerate() {
+            let (output_size, input_size) = (
+                pair[0].network().output_size()?,
+                pair[1].network().input_size()?,
+            );
+
+            if output_size != input_size {
+                return Err(format!(
+                    "Block {} outputs {} values, but block {} takes {}",
+                    i,
+                    output_size,
+                    i + 1,
+                    input_size
+                ));
+            }
+        }
+
+        Ok(Self { blocks })
+    }
+
+    /// Returns the input size of this network, as determined by its first
+    /// block.
+    pub fn input_size(&self) -> Result<usize, String> {
+        self.blocks
+            .first()
+            .ok_or_else(|| "There are no blocks in this network".to_owned())?
+            .network()
+            .input_size()
+    }
+
+    /// Returns the output size of this network, as determined by its last
+    /// block.
+    pub fn output_size(&self) -> Result<usize, String> {
+        self.blocks
+            .last()
+            .ok_or_else(|| "There are no blocks in this network".to_owned())?
+            .network()
+            .output_size()
+    }
+
+    /// Computes a list of floats and saves the result in an output buffer.
+    pub fn compute_values(&self, inputs: &[f32], outputs: &mut [f32]) -> Result<(), String> {
+        let mut in_values = inputs.to_vec();
+
+        for block in &self.blocks {
+            let mut dest = vec![0.0; block.network().output_size()?];
+
+            block.compute_values(&in_values, &mut dest)?;
+
+            in_values = dest;
+        }
+
+        if outputs.len() != in_values.len() {
+            return Err(
+                "The size of the destination array does not match the output size of this network"
+                    .to_owned(),
+            );
+        }
+
+        outputs.copy_from_slice(&in_values);
+
+        Ok(())
+    }
+}
+
+impl Assembly for BlockNetwork {
+    fn get_network_refs(&self) -> Vec<&SimpleNeuralNetwork> {
+        self.blocks.iter().map(Block::network).collect()
+    }
+
+    fn get_networks_mut(&mut self) -> Vec<&mut SimpleNeuralNetwork> {
+        self.blocks.iter_mut().map(Block::network_mut).collect()
+    }
+}
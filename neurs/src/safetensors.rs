@@ -0,0 +1,186 @@
+/*!
+ * Reading and writing network weights in the
+ * [safetensors](https://github.com/huggingface/safetensors) format.
+ *
+ * Only available with the `safetensors` feature. Each layer `i` is stored as
+ * two F32 tensors, `layers.{i}.weight` (of shape `[output_size, input_size]`,
+ * one row per neuron) and `layers.{i}.bias` (of shape `[output_size]`). The
+ * activation of each layer is stored by name in the file's metadata, under
+ * `layers.{i}.activation`, so it must be one of [crate::activations::ALL].
+ */
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use ::safetensors::tensor::{Dtype, SafeTensors, TensorView};
+
+use crate::activations;
+use crate::prelude::*;
+
+fn to_bytes(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn from_bytes(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect()
+}
+
+fn get_tensor<'data>(
+    tensors: &SafeTensors<'data>,
+    name: &str,
+) -> Result<TensorView<'data>, String> {
+    tensors
+        .tensor(name)
+        .map_err(|err| format!("Could not read tensor {}: {}", name, err))
+}
+
+fn read_tensor(tensors: &SafeTensors, name: &str, shape: &[usize]) -> Result<Vec<f32>, String> {
+    let tensor = get_tensor(tensors, name)?;
+
+    if tensor.dtype() != Dtype::F32 {
+        return Err(format!(
+            "Tensor {} has dtype {:?}, but only F32 is supported",
+            name,
+            tensor.dtype()
+        ));
+    }
+
+    if tensor.shape() != shape {
+        return Err(format!(
+            "Tensor {} has shape {:?}, expected {:?}",
+            name,
+            tensor.shape(),
+            shape
+        ));
+    }
+
+    Ok(from_bytes(tensor.data()))
+}
+
+impl SimpleNeuralNetwork {
+    /**
+     * Serializes the weights and biases of this network into safetensors
+     * bytes. See the [module documentation](self) for the layout.
+     */
+    pub fn to_safetensors(&self) -> Result<Vec<u8>, String> {
+        let mut metadata = HashMap::new();
+        let mut buffers = Vec::with_capacity(self.layers.len());
+
+        for (i, layer) in self.layers.iter().enumerate() {
+            let activation = activations::name_of(*layer.activation).ok_or_else(|| {
+                format!(
+                    "The activation of layer {} is not one of neurs::activations",
+                    i
+                )
+            })?;
+
+            metadata.insert(format!("layers.{}.activation", i), activation.to_owned());
+            buffers.push((to_bytes(&layer.weights), to_bytes(&layer.biases)));
+        }
+
+        let mut tensors = Vec::with_capacity(self.layers.len() * 2);
+
+        for (i, (layer, (weights, biases))) in self.layers.iter().zip(&buffers).enumerate() {
+            tensors.push((
+                format!("layers.{}.weight", i),
+                TensorView::new(
+                    Dtype::F32,
+                    vec![layer.output_size, layer.input_size],
+                    weights,
+                )
+                .map_err(|err| err.to_string())?,
+            ));
+
+            tensors.push((
+                format!("layers.{}.bias", i),
+                TensorView::new(Dtype::F32, vec![layer.output_size], biases)
+                    .map_err(|err| err.to_string())?,
+            ));
+        }
+
+        ::safetensors::serialize(tensors, &Some(metadata)).map_err(|err| err.to_string())
+    }
+
+    /**
+     * Deserializes a network from safetensors bytes, as written by
+     * [Self::to_safetensors].
+     *
+     * Layers are read in order, starting from `layers.0`, until no more
+     * `layers.{i}.activation` metadata entries are found. Each layer must take
+     * as many inputs as the one before it outputs.
+     */
+    pub fn from_safetensors(bytes: &[u8]) -> Result<Self, String> {
+        let (_, header) = SafeTensors::read_metadata(bytes).map_err(|err| err.to_string())?;
+        let tensors = SafeTensors::deserialize(bytes).map_err(|err| err.to_string())?;
+
+        let metadata = header
+            .metadata()
+            .as_ref()
+            .ok_or("The safetensors file has no metadata with the layer activations")?;
+
+        let mut layers = vec![];
+
+        while let Some(name) = metadata.get(&format!("layers.{}.activation", layers.len())) {
+            let i = layers.len();
+
+            let activation = activations::from_name(name)
+                .ok_or_else(|| format!("Unknown activation function {:?}", name))?;
+
+            // The sizes are taken from the tensors' shapes; read_tensor then
+            // checks that both tensors agree with each other.
+            let bias_name = format!("layers.{}.bias", i);
+            let output_size = get_tensor(&tensors, &bias_name)?
+                .shape()
+                .first()
+                .copied()
+                .unwrap_or(0);
+
+            let weight_name = format!("layers.{}.weight", i);
+            let input_size = get_tensor(&tensors, &weight_name)?
+                .shape()
+                .get(1)
+                .copied()
+                .unwrap_or(0);
+
+            layers.push(NeuralLayer {
+                activation: Box::from(activation),
+                weights: read_tensor(&tensors, &weight_name, &[output_size, input_size])?,
+                biases: read_tensor(&tensors, &bias_name, &[output_size])?,
+                input_size,
+                output_size,
+                area: input_size as u32 * output_size as u32,
+            });
+        }
+
+        if layers.is_empty() {
+            return Err("The safetensors file contains no layers".to_owned());
+        }
+
+        for (i, pair) in layers.windows(2).enumerate() {
+            if pair[0].output_size != pair[1].input_size {
+                return Err(format!(
+                    "Layer {} outputs {} values, but layer {} takes {} inputs",
+                    i,
+                    pair[0].output_size,
+                    i + 1,
+                    pair[1].input_size
+                ));
+            }
+        }
+
+        Ok(SimpleNeuralNetwork { layers })
+    }
+
+    /// Writes this network to a safetensors file. See [Self::to_safetensors].
+    pub fn save_safetensors<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        fs::write(path, self.to_safetensors()?).map_err(|err| err.to_string())
+    }
+
+    /// Reads a network from a safetensors file. See [Self::from_safetensors].
+    pub fn load_safetensors<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        Self::from_safetensors(&fs::read(path).map_err(|err| err.to_string())?)
+    }
+}
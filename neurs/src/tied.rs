@@ -0,0 +1,143 @@
+/*!
+ * Weight tying; layers which share the same weights.
+ *
+ * The classic example is an autoencoder whose decoder reuses the transposed
+ * weights of its encoder, which halves the number of parameters to train.
+ *
+ * A [TiedAssembly] wraps any assembly along with a list of [WeightTie]s, and
+ * copies the weights of every tie's source layer into its target layer
+ * whenever a training strategy updates it (see [Assembly::after_update]).
+ * Changes made to a target layer directly are thus overwritten.
+ */
+use crate::prelude::*;
+
+/// A layer whose weights are a copy of another's, possibly transposed.
+///
+/// Layers are referred to as (network index, layer index) pairs, where the
+/// network index follows [Assembly::get_network_refs]. Biases are not tied.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WeightTie {
+    /// The layer whose weights are shared.
+    pub source: (usize, usize),
+
+    /// The layer which reuses the source's weights.
+    pub target: (usize, usize),
+
+    /// Whether the target uses the transpose of the source's weights; its
+    /// input size is then the source's output size, and vice versa.
+    pub transpose: bool,
+}
+
+impl WeightTie {
+    /// Checks that the source and target layers exist, and that their shapes
+    /// are compatible.
+    pub fn check<A: Assembly + ?Sized>(&self, assembly: &A) -> Result<(), String> {
+        let networks = assembly.get_network_refs();
+
+        let layer_at = |(network, layer): (usize, usize)| {
+            networks
+                .get(network)
+                .and_then(|network| network.layers.get(layer))
+                .ok_or_else(|| format!("There is no layer {} in network {}", layer, network))
+        };
+
+        let (source, target) = (layer_at(self.source)?, layer_at(self.target)?);
+
+        let expected = if self.transpose {
+            (source.output_size, source.input_size)
+        } else {
+            (source.input_size, source.output_size)
+        };
+
+        if (target.input_size, target.output_size) != expected {
+            return Err(format!(
+                "Layer {:?} ({}x{}) cannot be tied to layer {:?} ({}x{})",
+                self.target,
+                target.input_size,
+                target.output_size,
+                self.source,
+                source.input_size,
+                source.output_size
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Copies the source layer's weights into the target layer. The tie must
+    /// have passed [Self::check] on this assembly.
+    pub fn apply<A: Assembly + ?Sized>(&self, assembly: &mut A) {
+        let mut networks = assembly.get_networks_mut();
+
+        let source = &networks[self.source.0].layers[self.source.1];
+        let (input_size, output_size) = (source.input_size, source.output_size);
+
+        let weights = if self.transpose {
+            let mut transposed = vec![0.0_f32; source.weights.len()];
+
+            for o in 0..output_size {
+                for i in 0..input_size {
+                    transposed[i * output_size + o] = source.weights[o * input_size + i];
+                }
+            }
+
+            transposed
+        } else {
+            source.weights.clone()
+        };
+
+        networks[self.target.0].layers[self.target.1].weights = weights;
+    }
+}
+
+/// An assembly whose layers are tied according to a list of [WeightTie]s.
+#[derive(Clone)]
+pub struct TiedAssembly<A: Assembly> {
+    /// The wrapped assembly.
+    pub inner: A,
+
+    /// The ties, applied in order.
+    ties: Vec<WeightTie>,
+}
+
+impl<A: Assembly> TiedAssembly<A> {
+    /// Wraps an assembly, checking the ties and applying them right away.
+    pub fn new(mut inner: A, ties: Vec<WeightTie>) -> Result<Self, String> {
+        for tie in &ties {
+            tie.check(&inner)?;
+        }
+
+        for tie in &ties {
+            tie.apply(&mut inner);
+        }
+
+        Ok(Self { inner, ties })
+    }
+
+    /// The ties of this assembly.
+    pub fn ties(&self) -> &[WeightTie] {
+        &self.ties
+    }
+
+    /// Copies the weights of every tie's source layer into its target layer.
+    pub fn sync(&mut self) {
+        for tie in &self.ties {
+            tie.apply(&mut self.inner);
+        }
+    }
+}
+
+impl<A: Assembly> Assembly for TiedAssembly<A> {
+    fn get_network_refs(&self) -> Vec<&SimpleNeuralNetwork> {
+        self.inner.get_network_refs()
+    }
+
+    fn get_networks_mut(&mut self) -> Vec<&mut SimpleNeuralNetwork> {
+        self.inner.get_networks_mut()
+    }
+
+    fn after_update(&mut self) {
+        self.inner.after_update();
+        self.sync();
+    }
+}
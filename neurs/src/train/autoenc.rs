@@ -0,0 +1,448 @@
+/*!
+ * Reconstruction-based learning frame for autoencoders, for the
+ * [SimpleFrame] interface.
+ *
+ * An autoencoder is an assembly of two networks: an encoder, which turns an
+ * input into a smaller 'latent' representation, and a decoder, which tries to
+ * turn that latent back into the original input.
+ *
+ * Both plain autoencoders ([AutoencoderAssembly]) and variational ones
+ * ([VariationalAutoencoder]) are supported.
+ */
+use crate::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+
+use rand::prelude::*;
+use rand_distr::Normal;
+
+/// An autoencoder assembly.
+#[derive(Clone)]
+pub struct AutoencoderAssembly {
+    /// The network which turns inputs into latents.
+    pub encoder: SimpleNeuralNetwork,
+
+    /// The network which turns latents back into inputs.
+    pub decoder: SimpleNeuralNetwork,
+}
+
+impl AutoencoderAssembly {
+    /// Encodes an input into its latent representation.
+    pub fn encode(&self, input: &[f32]) -> Result<Vec<f32>, String> {
+        let mut latent = vec![0.0_f32; self.encoder.output_size()?];
+        self.encoder.compute_values(input, &mut latent)?;
+
+        Ok(latent)
+    }
+
+    /// Decodes a latent representation back into an input.
+    pub fn decode(&self, latent: &[f32]) -> Result<Vec<f32>, String> {
+        let mut output = vec![0.0_f32; self.decoder.output_size()?];
+        self.decoder.compute_values(latent, &mut output)?;
+
+        Ok(output)
+    }
+
+    /// Encodes and then decodes an input.
+    pub fn reconstruct(&self, input: &[f32]) -> Result<Vec<f32>, String> {
+        self.decode(&self.encode(input)?)
+    }
+}
+
+impl Assembly for AutoencoderAssembly {
+    fn get_network_refs(&self) -> Vec<&SimpleNeuralNetwork> {
+        vec![&self.encoder, &self.decoder]
+    }
+
+    fn get_networks_mut(&mut self) -> Vec<&mut SimpleNeuralNetwork> {
+        vec![&mut self.encoder, &mut self.decoder]
+    }
+}
+
+/**
+ * A variational autoencoder assembly.
+ *
+ * Rather than a single latent vector, the encoder outputs the mean and the
+ * log-variance of a distribution over latents; its output is thus twice as
+ * large as the decoder's input, means first. A latent is sampled from that
+ * distribution (the 'reparameterization trick') and fed to the decoder.
+ *
+ * This makes the latent space smooth enough that decoding random latents
+ * yields new, plausible items.
+ */
+#[derive(Clone)]
+pub struct VariationalAutoencoder {
+    /// The network which turns inputs into latent means and log-variances.
+    pub encoder: SimpleNeuralNetwork,
+
+    /// The network which turns latents back into inputs.
+    pub decoder: SimpleNeuralNetwork,
+}
+
+impl VariationalAutoencoder {
+    /// The size of a latent vector, as determined by the decoder.
+    pub fn latent_size(&self) -> Result<usize, String> {
+        self.decoder.input_size()
+    }
+
+    /// Encodes an input into the mean and log-variance of its latent
+    /// distribution.
+    pub fn encode_distribution(&self, input: &[f32]) -> Result<(Vec<f32>, Vec<f32>), String> {
+        let latent_size = self.latent_size()?;
+        let mut encoded = vec![0.0_f32; self.encoder.output_size()?];
+
+        if encoded.len() != latent_size * 2 {
+            return Err(
+                "The encoder's output size must be twice the decoder's input size".to_owned(),
+            );
+        }
+
+        self.encoder.compute_values(input, &mut encoded)?;
+        let log_variance = encoded.split_off(latent_size);
+
+        Ok((encoded, log_variance))
+    }
+
+    /// Samples a latent vector from a distribution, as returned by
+    /// [Self::encode_distribution].
+    pub fn sample_latent<R: Rng>(mean: &[f32], log_variance: &[f32], rng: &mut R) -> Vec<f32> {
+        let unit = Normal::<f32>::new(0.0, 1.0).unwrap();
+
+        mean.iter()
+            .zip(log_variance)
+            .map(|(mu, logvar)| mu + (0.5 * logvar).exp() * unit.sample(rng))
+            .collect()
+    }
+
+    /// Decodes a latent vector back into an input.
+    pub fn decode(&self, latent: &[f32]) -> Result<Vec<f32>, String> {
+        let mut output = vec![0.0_f32; self.decoder.output_size()?];
+        self.decoder.compute_values(latent, &mut output)?;
+
+        Ok(output)
+    }
+
+    /// Encodes an input and decodes a latent sampled from its distribution.
+    pub fn reconstruct<R: Rng>(&self, input: &[f32], rng: &mut R) -> Result<Vec<f32>, String> {
+        let (mean, log_variance) = self.encode_distribution(input)?;
+        self.decode(&Self::sample_latent(&mean, &log_variance, rng))
+    }
+
+    /// Decodes a latent sampled from the unit normal distribution, yielding
+    /// an entirely new item.
+    pub fn generate<R: Rng>(&self, rng: &mut R) -> Result<Vec<f32>, String> {
+        let latent_size = self.latent_size()?;
+
+        self.decode(&Self::sample_latent(
+            &vec![0.0; latent_size],
+            &vec![0.0; latent_size],
+            rng,
+        ))
+    }
+}
+
+/// The Kullback-Leibler divergence between a latent distribution and the unit
+/// normal distribution, averaged over the latent's dimensions.
+pub fn kl_divergence(mean: &[f32], log_variance: &[f32]) -> f32 {
+    -0.5 * mean
+        .iter()
+        .zip(log_variance)
+        .map(|(mu, logvar)| 1.0 + logvar - mu * mu - logvar.exp())
+        .sum::<f32>()
+        / mean.len() as f32
+}
+
+impl Assembly for VariationalAutoencoder {
+    fn get_network_refs(&self) -> Vec<&SimpleNeuralNetwork> {
+        vec![&self.encoder, &self.decoder]
+    }
+
+    fn get_networks_mut(&mut self) -> Vec<&mut SimpleNeuralNetwork> {
+        vec![&mut self.encoder, &mut self.decoder]
+    }
+}
+
+/**
+ * How inputs should be corrupted before being fed to the encoder.
+ *
+ * This turns a plain autoencoder into a denoising one, which is forced to
+ * learn latents that are robust to small perturbations of its input. The
+ * reconstruction is still compared against the clean input.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InputCorruption {
+    /// The standard deviation of the Gaussian noise added to every input
+    /// value. Zero disables noise.
+    pub noise_stddev: f32,
+
+    /// The probability, from 0 to 1, that any given input value is zeroed
+    /// out ('masked'). Zero disables masking.
+    pub mask_rate: f32,
+}
+
+impl InputCorruption {
+    /// Corrupts a vector of input values in place.
+    pub fn apply<R: Rng>(&self, values: &mut [f32], rng: &mut R) {
+        let noise = if self.noise_stddev > 0.0 {
+            Some(Normal::<f32>::new(0.0, self.noise_stddev).unwrap())
+        } else {
+            None
+        };
+
+        for value in values {
+            if self.mask_rate > 0.0 && rng.gen::<f32>() < self.mask_rate {
+                *value = 0.0;
+            } else if let Some(noise) = &noise {
+                *value += noise.sample(rng);
+            }
+        }
+    }
+}
+
+/**
+ * A [SimpleFrame] implementation which trains an [AutoencoderAssembly] or a
+ * [VariationalAutoencoder] to reconstruct a set of inputs.
+ */
+#[derive(Clone)]
+pub struct AutoencoderFrame {
+    /// The inputs the autoencoder should learn to reconstruct.
+    cases: Vec<Vec<f32>>,
+
+    /// How much the error of each input weighs, if not all the same.
+    case_weights: Option<Vec<f32>>,
+
+    /// The inputs used in place of [AutoencoderFrame::cases] during training
+    /// runs, if any, e.g. augmented copies of them.
+    training_cases: Option<Vec<Vec<f32>>>,
+
+    /// The metric to use to measure the error of a reconstructed value.
+    distance_wrapper: Box<DistanceWrapper>,
+
+    /// The corruption applied to inputs during training runs, if any.
+    ///
+    /// Production runs always use the clean inputs.
+    pub corruption: Option<InputCorruption>,
+
+    /// How much the KL divergence term weighs against the reconstruction
+    /// error, when training a [VariationalAutoencoder].
+    ///
+    /// Higher values yield a smoother latent space, at the cost of blurrier
+    /// reconstructions. Defaults to 1.
+    pub kl_weight: f32,
+}
+
+impl AutoencoderFrame {
+    pub fn new(
+        cases: Vec<Vec<f32>>,
+        distance_wrapper: Option<Box<DistanceWrapper>>,
+        corruption: Option<InputCorruption>,
+    ) -> Self {
+        Self {
+            cases,
+            case_weights: None,
+            training_cases: None,
+            distance_wrapper: Box::from(
+                distance_wrapper.map_or(f32::abs as fn(f32) -> f32, |x| *x),
+            ),
+            corruption,
+            kl_weight: 1.0,
+        }
+    }
+
+    /// The number of inputs registered.
+    pub fn num_cases(&self) -> usize {
+        self.cases.len()
+    }
+
+    /**
+     * Weighs the reconstruction error of each input, e.g. so that rarer
+     * inputs count as much as common ones. There must be a weight per input.
+     */
+    pub fn set_case_weights(&mut self, weights: Vec<f32>) -> Result<(), String> {
+        if weights.len() != self.cases.len() {
+            return Err(format!(
+                "Expected {} case weights, got {}",
+                self.cases.len(),
+                weights.len()
+            ));
+        }
+
+        self.case_weights = Some(weights);
+
+        Ok(())
+    }
+
+    /**
+     * Sets the inputs to reconstruct during training runs in place of the
+     * registered ones, e.g. augmented copies of them, drawn anew every epoch;
+     * `None` goes back to the registered ones. There must be as many as are
+     * registered, in the same order, so that they weigh the same.
+     *
+     * Production runs always use the registered inputs.
+     */
+    pub fn set_training_cases(&mut self, cases: Option<Vec<Vec<f32>>>) -> Result<(), String> {
+        if let Some(cases) = &cases {
+            if cases.len() != self.cases.len() {
+                return Err(format!(
+                    "Expected {} training cases, got {}",
+                    self.cases.len(),
+                    cases.len()
+                ));
+            }
+        }
+
+        self.training_cases = cases;
+
+        Ok(())
+    }
+
+    /// The inputs to reconstruct, in training runs or production runs.
+    fn cases(&self, training: bool) -> &[Vec<f32>] {
+        match &self.training_cases {
+            Some(cases) if training => cases,
+            _ => &self.cases,
+        }
+    }
+
+    /// How much the error of the input at an index weighs.
+    fn case_weight(&self, index: usize) -> f32 {
+        self.case_weights
+            .as_ref()
+            .map_or(1.0, |weights| weights[index])
+    }
+
+    fn corrupted<R: Rng>(
+        case: &[f32],
+        corruption: Option<&InputCorruption>,
+        rng: &mut R,
+    ) -> Vec<f32> {
+        let mut input = case.to_vec();
+
+        if let Some(corruption) = corruption {
+            corruption.apply(&mut input, rng);
+        }
+
+        input
+    }
+
+    fn reconstruction_error(&self, output: &[f32], case: &[f32]) -> f32 {
+        output
+            .iter()
+            .zip(case)
+            .map(|(out, expected)| (self.distance_wrapper)(out - expected))
+            .sum::<f32>()
+            / case.len() as f32
+    }
+
+    fn reconstruction_fitness(
+        &self,
+        assembly: &AutoencoderAssembly,
+        corruption: Option<&InputCorruption>,
+        training: bool,
+    ) -> Result<f32, String> {
+        let mut fitness = 0.0_f32;
+        let mut rng = thread_rng();
+
+        for (index, case) in self.cases(training).iter().enumerate() {
+            let output = assembly.reconstruct(&Self::corrupted(case, corruption, &mut rng))?;
+            fitness -= self.case_weight(index) * self.reconstruction_error(&output, case);
+        }
+
+        Ok(fitness)
+    }
+
+    fn variational_fitness(
+        &self,
+        assembly: &VariationalAutoencoder,
+        corruption: Option<&InputCorruption>,
+        training: bool,
+    ) -> Result<f32, String> {
+        let mut fitness = 0.0_f32;
+        let mut rng = thread_rng();
+
+        for (index, case) in self.cases(training).iter().enumerate() {
+            let input = Self::corrupted(case, corruption, &mut rng);
+            let (mean, log_variance) = assembly.encode_distribution(&input)?;
+
+            // training runs sample the latent distribution, for a smoother
+            // latent space
+            let output = if training {
+                assembly.decode(&VariationalAutoencoder::sample_latent(
+                    &mean,
+                    &log_variance,
+                    &mut rng,
+                ))?
+            } else {
+                assembly.decode(&mean)?
+            };
+
+            fitness -= self.case_weight(index)
+                * (self.reconstruction_error(&output, case)
+                    + self.kl_weight * kl_divergence(&mean, &log_variance));
+        }
+
+        Ok(fitness)
+    }
+}
+
+impl SimpleFrame<AutoencoderAssembly> for AutoencoderFrame {
+    fn run(
+        &mut self,
+        assembly: AutoencoderAssembly,
+    ) -> Result<(AutoencoderAssembly, Result<f32, String>), (AutoencoderAssembly, String)> {
+        let fitness = self.reconstruction_fitness(&assembly, None, false);
+        Ok((assembly, fitness))
+    }
+
+    fn train_run(
+        &mut self,
+        assembly: AutoencoderAssembly,
+    ) -> Result<(AutoencoderAssembly, Result<f32, String>), (AutoencoderAssembly, String)> {
+        let fitness = self.reconstruction_fitness(&assembly, self.corruption.as_ref(), true);
+        Ok((assembly, fitness))
+    }
+
+    /// Only deterministic if no input corruption is applied, and the inputs
+    /// of training runs are not replaced, since they may change every epoch.
+    fn fitness_key(&self) -> Option<u64> {
+        if self.corruption.is_some() || self.training_cases.is_some() {
+            return None;
+        }
+
+        let mut state = DefaultHasher::new();
+
+        for case in &self.cases {
+            hash_floats(case, &mut state);
+        }
+
+        if let Some(case_weights) = &self.case_weights {
+            hash_floats(case_weights, &mut state);
+        }
+
+        state.write_usize(*self.distance_wrapper as usize);
+
+        Some(state.finish())
+    }
+}
+
+impl SimpleFrame<VariationalAutoencoder> for AutoencoderFrame {
+    /// Production runs decode the latent means directly, for a deterministic
+    /// fitness.
+    fn run(
+        &mut self,
+        assembly: VariationalAutoencoder,
+    ) -> Result<(VariationalAutoencoder, Result<f32, String>), (VariationalAutoencoder, String)>
+    {
+        let fitness = self.variational_fitness(&assembly, None, false);
+        Ok((assembly, fitness))
+    }
+
+    fn train_run(
+        &mut self,
+        assembly: VariationalAutoencoder,
+    ) -> Result<(VariationalAutoencoder, Result<f32, String>), (VariationalAutoencoder, String)>
+    {
+        let fitness = self.variational_fitness(&assembly, self.corruption.as_ref(), true);
+        Ok((assembly, fitness))
+    }
+}
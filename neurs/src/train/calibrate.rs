@@ -0,0 +1,155 @@
+/*!
+ * Post-training probability calibration for classifiers.
+ *
+ * Classifiers trained to separate labels well are often overconfident: the
+ * probabilities from [NeuralClassifier::predict_proba] are pushed towards 0
+ * and 1, regardless of how often the network is actually right.
+ * [TemperatureScaling] fixes this after training, by dividing the network's
+ * outputs by a single temperature, fitted on validation data, before the
+ * [softmax]. This does not change which label is most likely.
+ */
+use crate::prelude::*;
+
+use super::label::most_likely;
+
+/// A fitted temperature, to calibrate the probabilities of a
+/// [NeuralClassifier]. See the [module documentation](self).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TemperatureScaling {
+    /// The value the network's outputs are divided by before the [softmax].
+    ///
+    /// Values above 1 soften the probabilities, and values below 1 sharpen
+    /// them. Must be positive.
+    pub temperature: f32,
+}
+
+impl Default for TemperatureScaling {
+    /// A temperature of 1, which leaves probabilities unchanged.
+    fn default() -> Self {
+        Self { temperature: 1.0 }
+    }
+}
+
+/// The lowest and highest temperatures considered by [TemperatureScaling::fit],
+/// as their natural logarithms.
+const LOG_TEMPERATURE_RANGE: (f32, f32) = (-4.0, 6.0);
+
+/// How many golden-section search steps [TemperatureScaling::fit] takes.
+const FIT_STEPS: usize = 64;
+
+/// The mean negative log-likelihood of the expected labels, for raw outputs
+/// divided by a temperature.
+fn mean_nll(outputs: &[(Vec<f32>, usize)], temperature: f32) -> f32 {
+    let total: f32 = outputs
+        .iter()
+        .map(|(logits, label)| {
+            let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            let log_sum_exp = logits
+                .iter()
+                .map(|logit| ((logit - max) / temperature).exp())
+                .sum::<f32>()
+                .ln();
+
+            log_sum_exp - (logits[*label] - max) / temperature
+        })
+        .sum();
+
+    total / outputs.len() as f32
+}
+
+impl TemperatureScaling {
+    /// Fits a temperature to a classifier, such that its probabilities best
+    /// match validation inputs and their labels (by minimizing the negative
+    /// log-likelihood of the labels).
+    ///
+    /// The validation data should not have been used for training.
+    pub fn fit<LT: TrainingLabel>(
+        classifier: &NeuralClassifier,
+        inputs: &[Vec<f32>],
+        labels: &[LT],
+    ) -> Result<Self, String> {
+        if inputs.len() != labels.len() {
+            return Err("There must be exactly one label per input".to_owned());
+        }
+
+        if inputs.is_empty() {
+            return Err("Cannot calibrate on an empty validation set".to_owned());
+        }
+
+        let output_size = classifier.classifier.output_size()?;
+
+        let outputs = inputs
+            .iter()
+            .zip(labels)
+            .map(|(input, label)| {
+                if label.index() >= output_size {
+                    return Err(format!(
+                        "Label {} is out of bounds for a classifier with {} outputs",
+                        label.debug_name(),
+                        output_size
+                    ));
+                }
+
+                let mut logits = vec![0.0_f32; output_size];
+                classifier.classifier.compute_values(input, &mut logits)?;
+
+                Ok((logits, label.index()))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        // The loss is unimodal in the log-temperature, so a golden-section
+        // search finds its minimum.
+        let ratio = (5.0_f32.sqrt() - 1.0) / 2.0;
+        let (mut low, mut high) = LOG_TEMPERATURE_RANGE;
+
+        for _ in 0..FIT_STEPS {
+            let left = high - ratio * (high - low);
+            let right = low + ratio * (high - low);
+
+            if mean_nll(&outputs, left.exp()) < mean_nll(&outputs, right.exp()) {
+                high = right;
+            } else {
+                low = left;
+            }
+        }
+
+        Ok(Self {
+            temperature: ((low + high) / 2.0).exp(),
+        })
+    }
+
+    /// Turns a list of raw network outputs into calibrated probabilities, in
+    /// place.
+    pub fn apply(&self, outputs: &mut [f32]) {
+        for output in outputs.iter_mut() {
+            *output /= self.temperature;
+        }
+
+        softmax(outputs);
+    }
+
+    /// Like [NeuralClassifier::predict_proba], but with calibrated
+    /// probabilities.
+    pub fn predict_proba(
+        &self,
+        classifier: &NeuralClassifier,
+        input: &[f32],
+    ) -> Result<Vec<f32>, String> {
+        let mut outputs = vec![0.0_f32; classifier.classifier.output_size()?];
+
+        classifier.classifier.compute_values(input, &mut outputs)?;
+        self.apply(&mut outputs);
+
+        Ok(outputs)
+    }
+
+    /// Like [NeuralClassifier::predict]. Calibration never changes the most
+    /// likely label; this is only provided for convenience.
+    pub fn predict<LT: TrainingLabel>(
+        &self,
+        classifier: &NeuralClassifier,
+        input: &[f32],
+    ) -> Result<LT, String> {
+        most_likely(&self.predict_proba(classifier, input)?)
+    }
+}
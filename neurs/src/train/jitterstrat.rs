@@ -17,6 +17,11 @@ use crate::prelude::*;
 
 use rand::thread_rng;
 use rand_distr::*;
+use std::thread;
+use std::time::Duration;
+
+/// How long to wait between polls of candidate runs which are not done yet.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
 
 // Waiting for trait aliases to become stable so I can do this.
 //    pub trait AJW = Fn(f32, f32, f32) -> f32;
@@ -54,6 +59,10 @@ where
 
     /* Internals. */
     pub curr_jitter_width: f32,
+
+    /// The last evaluated reference, with the [Frame::fitness_key] of the
+    /// frame that evaluated it, if any, and its fitness.
+    reference_cache: Option<(AssemblyWnb, u64, f32)>,
 }
 
 pub struct WeightJitterStratOptions<AJW>
@@ -99,6 +108,7 @@ where
             apply_bad_jitters: options.apply_bad_jitters,
 
             curr_jitter_width: options.jitter_width,
+            reference_cache: None,
         }
     }
 
@@ -113,6 +123,25 @@ where
         H1: FrameHandle<AssemblyType>,
         H2: FrameHandle<AssemblyType>,
     {
+        let reference_wnb = AssemblyWnb::from(&*assembly);
+        let fitness_key = frame.fitness_key();
+
+        // Deterministic frames need not re-evaluate a reference unchanged
+        // since it was last evaluated by the same frame.
+        if let (Some(key), Some((cached_wnb, cached_key, cached_fitness))) =
+            (fitness_key, &self.reference_cache)
+        {
+            if key == *cached_key && *cached_wnb == reference_wnb {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(fitness = cached_fitness, "Reusing cached reference fitness");
+
+                return Ok((reference_wnb, *cached_fitness));
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        let started = std::time::Instant::now();
+
         let mut reference = frame
             .start_train_run(assembly.clone())
             .map_err(|(_, error_string)| error_string)?;
@@ -123,8 +152,14 @@ where
             return Err(err);
         }
 
-        let reference_wnb = AssemblyWnb::from(&*assembly);
-        Ok((reference_wnb, reference.get_fitness()))
+        let fitness = reference.get_fitness();
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(fitness, elapsed = ?started.elapsed(), "Evaluated reference");
+
+        self.reference_cache = fitness_key.map(|key| (reference_wnb.clone(), key, fitness));
+
+        Ok((reference_wnb, fitness))
     }
 }
 
@@ -134,7 +169,7 @@ fn jitter_values<D: Distribution<f32>>(values: &mut [f32], distrib: D) {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 struct WeightsAndBiases {
     w: Vec<f32>,
     b: Vec<f32>,
@@ -148,12 +183,12 @@ impl WeightsAndBiases {
     }
 
     fn jitter<D: Distribution<f32>>(&mut self, distrib: &D) {
-        jitter_values(&mut self.w, &distrib);
-        jitter_values(&mut self.b, &distrib);
+        jitter_values(&mut self.w, distrib);
+        jitter_values(&mut self.b, distrib);
     }
 
     fn apply_to(&self, dest_layer: &mut NeuralLayer) {
-        if cfg!(dbg) {
+        if cfg!(debug_assertions) {
             assert!(dest_layer.weights.len() == self.w.len());
             assert!(dest_layer.biases.len() == self.b.len());
         }
@@ -227,7 +262,7 @@ impl From<&mut NeuralLayer> for WeightsAndBiases {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 struct NetworkWnb {
     wnbs: Vec<WeightsAndBiases>,
 }
@@ -241,7 +276,7 @@ impl NetworkWnb {
     }
 
     fn apply_to(&self, dest_net: &mut SimpleNeuralNetwork) {
-        if cfg!(dbg) {
+        if cfg!(debug_assertions) {
             assert!(dest_net.layers.len() == self.wnbs.len());
         }
 
@@ -281,7 +316,7 @@ impl NetworkWnb {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 struct AssemblyWnb {
     wnbs: Vec<NetworkWnb>,
 }
@@ -301,8 +336,10 @@ impl AssemblyWnb {
         let mut netrefs = dest_net.get_networks_mut();
 
         for (nr, wnb) in netrefs.iter_mut().zip(self.wnbs.iter()) {
-            wnb.apply_to(*nr);
+            wnb.apply_to(nr);
         }
+
+        dest_net.after_update();
     }
 
     fn jitter<D: Distribution<f32>>(&mut self, distrib: &D) {
@@ -393,7 +430,7 @@ where
     HandleType: FrameHandle<AssemblyType>,
 {
     pub fn is_done(&self) -> bool {
-        matches!(self, Done) || matches!(self, Error)
+        matches!(self, Self::Done(..) | Self::Error(..))
     }
 }
 
@@ -407,7 +444,7 @@ where
             jitters: {
                 let mut res = vec![];
 
-                let reference_wnb: AssemblyWnb = AssemblyWnb::from(&*template);
+                let reference_wnb: AssemblyWnb = AssemblyWnb::from(template);
                 let distrib = Normal::<f32>::new(0.0, curr_jitter_width).unwrap();
 
                 for _ in 0..num_jitters {
@@ -445,25 +482,28 @@ where
     where
         FrameType: Frame<AssemblyType, ProdHandle = H1, TrainHandle = HandleType>,
     {
-        for (index, state) in self.jitters.iter_mut().enumerate() {
-            use EpochJitterState::*;
-            match state {
-                Pending(assembly) => {
-                    if frame.can_run() {
-                        self.jitters[index] = match frame.start_train_run(assembly) {
-                            Ok(handle) => Self::handle_to_state(handle),
-                            Err((assembly, str)) => EpochJitterState::Error(assembly, str),
-                        }
-                    }
-                }
+        use EpochJitterState::*;
 
-                Waiting(handle) | Running(handle) => {
-                    self.jitters[index] = Self::handle_to_state(handle);
-                }
+        self.jitters = std::mem::take(&mut self.jitters)
+            .into_iter()
+            .map(|state| match state {
+                Pending(assembly) if frame.can_run() => match frame.start_train_run(assembly) {
+                    Ok(handle) => Self::handle_to_state(handle),
+                    Err((assembly, str)) => Error(assembly, str),
+                },
 
-                _ => {}
-            }
-        }
+                Waiting(handle) | Running(handle) => Self::handle_to_state(handle),
+
+                state => state,
+            })
+            .collect();
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            done = self.jitters.iter().filter(|state| state.is_done()).count(),
+            total = self.jitters.len(),
+            "Polled candidate runs"
+        );
 
         self.all_done()
     }
@@ -472,32 +512,12 @@ where
         self.jitters.iter().all(|state| state.is_done())
     }
 
-    pub fn results<FrameType, H1>(
-        self,
-        frame: &mut FrameType,
-    ) -> Vec<Result<(AssemblyWnb, f32), String>>
-    where
-        FrameType: Frame<AssemblyType, ProdHandle = H1, TrainHandle = HandleType>,
-    {
+    pub fn results(self) -> Vec<Result<(AssemblyWnb, f32), String>> {
         self.jitters
             .into_iter()
             .map(|x| match x {
                 EpochJitterState::Done(assembly, fit) => Ok((AssemblyWnb::from(&assembly), fit)),
-                EpochJitterState::Error(assembly, err) => Err(err),
-                _ => unreachable!(),
-            })
-            .collect()
-    }
-
-    pub fn results_ok_only<FrameType, H1>(self, frame: &mut FrameType) -> Vec<(AssemblyType, f32)>
-    where
-        FrameType: Frame<AssemblyType, ProdHandle = H1, TrainHandle = HandleType>,
-    {
-        self.jitters
-            .into_iter()
-            .filter_map(|x| match x {
-                EpochJitterState::Done(assembly, fit) => Some((assembly, fit)),
-                EpochJitterState::Error(assembly, err) => None,
+                EpochJitterState::Error(_, err) => Err(err),
                 _ => unreachable!(),
             })
             .collect()
@@ -510,8 +530,17 @@ where
 {
     fn reset_training(&mut self) {
         self.curr_jitter_width = self.jitter_width;
+        self.reference_cache = None;
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "jitter_epoch",
+            skip_all,
+            fields(num_jitters = self.num_jitters, jitter_width = self.curr_jitter_width)
+        )
+    )]
     fn epoch<AssemblyType, FrameType, H1, H2>(
         &mut self,
         assembly: &mut AssemblyType,
@@ -533,12 +562,35 @@ where
         let mut state: EpochState<AssemblyType, H2> =
             EpochState::init(assembly, self.num_jitters, self.curr_jitter_width);
 
-        let results = state.results(frame);
+        #[cfg(feature = "tracing")]
+        let started = std::time::Instant::now();
 
-        let results = results
-            .into_iter()
-            .filter_map(|x| x.ok())
-            .collect::<Vec<_>>();
+        // Frame handles can only be polled, not waited on. Simple frames are
+        // done by the first poll; others are polled every POLL_INTERVAL until
+        // all of their runs are.
+        while !state.poll(frame) {
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        let results = state.results();
+
+        #[cfg(feature = "tracing")]
+        let elapsed = started.elapsed();
+
+        let results = results.into_iter();
+
+        #[cfg(feature = "tracing")]
+        let results = results.inspect(|result| {
+            if let Err(err) = result {
+                tracing::warn!(%err, "A candidate run failed");
+            }
+        });
+
+        let mut results = results.filter_map(|x| x.ok()).collect::<Vec<_>>();
+
+        if results.is_empty() {
+            return Err(format!("All {} candidate runs failed", self.num_jitters));
+        }
 
         let min_fitness = results
             .iter()
@@ -552,14 +604,33 @@ where
             .unwrap();
 
         let num_ok_jitters = if self.apply_bad_jitters {
-            self.num_jitters
+            results.len()
         } else {
             results
                 .iter()
-                .map(|x| if x.1 > 0.0 { 1_usize } else { 0_usize })
+                .map(|x| {
+                    if x.1 > reference_fitness {
+                        1_usize
+                    } else {
+                        0_usize
+                    }
+                })
                 .sum::<usize>()
         };
 
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            candidates = results.len(),
+            failed = self.num_jitters - results.len(),
+            applied = num_ok_jitters,
+            reference_fitness,
+            min_fitness,
+            max_fitness,
+            mean_fitness = results.iter().map(|x| x.1).sum::<f32>() / results.len() as f32,
+            ?elapsed,
+            "Evaluated candidates"
+        );
+
         let mut new_wnb: AssemblyWnb = reference_wnb.clone();
 
         if num_ok_jitters > 0 {
@@ -568,7 +639,7 @@ where
             // Normalize delta fitnesses and use them to weight jitter weights
             // and biases proportionately when applying them to the ref. net.
             for (wnbs, fitness) in &mut results {
-                if self.apply_bad_jitters || *fitness > 0.0 {
+                if self.apply_bad_jitters || *fitness > reference_fitness {
                     let fitness_scale = (*fitness - min_fitness)
                         / if max_fitness == min_fitness {
                             1.0
@@ -579,7 +650,7 @@ where
                         - 1.0;
 
                     wnbs.sub_from(&reference_wnb);
-                    wnbs.scale((fitness_scale * step_factor) as f32);
+                    wnbs.scale(fitness_scale * step_factor);
                     wnbs.add_to(&mut new_wnb);
                 }
             }
@@ -592,16 +663,13 @@ where
 
         self.curr_jitter_width *= 1.0 - self.jitter_width_falloff;
 
-        if self.adaptive_jitter_width.is_some() {
-            self.curr_jitter_width = self.adaptive_jitter_width.as_ref().unwrap()(
-                self.curr_jitter_width,
-                (max_fitness - reference_fitness) as f32,
-                (reference_fitness) as f32,
-            );
+        if let Some(adaptive_jitter_width) = &self.adaptive_jitter_width {
+            self.curr_jitter_width =
+                adaptive_jitter_width(self.curr_jitter_width, max_fitness, reference_fitness);
         }
 
         new_wnb.apply_to(assembly);
 
-        Ok(max_fitness + reference_fitness)
+        Ok(max_fitness)
     }
 }
@@ -2,6 +2,8 @@
  * Label-based supervised learning frame for the [SimpleFrame] interface.
  */
 use crate::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 
 /// A label that can be used by the [LabeledLearningFrame].
 pub trait TrainingLabel: Eq + Clone {
@@ -74,7 +76,41 @@ impl TrainingLabel for bool {
     }
 }
 
-type DistanceWrapper = fn(f32) -> f32;
+/// A metric applied to the difference between an output and its expected value.
+pub type DistanceWrapper = fn(f32) -> f32;
+
+/// Turns a list of raw network outputs into probabilities that sum up to 1,
+/// in place.
+pub fn softmax(values: &mut [f32]) {
+    let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+
+    for value in values.iter_mut() {
+        *value = (*value - max).exp();
+    }
+
+    let total: f32 = values.iter().sum();
+
+    for value in values.iter_mut() {
+        *value /= total;
+    }
+}
+
+/// How a [LabeledLearningFrame] scores a network's output against the expected
+/// label.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum LabelScoring {
+    /// Compares every output against its one-hot expected value using the
+    /// frame's distance metric, averaging the distances.
+    #[default]
+    Distance,
+
+    /// Applies [softmax] to the outputs and scores the log-probability of
+    /// the expected label (the negated cross-entropy, or log-loss).
+    ///
+    /// This tends to produce a much better-shaped fitness landscape than
+    /// [LabelScoring::Distance].
+    CrossEntropy,
+}
 
 /**
  * A [SimpleFrame] implementation which simulates supervised learning
@@ -99,6 +135,9 @@ where
     /// a training case matches the expected output as per the case's
     /// corresponding label.
     distance_wrapper: Box<DistanceWrapper>,
+
+    /// How outputs are scored against the expected labels.
+    pub scoring: LabelScoring,
 }
 
 impl<T> LabeledLearningFrame<T>
@@ -110,7 +149,7 @@ where
         cases_labels: Vec<T>,
         distance_wrapper: Option<Box<DistanceWrapper>>,
     ) -> Result<Self, String> {
-        if (cfg!(debug) || cfg!(tests)) && cases_inputs.len() != cases_labels.len() {
+        if cfg!(debug_assertions) && cases_inputs.len() != cases_labels.len() {
             return Err("".to_owned());
         }
 
@@ -124,9 +163,35 @@ where
             distance_wrapper: Box::from(
                 distance_wrapper.map_or(f32::abs as fn(f32) -> f32, |x| *x),
             ),
+
+            scoring: LabelScoring::default(),
         })
     }
 
+    /// Scores the outputs of a network for a single case; higher is better.
+    ///
+    /// May modify the outputs, e.g. to normalize them.
+    fn case_fitness(&self, outputs: &mut [f32], desired_idx: usize) -> f32 {
+        match self.scoring {
+            LabelScoring::Distance => {
+                -outputs
+                    .iter()
+                    .enumerate()
+                    .map(|iout| {
+                        let (i, out) = iout;
+                        (self.distance_wrapper)(*out - (if i == desired_idx { 1.0 } else { 0.0 }))
+                    })
+                    .sum::<f32>()
+                    / outputs.len() as f32
+            }
+
+            LabelScoring::CrossEntropy => {
+                softmax(outputs);
+                outputs[desired_idx].max(f32::MIN_POSITIVE).ln()
+            }
+        }
+    }
+
     pub fn find_label_for(&self, inputs: &[f32]) -> Option<&T> {
         for inp in &self.inputs {
             if inp.0 == inputs {
@@ -148,10 +213,43 @@ where
 }
 
 /// A classifier assembly.
+#[derive(Clone)]
 pub struct NeuralClassifier {
     pub classifier: SimpleNeuralNetwork,
 }
 
+impl NeuralClassifier {
+    /// Computes the probability of each label for an input, as per the
+    /// [softmax] of the network's outputs.
+    ///
+    /// The probabilities are indexed by [TrainingLabel::index].
+    pub fn predict_proba(&self, input: &[f32]) -> Result<Vec<f32>, String> {
+        let mut outputs = vec![0.0_f32; self.classifier.output_size()?];
+
+        self.classifier.compute_values(input, &mut outputs)?;
+        softmax(&mut outputs);
+
+        Ok(outputs)
+    }
+
+    /// Computes the most likely label for an input.
+    pub fn predict<LT: TrainingLabel>(&self, input: &[f32]) -> Result<LT, String> {
+        most_likely(&self.predict_proba(input)?)
+    }
+}
+
+/// Finds the label with the highest probability.
+pub(crate) fn most_likely<LT: TrainingLabel>(probabilities: &[f32]) -> Result<LT, String> {
+    let best = probabilities
+        .iter()
+        .enumerate()
+        .reduce(|best, curr| if curr.1 > best.1 { curr } else { best })
+        .ok_or_else(|| "This classifier has no outputs".to_owned())?
+        .0;
+
+    Ok(LT::from_index(best))
+}
+
 impl Assembly for NeuralClassifier {
     fn get_network_refs(&self) -> Vec<&SimpleNeuralNetwork> {
         vec![&self.classifier]
@@ -171,31 +269,40 @@ where
         assembly: NeuralClassifier,
     ) -> Result<(NeuralClassifier, Result<f32, String>), (NeuralClassifier, String)> {
         let mut fitness = 0.0_f32;
-        let mut outputs = vec![0.0_f32; T::num_labels()];
+
+        // Label types like usize have no meaningful label count; size outputs
+        // after the network itself.
+        let mut outputs = match assembly.classifier.output_size() {
+            Ok(size) => vec![0.0_f32; size],
+            Err(error_string) => return Err((assembly, error_string)),
+        };
 
         for (case, desired_label) in &self.inputs {
-            let desired_idx = desired_label.index() as usize;
+            let desired_idx = desired_label.index();
 
-            assembly
-                .classifier
-                .compute_values(case, &mut outputs)
-                .map_err(|error_string| (assembly, error_string))?;
+            if let Err(error_string) = assembly.classifier.compute_values(case, &mut outputs) {
+                return Err((assembly, error_string));
+            }
 
-            fitness -= outputs
-                .iter()
-                .enumerate()
-                .map(|iout| {
-                    let (i, out) = iout;
-                    (self.distance_wrapper)(
-                        *out as f32 - (if i == desired_idx { 1.0 } else { 0.0 }),
-                    )
-                })
-                .sum::<f32>()
-                / outputs.len() as f32;
+            fitness += self.case_fitness(&mut outputs, desired_idx);
         }
 
         Ok((assembly, Ok(fitness)))
     }
+
+    fn fitness_key(&self) -> Option<u64> {
+        let mut state = DefaultHasher::new();
+
+        for (case, label) in &self.inputs {
+            hash_floats(case, &mut state);
+            state.write_usize(label.index());
+        }
+
+        state.write_usize(*self.distance_wrapper as usize);
+        self.scoring.hash(&mut state);
+
+        Some(state.finish())
+    }
 }
 
 impl<LT> LabeledLearningFrame<LT>
@@ -207,24 +314,14 @@ where
         assembly: &mut NeuralClassifier,
     ) -> Result<f32, String> {
         let mut fitness = 0.0_f32;
-        let mut outputs = vec![0.0_f32; LT::num_labels()];
+        let mut outputs = vec![0.0_f32; assembly.classifier.output_size()?];
 
         for (case, desired_label) in &self.inputs {
-            let desired_idx = desired_label.index() as usize;
+            let desired_idx = desired_label.index();
 
             assembly.classifier.compute_values(case, &mut outputs)?;
 
-            fitness -= outputs
-                .iter()
-                .enumerate()
-                .map(|iout| {
-                    let (i, out) = iout;
-                    (self.distance_wrapper)(
-                        *out as f32 - (if i == desired_idx { 1.0 } else { 0.0 }),
-                    )
-                })
-                .sum::<f32>()
-                / outputs.len() as f32;
+            fitness += self.case_fitness(&mut outputs, desired_idx);
         }
 
         Ok(fitness)
@@ -4,14 +4,20 @@
  * Provides an interface for training strategies and rules,
  * as well as a simple implementation,
  */
+pub mod autoenc;
+pub mod calibrate;
 pub mod interface;
 pub mod jitterstrat;
 pub mod label;
+pub mod prune;
 pub mod trainer;
 
 pub mod prelude {
+    pub use super::autoenc::*;
+    pub use super::calibrate::*;
     pub use super::interface::*;
     pub use super::jitterstrat::*;
     pub use super::label::*;
+    pub use super::prune::*;
     pub use super::trainer::*;
 }
@@ -0,0 +1,358 @@
+/*!
+ * Magnitude-based pruning of network weights.
+ *
+ * Pruning zeroes the weights with the smallest magnitudes, which usually
+ * matter the least, making networks sparser (and thus easier to compress)
+ * at little cost in fitness. Biases are never pruned.
+ *
+ * Pruning a large fraction of the weights at once hurts fitness noticeably;
+ * [prune_and_finetune] instead prunes gradually, retraining the remaining
+ * weights through a [Trainer] after each step, and reports the trade-off
+ * between sparsity and fitness along the way. It trains a [PrunedAssembly],
+ * which keeps pruned weights at zero in every candidate a training strategy
+ * tries, so that fitness is always that of the pruned networks.
+ */
+use crate::prelude::*;
+
+/// Which weights are ranked against each other when pruning.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PruneScope {
+    /// The weights of all layers are ranked together, so some layers may end
+    /// up much sparser than others.
+    #[default]
+    Global,
+
+    /// Every layer is pruned by the same fraction.
+    PerLayer,
+}
+
+/// Which weights of a network are kept, layer by layer, in the same order as
+/// [NeuralLayer::weights].
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightMask {
+    /// Whether each weight of each layer is kept (true) or pruned (false).
+    pub layers: Vec<Vec<bool>>,
+}
+
+impl WeightMask {
+    /// Zeroes the pruned weights of a network of the same shape.
+    pub fn apply(&self, network: &mut SimpleNeuralNetwork) {
+        for (layer, mask) in network.layers.iter_mut().zip(&self.layers) {
+            for (weight, keep) in layer.weights.iter_mut().zip(mask) {
+                if !keep {
+                    *weight = 0.0;
+                }
+            }
+        }
+    }
+
+    /// The number of pruned weights.
+    pub fn num_pruned(&self) -> usize {
+        self.layers
+            .iter()
+            .map(|mask| mask.iter().filter(|keep| !**keep).count())
+            .sum()
+    }
+
+    /// The number of weights covered by this mask.
+    pub fn num_weights(&self) -> usize {
+        self.layers.iter().map(Vec::len).sum()
+    }
+
+    /// The fraction of pruned weights, from 0 to 1.
+    pub fn sparsity(&self) -> f32 {
+        match self.num_weights() {
+            0 => 0.0,
+            total => self.num_pruned() as f32 / total as f32,
+        }
+    }
+
+    /// Marks the weights pruned by another mask of the same shape as pruned
+    /// in this one too.
+    fn merge(&mut self, other: &WeightMask) {
+        for (mask, other) in self.layers.iter_mut().zip(&other.layers) {
+            for (keep, other_keep) in mask.iter_mut().zip(other) {
+                *keep &= other_keep;
+            }
+        }
+    }
+}
+
+/// Marks the `count` smallest-magnitude weights of a list as pruned.
+fn prune_smallest(weights: &[(usize, usize, f32)], count: usize, mask: &mut WeightMask) {
+    let mut ranked: Vec<&(usize, usize, f32)> = weights.iter().collect();
+    ranked.sort_by(|a, b| a.2.abs().total_cmp(&b.2.abs()));
+
+    for (layer, index, _) in ranked.into_iter().take(count) {
+        mask.layers[*layer][*index] = false;
+    }
+}
+
+/**
+ * Zeroes the smallest-magnitude `fraction` (from 0 to 1) of the weights of a
+ * network, returning a mask of the weights that were kept.
+ *
+ * Weights that are already zero count as the smallest, so pruning a network
+ * again with a larger fraction keeps previously pruned weights pruned.
+ */
+pub fn prune_network(
+    network: &mut SimpleNeuralNetwork,
+    fraction: f32,
+    scope: PruneScope,
+) -> Result<WeightMask, String> {
+    if !(0.0..=1.0).contains(&fraction) {
+        return Err(format!(
+            "The pruned fraction must be between 0 and 1, not {}",
+            fraction
+        ));
+    }
+
+    let mut mask = WeightMask {
+        layers: network
+            .layers
+            .iter()
+            .map(|layer| vec![true; layer.weights.len()])
+            .collect(),
+    };
+
+    let weights: Vec<Vec<(usize, usize, f32)>> = network
+        .layers
+        .iter()
+        .enumerate()
+        .map(|(layer_idx, layer)| {
+            layer
+                .weights
+                .iter()
+                .enumerate()
+                .map(|(idx, weight)| (layer_idx, idx, *weight))
+                .collect()
+        })
+        .collect();
+
+    match scope {
+        PruneScope::Global => {
+            let all: Vec<(usize, usize, f32)> = weights.into_iter().flatten().collect();
+            let count = (all.len() as f32 * fraction).round() as usize;
+
+            prune_smallest(&all, count, &mut mask);
+        }
+
+        PruneScope::PerLayer => {
+            for layer in &weights {
+                let count = (layer.len() as f32 * fraction).round() as usize;
+
+                prune_smallest(layer, count, &mut mask);
+            }
+        }
+    }
+
+    mask.apply(network);
+
+    Ok(mask)
+}
+
+/**
+ * An assembly whose networks are pruned by a [WeightMask] each, which is
+ * reapplied whenever a training strategy updates it (see
+ * [Assembly::after_update]). Every candidate a strategy evaluates is thus
+ * pruned as well.
+ *
+ * A [SimpleFrame] of the wrapped assembly can run it through [PrunedFrame].
+ */
+#[derive(Clone)]
+pub struct PrunedAssembly<A: Assembly> {
+    /// The wrapped assembly.
+    pub inner: A,
+
+    /// The mask of each network, in the order of [Assembly::get_network_refs].
+    masks: Vec<WeightMask>,
+}
+
+impl<A: Assembly> PrunedAssembly<A> {
+    /// Wraps an assembly, with none of its weights pruned yet.
+    pub fn new(inner: A) -> Self {
+        let masks = inner
+            .get_network_refs()
+            .into_iter()
+            .map(|network| WeightMask {
+                layers: network
+                    .layers
+                    .iter()
+                    .map(|layer| vec![true; layer.weights.len()])
+                    .collect(),
+            })
+            .collect();
+
+        Self { inner, masks }
+    }
+
+    /// The mask of each network.
+    pub fn masks(&self) -> &[WeightMask] {
+        &self.masks
+    }
+
+    /**
+     * Prunes the smallest-magnitude `fraction` of the weights of each
+     * network, like [prune_network]. Weights pruned before stay pruned.
+     */
+    pub fn prune(&mut self, fraction: f32, scope: PruneScope) -> Result<(), String> {
+        let pruned = self
+            .inner
+            .get_networks_mut()
+            .into_iter()
+            .map(|network| prune_network(network, fraction, scope))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (mask, pruned) in self.masks.iter_mut().zip(&pruned) {
+            mask.merge(pruned);
+        }
+
+        self.apply_masks();
+
+        Ok(())
+    }
+
+    /// Zeroes the pruned weights of every network.
+    pub fn apply_masks(&mut self) {
+        for (network, mask) in self.inner.get_networks_mut().into_iter().zip(&self.masks) {
+            mask.apply(network);
+        }
+    }
+
+    /// The fraction of pruned weights across all networks, from 0 to 1.
+    pub fn sparsity(&self) -> f32 {
+        let (pruned, total) = self.masks.iter().fold((0, 0), |(pruned, total), mask| {
+            (pruned + mask.num_pruned(), total + mask.num_weights())
+        });
+
+        if total == 0 {
+            0.0
+        } else {
+            pruned as f32 / total as f32
+        }
+    }
+}
+
+impl<A: Assembly> Assembly for PrunedAssembly<A> {
+    fn get_network_refs(&self) -> Vec<&SimpleNeuralNetwork> {
+        self.inner.get_network_refs()
+    }
+
+    fn get_networks_mut(&mut self) -> Vec<&mut SimpleNeuralNetwork> {
+        self.inner.get_networks_mut()
+    }
+
+    fn after_update(&mut self) {
+        self.inner.after_update();
+        self.apply_masks();
+    }
+}
+
+/// Runs the [PrunedAssembly] of its frame's assembly with that frame.
+pub struct PrunedFrame<F>(pub F);
+
+impl<A, F> SimpleFrame<PrunedAssembly<A>> for PrunedFrame<F>
+where
+    A: Assembly,
+    F: SimpleFrame<A>,
+{
+    fn run(
+        &mut self,
+        assembly: PrunedAssembly<A>,
+    ) -> Result<(PrunedAssembly<A>, Result<f32, String>), (PrunedAssembly<A>, String)> {
+        let PrunedAssembly { inner, masks } = assembly;
+
+        match self.0.run(inner) {
+            Ok((inner, result)) => Ok((PrunedAssembly { inner, masks }, result)),
+            Err((inner, err)) => Err((PrunedAssembly { inner, masks }, err)),
+        }
+    }
+
+    fn train_run(
+        &mut self,
+        assembly: PrunedAssembly<A>,
+    ) -> Result<(PrunedAssembly<A>, Result<f32, String>), (PrunedAssembly<A>, String)> {
+        let PrunedAssembly { inner, masks } = assembly;
+
+        match self.0.train_run(inner) {
+            Ok((inner, result)) => Ok((PrunedAssembly { inner, masks }, result)),
+            Err((inner, err)) => Err((PrunedAssembly { inner, masks }, err)),
+        }
+    }
+
+    fn fitness_key(&self) -> Option<u64> {
+        self.0.fitness_key()
+    }
+}
+
+/// Options for [prune_and_finetune].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PruneOptions {
+    /// The fraction of weights, from 0 to 1, pruned by the end of the last
+    /// cycle.
+    pub target_sparsity: f32,
+
+    /// In how many prune-then-retrain cycles to reach the target sparsity.
+    /// Each cycle prunes an equal share of it.
+    pub num_cycles: usize,
+
+    /// How many training epochs to run after each pruning step.
+    pub epochs_per_cycle: usize,
+
+    /// How the weights are ranked against each other.
+    pub scope: PruneScope,
+}
+
+/// The outcome of one cycle of [prune_and_finetune].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PruneCycle {
+    /// The fraction of weights, across all networks of the assembly, that
+    /// are pruned after this cycle.
+    pub sparsity: f32,
+
+    /// The best fitness of the last training epoch of this cycle.
+    pub fitness: f32,
+}
+
+/**
+ * Gradually prunes the networks of a trainer's assembly, retraining it after
+ * each step; pruned weights are kept at zero throughout, in the assembly and
+ * in every candidate the training strategy evaluates.
+ *
+ * Returns the sparsity and fitness after every cycle, to help pick a good
+ * trade-off between them.
+ */
+pub fn prune_and_finetune<AssemblyType, ATF, TS>(
+    trainer: &mut Trainer<PrunedAssembly<AssemblyType>, ATF, TS>,
+    options: &PruneOptions,
+) -> Result<Vec<PruneCycle>, String>
+where
+    AssemblyType: Assembly + Clone,
+    ATF: Frame<PrunedAssembly<AssemblyType>>,
+    TS: TrainingStrategy,
+{
+    if options.num_cycles == 0 || options.epochs_per_cycle == 0 {
+        return Err("Pruning needs at least one cycle of at least one epoch".to_owned());
+    }
+
+    let mut cycles = Vec::with_capacity(options.num_cycles);
+
+    for cycle in 1..=options.num_cycles {
+        let fraction = options.target_sparsity * cycle as f32 / options.num_cycles as f32;
+
+        trainer.reference_assembly.prune(fraction, options.scope)?;
+
+        let mut fitness = 0.0;
+
+        for _ in 0..options.epochs_per_cycle {
+            fitness = trainer.epoch()?;
+        }
+
+        cycles.push(PruneCycle {
+            sparsity: trainer.reference_assembly.sparsity(),
+            fitness,
+        });
+    }
+
+    Ok(cycles)
+}
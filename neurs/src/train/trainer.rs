@@ -47,7 +47,7 @@ where
         assembly: &'a mut AssemblyType,
         frame: ATF,
         strategy: TS,
-    ) -> Trainer<AssemblyType, ATF, TS> {
+    ) -> Trainer<'a, AssemblyType, ATF, TS> {
         Trainer {
             reference_assembly: assembly,
             frame,
@@ -60,8 +60,18 @@ where
      *
      * Should return the best fitness arising from this epoch.
      */
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "epoch", skip_all))]
     pub fn epoch(&mut self) -> Result<f32, String> {
-        self.strategy
-            .epoch(self.reference_assembly, &mut self.frame)
+        let result = self
+            .strategy
+            .epoch(self.reference_assembly, &mut self.frame);
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(fitness) => tracing::info!(fitness, "Epoch done"),
+            Err(err) => tracing::warn!(%err, "Epoch failed"),
+        }
+
+        result
     }
 }
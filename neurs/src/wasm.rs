@@ -0,0 +1,166 @@
+/*!
+ * WebAssembly bindings for neural network inference.
+ *
+ * Only available with the `wasm` feature. Meant to be built for the
+ * `wasm32-unknown-unknown` target with `wasm-bindgen`, so that networks can be
+ * evaluated from JavaScript, e.g. in browser demos.
+ */
+use crate::prelude::*;
+use wasm_bindgen::prelude::*;
+
+/// A [SimpleNeuralNetwork], as exposed to JavaScript.
+#[wasm_bindgen]
+pub struct WasmNeuralNetwork {
+    network: SimpleNeuralNetwork,
+}
+
+#[wasm_bindgen]
+impl WasmNeuralNetwork {
+    /// Constructs a randomly initialized network from layer sizes, using the
+    /// same activation for every layer, by name (see [activations::from_name]).
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        layer_sizes: &[usize],
+        activation: Option<String>,
+    ) -> Result<WasmNeuralNetwork, JsValue> {
+        Self::try_new(layer_sizes, activation).map_err(js_error)
+    }
+
+    /// The number of layers in this network.
+    #[wasm_bindgen(getter)]
+    pub fn num_layers(&self) -> usize {
+        self.network.layers.len()
+    }
+
+    /// Overwrites the weights and biases of a layer, e.g. to load a trained
+    /// network.
+    pub fn set_layer(
+        &mut self,
+        index: usize,
+        weights: &[f32],
+        biases: &[f32],
+    ) -> Result<(), JsValue> {
+        self.try_set_layer(index, weights, biases).map_err(js_error)
+    }
+
+    /// Computes the outputs of this network for a list of inputs, which must
+    /// be as many as this network's input size.
+    pub fn compute(&self, inputs: &[f32]) -> Result<Vec<f32>, JsValue> {
+        self.try_compute(inputs).map_err(js_error)
+    }
+}
+
+/// The Rust side of the [WasmNeuralNetwork] methods, which only builds a
+/// [JsValue] out of their errors.
+impl WasmNeuralNetwork {
+    fn try_new(layer_sizes: &[usize], activation: Option<String>) -> Result<Self, String> {
+        if layer_sizes.len() < 2 {
+            return Err("A network needs at least an input and an output size".to_owned());
+        }
+
+        let activation = match activation {
+            Some(name) => Some(
+                activations::from_name(&name)
+                    .ok_or_else(|| format!("Unknown activation function {:?}", name))?,
+            ),
+
+            None => None,
+        };
+
+        Ok(WasmNeuralNetwork {
+            network: SimpleNeuralNetwork::new_simple_with_activation(layer_sizes, activation),
+        })
+    }
+
+    fn try_set_layer(
+        &mut self,
+        index: usize,
+        weights: &[f32],
+        biases: &[f32],
+    ) -> Result<(), String> {
+        let layer = self
+            .network
+            .layers
+            .get_mut(index)
+            .ok_or_else(|| "Layer index out of bounds".to_owned())?;
+
+        if weights.len() != layer.weights.len() || biases.len() != layer.biases.len() {
+            return Err(
+                "The given weights and biases do not match the shape of this layer".to_owned(),
+            );
+        }
+
+        layer.weights.copy_from_slice(weights);
+        layer.biases.copy_from_slice(biases);
+
+        Ok(())
+    }
+
+    fn try_compute(&self, inputs: &[f32]) -> Result<Vec<f32>, String> {
+        let input_size = self.network.input_size()?;
+
+        if inputs.len() != input_size {
+            return Err(format!(
+                "Expected {} inputs, got {}",
+                input_size,
+                inputs.len()
+            ));
+        }
+
+        let mut outputs = vec![0.0_f32; self.network.output_size()?];
+
+        self.network.compute_values(inputs, &mut outputs)?;
+
+        Ok(outputs)
+    }
+}
+
+/// Turns an error message into a JavaScript string.
+fn js_error(message: String) -> JsValue {
+    JsValue::from_str(&message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Building a [JsValue] only works on wasm32, so these test the Rust side
+    // of each method instead.
+
+    fn network() -> WasmNeuralNetwork {
+        let mut network = WasmNeuralNetwork::try_new(&[2, 1], Some("identity".to_owned())).unwrap();
+
+        network.try_set_layer(0, &[1.0, 2.0], &[0.5]).unwrap();
+
+        network
+    }
+
+    #[test]
+    fn compute_outputs() {
+        assert_eq!(network().try_compute(&[1.0, 1.0]).unwrap(), [3.5]);
+    }
+
+    #[test]
+    fn compute_rejects_wrong_input_size() {
+        let network = network();
+
+        assert!(network.try_compute(&[1.0]).is_err());
+        assert!(network.try_compute(&[1.0, 1.0, 1.0]).is_err());
+    }
+
+    #[test]
+    fn new_rejects_bad_arguments() {
+        assert!(WasmNeuralNetwork::try_new(&[2], None).is_err());
+        assert!(WasmNeuralNetwork::try_new(&[2, 1], Some("nonexistent".to_owned())).is_err());
+    }
+
+    #[test]
+    fn set_layer_rejects_bad_shapes() {
+        let mut network = network();
+
+        assert!(network.try_set_layer(1, &[1.0, 2.0], &[0.5]).is_err());
+        assert!(network.try_set_layer(0, &[1.0], &[0.5]).is_err());
+        assert!(network.try_set_layer(0, &[1.0, 2.0], &[]).is_err());
+        assert_eq!(network.num_layers(), 1);
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+    use neurs::prelude::*;
+
+    #[test]
+    fn attention_averages_matching_values() {
+        // The first query matches the first key much better than the second.
+        let queries = [10.0, 0.0, 0.0, 0.0];
+        let keys = [1.0, 0.0, 0.0, 1.0];
+        let values = [1.0, 2.0, 3.0, 4.0];
+        let mut outputs = [0.0; 4];
+
+        dot_product_attention(&queries, &keys, &values, 2, 2, None, &mut outputs).unwrap();
+
+        assert!((outputs[0] - 1.0).abs() < 0.01);
+        assert!((outputs[1] - 2.0).abs() < 0.01);
+
+        // The second query matches both keys equally.
+        assert_float_eq!(outputs[2], 2.0, abs <= 1e-5);
+        assert_float_eq!(outputs[3], 3.0, abs <= 1e-5);
+    }
+
+    #[test]
+    fn attention_respects_mask() {
+        let queries = [0.0, 0.0, 0.0, 0.0];
+        let keys = [1.0, 0.0, 0.0, 1.0];
+        let values = [1.0, 2.0, 3.0, 4.0];
+        let mut outputs = [0.0; 4];
+
+        let mask = causal_mask(2);
+        assert_eq!(mask, [true, false, true, true]);
+
+        dot_product_attention(&queries, &keys, &values, 2, 2, Some(&mask), &mut outputs).unwrap();
+
+        // The first position can only see itself.
+        assert_eq!(outputs[..2], [1.0, 2.0]);
+        assert_float_eq!(outputs[2], 2.0, abs <= 1e-5);
+    }
+
+    #[test]
+    fn attention_layer_maps_sequences() {
+        let attention = Attention::new(3, 4, 2);
+        let mut outputs = [0.0; 10];
+
+        attention
+            .compute_sequence(&[0.5; 15], None, &mut outputs)
+            .unwrap();
+
+        assert_eq!(attention.get_network_refs().len(), 3);
+        assert!(attention
+            .compute_sequence(&[0.5; 14], None, &mut outputs)
+            .is_err());
+    }
+}
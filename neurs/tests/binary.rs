@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod tests {
+    use neurs::prelude::*;
+
+    fn network() -> SimpleNeuralNetwork {
+        SimpleNeuralNetwork::new_simple(
+            &[3, 4, 2],
+            &[Some(activations::fast_sigmoid), Some(activations::identity)],
+        )
+    }
+
+    /// The CRC-32 checksum the format ends with.
+    fn crc32(bytes: &[u8]) -> u32 {
+        let mut crc = !0_u32;
+
+        for byte in bytes {
+            crc ^= *byte as u32;
+
+            for _ in 0..8 {
+                crc = (crc >> 1) ^ (0xEDB8_8320 & (!(crc & 1)).wrapping_add(1));
+            }
+        }
+
+        !crc
+    }
+
+    /// Builds a single network file out of layers, given as their input and
+    /// output sizes, with identity activations and zero weights and biases.
+    fn network_file(layers: &[(u32, u32)]) -> Vec<u8> {
+        let mut bytes = BINARY_MAGIC.to_vec();
+        bytes.extend(BINARY_FORMAT_VERSION.to_le_bytes());
+        bytes.push(0);
+        bytes.extend((layers.len() as u32).to_le_bytes());
+
+        for (input_size, output_size) in layers {
+            bytes.extend(input_size.to_le_bytes());
+            bytes.extend(output_size.to_le_bytes());
+
+            bytes.push(b"identity".len() as u8);
+            bytes.extend(b"identity");
+
+            let num_values = (input_size * output_size + output_size) as usize;
+            bytes.resize(bytes.len() + num_values * 4, 0);
+        }
+
+        bytes.extend(crc32(&bytes).to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let network = network();
+        let loaded = SimpleNeuralNetwork::from_binary(&network.to_binary().unwrap()).unwrap();
+
+        assert_eq!(diff(&network, &loaded).unwrap().linf(), 0.0);
+        assert_eq!(
+            activations::name_of(*loaded.layers[0].activation),
+            Some("fast_sigmoid")
+        );
+    }
+
+    #[test]
+    fn binary_detects_corruption() {
+        let mut bytes = network().to_binary().unwrap();
+
+        let truncated = &bytes[..bytes.len() - 7];
+        assert!(SimpleNeuralNetwork::from_binary(truncated).is_err());
+
+        bytes[20] ^= 0x10;
+        assert!(SimpleNeuralNetwork::from_binary(&bytes).is_err());
+    }
+
+    #[test]
+    fn binary_rejects_newer_versions() {
+        let mut bytes = network().to_binary().unwrap();
+        bytes[4..6].copy_from_slice(&(BINARY_FORMAT_VERSION + 1).to_le_bytes());
+
+        match SimpleNeuralNetwork::from_binary(&bytes) {
+            Ok(_) => panic!("A file from a newer version was loaded"),
+            Err(err) => assert!(err.contains("version")),
+        }
+    }
+
+    #[test]
+    fn binary_assembly_round_trip() {
+        let assembly = AutoencoderAssembly {
+            encoder: SimpleNeuralNetwork::new_simple_with_activation(&[4, 2], None),
+            decoder: SimpleNeuralNetwork::new_simple_with_activation(&[2, 4], None),
+        };
+
+        let mut other = AutoencoderAssembly {
+            encoder: SimpleNeuralNetwork::new_simple_with_activation(&[4, 2], None),
+            decoder: SimpleNeuralNetwork::new_simple_with_activation(&[2, 4], None),
+        };
+
+        let bytes = assembly_to_binary(&assembly).unwrap();
+        assert!(SimpleNeuralNetwork::from_binary(&bytes).is_err());
+
+        assembly_from_binary(&mut other, &bytes).unwrap();
+        assert_eq!(diff(&assembly.encoder, &other.encoder).unwrap().linf(), 0.0);
+        assert_eq!(diff(&assembly.decoder, &other.decoder).unwrap().linf(), 0.0);
+    }
+
+    #[test]
+    fn binary_rejects_unfit_layers() {
+        let network = SimpleNeuralNetwork::from_binary(&network_file(&[(3, 4), (4, 2)])).unwrap();
+        assert_eq!(network.input_size().unwrap(), 3);
+        assert_eq!(network.output_size().unwrap(), 2);
+
+        assert!(SimpleNeuralNetwork::from_binary(&network_file(&[])).is_err());
+
+        match SimpleNeuralNetwork::from_binary(&network_file(&[(3, 4), (5, 2)])) {
+            Ok(_) => panic!("A network whose layers do not chain was loaded"),
+            Err(err) => assert!(err.contains("layer 1 takes 5 inputs")),
+        }
+    }
+}
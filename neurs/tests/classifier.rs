@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+    use neurs::prelude::*;
+
+    fn fixed_classifier() -> NeuralClassifier {
+        let mut net = SimpleNeuralNetwork::new_simple(&[2, 2], &[Some(activations::identity)]);
+
+        // Output 0 follows the first input, output 1 follows the second.
+        net.layers[0].weights.copy_from_slice(&[1.0, 0.0, 0.0, 1.0]);
+        net.layers[0].biases.copy_from_slice(&[0.0, 0.0]);
+
+        NeuralClassifier { classifier: net }
+    }
+
+    #[test]
+    fn predict_picks_highest_output() {
+        let classifier = fixed_classifier();
+
+        assert!(!classifier.predict::<bool>(&[3.0, 1.0]).unwrap());
+        assert!(classifier.predict::<bool>(&[1.0, 3.0]).unwrap());
+        assert_eq!(classifier.predict::<usize>(&[1.0, 3.0]).unwrap(), 1);
+    }
+
+    #[test]
+    fn predict_proba_sums_to_one() {
+        let classifier = fixed_classifier();
+
+        let proba = classifier.predict_proba(&[2.0, 2.0]).unwrap();
+
+        assert_eq!(proba.len(), 2);
+        assert_float_eq!(proba[0], 0.5, abs <= f32::EPSILON);
+        assert_float_eq!(proba.iter().sum::<f32>(), 1.0, abs <= f32::EPSILON);
+    }
+
+    #[test]
+    fn temperature_scaling_softens_overconfident_outputs() {
+        let classifier = fixed_classifier();
+
+        // The classifier is almost certain of every input, but is only right
+        // three times out of four.
+        let inputs = vec![
+            vec![10.0, 0.0],
+            vec![10.0, 0.0],
+            vec![10.0, 0.0],
+            vec![10.0, 0.0],
+            vec![0.0, 10.0],
+            vec![0.0, 10.0],
+            vec![0.0, 10.0],
+            vec![0.0, 10.0],
+        ];
+        let labels = vec![0_usize, 0, 0, 1, 1, 1, 1, 0];
+
+        let scaling = TemperatureScaling::fit(&classifier, &inputs, &labels).unwrap();
+        assert_float_eq!(scaling.temperature, 10.0 / 3.0_f32.ln(), abs <= 0.01);
+
+        let proba = scaling.predict_proba(&classifier, &[10.0, 0.0]).unwrap();
+        assert_float_eq!(proba[0], 0.75, abs <= 1e-3);
+        assert_eq!(
+            scaling.predict::<usize>(&classifier, &[10.0, 0.0]).unwrap(),
+            0
+        );
+    }
+}
@@ -0,0 +1,36 @@
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+    use neurs::prelude::*;
+
+    #[test]
+    fn diff_finds_changed_neuron() {
+        let before =
+            SimpleNeuralNetwork::new_simple_with_activation(&[3, 4, 2], Some(activations::relu));
+        let mut after = before.clone();
+
+        after.layers[1].weights[3] += 3.0;
+        after.layers[1].biases[1] -= 4.0;
+
+        let changes = diff(&before, &after).unwrap();
+
+        assert_eq!(changes.layers.len(), 2);
+        assert_float_eq!(changes.layers[0].weights_l2, 0.0, abs <= f32::EPSILON);
+        assert_float_eq!(changes.layers[1].weights_linf, 3.0, abs <= 1e-5);
+        assert_float_eq!(changes.l2(), 5.0, abs <= 1e-5);
+
+        // Weight 3 is the last input weight of neuron 0; bias 1 belongs to
+        // neuron 1.
+        let most_changed = changes.most_changed_neurons(2);
+        assert_eq!((most_changed[0].0, most_changed[0].1), (1, 1));
+        assert_eq!((most_changed[1].0, most_changed[1].1), (1, 0));
+    }
+
+    #[test]
+    fn diff_rejects_mismatched_shapes() {
+        let a = SimpleNeuralNetwork::new_simple_with_activation(&[3, 4, 2], None);
+        let b = SimpleNeuralNetwork::new_simple_with_activation(&[3, 5, 2], None);
+
+        assert!(diff(&a, &b).is_err());
+    }
+}
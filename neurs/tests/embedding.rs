@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+    use neurs::prelude::*;
+
+    #[test]
+    fn embedding_looks_up_rows() {
+        let mut embedding = Embedding::new(4, 3);
+        embedding.table.layers[0].weights[6..9].copy_from_slice(&[1.0, 2.0, 3.0]);
+
+        assert_eq!(embedding.vocab_size().unwrap(), 4);
+        assert_eq!(embedding.dim().unwrap(), 3);
+        assert_eq!(embedding.lookup(2).unwrap(), [1.0, 2.0, 3.0]);
+        assert!(embedding.lookup(4).is_err());
+
+        let mut outputs = [0.0; 6];
+        embedding.embed(&[2, 2], &mut outputs).unwrap();
+        assert_eq!(outputs, [1.0, 2.0, 3.0, 1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn embedded_network_matches_manual_embedding() {
+        let network = EmbeddedNetwork {
+            embedding: Embedding::new(10, 2),
+            network: SimpleNeuralNetwork::new_simple_with_activation(
+                &[6, 4, 1],
+                Some(activations::fast_sigmoid),
+            ),
+        };
+
+        assert_eq!(network.num_tokens().unwrap(), 3);
+
+        let mut embedded = [0.0; 6];
+        network.embedding.embed(&[7, 0, 3], &mut embedded).unwrap();
+
+        let (mut expected, mut actual) = ([0.0], [0.0]);
+        network
+            .network
+            .compute_values(&embedded, &mut expected)
+            .unwrap();
+        network.compute_tokens(&[7, 0, 3], &mut actual).unwrap();
+
+        assert_float_eq!(actual[0], expected[0], abs <= f32::EPSILON);
+        assert!(network.compute_tokens(&[7, 0], &mut actual).is_err());
+    }
+}
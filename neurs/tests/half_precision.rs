@@ -0,0 +1,26 @@
+#[cfg(all(test, feature = "half"))]
+mod tests {
+    use neurs::prelude::*;
+
+    #[test]
+    fn half_network_matches_full_network() {
+        let network = SimpleNeuralNetwork::new_simple_with_activation(
+            &[4, 6, 2],
+            Some(activations::fast_sigmoid),
+        );
+        let half = HalfNeuralNetwork::from(&network);
+
+        let inputs = [0.5, -1.0, 0.25, 2.0];
+        let (mut expected, mut actual) = ([0.0; 2], [0.0; 2]);
+
+        network.compute_values(&inputs, &mut expected).unwrap();
+        half.compute_values(&inputs, &mut actual).unwrap();
+
+        for (e, a) in expected.iter().zip(&actual) {
+            assert!((e - a).abs() < 1e-2);
+        }
+
+        let back = SimpleNeuralNetwork::from(&half);
+        assert!(diff(&network, &back).unwrap().linf() < 1e-2);
+    }
+}
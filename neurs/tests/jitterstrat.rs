@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod tests {
+    use neurs::prelude::*;
+
+    /// A frame which scores a classifier by the sum of its weights, times a
+    /// scale, and counts its runs.
+    struct CountingFrame {
+        runs: usize,
+        scale: f32,
+
+        /// How many runs succeed before every other one fails.
+        fail_after: Option<usize>,
+
+        /// The [SimpleFrame::fitness_key] of this frame.
+        key: Option<u64>,
+    }
+
+    impl CountingFrame {
+        fn new() -> Self {
+            CountingFrame {
+                runs: 0,
+                scale: 1.0,
+                fail_after: None,
+                key: None,
+            }
+        }
+    }
+
+    impl SimpleFrame<NeuralClassifier> for CountingFrame {
+        fn run(
+            &mut self,
+            assembly: NeuralClassifier,
+        ) -> Result<(NeuralClassifier, Result<f32, String>), (NeuralClassifier, String)> {
+            self.runs += 1;
+
+            if self.fail_after.is_some_and(|after| self.runs > after) {
+                return Ok((assembly, Err("Failed on purpose".to_owned())));
+            }
+
+            let fitness = assembly.classifier.layers[0].weights.iter().sum::<f32>() * self.scale;
+
+            Ok((assembly, Ok(fitness)))
+        }
+
+        fn fitness_key(&self) -> Option<u64> {
+            self.key
+        }
+    }
+
+    fn classifier() -> NeuralClassifier {
+        NeuralClassifier {
+            classifier: SimpleNeuralNetwork::new_simple(&[2, 2], &[Some(activations::identity)]),
+        }
+    }
+
+    fn strategy() -> WeightJitterStrat<fn(f32, f32, f32) -> f32> {
+        WeightJitterStrat::new(WeightJitterStratOptions {
+            apply_bad_jitters: false,
+            num_jitters: 4,
+            jitter_width: 0.1,
+            adaptive_jitter_width: None,
+            jitter_width_falloff: 0.0,
+            step_factor: 0.5,
+            num_steps_per_epoch: 1,
+        })
+    }
+
+    #[test]
+    fn epoch_runs_every_candidate() {
+        let mut frame = CountingFrame::new();
+
+        strategy().epoch(&mut classifier(), &mut frame).unwrap();
+
+        // The reference, then each of the 4 candidates.
+        assert_eq!(frame.runs, 5);
+    }
+
+    #[test]
+    fn epoch_fails_if_every_candidate_fails() {
+        let mut frame = CountingFrame::new();
+        frame.fail_after = Some(1);
+
+        let mut classifier = classifier();
+        let weights = classifier.classifier.layers[0].weights.clone();
+
+        assert!(strategy().epoch(&mut classifier, &mut frame).is_err());
+        assert_eq!(classifier.classifier.layers[0].weights, weights);
+    }
+
+    /// A deterministic frame under which no candidate ever beats the
+    /// reference, so that it stays unchanged between epochs.
+    fn plateau_frame() -> CountingFrame {
+        let mut frame = CountingFrame::new();
+        frame.scale = 0.0;
+        frame.key = Some(1);
+        frame
+    }
+
+    #[test]
+    fn reference_cached_while_unchanged() {
+        let mut frame = plateau_frame();
+        let mut classifier = classifier();
+        let mut strategy = strategy();
+
+        strategy.epoch(&mut classifier, &mut frame).unwrap();
+        strategy.epoch(&mut classifier, &mut frame).unwrap();
+
+        // The reference is only evaluated in the first epoch.
+        assert_eq!(frame.runs, 5 + 4);
+    }
+
+    #[test]
+    fn reference_cache_invalidated_by_frame_change() {
+        let mut frame = plateau_frame();
+        let mut classifier = classifier();
+        let mut strategy = strategy();
+
+        strategy.epoch(&mut classifier, &mut frame).unwrap();
+
+        frame.key = Some(2);
+        strategy.epoch(&mut classifier, &mut frame).unwrap();
+
+        assert_eq!(frame.runs, 5 + 5);
+    }
+
+    #[test]
+    fn reference_not_cached_without_key() {
+        let mut frame = plateau_frame();
+        frame.key = None;
+
+        let mut classifier = classifier();
+        let mut strategy = strategy();
+
+        strategy.epoch(&mut classifier, &mut frame).unwrap();
+        strategy.epoch(&mut classifier, &mut frame).unwrap();
+
+        assert_eq!(frame.runs, 5 + 5);
+    }
+
+    #[test]
+    fn labeled_frame_key_follows_settings() {
+        let mut frame =
+            LabeledLearningFrame::new(vec![vec![0.0, 1.0]], vec![1_usize], None).unwrap();
+        let key = SimpleFrame::<NeuralClassifier>::fitness_key(&frame);
+
+        assert!(key.is_some());
+
+        frame.scoring = LabelScoring::CrossEntropy;
+        assert_ne!(SimpleFrame::<NeuralClassifier>::fitness_key(&frame), key);
+    }
+}
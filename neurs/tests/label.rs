@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+    use neurs::prelude::*;
+
+    fn fixed_classifier() -> NeuralClassifier {
+        let mut net = SimpleNeuralNetwork::new_simple(&[2, 2], &[Some(activations::identity)]);
+
+        // Output 0 follows the first input, output 1 follows the second.
+        net.layers[0].weights.copy_from_slice(&[1.0, 0.0, 0.0, 1.0]);
+        net.layers[0].biases.copy_from_slice(&[0.0, 0.0]);
+
+        NeuralClassifier { classifier: net }
+    }
+
+    #[test]
+    fn softmax_sums_to_one() {
+        let mut values = [1000.0, 1000.0, f32::NEG_INFINITY];
+        softmax(&mut values);
+
+        assert_eq!(values, [0.5, 0.5, 0.0]);
+
+        let mut values = [0.0, 1.0];
+        softmax(&mut values);
+
+        assert_float_eq!(
+            values[1],
+            1.0 / (1.0 + (-1.0_f32).exp()),
+            abs <= f32::EPSILON
+        );
+        assert_float_eq!(values.iter().sum::<f32>(), 1.0, abs <= f32::EPSILON);
+    }
+
+    #[test]
+    fn labeled_frame_scoring() {
+        let mut frame =
+            LabeledLearningFrame::new(vec![vec![0.0, 1.0]], vec![1_usize], None).unwrap();
+
+        // the outputs are exactly the expected one-hot vector
+        let (_, fitness) = frame.run(fixed_classifier()).ok().unwrap();
+        assert_eq!(fitness, Ok(0.0));
+
+        // but still not certain, as probabilities
+        frame.scoring = LabelScoring::CrossEntropy;
+
+        let (_, fitness) = frame.run(fixed_classifier()).ok().unwrap();
+        let expected = 1.0 - (1.0 + 1.0_f32.exp()).ln();
+
+        assert_float_eq!(fitness.unwrap(), expected, abs <= 1e-6);
+
+        // a confident, wrong guess is penalized much more
+        let mut frame =
+            LabeledLearningFrame::new(vec![vec![20.0, 0.0]], vec![1_usize], None).unwrap();
+        frame.scoring = LabelScoring::CrossEntropy;
+
+        let (_, fitness) = frame.run(fixed_classifier()).ok().unwrap();
+        assert!(fitness.unwrap() < -19.0);
+    }
+}
@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+    use neurs::prelude::*;
+
+    fn network() -> SimpleNeuralNetwork {
+        let mut net =
+            SimpleNeuralNetwork::new_simple_with_activation(&[2, 2, 1], Some(activations::relu));
+
+        net.layers[0]
+            .weights
+            .copy_from_slice(&[0.1, -4.0, 3.0, -0.2]);
+        net.layers[1].weights.copy_from_slice(&[0.5, -1.0]);
+
+        net
+    }
+
+    #[test]
+    fn prune_globally() {
+        let mut net = network();
+
+        let mask = prune_network(&mut net, 0.5, PruneScope::Global).unwrap();
+
+        assert_eq!(net.layers[0].weights, [0.0, -4.0, 3.0, 0.0]);
+        assert_eq!(net.layers[1].weights, [0.0, -1.0]);
+        assert_float_eq!(mask.sparsity(), 0.5, abs <= f32::EPSILON);
+    }
+
+    #[test]
+    fn prune_per_layer() {
+        let mut net = network();
+
+        let mask = prune_network(&mut net, 0.5, PruneScope::PerLayer).unwrap();
+
+        assert_eq!(net.layers[0].weights, [0.0, -4.0, 3.0, 0.0]);
+        assert_eq!(net.layers[1].weights, [0.0, -1.0]);
+        assert_eq!(mask.num_pruned(), 3);
+    }
+
+    #[test]
+    fn prune_mask_reapplies() {
+        let mut net = network();
+
+        let mask = prune_network(&mut net, 0.25, PruneScope::PerLayer).unwrap();
+        assert_eq!(net.layers[0].weights, [0.0, -4.0, 3.0, -0.2]);
+
+        net.layers[0].weights[0] = 1.0;
+        mask.apply(&mut net);
+        assert_eq!(net.layers[0].weights[0], 0.0);
+    }
+
+    #[test]
+    fn prune_rejects_bad_fraction() {
+        assert!(prune_network(&mut network(), 1.5, PruneScope::Global).is_err());
+    }
+
+    /// The weights [network] loses when half of them are pruned globally,
+    /// by layer and index.
+    const PRUNED: [(usize, usize); 3] = [(0, 0), (0, 3), (1, 0)];
+
+    /// A frame which scores a classifier by the sum of its weights, and
+    /// counts the runs on which any weight in [PRUNED] was not zero.
+    struct WeightSumFrame {
+        runs: usize,
+        unpruned_runs: usize,
+    }
+
+    impl SimpleFrame<NeuralClassifier> for WeightSumFrame {
+        fn run(
+            &mut self,
+            assembly: NeuralClassifier,
+        ) -> Result<(NeuralClassifier, Result<f32, String>), (NeuralClassifier, String)> {
+            let layers = &assembly.classifier.layers;
+
+            self.runs += 1;
+
+            if PRUNED
+                .iter()
+                .any(|&(layer, index)| layers[layer].weights[index] != 0.0)
+            {
+                self.unpruned_runs += 1;
+            }
+
+            let fitness = layers.iter().flat_map(|layer| &layer.weights).sum();
+
+            Ok((assembly, Ok(fitness)))
+        }
+    }
+
+    #[test]
+    fn prune_and_finetune_keeps_weights_pruned() {
+        let mut assembly = PrunedAssembly::new(NeuralClassifier {
+            classifier: network(),
+        });
+
+        let frame = PrunedFrame(WeightSumFrame {
+            runs: 0,
+            unpruned_runs: 0,
+        });
+
+        let strategy = WeightJitterStrat::new(WeightJitterStratOptions {
+            apply_bad_jitters: false,
+            num_jitters: 4,
+            jitter_width: 0.5,
+            adaptive_jitter_width: None::<fn(f32, f32, f32) -> f32>,
+            jitter_width_falloff: 0.0,
+            step_factor: 1.0,
+            num_steps_per_epoch: 1,
+        });
+
+        let mut trainer = Trainer::new(&mut assembly, frame, strategy);
+
+        let cycles = prune_and_finetune(
+            &mut trainer,
+            &PruneOptions {
+                target_sparsity: 0.5,
+                num_cycles: 1,
+                epochs_per_cycle: 5,
+                scope: PruneScope::Global,
+            },
+        )
+        .unwrap();
+
+        // Every candidate was evaluated with its pruned weights at zero,
+        // even though growing them would have raised its fitness.
+        assert!(trainer.frame.0.runs > 0);
+        assert_eq!(trainer.frame.0.unpruned_runs, 0);
+
+        assert_eq!(cycles.len(), 1);
+        assert_float_eq!(cycles[0].sparsity, 0.5, abs <= f32::EPSILON);
+
+        let layers = &assembly.inner.classifier.layers;
+
+        for (layer, index) in PRUNED {
+            assert_eq!(layers[layer].weights[index], 0.0);
+        }
+    }
+}
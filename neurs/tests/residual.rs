@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+    use neurs::prelude::*;
+
+    #[test]
+    fn residual_block_adds_input() {
+        let mut inner = SimpleNeuralNetwork::new_simple(&[2, 2], &[Some(activations::identity)]);
+        inner.layers[0]
+            .weights
+            .copy_from_slice(&[0.0, 1.0, 1.0, 0.0]);
+        inner.layers[0].biases.copy_from_slice(&[0.5, 0.0]);
+
+        let block = ResidualBlock::new(inner).unwrap();
+        let mut outputs = [0.0; 2];
+
+        block.compute_values(&[1.0, 2.0], &mut outputs).unwrap();
+
+        assert_float_eq!(outputs[0], 3.5, abs <= f32::EPSILON);
+        assert_float_eq!(outputs[1], 3.0, abs <= f32::EPSILON);
+    }
+
+    #[test]
+    fn residual_block_needs_matching_sizes() {
+        let inner = SimpleNeuralNetwork::new_simple_with_activation(&[3, 2], None);
+
+        assert!(ResidualBlock::new(inner).is_err());
+    }
+
+    #[test]
+    fn block_network_chains_blocks() {
+        let network = BlockNetwork::new(vec![
+            Block::Plain(SimpleNeuralNetwork::new_simple_with_activation(
+                &[3, 4],
+                None,
+            )),
+            Block::Residual(
+                ResidualBlock::new(SimpleNeuralNetwork::new_simple_with_activation(
+                    &[4, 8, 4],
+                    None,
+                ))
+                .unwrap(),
+            ),
+            Block::Plain(SimpleNeuralNetwork::new_simple_with_activation(
+                &[4, 2],
+                None,
+            )),
+        ])
+        .unwrap();
+
+        assert_eq!(network.get_network_refs().len(), 3);
+        assert_eq!(network.output_size().unwrap(), 2);
+
+        let mut outputs = [0.0; 2];
+        network
+            .compute_values(&[1.0, -1.0, 0.5], &mut outputs)
+            .unwrap();
+
+        assert!(BlockNetwork::new(vec![
+            Block::Plain(SimpleNeuralNetwork::new_simple_with_activation(
+                &[3, 4],
+                None
+            )),
+            Block::Plain(SimpleNeuralNetwork::new_simple_with_activation(
+                &[5, 2],
+                None
+            )),
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,40 @@
+#[cfg(all(test, feature = "safetensors"))]
+mod tests {
+    use neurs::prelude::*;
+
+    #[test]
+    fn safetensors_round_trip() {
+        let network = SimpleNeuralNetwork::new_simple(
+            &[3, 4, 2],
+            &[Some(activations::fast_sigmoid), Some(activations::identity)],
+        );
+
+        let bytes = network.to_safetensors().unwrap();
+        let loaded = SimpleNeuralNetwork::from_safetensors(&bytes).unwrap();
+
+        assert_eq!(loaded.layers.len(), 2);
+        assert_eq!(diff(&network, &loaded).unwrap().linf(), 0.0);
+        assert_eq!(
+            activations::name_of(*loaded.layers[0].activation),
+            Some("fast_sigmoid")
+        );
+    }
+
+    #[test]
+    fn safetensors_rejects_garbage() {
+        assert!(SimpleNeuralNetwork::from_safetensors(b"not a safetensors file").is_err());
+    }
+
+    #[test]
+    fn safetensors_rejects_unchained_layers() {
+        let mut network = SimpleNeuralNetwork::new_simple_with_activation(&[3, 4, 2], None);
+        network.layers.swap(0, 1);
+
+        let bytes = network.to_safetensors().unwrap();
+
+        match SimpleNeuralNetwork::from_safetensors(&bytes) {
+            Ok(_) => panic!("A network whose layers do not chain was loaded"),
+            Err(err) => assert!(err.contains("layer 1 takes 3 inputs")),
+        }
+    }
+}
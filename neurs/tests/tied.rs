@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod tests {
+    use neurs::prelude::*;
+
+    fn autoencoder() -> AutoencoderAssembly {
+        AutoencoderAssembly {
+            encoder: SimpleNeuralNetwork::new_simple_with_activation(&[3, 2], None),
+            decoder: SimpleNeuralNetwork::new_simple_with_activation(&[2, 3], None),
+        }
+    }
+
+    #[test]
+    fn tied_decoder_is_transposed_encoder() {
+        let mut tied = TiedAssembly::new(
+            autoencoder(),
+            vec![WeightTie {
+                source: (0, 0),
+                target: (1, 0),
+                transpose: true,
+            }],
+        )
+        .unwrap();
+
+        tied.inner.encoder.layers[0]
+            .weights
+            .copy_from_slice(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        tied.after_update();
+
+        assert_eq!(
+            tied.inner.decoder.layers[0].weights,
+            [1.0, 4.0, 2.0, 5.0, 3.0, 6.0]
+        );
+    }
+
+    #[test]
+    fn tie_rejects_incompatible_shapes() {
+        let tie = WeightTie {
+            source: (0, 0),
+            target: (1, 0),
+            transpose: false,
+        };
+
+        assert!(TiedAssembly::new(autoencoder(), vec![tie]).is_err());
+    }
+}
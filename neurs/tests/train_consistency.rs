@@ -17,7 +17,7 @@ mod tests {
         for inp in &test_cases {
             classifier
                 .classifier
-                .compute_values(&inp, &mut outputs)
+                .compute_values(inp, &mut outputs)
                 .unwrap();
 
             println!(
@@ -33,7 +33,7 @@ mod tests {
         println!("Asserting answers make sense...");
         let mut ok_cases = 0;
 
-        for (i, inp) in vec![[0.0, 1.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]]
+        for (i, inp) in [[0.0, 1.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]]
             .iter()
             .enumerate()
         {
@@ -96,7 +96,7 @@ mod tests {
 
         let mut jitter_width = strategy.jitter_width;
         let jitter_width_falloff = strategy.jitter_width_falloff;
-        let adaptive_jitter_width = strategy.adaptive_jitter_width.clone();
+        let adaptive_jitter_width = strategy.adaptive_jitter_width;
 
         let mut trainer = trainer::Trainer::new(&mut classifier, frame.clone(), strategy);
 
@@ -106,14 +106,14 @@ mod tests {
 
         for epoch in 1..=250 {
             let ref_fitness = frame
-                .avg_reference_fitness(&mut trainer.reference_assembly)
+                .avg_reference_fitness(trainer.reference_assembly)
                 .unwrap();
-            let best_fitness = trainer.epoch().await.unwrap();
+            let best_fitness = trainer.epoch().unwrap();
 
             jitter_width *= 1.0 - jitter_width_falloff;
 
-            if adaptive_jitter_width.is_some() {
-                jitter_width = adaptive_jitter_width.as_ref().unwrap()(
+            if let Some(adaptive_jitter_width) = &adaptive_jitter_width {
+                jitter_width = adaptive_jitter_width(
                     jitter_width,
                     best_fitness,
                     ref_fitness,
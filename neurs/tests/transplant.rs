@@ -0,0 +1,29 @@
+#[cfg(test)]
+mod tests {
+    use neurs::prelude::*;
+
+    #[test]
+    fn transplant_copies_matching_layers() {
+        let extractor = SimpleNeuralNetwork::new_simple_with_activation(&[4, 3, 2], None);
+        let mut classifier = SimpleNeuralNetwork::new_simple_with_activation(&[4, 3, 5], None);
+
+        let transplanted = classifier
+            .transplant_from(&extractor, &[(0, 0), (1, 1)])
+            .unwrap();
+
+        // The second layers have different shapes, so only the first one is
+        // copied.
+        assert_eq!(transplanted, [0]);
+        assert_eq!(classifier.layers[0].weights, extractor.layers[0].weights);
+        assert_eq!(classifier.layers[0].biases, extractor.layers[0].biases);
+        assert_eq!(classifier.layers[1].weights.len(), 15);
+    }
+
+    #[test]
+    fn transplant_rejects_out_of_bounds_mapping() {
+        let extractor = SimpleNeuralNetwork::new_simple_with_activation(&[4, 3], None);
+        let mut classifier = SimpleNeuralNetwork::new_simple_with_activation(&[4, 3, 5], None);
+
+        assert!(classifier.transplant_from(&extractor, &[(1, 0)]).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod tests {
+    use neurs::prelude::*;
+
+    /// A variational autoencoder of 2 values whose latent means are its
+    /// inputs, with unit variance, and whose decoder returns latents as they
+    /// are.
+    fn identity_vae() -> VariationalAutoencoder {
+        let mut vae = VariationalAutoencoder {
+            encoder: SimpleNeuralNetwork::new_simple_with_activation(
+                &[2, 4],
+                Some(activations::identity),
+            ),
+            decoder: SimpleNeuralNetwork::new_simple_with_activation(
+                &[2, 2],
+                Some(activations::identity),
+            ),
+        };
+
+        // means first, then log-variances
+        vae.encoder.layers[0]
+            .weights
+            .copy_from_slice(&[1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+        vae.decoder.layers[0]
+            .weights
+            .copy_from_slice(&[1.0, 0.0, 0.0, 1.0]);
+
+        for network in vae.get_networks_mut() {
+            network.layers[0].biases.fill(0.0);
+        }
+
+        vae
+    }
+
+    #[test]
+    fn kl_divergence_from_unit_normal() {
+        assert_eq!(kl_divergence(&[0.0, 0.0], &[0.0, 0.0]), 0.0);
+        assert_eq!(kl_divergence(&[1.0, 0.0], &[0.0, 0.0]), 0.25);
+        assert!(kl_divergence(&[0.0], &[1.0]) > 0.0);
+        assert!(kl_divergence(&[0.0], &[-1.0]) > 0.0);
+    }
+
+    #[test]
+    fn vae_encodes_distribution() {
+        let vae = identity_vae();
+        let mut rng = rand::thread_rng();
+
+        assert_eq!(vae.latent_size(), Ok(2));
+        assert_eq!(
+            vae.encode_distribution(&[0.5, -1.0]),
+            Ok((vec![0.5, -1.0], vec![0.0, 0.0]))
+        );
+
+        // a latent sampled with next to no variance is the mean
+        let latent = VariationalAutoencoder::sample_latent(&[0.5, -1.0], &[-40.0, -40.0], &mut rng);
+
+        assert!((latent[0] - 0.5).abs() < 1e-6 && (latent[1] + 1.0).abs() < 1e-6);
+
+        assert_eq!(vae.reconstruct(&[0.5, -1.0], &mut rng).unwrap().len(), 2);
+        assert_eq!(vae.generate(&mut rng).unwrap().len(), 2);
+
+        let mut mismatched = vae;
+        mismatched.decoder = SimpleNeuralNetwork::new_simple_with_activation(&[3, 2], None);
+
+        assert!(mismatched.encode_distribution(&[0.5, -1.0]).is_err());
+    }
+
+    #[test]
+    fn vae_fitness_weighs_kl_divergence() {
+        let mut frame = AutoencoderFrame::new(vec![vec![1.0, 0.0]], None, None);
+
+        let (_, fitness) = frame.run(identity_vae()).ok().unwrap();
+        assert_eq!(fitness, Ok(-0.25));
+
+        frame.kl_weight = 0.0;
+
+        let (_, fitness) = frame.run(identity_vae()).ok().unwrap();
+        assert_eq!(fitness, Ok(0.0));
+
+        let (_, fitness) = frame.train_run(identity_vae()).ok().unwrap();
+        assert!(fitness.unwrap() <= 0.0);
+    }
+}
@@ -0,0 +1,43 @@
+/*
+ * neurs.h - C interface for embedding trained neurs networks.
+ *
+ * See neurs_ffi/src/lib.rs for the documentation of each function.
+ */
+#ifndef NEURS_H
+#define NEURS_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define NEURS_OK 0
+#define NEURS_ERR_NULL (-1)
+#define NEURS_ERR_SIZE (-2)
+#define NEURS_ERR_COMPUTE (-3)
+
+/* An opaque handle to a loaded network. */
+typedef struct NeursNetwork NeursNetwork;
+
+/* Loading; these return NULL on failure. */
+NeursNetwork *neurs_network_load(const char *path);
+NeursNetwork *neurs_network_load_json(const char *json);
+
+/* Shape queries; these return 0 for a NULL network. */
+size_t neurs_network_input_size(const NeursNetwork *network);
+size_t neurs_network_output_size(const NeursNetwork *network);
+
+/* Forward pass; returns NEURS_OK or a negative NEURS_ERR_* code. */
+int neurs_network_compute(const NeursNetwork *network, const float *inputs,
+                          size_t num_inputs, float *outputs,
+                          size_t num_outputs);
+
+/* Frees a network; passing NULL is a no-op. */
+void neurs_network_free(NeursNetwork *network);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* NEURS_H */
@@ -0,0 +1,195 @@
+/*!
+ * A C interface for embedding trained neurs networks.
+ *
+ * Networks are loaded from their serialized form into an opaque handle,
+ * which can then run forward passes on plain float buffers, and must be
+ * freed once no longer needed. See `include/neurs.h` for the C declarations.
+ */
+use std::ffi::{c_char, c_int, CStr};
+use std::panic::{self, AssertUnwindSafe};
+use std::{fs, ptr, slice};
+
+use neurs::prelude::*;
+
+/// The operation succeeded.
+pub const NEURS_OK: c_int = 0;
+
+/// A required pointer argument was null.
+pub const NEURS_ERR_NULL: c_int = -1;
+
+/// A buffer's size does not match the network's input or output size.
+pub const NEURS_ERR_SIZE: c_int = -2;
+
+/// The network failed to compute its outputs.
+pub const NEURS_ERR_COMPUTE: c_int = -3;
+
+/// Runs the body of an exported function, returning `fallback` if it panics,
+/// since unwinding into the caller would abort it.
+fn guard<T>(fallback: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(fallback)
+}
+
+/// Whether every layer of a network has as many weights and biases as its
+/// sizes call for, and takes as many inputs as the layer before it outputs.
+fn is_well_formed(network: &SimpleNeuralNetwork) -> bool {
+    let layers_ok = network.layers.iter().all(|layer| {
+        layer.input_size.checked_mul(layer.output_size) == Some(layer.weights.len())
+            && layer.biases.len() == layer.output_size
+    });
+
+    layers_ok
+        && network
+            .layers
+            .windows(2)
+            .all(|pair| pair[0].output_size == pair[1].input_size)
+}
+
+/// An opaque handle to a loaded network.
+pub struct NeursNetwork {
+    network: SimpleNeuralNetwork,
+    input_size: usize,
+    output_size: usize,
+}
+
+impl NeursNetwork {
+    fn from_json(json: &str) -> Option<Box<NeursNetwork>> {
+        let network: SimpleNeuralNetwork = serde_json::from_str(json).ok()?;
+
+        if !is_well_formed(&network) {
+            return None;
+        }
+
+        Some(Box::new(NeursNetwork {
+            input_size: network.input_size().ok()?,
+            output_size: network.output_size().ok()?,
+            network,
+        }))
+    }
+}
+
+/// Loads a serialized network from a JSON string.
+///
+/// Returns null if the string is not a valid serialized network, or if the
+/// sizes of its layers do not fit together.
+///
+/// # Safety
+///
+/// `json` must be null or point to a valid, NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn neurs_network_load_json(json: *const c_char) -> *mut NeursNetwork {
+    if json.is_null() {
+        return ptr::null_mut();
+    }
+
+    guard(ptr::null_mut(), || {
+        CStr::from_ptr(json)
+            .to_str()
+            .ok()
+            .and_then(NeursNetwork::from_json)
+            .map_or(ptr::null_mut(), Box::into_raw)
+    })
+}
+
+/// Loads a serialized network from a JSON file.
+///
+/// Returns null if the file cannot be read, or does not hold a valid network,
+/// like [neurs_network_load_json].
+///
+/// # Safety
+///
+/// `path` must be null or point to a valid, NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn neurs_network_load(path: *const c_char) -> *mut NeursNetwork {
+    if path.is_null() {
+        return ptr::null_mut();
+    }
+
+    guard(ptr::null_mut(), || {
+        CStr::from_ptr(path)
+            .to_str()
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| NeursNetwork::from_json(&json))
+            .map_or(ptr::null_mut(), Box::into_raw)
+    })
+}
+
+/// The number of inputs a network takes; 0 if `network` is null.
+///
+/// # Safety
+///
+/// `network` must be null or a handle returned by one of the load functions,
+/// which has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn neurs_network_input_size(network: *const NeursNetwork) -> usize {
+    guard(0, || {
+        network.as_ref().map_or(0, |network| network.input_size)
+    })
+}
+
+/// The number of outputs a network produces; 0 if `network` is null.
+///
+/// # Safety
+///
+/// `network` must be null or a handle returned by one of the load functions,
+/// which has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn neurs_network_output_size(network: *const NeursNetwork) -> usize {
+    guard(0, || {
+        network.as_ref().map_or(0, |network| network.output_size)
+    })
+}
+
+/// Runs a forward pass, reading `num_inputs` floats from `inputs` and writing
+/// `num_outputs` floats to `outputs`.
+///
+/// Returns [NEURS_OK] on success, or one of the negative `NEURS_ERR_*` codes.
+///
+/// # Safety
+///
+/// `network` must be null or a live handle. `inputs` and `outputs` must be
+/// null or point to buffers of at least `num_inputs` and `num_outputs` floats
+/// respectively, and must not overlap.
+#[no_mangle]
+pub unsafe extern "C" fn neurs_network_compute(
+    network: *const NeursNetwork,
+    inputs: *const f32,
+    num_inputs: usize,
+    outputs: *mut f32,
+    num_outputs: usize,
+) -> c_int {
+    let network = match network.as_ref() {
+        Some(network) => network,
+        None => return NEURS_ERR_NULL,
+    };
+
+    if inputs.is_null() || outputs.is_null() {
+        return NEURS_ERR_NULL;
+    }
+
+    if num_inputs != network.input_size || num_outputs != network.output_size {
+        return NEURS_ERR_SIZE;
+    }
+
+    let inputs = slice::from_raw_parts(inputs, num_inputs);
+    let outputs = slice::from_raw_parts_mut(outputs, num_outputs);
+
+    guard(NEURS_ERR_COMPUTE, || {
+        match network.network.compute_values(inputs, outputs) {
+            Ok(()) => NEURS_OK,
+            Err(_) => NEURS_ERR_COMPUTE,
+        }
+    })
+}
+
+/// Frees a network handle. Does nothing if `network` is null.
+///
+/// # Safety
+///
+/// `network` must be null or a live handle; it must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn neurs_network_free(network: *mut NeursNetwork) {
+    if !network.is_null() {
+        guard((), || drop(Box::from_raw(network)));
+    }
+}
@@ -0,0 +1,89 @@
+#![cfg(test)]
+
+use std::ffi::CString;
+use std::ptr;
+
+use neurs::prelude::*;
+use neurs_ffi::*;
+
+#[test]
+fn test_load_and_compute() {
+    let net = SimpleNeuralNetwork::new_simple_with_activation(
+        &[3, 4, 2],
+        Some(activations::fast_sigmoid),
+    );
+    let json = CString::new(serde_json::to_string(&net).unwrap()).unwrap();
+
+    let inputs = [0.5_f32, -1.0, 2.0];
+    let mut expected = [0.0_f32; 2];
+    net.compute_values(&inputs, &mut expected).unwrap();
+
+    unsafe {
+        let handle = neurs_network_load_json(json.as_ptr());
+        assert!(!handle.is_null());
+
+        assert_eq!(neurs_network_input_size(handle), 3);
+        assert_eq!(neurs_network_output_size(handle), 2);
+
+        let mut outputs = [0.0_f32; 2];
+
+        assert_eq!(
+            neurs_network_compute(handle, inputs.as_ptr(), 3, outputs.as_mut_ptr(), 2),
+            NEURS_OK
+        );
+        assert_eq!(outputs, expected);
+
+        assert_eq!(
+            neurs_network_compute(handle, inputs.as_ptr(), 2, outputs.as_mut_ptr(), 2),
+            NEURS_ERR_SIZE
+        );
+
+        neurs_network_free(handle);
+    }
+}
+
+#[test]
+fn test_load_invalid() {
+    let garbage = CString::new("{ not a network }").unwrap();
+
+    unsafe {
+        assert!(neurs_network_load_json(garbage.as_ptr()).is_null());
+        assert!(neurs_network_load_json(ptr::null()).is_null());
+        assert_eq!(neurs_network_output_size(ptr::null()), 0);
+
+        neurs_network_free(ptr::null_mut());
+    }
+}
+
+#[test]
+fn test_load_malformed() {
+    let net = SimpleNeuralNetwork::new_simple_with_activation(
+        &[3, 4, 2],
+        Some(activations::fast_sigmoid),
+    );
+    let json = serde_json::to_value(&net).unwrap();
+
+    let load = |json: &serde_json::Value| unsafe {
+        let json = CString::new(json.to_string()).unwrap();
+        let handle = neurs_network_load_json(json.as_ptr());
+        let loaded = !handle.is_null();
+
+        neurs_network_free(handle);
+        loaded
+    };
+
+    assert!(load(&json));
+
+    let mut few_biases = json.clone();
+    few_biases["layers"][0]["biases"] = serde_json::json!([0.0]);
+    assert!(!load(&few_biases));
+
+    let mut few_weights = json.clone();
+    few_weights["layers"][1]["weights"] = serde_json::json!([0.0, 1.0]);
+    assert!(!load(&few_weights));
+
+    let mut mismatched = json.clone();
+    mismatched["layers"][1]["input_size"] = serde_json::json!(2);
+    mismatched["layers"][1]["weights"] = serde_json::json!([0.0, 0.0, 0.0, 0.0]);
+    assert!(!load(&mismatched));
+}
@@ -0,0 +1,285 @@
+/*!
+ * Python bindings for neurs.
+ *
+ * Exposes network construction and evaluation, classifier training through
+ * a [LabeledLearningFrame] and a [WeightJitterStrat], and saving and loading
+ * networks as JSON.
+ */
+use std::fs;
+
+use neurs::prelude::*;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+
+fn value_error(err: String) -> PyErr {
+    PyValueError::new_err(err)
+}
+
+/// Checks that every layer of a network has as many weights and biases as its
+/// sizes call for, and takes as many inputs as the layer before it outputs.
+fn check_layers(network: &SimpleNeuralNetwork) -> Result<(), String> {
+    for (index, layer) in network.layers.iter().enumerate() {
+        if layer.input_size.checked_mul(layer.output_size) != Some(layer.weights.len())
+            || layer.biases.len() != layer.output_size
+        {
+            return Err(format!(
+                "Layer {} does not have as many weights and biases as its sizes call for",
+                index
+            ));
+        }
+    }
+
+    for (index, pair) in network.layers.windows(2).enumerate() {
+        if pair[0].output_size != pair[1].input_size {
+            return Err(format!(
+                "Layer {} outputs {} values, but layer {} takes {} inputs",
+                index,
+                pair[0].output_size,
+                index + 1,
+                pair[1].input_size
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// A feed-forward neural network.
+#[pyclass(name = "NeuralNetwork")]
+#[derive(Clone)]
+pub struct PyNeuralNetwork {
+    inner: SimpleNeuralNetwork,
+}
+
+#[pymethods]
+impl PyNeuralNetwork {
+    /// Constructs a randomly initialized network from layer sizes, using the
+    /// same activation for every layer, by name (e.g. "fast_sigmoid").
+    #[new]
+    #[pyo3(signature = (layer_sizes, activation = None))]
+    fn new(layer_sizes: Vec<usize>, activation: Option<&str>) -> PyResult<Self> {
+        if layer_sizes.len() < 2 {
+            return Err(value_error(
+                "A network needs at least an input and an output size".to_owned(),
+            ));
+        }
+
+        let activation =
+            match activation {
+                Some(name) => Some(activations::from_name(name).ok_or_else(|| {
+                    value_error(format!("Unknown activation function {:?}", name))
+                })?),
+
+                None => None,
+            };
+
+        Ok(Self {
+            inner: SimpleNeuralNetwork::new_simple_with_activation(&layer_sizes, activation),
+        })
+    }
+
+    /// The input size of this network.
+    #[getter]
+    fn input_size(&self) -> PyResult<usize> {
+        self.inner.input_size().map_err(value_error)
+    }
+
+    /// The output size of this network.
+    #[getter]
+    fn output_size(&self) -> PyResult<usize> {
+        self.inner.output_size().map_err(value_error)
+    }
+
+    /// Computes the outputs of this network for a list of inputs, which must
+    /// be as many as this network's input size.
+    fn compute(&self, inputs: Vec<f32>) -> PyResult<Vec<f32>> {
+        let input_size = self.input_size()?;
+
+        if inputs.len() != input_size {
+            return Err(value_error(format!(
+                "Expected {} inputs, got {}",
+                input_size,
+                inputs.len()
+            )));
+        }
+
+        let mut outputs = vec![0.0_f32; self.output_size()?];
+
+        self.inner
+            .compute_values(&inputs, &mut outputs)
+            .map_err(value_error)?;
+
+        Ok(outputs)
+    }
+
+    /// Serializes this network into a JSON string.
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.inner).map_err(|err| value_error(err.to_string()))
+    }
+
+    /// Deserializes a network from a JSON string, checking that the sizes of
+    /// its layers fit together.
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        let inner: SimpleNeuralNetwork =
+            serde_json::from_str(json).map_err(|err| value_error(err.to_string()))?;
+
+        check_layers(&inner).map_err(value_error)?;
+
+        Ok(Self { inner })
+    }
+
+    /// Saves this network to a JSON file.
+    fn save(&self, path: &str) -> PyResult<()> {
+        fs::write(path, self.to_json()?).map_err(|err| PyIOError::new_err(err.to_string()))
+    }
+
+    /// Loads a network from a JSON file.
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<Self> {
+        let json = fs::read_to_string(path).map_err(|err| PyIOError::new_err(err.to_string()))?;
+        Self::from_json(&json)
+    }
+}
+
+/// Trains a network in place as a classifier of integer labels, returning the
+/// best fitness of each epoch.
+///
+/// The network must have one output per label.
+#[pyfunction]
+#[pyo3(signature = (
+    network,
+    inputs,
+    labels,
+    epochs,
+    num_jitters = 100,
+    jitter_width = 1.0,
+    jitter_width_falloff = 0.0,
+    step_factor = 0.6,
+    apply_bad_jitters = false,
+    cross_entropy = false,
+))]
+#[allow(clippy::too_many_arguments)]
+fn train_classifier(
+    mut network: PyRefMut<PyNeuralNetwork>,
+    inputs: Vec<Vec<f32>>,
+    labels: Vec<usize>,
+    epochs: usize,
+    num_jitters: usize,
+    jitter_width: f32,
+    jitter_width_falloff: f32,
+    step_factor: f32,
+    apply_bad_jitters: bool,
+    cross_entropy: bool,
+) -> PyResult<Vec<f32>> {
+    if inputs.len() != labels.len() {
+        return Err(value_error(
+            "There must be exactly one label per input".to_owned(),
+        ));
+    }
+
+    let num_cases = inputs.len();
+
+    let mut frame = LabeledLearningFrame::new(inputs, labels, None).map_err(value_error)?;
+
+    if cross_entropy {
+        frame.scoring = LabelScoring::CrossEntropy;
+    }
+
+    let strategy = WeightJitterStrat::new(WeightJitterStratOptions {
+        num_jitters,
+        jitter_width,
+        jitter_width_falloff,
+        step_factor,
+        apply_bad_jitters,
+        adaptive_jitter_width: None::<fn(f32, f32, f32) -> f32>,
+        num_steps_per_epoch: num_cases,
+    });
+
+    let mut classifier = NeuralClassifier {
+        classifier: network.inner.clone(),
+    };
+
+    let mut fitnesses = Vec::with_capacity(epochs);
+
+    {
+        let mut trainer = Trainer::new(&mut classifier, frame, strategy);
+
+        for _ in 0..epochs {
+            fitnesses.push(trainer.epoch().map_err(value_error)?);
+        }
+    }
+
+    network.inner = classifier.classifier;
+
+    Ok(fitnesses)
+}
+
+/// Python bindings for neurs.
+#[pymodule]
+fn neurs_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyNeuralNetwork>()?;
+    m.add_function(wrap_pyfunction!(train_classifier, m)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn network_roundtrip() {
+        let network = PyNeuralNetwork::new(vec![3, 4, 2], Some("fast_sigmoid")).unwrap();
+
+        assert_eq!(network.input_size().unwrap(), 3);
+        assert_eq!(network.output_size().unwrap(), 2);
+
+        let outputs = network.compute(vec![0.5, -1.0, 2.0]).unwrap();
+
+        assert_eq!(outputs.len(), 2);
+        assert!(outputs.iter().all(|output| output.is_finite()));
+
+        let json = network.to_json().unwrap();
+        let loaded = PyNeuralNetwork::from_json(&json).unwrap();
+
+        assert_eq!(loaded.compute(vec![0.5, -1.0, 2.0]).unwrap(), outputs);
+
+        let path = std::env::temp_dir().join(format!("neurs_py-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        network.save(path).unwrap();
+        let loaded = PyNeuralNetwork::load(path);
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(
+            loaded.unwrap().compute(vec![0.5, -1.0, 2.0]).unwrap(),
+            outputs
+        );
+    }
+
+    #[test]
+    fn network_errors() {
+        assert!(PyNeuralNetwork::new(vec![3], None).is_err());
+        assert!(PyNeuralNetwork::new(vec![3, 2], Some("no_such_activation")).is_err());
+
+        let network = PyNeuralNetwork::new(vec![3, 2], None).unwrap();
+
+        assert!(network.compute(vec![1.0]).is_err());
+        assert!(network.compute(vec![1.0; 4]).is_err());
+        assert!(PyNeuralNetwork::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn from_json_checks_layers() {
+        let network = PyNeuralNetwork::new(vec![3, 4, 2], None).unwrap();
+
+        let mut short = network.clone();
+        short.inner.layers[0].weights.pop();
+        assert!(PyNeuralNetwork::from_json(&short.to_json().unwrap()).is_err());
+
+        let mut unchained = network.clone();
+        unchained.inner.layers.swap(0, 1);
+        assert!(PyNeuralNetwork::from_json(&unchained.to_json().unwrap()).is_err());
+    }
+}
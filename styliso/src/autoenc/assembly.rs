@@ -0,0 +1,195 @@
+/*!
+ * The autoencoder assembly for labeled images, built after the size of the
+ * images and the number of labels.
+ */
+use crate::image::prelude::*;
+use crate::prelude::*;
+use neurs::prelude::*;
+
+/// The shape of the labeled images an [ImageAutoencoder] works with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImageShape {
+    /// The width of the images.
+    pub width: u16,
+
+    /// The height of the images.
+    pub height: u16,
+
+    /// Whether the images are in colour, rather than grayscale.
+    pub colour: bool,
+
+    /// Whether the images have an alpha plane.
+    pub alpha: bool,
+
+    /// How many labels the images can have.
+    pub num_labels: usize,
+}
+
+impl ImageShape {
+    /// The shape of a labeled image.
+    pub fn of<L: TrainingLabel>(image: &LabeledImage<L>) -> Self {
+        ImageShape {
+            width: image.img.width,
+            height: image.img.height,
+            colour: image.img.colour.is_some(),
+            alpha: image.img.alpha.is_some(),
+            num_labels: image.num_labels,
+        }
+    }
+
+    /// Describes this shape, e.g. in error messages.
+    fn describe(&self) -> String {
+        format!(
+            "a {}x{} {}{} image out of {} labels",
+            self.width,
+            self.height,
+            if self.colour { "colour" } else { "grayscale" },
+            if self.alpha { " and alpha" } else { "" },
+            self.num_labels
+        )
+    }
+
+    /// Makes an opaque black image of this shape, with an alpha plane if the
+    /// shape has one.
+    pub fn blank_image(&self) -> ImageData {
+        let mut image = ImageData::blank(self.width, self.height, self.colour);
+
+        if self.alpha {
+            image.alpha = Some(vec![1.0; image.area as usize]);
+        }
+
+        image
+    }
+
+    /// How many values an image of this shape is encoded into, along with
+    /// its label; see [Item::encode].
+    pub fn input_size(&self) -> usize {
+        let area = self.width as usize * self.height as usize;
+        let planes = 1 + 2 * self.colour as usize + self.alpha as usize;
+
+        area * planes + self.num_labels
+    }
+}
+
+/**
+ * An autoencoder for labeled images of a given [ImageShape].
+ *
+ * The encoder narrows an image, along with its label, down to a latent vector
+ * through a number of hidden layers, and the decoder mirrors it.
+ */
+#[derive(Clone)]
+pub struct ImageAutoencoder {
+    /// The shape of the images this autoencoder works with.
+    pub shape: ImageShape,
+
+    /// The encoder and decoder networks.
+    pub assembly: AutoencoderAssembly,
+}
+
+impl ImageAutoencoder {
+    /**
+     * Builds an autoencoder with random weights, from the shape of its images,
+     * the size of its latent vectors, and the sizes of the hidden layers of
+     * its encoder, from the input inward. The decoder's hidden layers are the
+     * same, in reverse.
+     */
+    pub fn new(shape: ImageShape, latent_size: usize, hidden_sizes: &[usize]) -> Self {
+        let mut encoder_sizes = vec![shape.input_size()];
+        encoder_sizes.extend_from_slice(hidden_sizes);
+        encoder_sizes.push(latent_size);
+
+        let decoder_sizes: Vec<usize> = encoder_sizes.iter().rev().copied().collect();
+
+        // hidden layers use the default activation; latents are left linear,
+        // and outputs are squashed between 0 and 1, like the encoded images
+        let mut encoder_activations = vec![None; hidden_sizes.len()];
+        encoder_activations.push(Some(activations::identity as NNActivation));
+
+        let mut decoder_activations = vec![None; hidden_sizes.len()];
+        decoder_activations.push(Some(activations::fast_sigmoid as NNActivation));
+
+        ImageAutoencoder {
+            shape,
+            assembly: AutoencoderAssembly {
+                encoder: SimpleNeuralNetwork::new_simple(&encoder_sizes, &encoder_activations),
+                decoder: SimpleNeuralNetwork::new_simple(&decoder_sizes, &decoder_activations),
+            },
+        }
+    }
+
+    /// The size of the latent vectors of this autoencoder.
+    pub fn latent_size(&self) -> Result<usize, String> {
+        self.assembly.encoder.output_size()
+    }
+
+    /// Checks that an image has the shape this autoencoder works with.
+    pub fn check_shape<L: TrainingLabel>(&self, image: &LabeledImage<L>) -> Result<(), String> {
+        let shape = ImageShape::of(image);
+
+        if shape != self.shape {
+            return Err(format!(
+                "Expected {}, got {}",
+                self.shape.describe(),
+                shape.describe()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl<L: TrainingLabel> Autoencoder<LabeledImage<L>> for ImageAutoencoder {
+    fn implode(&self, item: &LabeledImage<L>) -> Result<Vec<f32>, String> {
+        self.check_shape(item)?;
+
+        self.assembly.encode(&item.encode().map_err(str::to_owned)?)
+    }
+
+    fn explode(&self, imploded: &[f32]) -> Result<LabeledImage<L>, String> {
+        let latent_size = self.latent_size()?;
+
+        if imploded.len() != latent_size {
+            return Err(format!(
+                "Expected a latent vector of {} values, got {}",
+                latent_size,
+                imploded.len()
+            ));
+        }
+
+        let mut image = LabeledImage::new(
+            L::from_index(0),
+            self.shape.blank_image(),
+            self.shape.num_labels,
+        );
+
+        image.decode_from(&self.assembly.decode(imploded)?)?;
+
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explode_checks_latent_size() {
+        let shape = ImageShape {
+            width: 2,
+            height: 2,
+            colour: false,
+            alpha: false,
+            num_labels: 2,
+        };
+
+        let autoencoder = ImageAutoencoder::new(shape, 3, &[4]);
+
+        let image: LabeledImage<usize> = autoencoder.explode(&[0.0; 3]).unwrap();
+        assert_eq!(ImageShape::of(&image), shape);
+
+        for len in [2, 4] {
+            let exploded: Result<LabeledImage<usize>, _> = autoencoder.explode(&vec![0.0; len]);
+            assert!(exploded.is_err());
+        }
+    }
+}
@@ -0,0 +1,44 @@
+/*!
+ * Generation of new images in the style of a label: random image data is
+ * given the label and run through the autoencoder, which reconstructs it the
+ * way it learned images of that label look.
+ */
+use super::assembly::ImageAutoencoder;
+use crate::image::prelude::*;
+use crate::prelude::*;
+use neurs::train::label::TrainingLabel;
+use rand::Rng;
+
+impl ImageAutoencoder {
+    /**
+     * Generates a new image in the style of a label, from random image data.
+     *
+     * `noise_scale` is how far the random brightnesses, hues and saturations
+     * stray from the middle of their ranges: 1 spreads them all over, and 0
+     * leaves a flat gray image, so that only the label shapes the result.
+     */
+    pub fn generate<L: TrainingLabel, R: Rng>(
+        &self,
+        label: L,
+        noise_scale: f32,
+        rng: &mut R,
+    ) -> Result<ImageData, String> {
+        let mut noise = |range: f32| range / 2.0 + (rng.gen::<f32>() - 0.5) * range * noise_scale;
+
+        let mut img = self.shape.blank_image();
+
+        img.brightness
+            .iter_mut()
+            .for_each(|value| *value = noise(1.0));
+
+        if let Some((hue, saturation)) = &mut img.colour {
+            hue.iter_mut().for_each(|value| *value = noise(360.0));
+            saturation.iter_mut().for_each(|value| *value = noise(1.0));
+        }
+
+        let image = LabeledImage::new(label, img, self.shape.num_labels);
+        let generated: LabeledImage<L> = self.explode(&self.implode(&image)?)?;
+
+        Ok(generated.img)
+    }
+}
@@ -26,8 +26,8 @@ pub trait Item {
  */
 pub trait Autoencoder<T: Item> {
     /// "Implodes" an item into a distilled representation of f32.
-    fn implode(&self, item: &T) -> Vec<f32>;
+    fn implode(&self, item: &T) -> Result<Vec<f32>, String>;
 
     /// "Explodes" a distilled representation into an item.
-    fn explode(&self, imploded: &[f32]) -> T;
+    fn explode(&self, imploded: &[f32]) -> Result<T, String>;
 }
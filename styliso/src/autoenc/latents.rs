@@ -0,0 +1,209 @@
+/*!
+ * Export of the latent vectors a [StyleModel] encodes the images of a
+ * dataset into, the "distilled representation" of their style, so that they
+ * can be inspected and reused by other tools.
+ *
+ * They are saved as CSV, with a header row, then a row per image of its
+ * path, its labels separated by `;`, and each value of its latent vector; as
+ * JSON, a list of objects with a `path`, `labels` and `latent`; or as NPY, a
+ * NumPy array of 32-bit floats with a row per image, of its latent vector and
+ * then the index of its main label.
+ */
+use super::interface::Autoencoder;
+use super::model::StyleModel;
+use crate::image::prelude::*;
+use neurs::train::label::TrainingLabel;
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The latent vector of an image of a dataset.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LatentRecord {
+    /// The path the image was loaded from.
+    pub path: PathBuf,
+
+    /// The indices of the labels of the image among the labels of the model,
+    /// the main one first.
+    pub labels: Vec<usize>,
+
+    /// The latent vector.
+    pub latent: Vec<f32>,
+}
+
+/// A format to save latent vectors in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LatentFormat {
+    Csv,
+    Json,
+    Npy,
+}
+
+impl LatentFormat {
+    /// The format told by the extension of a path, if any.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+
+        match extension.as_str() {
+            "csv" => Some(LatentFormat::Csv),
+            "json" => Some(LatentFormat::Json),
+            "npy" => Some(LatentFormat::Npy),
+            _ => None,
+        }
+    }
+}
+
+impl StyleModel {
+    /**
+     * Encodes every image of a dataset into a latent vector, with its labels
+     * told apart by name, so that the dataset need not have the same labels
+     * as the one the model was trained with, as long as the model knows
+     * them all. Every image must have the shape the model works with.
+     */
+    pub fn encode_dataset<L: TrainingLabel>(
+        &self,
+        dataset: &Dataset<L>,
+    ) -> Result<Vec<LatentRecord>, String> {
+        let indices = dataset
+            .labels
+            .iter()
+            .map(|name| self.label_index(name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        dataset
+            .images
+            .iter()
+            .zip(&dataset.paths)
+            .map(|(image, path)| {
+                let labels: Vec<usize> =
+                    image.labels().map(|label| indices[label.index()]).collect();
+
+                let mut relabeled =
+                    LabeledImage::new(labels[0], image.img.clone(), self.labels.len());
+                relabeled.extra_labels = labels[1..].to_vec();
+
+                let latent = self
+                    .autoencoder
+                    .implode(&relabeled)
+                    .map_err(|err| format!("Could not encode {}: {}", path.display(), err))?;
+
+                Ok(LatentRecord {
+                    path: path.clone(),
+                    labels,
+                    latent,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Writes latent vectors as CSV.
+fn to_csv(records: &[LatentRecord], label_names: &[String]) -> Result<Vec<u8>, String> {
+    let latent_size = records.first().map_or(0, |record| record.latent.len());
+    let mut writer = csv::Writer::from_writer(vec![]);
+
+    let mut header = vec!["path".to_owned(), "labels".to_owned()];
+    header.extend((0..latent_size).map(|index| format!("z{}", index)));
+
+    writer
+        .write_record(&header)
+        .map_err(|err| err.to_string())?;
+
+    for record in records {
+        let labels: Vec<&str> = record
+            .labels
+            .iter()
+            .map(|&label| label_names[label].as_str())
+            .collect();
+
+        let mut row = vec![record.path.to_string_lossy().into_owned(), labels.join(";")];
+        row.extend(record.latent.iter().map(f32::to_string));
+
+        writer.write_record(&row).map_err(|err| err.to_string())?;
+    }
+
+    writer.into_inner().map_err(|err| err.to_string())
+}
+
+/// Writes latent vectors as JSON.
+fn to_json(records: &[LatentRecord], label_names: &[String]) -> Vec<u8> {
+    let entries: Vec<_> = records
+        .iter()
+        .map(|record| {
+            let labels: Vec<&str> = record
+                .labels
+                .iter()
+                .map(|&label| label_names[label].as_str())
+                .collect();
+
+            json!({
+                "path": record.path.to_string_lossy(),
+                "labels": labels,
+                "latent": record.latent,
+            })
+        })
+        .collect();
+
+    serde_json::to_vec_pretty(&entries).unwrap()
+}
+
+/// Writes latent vectors, each followed by the index of its main label, as
+/// a 2-dimensional NPY array of little-endian 32-bit floats.
+fn to_npy(records: &[LatentRecord]) -> Vec<u8> {
+    let columns = records.first().map_or(0, |record| record.latent.len()) + 1;
+
+    let mut header = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}",
+        records.len(),
+        columns
+    );
+
+    // the header is padded with spaces, and ended with a newline, so that
+    // the data is aligned to 64 bytes
+    let unpadded = 10 + header.len() + 1;
+    header.push_str(&" ".repeat(unpadded.next_multiple_of(64) - unpadded));
+    header.push('\n');
+
+    let mut out = Vec::with_capacity(10 + header.len() + records.len() * columns * 4);
+    out.extend(b"\x93NUMPY\x01\x00");
+    out.extend((header.len() as u16).to_le_bytes());
+    out.extend(header.as_bytes());
+
+    for record in records {
+        for value in &record.latent {
+            out.extend(value.to_le_bytes());
+        }
+
+        out.extend((record.labels[0] as f32).to_le_bytes());
+    }
+
+    out
+}
+
+/**
+ * Saves latent vectors, from [StyleModel::encode_dataset], to a file in the
+ * format told by its extension: `.csv`, `.json` or `.npy`. Labels are saved
+ * by name, out of `label_names`, except in NPY, which holds only numbers.
+ */
+pub fn save_latents<P: AsRef<Path>>(
+    path: P,
+    records: &[LatentRecord],
+    label_names: &[String],
+) -> Result<(), String> {
+    let path = path.as_ref();
+
+    let bytes = match LatentFormat::from_path(path) {
+        Some(LatentFormat::Csv) => to_csv(records, label_names)?,
+        Some(LatentFormat::Json) => to_json(records, label_names),
+        Some(LatentFormat::Npy) => to_npy(records),
+
+        None => {
+            return Err(format!(
+                "Could not save {}: expected a .csv, .json or .npy file",
+                path.display()
+            ))
+        }
+    };
+
+    fs::write(path, bytes).map_err(|err| format!("Could not save {}: {}", path.display(), err))
+}
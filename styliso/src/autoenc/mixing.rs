@@ -0,0 +1,105 @@
+/*!
+ * Mixing and matching of styles: arithmetic on latent vectors, and encoding
+ * of images with a mix of labels rather than a single one.
+ */
+use super::assembly::ImageAutoencoder;
+use super::latents::LatentRecord;
+use crate::image::prelude::*;
+use crate::prelude::*;
+use neurs::train::label::TrainingLabel;
+
+/**
+ * Interpolates linearly between two latent vectors of the same size: `t` of
+ * 0 gives `a`, 1 gives `b`, and anything in between a mix of both. Values
+ * outside of 0 and 1 extrapolate.
+ */
+pub fn interpolate(a: &[f32], b: &[f32], t: f32) -> Result<Vec<f32>, String> {
+    if a.len() != b.len() {
+        return Err(format!(
+            "Cannot interpolate between latent vectors of sizes {} and {}",
+            a.len(),
+            b.len()
+        ));
+    }
+
+    Ok(a.iter().zip(b).map(|(a, b)| a + (b - a) * t).collect())
+}
+
+/**
+ * Averages the latent vectors of the images with any of a set of labels,
+ * e.g. from [StyleModel::encode_dataset], into one which stands for their
+ * style as a whole.
+ *
+ * Fails if no image has any of the labels.
+ */
+pub fn label_mean(records: &[LatentRecord], labels: &[usize]) -> Result<Vec<f32>, String> {
+    let mut matching = records
+        .iter()
+        .filter(|record| record.labels.iter().any(|label| labels.contains(label)));
+
+    let mut sum = match matching.next() {
+        Some(record) => record.latent.clone(),
+        None => return Err("No image has any of the labels".to_owned()),
+    };
+
+    let mut count = 1;
+
+    for record in matching {
+        if record.latent.len() != sum.len() {
+            return Err(format!(
+                "Cannot average latent vectors of sizes {} and {}",
+                sum.len(),
+                record.latent.len()
+            ));
+        }
+
+        for (sum, value) in sum.iter_mut().zip(&record.latent) {
+            *sum += value;
+        }
+
+        count += 1;
+    }
+
+    Ok(sum.into_iter().map(|value| value / count as f32).collect())
+}
+
+impl ImageAutoencoder {
+    /**
+     * Encodes an image with a mix of labels: `label_weights` gives a value
+     * per label, in place of a one-hot vector, e.g. 0.5 for each of two
+     * labels to encode the image halfway between their styles.
+     */
+    pub fn encode_mixed(
+        &self,
+        image: &ImageData,
+        label_weights: &[f32],
+    ) -> Result<Vec<f32>, String> {
+        if label_weights.len() != self.shape.num_labels {
+            return Err(format!(
+                "Expected a weight for each of {} labels, got {}",
+                self.shape.num_labels,
+                label_weights.len()
+            ));
+        }
+
+        let image = LabeledImage::new(0_usize, image.clone(), self.shape.num_labels);
+        self.check_shape(&image)?;
+
+        let mut input = image.img.encode().map_err(str::to_owned)?;
+        input.extend_from_slice(label_weights);
+
+        self.assembly.encode(&input)
+    }
+
+    /**
+     * Restyles an image with a mix of labels, by encoding it with
+     * [ImageAutoencoder::encode_mixed] and decoding it back.
+     */
+    pub fn mix_labels<L: TrainingLabel>(
+        &self,
+        image: &ImageData,
+        label_weights: &[f32],
+    ) -> Result<LabeledImage<L>, String> {
+        self.explode(&self.encode_mixed(image, label_weights)?)
+    }
+}
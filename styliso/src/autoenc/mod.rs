@@ -2,5 +2,13 @@
  * Autoencoder code, which uses [neurs] under the hood.
  */
 
+pub mod assembly;
+pub mod generate;
 pub mod interface;
+pub mod latents;
+pub mod mixing;
+pub mod model;
 pub mod prelude;
+pub mod similarity;
+pub mod tiled;
+pub mod train;
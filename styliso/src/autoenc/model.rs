@@ -0,0 +1,249 @@
+/*!
+ * Trained style models: an [ImageAutoencoder] along with the names of its
+ * labels, and their persistence.
+ *
+ * A model file starts with the [MODEL_MAGIC] bytes and the format version (a
+ * little-endian `u16`), then holds the width and height of the images
+ * (`u16`), their flags (a `u8` with bit 0 set if they are in colour, and bit
+ * 1 if they have an alpha plane), the number of labels (`u32`) and the name
+ * of each (a `u16` length and as many UTF-8 bytes), and finally the encoder
+ * and decoder, each as a `u32` length and a network in the compact binary
+ * format of [neurs::binary].
+ */
+use super::assembly::{ImageAutoencoder, ImageShape};
+use neurs::prelude::*;
+use std::fs;
+use std::path::Path;
+
+/// The bytes every model file starts with.
+pub const MODEL_MAGIC: [u8; 4] = *b"STYL";
+
+/// The version of the model format written by this version of styliso.
+pub const MODEL_FORMAT_VERSION: u16 = 1;
+
+/// A trained autoencoder, and the names of the labels of the images it was
+/// trained with, in the order of their indices.
+#[derive(Clone)]
+pub struct StyleModel {
+    /// The autoencoder.
+    pub autoencoder: ImageAutoencoder,
+
+    /// The name of each label.
+    pub labels: Vec<String>,
+}
+
+/// Reads values off a byte slice, failing instead of panicking when it runs
+/// out.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if len > self.bytes.len() {
+            return Err("Unexpected end of data".to_owned());
+        }
+
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        Ok(taken)
+    }
+
+    fn read_u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn read_u32(&mut self) -> Result<usize, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()) as usize)
+    }
+
+    fn read_network(&mut self) -> Result<SimpleNeuralNetwork, String> {
+        let len = self.read_u32()?;
+        SimpleNeuralNetwork::from_binary(self.take(len)?)
+    }
+}
+
+fn write_network(out: &mut Vec<u8>, network: &SimpleNeuralNetwork) -> Result<(), String> {
+    let bytes = network.to_binary()?;
+
+    out.extend((bytes.len() as u32).to_le_bytes());
+    out.extend(bytes);
+
+    Ok(())
+}
+
+impl StyleModel {
+    /// The index of the label with the given name.
+    pub fn label_index(&self, name: &str) -> Result<usize, String> {
+        self.labels
+            .iter()
+            .position(|label| label == name)
+            .ok_or_else(|| {
+                format!(
+                    "Unknown label {:?}; expected one of {}",
+                    name,
+                    self.labels.join(", ")
+                )
+            })
+    }
+
+    /// Encodes this model in the model format. See the [module
+    /// documentation](self).
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let shape = &self.autoencoder.shape;
+        let mut out = MODEL_MAGIC.to_vec();
+
+        out.extend(MODEL_FORMAT_VERSION.to_le_bytes());
+        out.extend(shape.width.to_le_bytes());
+        out.extend(shape.height.to_le_bytes());
+        out.push(shape.colour as u8 | (shape.alpha as u8) << 1);
+        out.extend((self.labels.len() as u32).to_le_bytes());
+
+        for label in &self.labels {
+            let len = u16::try_from(label.len())
+                .map_err(|_| format!("Label name {:?} is too long", label))?;
+
+            out.extend(len.to_le_bytes());
+            out.extend(label.as_bytes());
+        }
+
+        write_network(&mut out, &self.autoencoder.assembly.encoder)?;
+        write_network(&mut out, &self.autoencoder.assembly.decoder)?;
+
+        Ok(out)
+    }
+
+    /// Decodes a model from the model format, as written by
+    /// [Self::to_bytes].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes };
+
+        if reader.take(4).ok() != Some(&MODEL_MAGIC[..]) {
+            return Err("Not a styliso model file".to_owned());
+        }
+
+        let version = reader.read_u16()?;
+
+        if version > MODEL_FORMAT_VERSION {
+            return Err(format!(
+                "This model uses version {} of the format, but only up to version {} is supported; try a newer version of styliso",
+                version, MODEL_FORMAT_VERSION
+            ));
+        }
+
+        let width = reader.read_u16()?;
+        let height = reader.read_u16()?;
+        let flags = reader.take(1)?[0];
+        let num_labels = reader.read_u32()?;
+
+        let mut labels = vec![];
+
+        for _ in 0..num_labels {
+            let len = reader.read_u16()? as usize;
+            let name = std::str::from_utf8(reader.take(len)?)
+                .map_err(|_| "Label name is not valid UTF-8".to_owned())?;
+
+            labels.push(name.to_owned());
+        }
+
+        let shape = ImageShape {
+            width,
+            height,
+            colour: flags & 1 != 0,
+            alpha: flags & 2 != 0,
+            num_labels,
+        };
+
+        let encoder = reader.read_network()?;
+        let decoder = reader.read_network()?;
+
+        if !reader.bytes.is_empty() {
+            return Err("Unexpected trailing data after the model".to_owned());
+        }
+
+        if encoder.input_size()? != shape.input_size()
+            || decoder.output_size()? != shape.input_size()
+        {
+            return Err(
+                "The networks of the model do not match the shape of its images".to_owned(),
+            );
+        }
+
+        if decoder.input_size()? != encoder.output_size()? {
+            return Err(format!(
+                "The decoder of the model takes {} latent values, but its encoder outputs {}",
+                decoder.input_size()?,
+                encoder.output_size()?
+            ));
+        }
+
+        Ok(StyleModel {
+            autoencoder: ImageAutoencoder {
+                shape,
+                assembly: AutoencoderAssembly { encoder, decoder },
+            },
+            labels,
+        })
+    }
+
+    /// Writes this model to a file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let path = path.as_ref();
+
+        fs::write(path, self.to_bytes()?)
+            .map_err(|err| format!("Could not save {}: {}", path.display(), err))
+    }
+
+    /// Reads a model from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+
+        let bytes =
+            fs::read(path).map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+
+        Self::from_bytes(&bytes)
+            .map_err(|err| format!("Could not load {}: {}", path.display(), err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model() -> StyleModel {
+        let shape = ImageShape {
+            width: 2,
+            height: 2,
+            colour: true,
+            alpha: false,
+            num_labels: 2,
+        };
+
+        StyleModel {
+            autoencoder: ImageAutoencoder::new(shape, 3, &[4]),
+            labels: vec!["cats".to_owned(), "dogs".to_owned()],
+        }
+    }
+
+    #[test]
+    fn model_roundtrip() {
+        let model = model();
+        let loaded = StyleModel::from_bytes(&model.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(loaded.autoencoder.shape, model.autoencoder.shape);
+        assert_eq!(loaded.labels, model.labels);
+        assert_eq!(loaded.autoencoder.latent_size().unwrap(), 3);
+    }
+
+    #[test]
+    fn model_checks_latent_size() {
+        let mut model = model();
+        let input_size = model.autoencoder.shape.input_size();
+
+        model.autoencoder.assembly.decoder =
+            SimpleNeuralNetwork::new_simple_with_activation(&[2, 4, input_size], None);
+
+        assert!(StyleModel::from_bytes(&model.to_bytes().unwrap()).is_err());
+    }
+}
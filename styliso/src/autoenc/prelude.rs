@@ -1,2 +1,9 @@
 //! An useful working set of basic autoencoder types.
+pub use super::assembly::*;
 pub use super::interface::*;
+pub use super::latents::*;
+pub use super::mixing::*;
+pub use super::model::*;
+pub use super::similarity::*;
+pub use super::tiled::*;
+pub use super::train::*;
@@ -0,0 +1,219 @@
+/*!
+ * Style similarity between sets of images: how far apart the distributions
+ * of their latent vectors lie, as encoded by the same autoencoder. The
+ * further, the more different their styles.
+ */
+use super::assembly::ImageAutoencoder;
+use crate::image::prelude::*;
+use crate::prelude::*;
+use neurs::train::label::TrainingLabel;
+
+/// The mean and covariance of a set of latent vectors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LatentDistribution {
+    /// The mean of each value of the latent vectors.
+    pub mean: Vec<f64>,
+
+    /// The covariance of each pair of values of the latent vectors, row by
+    /// row, as many rows as values.
+    pub covariance: Vec<Vec<f64>>,
+}
+
+/// How far apart two distributions of latent vectors lie. See
+/// [LatentDistribution::distance].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StyleDistance {
+    /// The Euclidean distance between the means.
+    pub mean: f64,
+
+    /// The Frobenius norm of the difference of the covariances, i.e. the
+    /// Euclidean distance between them as flat lists of values.
+    pub covariance: f64,
+
+    /**
+     * The Fréchet distance between normal distributions of the same means
+     * and variances, ignoring covariances between values: the squared
+     * distance between the means plus that between the standard deviations.
+     * A single measure of how different the styles are; 0 if alike.
+     */
+    pub frechet: f64,
+}
+
+impl LatentDistribution {
+    /// Measures the distribution of a set of latent vectors, all of the same
+    /// size. Fails if there are none.
+    pub fn of(latents: &[Vec<f32>]) -> Result<Self, String> {
+        let size = match latents.first() {
+            Some(latent) => latent.len(),
+            None => return Err("No latent vectors to measure".to_owned()),
+        };
+
+        if let Some(latent) = latents.iter().find(|latent| latent.len() != size) {
+            return Err(format!(
+                "Expected latent vectors of size {}, got one of {}",
+                size,
+                latent.len()
+            ));
+        }
+
+        let count = latents.len() as f64;
+
+        let mean: Vec<f64> = (0..size)
+            .map(|i| latents.iter().map(|latent| latent[i] as f64).sum::<f64>() / count)
+            .collect();
+
+        let covariance = (0..size)
+            .map(|i| {
+                (0..size)
+                    .map(|j| {
+                        latents
+                            .iter()
+                            .map(|latent| {
+                                (latent[i] as f64 - mean[i]) * (latent[j] as f64 - mean[j])
+                            })
+                            .sum::<f64>()
+                            / count
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(LatentDistribution { mean, covariance })
+    }
+
+    /// Measures how far apart this distribution lies from another, of
+    /// latent vectors of the same size.
+    pub fn distance(&self, other: &LatentDistribution) -> Result<StyleDistance, String> {
+        if self.mean.len() != other.mean.len() {
+            return Err(format!(
+                "Cannot compare distributions of latent vectors of sizes {} and {}",
+                self.mean.len(),
+                other.mean.len()
+            ));
+        }
+
+        let squared_mean: f64 = self
+            .mean
+            .iter()
+            .zip(&other.mean)
+            .map(|(a, b)| (a - b).powi(2))
+            .sum();
+
+        let covariance = self
+            .covariance
+            .iter()
+            .flatten()
+            .zip(other.covariance.iter().flatten())
+            .map(|(a, b)| (a - b).powi(2))
+            .sum::<f64>()
+            .sqrt();
+
+        let squared_deviation: f64 = (0..self.mean.len())
+            .map(|i| (self.covariance[i][i].sqrt() - other.covariance[i][i].sqrt()).powi(2))
+            .sum();
+
+        Ok(StyleDistance {
+            mean: squared_mean.sqrt(),
+            covariance,
+            frechet: squared_mean + squared_deviation,
+        })
+    }
+}
+
+impl ImageAutoencoder {
+    /// Encodes a set of labeled images, and measures the distribution of
+    /// their latent vectors.
+    pub fn latent_distribution<L: TrainingLabel>(
+        &self,
+        images: &[LabeledImage<L>],
+    ) -> Result<LatentDistribution, String> {
+        let latents = images
+            .iter()
+            .map(|image| self.implode(image))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        LatentDistribution::of(&latents)
+    }
+
+    /**
+     * Measures how stylistically different two sets of labeled images are,
+     * by how far apart the distributions of their latent vectors lie. To
+     * compare the images alone, give them all the same label.
+     */
+    pub fn style_distance<L: TrainingLabel>(
+        &self,
+        a: &[LabeledImage<L>],
+        b: &[LabeledImage<L>],
+    ) -> Result<StyleDistance, String> {
+        self.latent_distribution(a)?
+            .distance(&self.latent_distribution(b)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn distribution_of_latents() {
+        let distribution =
+            LatentDistribution::of(&[vec![1.0, 1.0], vec![3.0, 3.0], vec![2.0, 5.0]]).unwrap();
+
+        assert_eq!(distribution.mean, [2.0, 3.0]);
+
+        let expected = [[2.0 / 3.0, 2.0 / 3.0], [2.0 / 3.0, 8.0 / 3.0]];
+
+        for (row, expected) in distribution.covariance.iter().zip(expected) {
+            for (value, expected) in row.iter().zip(expected) {
+                assert_close(*value, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn distribution_errors() {
+        assert!(LatentDistribution::of(&[]).is_err());
+        assert!(LatentDistribution::of(&[vec![1.0, 2.0], vec![3.0]]).is_err());
+    }
+
+    #[test]
+    fn distance_between_distributions() {
+        let a = LatentDistribution {
+            mean: vec![0.0, 0.0],
+            covariance: vec![vec![1.0, 0.0], vec![0.0, 4.0]],
+        };
+
+        let b = LatentDistribution {
+            mean: vec![3.0, 4.0],
+            covariance: vec![vec![4.0, 1.0], vec![1.0, 4.0]],
+        };
+
+        let distance = a.distance(&b).unwrap();
+
+        assert_close(distance.mean, 5.0);
+        assert_close(distance.covariance, 11.0_f64.sqrt());
+
+        // 25 between the means, plus 1 between the deviations of the first
+        // values and none between those of the second.
+        assert_close(distance.frechet, 26.0);
+
+        let same = a.distance(&a).unwrap();
+
+        assert_eq!(
+            same,
+            StyleDistance {
+                mean: 0.0,
+                covariance: 0.0,
+                frechet: 0.0,
+            }
+        );
+
+        let smaller = LatentDistribution::of(&[vec![1.0]]).unwrap();
+
+        assert!(a.distance(&smaller).is_err());
+    }
+}
@@ -0,0 +1,329 @@
+/*!
+ * Tiled processing of images larger than an [ImageAutoencoder] works with,
+ * e.g. one trained on patches: the image is split into overlapping tiles of
+ * the autoencoder's size, each encoded on its own, and the decoded tiles are
+ * blended back together, fading into each other where they overlap.
+ */
+use super::assembly::ImageAutoencoder;
+use crate::image::prelude::*;
+use crate::prelude::*;
+use neurs::prelude::*;
+
+/// The latent vectors of the tiles of an image, from
+/// [ImageAutoencoder::encode_tiled].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TiledLatents {
+    /// The width of the image.
+    pub width: u16,
+
+    /// The height of the image.
+    pub height: u16,
+
+    /// How many pixels each tile overlaps its neighbours by.
+    pub overlap: u16,
+
+    /// The latent vector of each tile, row by row.
+    pub tiles: Vec<Vec<f32>>,
+}
+
+/**
+ * Where tiles start along one side of an image, so that they cover it, each
+ * overlapping the one before by at least `overlap`. The last tile ends at
+ * the end of the side. The side must be at least as long as the tiles.
+ */
+fn tile_starts(side: u16, tile: u16, overlap: u16) -> Vec<u16> {
+    let step = (tile - overlap).max(1) as usize;
+    let mut starts: Vec<u16> = (0..=side - tile).step_by(step).collect();
+
+    if starts.last() != Some(&(side - tile)) {
+        starts.push(side - tile);
+    }
+
+    starts
+}
+
+/// How much a pixel of a tile weighs when blending, by its position along
+/// one side: less the closer it is to the edge, within the overlap.
+fn feather(pos: u16, tile: u16, overlap: u16) -> f32 {
+    (pos + 1).min(tile - pos).min(overlap + 1) as f32
+}
+
+impl ImageAutoencoder {
+    /// The size tiled images are processed at: padded up to the size of a
+    /// tile if smaller.
+    fn tiled_size(&self, width: u16, height: u16) -> (u16, u16) {
+        (width.max(self.shape.width), height.max(self.shape.height))
+    }
+
+    /// Where each tile of an image of a size starts, row by row.
+    fn tile_origins(&self, width: u16, height: u16, overlap: u16) -> Vec<(u16, u16)> {
+        let (width, height) = self.tiled_size(width, height);
+        let lefts = tile_starts(width, self.shape.width, overlap);
+
+        tile_starts(height, self.shape.height, overlap)
+            .into_iter()
+            .flat_map(|top| lefts.iter().map(move |&left| (left, top)))
+            .collect()
+    }
+
+    /**
+     * Encodes a labeled image of any size tile by tile, each tile of the size
+     * this autoencoder works with and labeled like the image, overlapping
+     * its neighbours by `overlap` pixels, which must be less than the size
+     * of a tile. Images smaller than a tile are padded up to it.
+     */
+    pub fn encode_tiled<L: TrainingLabel>(
+        &self,
+        image: &LabeledImage<L>,
+        overlap: u16,
+    ) -> Result<TiledLatents, String> {
+        if overlap >= self.shape.width || overlap >= self.shape.height {
+            return Err(format!(
+                "Tiles of {}x{} cannot overlap by {}",
+                self.shape.width, self.shape.height, overlap
+            ));
+        }
+
+        let (width, height) = (image.img.width, image.img.height);
+        let (padded_width, padded_height) = self.tiled_size(width, height);
+        let padded = image.img.pad(padded_width, padded_height)?;
+
+        let tiles = self
+            .tile_origins(width, height, overlap)
+            .into_iter()
+            .map(|(left, top)| {
+                let tile = padded.crop(left, top, self.shape.width, self.shape.height)?;
+                self.implode(&image.with_image(tile))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(TiledLatents {
+            width,
+            height,
+            overlap,
+            tiles,
+        })
+    }
+
+    /**
+     * Reconstructs a labeled image through this autoencoder: encodes it and
+     * decodes it back, tile by tile if it is not of the size this
+     * autoencoder works with (see [ImageAutoencoder::encode_tiled]), with
+     * tiles overlapping by a quarter.
+     */
+    pub fn reconstruct<L: TrainingLabel>(
+        &self,
+        image: &LabeledImage<L>,
+    ) -> Result<LabeledImage<L>, String> {
+        if (image.img.width, image.img.height) == (self.shape.width, self.shape.height) {
+            return self.explode(&self.implode(image)?);
+        }
+
+        let overlap = self.shape.width.min(self.shape.height) / 4;
+        self.decode_tiled(&self.encode_tiled(image, overlap)?)
+    }
+
+    /**
+     * Decodes the tiles of an image, encoded with
+     * [ImageAutoencoder::encode_tiled], and blends them back into the whole
+     * image. Its labels are told by the label values of all tiles together.
+     */
+    pub fn decode_tiled<L: TrainingLabel>(
+        &self,
+        latents: &TiledLatents,
+    ) -> Result<LabeledImage<L>, String> {
+        let origins = self.tile_origins(latents.width, latents.height, latents.overlap);
+
+        if origins.len() != latents.tiles.len() {
+            return Err(format!(
+                "Expected {} tiles for a {}x{} image, got {}",
+                origins.len(),
+                latents.width,
+                latents.height,
+                latents.tiles.len()
+            ));
+        }
+
+        let (width, height) = self.tiled_size(latents.width, latents.height);
+        let (tile_width, tile_height) = (self.shape.width, self.shape.height);
+        let num_labels = self.shape.num_labels;
+
+        let mut blended = ImageData::blank(width, height, self.shape.colour);
+        let area = blended.area as usize;
+
+        // hues are blended as vectors, along the shortest way around
+        let mut hue_sin = vec![0.0; area];
+        let mut hue_cos = vec![0.0; area];
+        let mut alpha = vec![0.0; area];
+        let mut weights = vec![0.0; area];
+        let mut label_values = vec![0.0; num_labels];
+
+        for (&(left, top), latent) in origins.iter().zip(&latents.tiles) {
+            let output = self.assembly.decode(latent)?;
+
+            let mut tile =
+                LabeledImage::<L>::new(L::from_index(0), self.shape.blank_image(), num_labels);
+            tile.decode_from(&output)?;
+
+            for (sum, value) in label_values
+                .iter_mut()
+                .zip(&output[output.len() - num_labels..])
+            {
+                *sum += value;
+            }
+
+            for y in 0..tile_height {
+                for x in 0..tile_width {
+                    let src = y as usize * tile_width as usize + x as usize;
+                    let dst = (top + y) as usize * width as usize + (left + x) as usize;
+
+                    let weight = feather(x, tile_width, latents.overlap)
+                        * feather(y, tile_height, latents.overlap);
+
+                    weights[dst] += weight;
+                    blended.brightness[dst] += weight * tile.img.brightness[src];
+
+                    if let (Some((_, saturation)), Some((tile_hue, tile_saturation))) =
+                        (&mut blended.colour, &tile.img.colour)
+                    {
+                        let angle = tile_hue[src].to_radians();
+
+                        hue_sin[dst] += weight * angle.sin();
+                        hue_cos[dst] += weight * angle.cos();
+                        saturation[dst] += weight * tile_saturation[src];
+                    }
+
+                    if let Some(tile_alpha) = &tile.img.alpha {
+                        alpha[dst] += weight * tile_alpha[src];
+                    }
+                }
+            }
+        }
+
+        for (index, weight) in weights.iter().enumerate() {
+            blended.brightness[index] /= weight;
+
+            if let Some((hue, saturation)) = &mut blended.colour {
+                hue[index] = f32::atan2(hue_sin[index], hue_cos[index])
+                    .to_degrees()
+                    .rem_euclid(360.0);
+                saturation[index] /= weight;
+            }
+
+            alpha[index] /= weight;
+        }
+
+        if self.shape.alpha {
+            blended.alpha = Some(alpha);
+        }
+
+        let blended = blended.center_crop(latents.width, latents.height)?;
+
+        // the image is decoded again along with the label values, to tell
+        // the labels like a single decoded image would
+        let mut output = blended.encode().map_err(str::to_owned)?;
+        output.extend(
+            label_values
+                .iter()
+                .map(|value| value / origins.len() as f32),
+        );
+
+        let mut image = LabeledImage::new(L::from_index(0), blended, num_labels);
+        image.decode_from(&output)?;
+
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An autoencoder of 4x4 grayscale images with one label, whose latent
+    /// vectors are its inputs, so that it reconstructs images exactly.
+    fn identity_autoencoder() -> ImageAutoencoder {
+        let shape = ImageShape {
+            width: 4,
+            height: 4,
+            colour: false,
+            alpha: false,
+            num_labels: 1,
+        };
+
+        let size = shape.input_size();
+        let mut autoencoder = ImageAutoencoder::new(shape, size, &[]);
+
+        for network in [
+            &mut autoencoder.assembly.encoder,
+            &mut autoencoder.assembly.decoder,
+        ] {
+            let layer = &mut network.layers[0];
+
+            *layer.activation = activations::identity;
+            layer.biases.fill(0.0);
+
+            for (index, weight) in layer.weights.iter_mut().enumerate() {
+                *weight = if index % (size + 1) == 0 { 1.0 } else { 0.0 };
+            }
+        }
+
+        autoencoder
+    }
+
+    fn gradient(width: u16, height: u16) -> LabeledImage<usize> {
+        LabeledImage::new(0, ImageData::gradient(width, height), 1)
+    }
+
+    #[test]
+    fn tile_starts_cover_side() {
+        assert_eq!(tile_starts(10, 4, 1), [0, 3, 6]);
+        assert_eq!(tile_starts(10, 4, 2), [0, 2, 4, 6]);
+        assert_eq!(tile_starts(11, 4, 1), [0, 3, 6, 7]);
+        assert_eq!(tile_starts(4, 4, 3), [0]);
+    }
+
+    #[test]
+    fn feather_fades_into_overlap() {
+        let weights: Vec<f32> = (0..6).map(|pos| feather(pos, 6, 2)).collect();
+
+        assert_eq!(weights, [1.0, 2.0, 3.0, 3.0, 2.0, 1.0]);
+    }
+
+    #[test]
+    fn tiled_roundtrip() {
+        let autoencoder = identity_autoencoder();
+
+        for (width, height) in [(10, 7), (3, 2), (4, 4)] {
+            let image = gradient(width, height);
+
+            let latents = autoencoder.encode_tiled(&image, 1).unwrap();
+            assert_eq!((latents.width, latents.height), (width, height));
+
+            let decoded: LabeledImage<usize> = autoencoder.decode_tiled(&latents).unwrap();
+
+            assert_eq!((decoded.img.width, decoded.img.height), (width, height));
+
+            for (a, b) in decoded.img.brightness.iter().zip(&image.img.brightness) {
+                assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+            }
+        }
+
+        let latents = autoencoder.encode_tiled(&gradient(10, 7), 1).unwrap();
+
+        // 3 tiles across and 2 down
+        assert_eq!(latents.tiles.len(), 6);
+    }
+
+    #[test]
+    fn tiled_errors() {
+        let autoencoder = identity_autoencoder();
+        let image = gradient(10, 7);
+
+        assert!(autoencoder.encode_tiled(&image, 4).is_err());
+
+        let mut latents = autoencoder.encode_tiled(&image, 1).unwrap();
+        latents.tiles.pop();
+
+        assert!(autoencoder.decode_tiled::<usize>(&latents).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+/*!
+ * Training of [ImageAutoencoder]s to reconstruct labeled images, through
+ * [neurs]'s [Trainer].
+ */
+use super::assembly::ImageAutoencoder;
+use crate::image::prelude::*;
+use crate::prelude::*;
+use neurs::prelude::*;
+use rand::Rng;
+
+/// Options for [train].
+#[derive(Clone, Copy, Debug)]
+pub struct TrainOptions {
+    /// How many epochs to train for.
+    pub epochs: usize,
+
+    /// How many jittered copies of the autoencoder are tried per epoch.
+    pub num_jitters: usize,
+
+    /// How much the weights are randomized in each jittered copy.
+    pub jitter_width: f32,
+
+    /// How much of the jitter width is culled away with each epoch.
+    pub jitter_width_falloff: f32,
+
+    /// How much the weights are adjusted after each epoch.
+    pub step_factor: f32,
+
+    /// The random changes made to the images every epoch.
+    pub augmentation: Augmentation,
+
+    /// If given, the width and height of the patches to train on, sampled
+    /// anew from random places of each image every epoch, rather than on
+    /// whole images. The autoencoder must work with images of this size.
+    pub patch_size: Option<(u16, u16)>,
+}
+
+impl Default for TrainOptions {
+    fn default() -> Self {
+        TrainOptions {
+            epochs: 100,
+            num_jitters: 20,
+            jitter_width: 0.1,
+            jitter_width_falloff: 0.01,
+            step_factor: 0.5,
+            augmentation: Augmentation::default(),
+            patch_size: None,
+        }
+    }
+}
+
+/**
+ * Builds a frame which rates an autoencoder by how well it reconstructs a set
+ * of labeled images, by the mean squared error of each value, optionally
+ * weighing each image's error.
+ *
+ * Every image must have the shape the autoencoder works with.
+ */
+pub fn reconstruction_frame<L: TrainingLabel>(
+    autoencoder: &ImageAutoencoder,
+    images: &[LabeledImage<L>],
+    weights: Option<&[f32]>,
+) -> Result<AutoencoderFrame, String> {
+    if images.is_empty() {
+        return Err("No images to train with".to_owned());
+    }
+
+    let mut cases = Vec::with_capacity(images.len());
+
+    for image in images {
+        autoencoder.check_shape(image)?;
+        cases.push(image.encode().map_err(str::to_owned)?);
+    }
+
+    let squared: DistanceWrapper = |x| x * x;
+
+    let mut frame = AutoencoderFrame::new(cases, Some(Box::new(squared)), None);
+
+    if let Some(weights) = weights {
+        frame.set_case_weights(weights.to_vec())?;
+    }
+
+    Ok(frame)
+}
+
+/**
+ * Measures how well an autoencoder reconstructs a set of labeled images, on
+ * average; see [ImageMetrics::mean] and [ImageAutoencoder::reconstruct].
+ */
+pub fn reconstruction_metrics<L: TrainingLabel>(
+    autoencoder: &ImageAutoencoder,
+    images: &[LabeledImage<L>],
+) -> Result<ImageMetrics, String> {
+    let metrics = images
+        .iter()
+        .map(|image| {
+            let reconstructed: LabeledImage<L> = autoencoder.reconstruct(image)?;
+            ImageMetrics::compare(&image.img, &reconstructed.img)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    ImageMetrics::mean(&metrics).ok_or_else(|| "No images to measure".to_owned())
+}
+
+/// Crops a patch of a size out of a random place of an image.
+fn random_patch<R: Rng>(image: &ImageData, (width, height): (u16, u16), rng: &mut R) -> ImageData {
+    let left = rng.gen_range(0..=image.width - width);
+    let top = rng.gen_range(0..=image.height - height);
+
+    image.crop(left, top, width, height).unwrap()
+}
+
+/**
+ * Trains an autoencoder to reconstruct a set of labeled images, optionally
+ * weighted (see [reconstruction_frame]), with the weight jitter strategy.
+ * `on_epoch` is called after each epoch with its number, from 0, the best
+ * fitness found in it, and the autoencoder as trained so far, e.g. to preview
+ * its reconstructions.
+ *
+ * If [TrainOptions::augmentation] makes any change, each epoch trains on
+ * freshly augmented copies of the images, and its fitness is theirs. So it
+ * does with [TrainOptions::patch_size], on random patches of the images,
+ * which must all be at least as large as the patches.
+ *
+ * Returns the fitness of the last epoch; the closer to 0, the better.
+ */
+pub fn train<L: TrainingLabel>(
+    autoencoder: &mut ImageAutoencoder,
+    images: &[LabeledImage<L>],
+    weights: Option<&[f32]>,
+    options: &TrainOptions,
+    mut on_epoch: impl FnMut(usize, f32, &ImageAutoencoder),
+) -> Result<f32, String> {
+    let mut frame = match options.patch_size {
+        Some((width, height)) => {
+            let patches = images
+                .iter()
+                .map(|image| {
+                    image
+                        .img
+                        .center_crop(width, height)
+                        .map(|img| image.with_image(img))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| format!("Images must be at least as large as patches: {}", err))?;
+
+            reconstruction_frame(autoencoder, &patches, weights)?
+        }
+
+        None => reconstruction_frame(autoencoder, images, weights)?,
+    };
+
+    let mut strategy = WeightJitterStrat::new(WeightJitterStratOptions {
+        num_jitters: options.num_jitters,
+        apply_bad_jitters: true,
+        adaptive_jitter_width: None::<fn(f32, f32, f32) -> f32>,
+        jitter_width: options.jitter_width,
+        jitter_width_falloff: options.jitter_width_falloff,
+        step_factor: options.step_factor,
+        num_steps_per_epoch: 1,
+    });
+
+    let mut fitness = 0.0;
+
+    let mut rng = rand::thread_rng();
+
+    for epoch in 0..options.epochs {
+        if !options.augmentation.is_none() || options.patch_size.is_some() {
+            let cases = images
+                .iter()
+                .map(|image| {
+                    let patch;
+
+                    let img = match options.patch_size {
+                        Some(size) => {
+                            patch = random_patch(&image.img, size, &mut rng);
+                            &patch
+                        }
+
+                        None => &image.img,
+                    };
+
+                    image
+                        .with_image(options.augmentation.apply(img, &mut rng))
+                        .encode()
+                        .map_err(str::to_owned)
+                })
+                .collect::<Result<_, _>>()?;
+
+            frame.set_training_cases(Some(cases))?;
+        }
+
+        // the trainer borrows the autoencoder only for the epoch, so that
+        // on_epoch can see it
+        let mut trainer = Trainer::new(&mut autoencoder.assembly, frame, strategy);
+        fitness = trainer.epoch()?;
+        (frame, strategy) = (trainer.frame, trainer.strategy);
+
+        on_epoch(epoch, fitness, autoencoder);
+    }
+
+    Ok(fitness)
+}
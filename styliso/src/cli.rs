@@ -0,0 +1,790 @@
+/*!
+ * The command line interface of styliso, with a subcommand to train a model
+ * and one for each thing a trained model can do.
+ */
+use crate::image::png::PngDepth;
+use crate::prelude::*;
+use clap::{Parser, Subcommand};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Isolates the styles of sets of images with an autoencoder.
+#[derive(Parser)]
+#[command(version)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// A subcommand of styliso. See [Args::command].
+#[derive(Subcommand)]
+pub enum Command {
+    /// Train a model on a dataset: a directory with a subdirectory per label,
+    /// holding images of the same size (unless --size is given), or a CSV or
+    /// JSON manifest listing them.
+    Train {
+        /// The dataset directory or manifest.
+        #[arg(long, value_name = "PATH")]
+        dataset: PathBuf,
+
+        /// Fit every image to this size, e.g. 64x64, as it is loaded.
+        #[arg(long, value_name = "WxH", value_parser = parse_size)]
+        size: Option<(u16, u16)>,
+
+        /// How to fit images of other aspect ratios to --size: stretch, crop
+        /// or pad.
+        #[arg(long, value_name = "MODE", default_value = "stretch")]
+        fit: FitMode,
+
+        /// How to resample images fitted to --size: nearest or bilinear.
+        #[arg(long, value_name = "FILTER", default_value = "bilinear")]
+        filter: ResizeFilter,
+
+        /// Randomly change the images every epoch, e.g.
+        /// flip,rotate=10,brightness=0.1,saturation=0.1,noise=0.02, or none;
+        /// overrides the augmentation a JSON manifest gives.
+        #[arg(long, value_name = "CHANGES")]
+        augment: Option<Augmentation>,
+
+        /// Train on random patches of this size, e.g. 32x32, sampled from the
+        /// images anew every epoch, rather than on whole images; the model
+        /// then works with images of this size.
+        #[arg(long, value_name = "WxH", value_parser = parse_size)]
+        patch: Option<(u16, u16)>,
+
+        /// How many threads to load images with; as many as the machine can
+        /// run in parallel by default.
+        #[arg(long, value_name = "N", default_value_t = 0, hide_default_value = true)]
+        threads: usize,
+
+        /// Where to save the trained model.
+        #[arg(long, value_name = "PATH", default_value = "model.bin")]
+        out: PathBuf,
+
+        /// The size of the latent vectors images are encoded into.
+        #[arg(long, value_name = "N", default_value_t = 32)]
+        latent_size: usize,
+
+        /// The sizes of the hidden layers of the encoder, from the input
+        /// inward, separated by commas.
+        #[arg(
+            long,
+            value_name = "SIZES",
+            value_delimiter = ',',
+            default_value = "256"
+        )]
+        hidden: Vec<usize>,
+
+        /// How many epochs to train for.
+        #[arg(long, value_name = "N", default_value_t = 100)]
+        epochs: usize,
+
+        /// Save a reconstruction of a preview image to this directory before
+        /// training, every --preview-every epochs, and after the last one.
+        #[arg(long, value_name = "DIR")]
+        preview_dir: Option<PathBuf>,
+
+        /// How many epochs to train between previews.
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        preview_every: usize,
+
+        /// The image to preview; the first of the dataset by default.
+        #[arg(long, value_name = "PATH")]
+        preview_image: Option<PathBuf>,
+
+        /// The label to preview the image with; its own by default, or the
+        /// first label if given with --preview-image.
+        #[arg(long, value_name = "LABEL")]
+        preview_label: Option<String>,
+    },
+
+    /// Encode an image into a latent vector, printed as comma-separated
+    /// numbers.
+    Encode {
+        /// The image to encode.
+        image: PathBuf,
+
+        /// The label to encode the image with; the first label by default.
+        #[arg(long)]
+        label: Option<String>,
+
+        /// Fit the image to the size the model works with, if it differs:
+        /// stretch, crop or pad.
+        #[arg(long, value_name = "MODE")]
+        fit: Option<FitMode>,
+
+        /// The trained model.
+        #[arg(long, value_name = "PATH", default_value = "model.bin")]
+        model: PathBuf,
+    },
+
+    /// Decode a latent vector, given as comma-separated numbers, into an
+    /// image, and print the label it was decoded with.
+    Decode {
+        /// The latent vector.
+        #[arg(value_delimiter = ',', allow_negative_numbers = true)]
+        vector: Vec<f32>,
+
+        /// Where to save the image; its format is told by its extension.
+        #[arg(long, value_name = "PATH", default_value = "out.png")]
+        out: PathBuf,
+
+        /// How many bits each sample takes, if saved as PNG: 8 or 16.
+        #[arg(long, value_name = "BITS", default_value = "8")]
+        png_depth: PngDepth,
+
+        /// The trained model.
+        #[arg(long, value_name = "PATH", default_value = "model.bin")]
+        model: PathBuf,
+    },
+
+    /// Generate a new image in the style of a label, from random image data.
+    Generate {
+        /// The label whose style to mimick.
+        #[arg(long)]
+        label: String,
+
+        /// How much the random image data strays from flat gray, from 0 to 1.
+        #[arg(long, value_name = "SCALE", default_value_t = 1.0)]
+        noise_scale: f32,
+
+        /// Where to save the image; its format is told by its extension.
+        #[arg(long, value_name = "PATH", default_value = "out.png")]
+        out: PathBuf,
+
+        /// How many bits each sample takes, if saved as PNG: 8 or 16.
+        #[arg(long, value_name = "BITS", default_value = "8")]
+        png_depth: PngDepth,
+
+        /// The trained model.
+        #[arg(long, value_name = "PATH", default_value = "model.bin")]
+        model: PathBuf,
+    },
+
+    /// Encode every image of a dataset, and save their latent vectors along
+    /// with their paths and labels, as CSV, JSON or NPY.
+    Export {
+        /// The dataset directory or manifest.
+        #[arg(long, value_name = "PATH")]
+        dataset: PathBuf,
+
+        /// How to fit images to the size the model works with, if they
+        /// differ: stretch, crop or pad.
+        #[arg(long, value_name = "MODE", default_value = "stretch")]
+        fit: FitMode,
+
+        /// Where to save the latent vectors; the format is told by the
+        /// extension.
+        #[arg(long, value_name = "PATH", default_value = "latents.csv")]
+        out: PathBuf,
+
+        /// The trained model.
+        #[arg(long, value_name = "PATH", default_value = "model.bin")]
+        model: PathBuf,
+    },
+
+    /// Restyle an image with a mix of labels, e.g. halfway between two.
+    Mix {
+        /// The image to restyle.
+        image: PathBuf,
+
+        /// The labels to mix, each with its weight, e.g. cats=0.5,dogs=0.5; a
+        /// label without one weighs 1.
+        #[arg(
+            long,
+            value_name = "LABEL=WEIGHT",
+            value_delimiter = ',',
+            value_parser = parse_label_weight,
+            required = true
+        )]
+        labels: Vec<(String, f32)>,
+
+        /// Fit the image to the size the model works with, if it differs:
+        /// stretch, crop or pad.
+        #[arg(long, value_name = "MODE")]
+        fit: Option<FitMode>,
+
+        /// Where to save the image; its format is told by its extension.
+        #[arg(long, value_name = "PATH", default_value = "out.png")]
+        out: PathBuf,
+
+        /// How many bits each sample takes, if saved as PNG: 8 or 16.
+        #[arg(long, value_name = "BITS", default_value = "8")]
+        png_depth: PngDepth,
+
+        /// The trained model.
+        #[arg(long, value_name = "PATH", default_value = "model.bin")]
+        model: PathBuf,
+    },
+
+    /// Measure how alike two images of the same size are: the mean squared
+    /// error and PSNR of their colours, and the SSIM of their luma.
+    Compare {
+        /// One image.
+        a: PathBuf,
+
+        /// The other image.
+        b: PathBuf,
+    },
+
+    /// Measure how stylistically different two directories of images are,
+    /// by how far apart their latent vectors lie.
+    Distance {
+        /// One directory of images, searched recursively.
+        a: PathBuf,
+
+        /// The other directory of images, searched recursively.
+        b: PathBuf,
+
+        /// The label to encode all images with; the first label by default.
+        #[arg(long)]
+        label: Option<String>,
+
+        /// How to fit images to the size the model works with, if they
+        /// differ: stretch, crop or pad.
+        #[arg(long, value_name = "MODE", default_value = "stretch")]
+        fit: FitMode,
+
+        /// The trained model.
+        #[arg(long, value_name = "PATH", default_value = "model.bin")]
+        model: PathBuf,
+    },
+
+    /// Reconstruct an image of any size through the model, tile by tile,
+    /// with tiles of the size the model works with, blended together.
+    Reconstruct {
+        /// The image to reconstruct.
+        image: PathBuf,
+
+        /// The label to encode the image with; the first label by default.
+        #[arg(long)]
+        label: Option<String>,
+
+        /// How many pixels each tile overlaps its neighbours by; a quarter of
+        /// a tile by default.
+        #[arg(long, value_name = "PIXELS")]
+        overlap: Option<u16>,
+
+        /// Where to save the image; its format is told by its extension.
+        #[arg(long, value_name = "PATH", default_value = "out.png")]
+        out: PathBuf,
+
+        /// How many bits each sample takes, if saved as PNG: 8 or 16.
+        #[arg(long, value_name = "BITS", default_value = "8")]
+        png_depth: PngDepth,
+
+        /// The trained model.
+        #[arg(long, value_name = "PATH", default_value = "model.bin")]
+        model: PathBuf,
+    },
+}
+
+/// Parses a size given as WxH, e.g. 64x64.
+fn parse_size(size: &str) -> Result<(u16, u16), String> {
+    let parsed = size
+        .split_once('x')
+        .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+
+    match parsed {
+        Some((width, height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(format!("Invalid size {:?}; expected WxH, e.g. 64x64", size)),
+    }
+}
+
+/// Parses a label and its weight, given as LABEL=WEIGHT or as LABEL alone.
+fn parse_label_weight(spec: &str) -> Result<(String, f32), String> {
+    match spec.split_once('=') {
+        Some((label, weight)) => match weight.parse() {
+            Ok(weight) => Ok((label.to_owned(), weight)),
+            Err(_) => Err(format!("Invalid weight {:?} for {}", weight, label)),
+        },
+
+        None => Ok((spec.to_owned(), 1.0)),
+    }
+}
+
+/// Where and how often to save previews of reconstructions while training.
+struct PreviewOptions {
+    dir: PathBuf,
+    every: usize,
+    image: Option<PathBuf>,
+    label: Option<String>,
+}
+
+impl PreviewOptions {
+    /// The labeled image to preview with a dataset.
+    fn image(&self, dataset: &Dataset) -> Result<LabeledImage<usize>, String> {
+        let first = &dataset.images[0];
+
+        let mut image = match &self.image {
+            Some(path) => LabeledImage::new(0, ImageData::open(path)?, dataset.labels.len()),
+            None => first.with_image(first.img.clone()),
+        };
+
+        if let Some(label) = &self.label {
+            image.label = dataset
+                .labels
+                .iter()
+                .position(|name| name == label)
+                .ok_or_else(|| format!("Unknown preview label {:?}", label))?;
+            image.extra_labels.clear();
+        }
+
+        Ok(image)
+    }
+
+    /// Saves the reconstruction of an image after an epoch, 0 being before
+    /// training.
+    fn save(
+        &self,
+        autoencoder: &ImageAutoencoder,
+        image: &LabeledImage<usize>,
+        epoch: usize,
+    ) -> Result<(), String> {
+        let reconstructed = autoencoder.reconstruct(image)?;
+
+        reconstructed
+            .img
+            .save(self.dir.join(format!("epoch_{:04}.png", epoch)))
+    }
+}
+
+/// Describes image metrics, e.g. `MSE 0.01, PSNR 20.00 dB, SSIM 0.800`.
+fn describe_metrics(metrics: &ImageMetrics) -> String {
+    format!(
+        "MSE {:.5}, PSNR {:.2} dB, SSIM {:.3}",
+        metrics.mse, metrics.psnr, metrics.ssim
+    )
+}
+
+fn train_command(
+    dataset: &Path,
+    dataset_options: &DatasetOptions,
+    out: &Path,
+    latent_size: usize,
+    hidden: &[usize],
+    options: TrainOptions,
+    preview: Option<&PreviewOptions>,
+) -> Result<(), String> {
+    let mut reported = false;
+
+    let dataset = Dataset::open(dataset, dataset_options, |done, total, _| {
+        eprint!("\rLoading images: {}/{}", done, total);
+        reported = true;
+    });
+
+    if reported {
+        eprintln!();
+    }
+
+    let dataset: Dataset = dataset?;
+    let stats = dataset.load_stats;
+
+    eprintln!(
+        "Loaded {} files ({:.1} MB) in {:.1?}: {:.1} files/s, {:.1} MB/s",
+        stats.files,
+        stats.bytes as f64 / 1e6,
+        stats.elapsed,
+        stats.files_per_second(),
+        stats.megabytes_per_second()
+    );
+
+    for file in &dataset.skipped {
+        eprintln!("WARN: Skipped {}: {}", file.path.display(), file.reason);
+    }
+
+    for (label, count) in dataset.labels.iter().zip(dataset.label_counts()) {
+        eprintln!("{}: {} images", label, count);
+    }
+
+    let mut shape = dataset.shape();
+
+    if let Some((width, height)) = options.patch_size {
+        (shape.width, shape.height) = (width, height);
+    }
+
+    let mut autoencoder = ImageAutoencoder::new(shape, latent_size, hidden);
+
+    let options = TrainOptions {
+        augmentation: dataset.augmentation,
+        ..options
+    };
+
+    // the preview before training is saved right away, so that a bad preview
+    // image fails before training rather than after every epoch
+    let preview = match preview {
+        Some(preview) => {
+            let image = preview.image(&dataset)?;
+
+            fs::create_dir_all(&preview.dir)
+                .map_err(|err| format!("Could not create {}: {}", preview.dir.display(), err))?;
+            preview.save(&autoencoder, &image, 0)?;
+
+            Some((preview, image))
+        }
+
+        None => None,
+    };
+
+    let started = Instant::now();
+
+    let fitness = train(
+        &mut autoencoder,
+        &dataset.images,
+        Some(&dataset.weights),
+        &options,
+        |epoch, fitness, autoencoder| {
+            let done = epoch + 1;
+            let elapsed = started.elapsed();
+            let left = elapsed.mul_f64((options.epochs - done) as f64 / done as f64);
+
+            eprintln!(
+                "Epoch {}/{}: fitness {} ({:.1?} elapsed, about {:.1?} left)",
+                done, options.epochs, fitness, elapsed, left
+            );
+
+            if let Some((preview, image)) = &preview {
+                if done % preview.every.max(1) == 0 || done == options.epochs {
+                    if let Err(err) = preview.save(autoencoder, image, done) {
+                        eprintln!("WARN: Could not save preview: {}", err);
+                    }
+                }
+            }
+        },
+    )?;
+
+    eprintln!("Trained to a fitness of {}", fitness);
+
+    let metrics = reconstruction_metrics(&autoencoder, &dataset.images)?;
+    eprintln!("Reconstructions: {}", describe_metrics(&metrics));
+
+    StyleModel {
+        autoencoder,
+        labels: dataset.labels,
+    }
+    .save(out)
+}
+
+fn encode_command(
+    image: &Path,
+    label: Option<&str>,
+    fit: Option<FitMode>,
+    model: &Path,
+) -> Result<(), String> {
+    let model = StyleModel::load(model)?;
+    let shape = model.autoencoder.shape;
+
+    let label = match label {
+        Some(label) => model.label_index(label)?,
+        None => 0,
+    };
+
+    let mut img = ImageData::open(image)?;
+
+    if let Some(fit) = fit {
+        img = img.fit(shape.width, shape.height, fit, ResizeFilter::Bilinear);
+    }
+
+    let image = LabeledImage::new(label, img, model.labels.len());
+    let latent = model.autoencoder.implode(&image)?;
+
+    let numbers: Vec<String> = latent.iter().map(f32::to_string).collect();
+    println!("{}", numbers.join(","));
+
+    Ok(())
+}
+
+fn decode_command(
+    vector: &[f32],
+    out: &Path,
+    options: &SaveOptions,
+    model: &Path,
+) -> Result<(), String> {
+    let model = StyleModel::load(model)?;
+
+    let image: LabeledImage<usize> = model.autoencoder.explode(vector)?;
+
+    image.img.save_with(out, options)?;
+    println!("{}", model.labels[image.label]);
+
+    Ok(())
+}
+
+fn generate_command(
+    label: &str,
+    noise_scale: f32,
+    out: &Path,
+    options: &SaveOptions,
+    model: &Path,
+) -> Result<(), String> {
+    let model = StyleModel::load(model)?;
+
+    let generated = model.autoencoder.generate(
+        model.label_index(label)?,
+        noise_scale,
+        &mut rand::thread_rng(),
+    )?;
+
+    generated.save_with(out, options)
+}
+
+fn export_command(dataset: &Path, fit: FitMode, out: &Path, model: &Path) -> Result<(), String> {
+    let model = StyleModel::load(model)?;
+    let shape = model.autoencoder.shape;
+
+    let options = DatasetOptions {
+        size: Some((shape.width, shape.height)),
+        fit,
+        ..Default::default()
+    };
+
+    let dataset: Dataset = Dataset::open(dataset, &options, |_, _, _| {})?;
+
+    for file in &dataset.skipped {
+        eprintln!("WARN: Skipped {}: {}", file.path.display(), file.reason);
+    }
+
+    let records = model.encode_dataset(&dataset)?;
+    save_latents(out, &records, &model.labels)?;
+
+    eprintln!(
+        "Saved {} latent vectors to {}",
+        records.len(),
+        out.display()
+    );
+
+    Ok(())
+}
+
+fn mix_command(
+    image: &Path,
+    labels: &[(String, f32)],
+    fit: Option<FitMode>,
+    out: &Path,
+    options: &SaveOptions,
+    model: &Path,
+) -> Result<(), String> {
+    let model = StyleModel::load(model)?;
+    let shape = model.autoencoder.shape;
+
+    let mut weights = vec![0.0; model.labels.len()];
+
+    for (label, weight) in labels {
+        weights[model.label_index(label)?] += weight;
+    }
+
+    let mut img = ImageData::open(image)?;
+
+    if let Some(fit) = fit {
+        img = img.fit(shape.width, shape.height, fit, ResizeFilter::Bilinear);
+    }
+
+    let mixed: LabeledImage<usize> = model.autoencoder.mix_labels(&img, &weights)?;
+
+    mixed.img.save_with(out, options)
+}
+
+fn compare_command(a: &Path, b: &Path) -> Result<(), String> {
+    let metrics = ImageMetrics::compare(&ImageData::open(a)?, &ImageData::open(b)?)?;
+    println!("{}", describe_metrics(&metrics));
+
+    Ok(())
+}
+
+fn distance_command(
+    a: &Path,
+    b: &Path,
+    label: Option<&str>,
+    fit: FitMode,
+    model: &Path,
+) -> Result<(), String> {
+    let model = StyleModel::load(model)?;
+    let shape = model.autoencoder.shape;
+
+    let label = match label {
+        Some(label) => model.label_index(label)?,
+        None => 0,
+    };
+
+    let options = DatasetOptions {
+        size: Some((shape.width, shape.height)),
+        fit,
+        ..Default::default()
+    };
+
+    let load = |dir: &Path| {
+        let (images, skipped) = load_images(dir, &options);
+
+        for file in &skipped {
+            eprintln!("WARN: Skipped {}: {}", file.path.display(), file.reason);
+        }
+
+        if images.is_empty() {
+            return Err(format!("No images could be loaded from {}", dir.display()));
+        }
+
+        Ok(images
+            .into_iter()
+            .map(|img| LabeledImage::new(label, img, model.labels.len()))
+            .collect::<Vec<_>>())
+    };
+
+    let distance = model.autoencoder.style_distance(&load(a)?, &load(b)?)?;
+
+    println!(
+        "Fréchet distance {:.5} (means {:.5} apart, covariances {:.5} apart)",
+        distance.frechet, distance.mean, distance.covariance
+    );
+
+    Ok(())
+}
+
+fn reconstruct_command(
+    image: &Path,
+    label: Option<&str>,
+    overlap: Option<u16>,
+    out: &Path,
+    options: &SaveOptions,
+    model: &Path,
+) -> Result<(), String> {
+    let model = StyleModel::load(model)?;
+    let shape = model.autoencoder.shape;
+    let overlap = overlap.unwrap_or(shape.width.min(shape.height) / 4);
+
+    let label = match label {
+        Some(label) => model.label_index(label)?,
+        None => 0,
+    };
+
+    let image = LabeledImage::new(label, ImageData::open(image)?, model.labels.len());
+    let latents = model.autoencoder.encode_tiled(&image, overlap)?;
+    let reconstructed: LabeledImage<usize> = model.autoencoder.decode_tiled(&latents)?;
+
+    reconstructed.img.save_with(out, options)
+}
+
+/// Runs the subcommand given in the command line.
+pub fn run(args: Args) -> Result<(), String> {
+    match args.command {
+        Command::Train {
+            dataset,
+            size,
+            fit,
+            filter,
+            augment,
+            patch,
+            threads,
+            out,
+            latent_size,
+            hidden,
+            epochs,
+            preview_dir,
+            preview_every,
+            preview_image,
+            preview_label,
+        } => train_command(
+            &dataset,
+            &DatasetOptions {
+                size,
+                fit,
+                filter,
+                augmentation: augment,
+                threads,
+            },
+            &out,
+            latent_size,
+            &hidden,
+            TrainOptions {
+                epochs,
+                patch_size: patch,
+                ..Default::default()
+            },
+            preview_dir
+                .map(|dir| PreviewOptions {
+                    dir,
+                    every: preview_every,
+                    image: preview_image,
+                    label: preview_label,
+                })
+                .as_ref(),
+        ),
+
+        Command::Encode {
+            image,
+            label,
+            fit,
+            model,
+        } => encode_command(&image, label.as_deref(), fit, &model),
+
+        Command::Decode {
+            vector,
+            out,
+            png_depth,
+            model,
+        } => decode_command(&vector, &out, &SaveOptions { png_depth }, &model),
+
+        Command::Generate {
+            label,
+            noise_scale,
+            out,
+            png_depth,
+            model,
+        } => generate_command(
+            &label,
+            noise_scale,
+            &out,
+            &SaveOptions { png_depth },
+            &model,
+        ),
+
+        Command::Export {
+            dataset,
+            fit,
+            out,
+            model,
+        } => export_command(&dataset, fit, &out, &model),
+
+        Command::Mix {
+            image,
+            labels,
+            fit,
+            out,
+            png_depth,
+            model,
+        } => mix_command(
+            &image,
+            &labels,
+            fit,
+            &out,
+            &SaveOptions { png_depth },
+            &model,
+        ),
+
+        Command::Compare { a, b } => compare_command(&a, &b),
+
+        Command::Distance {
+            a,
+            b,
+            label,
+            fit,
+            model,
+        } => distance_command(&a, &b, label.as_deref(), fit, &model),
+
+        Command::Reconstruct {
+            image,
+            label,
+            overlap,
+            out,
+            png_depth,
+            model,
+        } => reconstruct_command(
+            &image,
+            label.as_deref(),
+            overlap,
+            &out,
+            &SaveOptions { png_depth },
+            &model,
+        ),
+    }
+}
@@ -0,0 +1,241 @@
+/*!
+ * Data augmentation: random, small changes made to images every epoch of
+ * training, so that an autoencoder learns the style of a small dataset
+ * rather than its exact images.
+ */
+use super::data::ImageData;
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+use std::str::FromStr;
+
+/**
+ * Which random changes to make to images in training, and how strong. All
+ * are off by default.
+ *
+ * Written as a comma-separated list of changes, e.g.
+ * `flip,rotate=10,brightness=0.1,saturation=0.1,noise=0.02`, or `none`.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Augmentation {
+    /// Whether to flip half of the images horizontally.
+    pub flip: bool,
+
+    /// The most degrees to rotate images by, either way.
+    pub rotation: f32,
+
+    /// The most brightness to add to or take from images, from 0 to 1.
+    pub brightness: f32,
+
+    /// The most saturation to add to or take from images, from 0 to 1.
+    pub saturation: f32,
+
+    /// The standard deviation of the Gaussian noise added to the brightness
+    /// of every pixel.
+    pub noise: f32,
+}
+
+impl Augmentation {
+    /// Whether no change is made at all.
+    pub fn is_none(&self) -> bool {
+        *self == Augmentation::default()
+    }
+
+    /// Makes a randomly changed copy of an image.
+    pub fn apply<R: Rng>(&self, image: &ImageData, rng: &mut R) -> ImageData {
+        let mut image = if self.flip && rng.gen() {
+            image.flip_horizontal()
+        } else {
+            image.clone()
+        };
+
+        if self.rotation > 0.0 {
+            image = image.rotate(rng.gen_range(-self.rotation..=self.rotation));
+        }
+
+        if self.brightness > 0.0 {
+            let shift = rng.gen_range(-self.brightness..=self.brightness);
+
+            for value in &mut image.brightness {
+                *value = (*value + shift).clamp(0.0, 1.0);
+            }
+        }
+
+        if let Some((_, saturation)) = &mut image.colour {
+            if self.saturation > 0.0 {
+                let shift = rng.gen_range(-self.saturation..=self.saturation);
+
+                for value in saturation {
+                    *value = (*value + shift).clamp(0.0, 1.0);
+                }
+            }
+        }
+
+        if self.noise > 0.0 {
+            let noise = Normal::new(0.0, self.noise).unwrap();
+
+            for value in &mut image.brightness {
+                *value = (*value + noise.sample(rng)).clamp(0.0, 1.0);
+            }
+        }
+
+        image
+    }
+}
+
+impl FromStr for Augmentation {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, String> {
+        let mut augmentation = Augmentation::default();
+
+        if spec.trim() == "none" {
+            return Ok(augmentation);
+        }
+
+        for change in spec.split(',').map(str::trim) {
+            let (name, amount) = change.split_once('=').unwrap_or((change, ""));
+
+            let amount = || match amount.parse::<f32>() {
+                Ok(amount) if amount.is_finite() && amount >= 0.0 => Ok(amount),
+                _ => Err(format!("Invalid amount {:?} for {}", amount, name)),
+            };
+
+            match name {
+                "flip" => augmentation.flip = true,
+                "rotate" => augmentation.rotation = amount()?,
+                "brightness" => augmentation.brightness = amount()?,
+                "saturation" => augmentation.saturation = amount()?,
+                "noise" => augmentation.noise = amount()?,
+
+                _ => {
+                    return Err(format!(
+                        "Unknown augmentation {:?}; expected flip, rotate=DEGREES, \
+                         brightness=X, saturation=X or noise=X",
+                        name
+                    ))
+                }
+            }
+        }
+
+        Ok(augmentation)
+    }
+}
+
+impl ImageData {
+    /// Flips this image horizontally, as in a mirror.
+    pub fn flip_horizontal(&self) -> Self {
+        let width = self.width as usize;
+
+        self.map_planes(self.width, self.height, |plane, _| {
+            plane
+                .chunks(width.max(1))
+                .flat_map(|row| row.iter().rev())
+                .copied()
+                .collect()
+        })
+    }
+
+    /**
+     * Rotates this image about its center by some degrees, clockwise,
+     * keeping its size. Pixels rotated in from outside the image take the
+     * value of the nearest edge pixel.
+     */
+    pub fn rotate(&self, degrees: f32) -> Self {
+        let (width, height) = (self.width as usize, self.height as usize);
+
+        if width == 0 || height == 0 {
+            return self.clone();
+        }
+
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+
+        // the pixel each pixel of the rotated image is taken from
+        let sources: Vec<usize> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+                let sx = (cos * dx + sin * dy + cx).floor();
+                let sy = (cos * dy - sin * dx + cy).floor();
+
+                let sx = sx.clamp(0.0, (width - 1) as f32) as usize;
+                let sy = sy.clamp(0.0, (height - 1) as f32) as usize;
+
+                sy * width + sx
+            })
+            .collect();
+
+        self.map_planes(self.width, self.height, |plane, _| {
+            sources.iter().map(|&index| plane[index]).collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn luma(width: u16, height: u16, pixels: &[f64]) -> ImageData {
+        ImageData::from_luma(width, height, pixels.iter().copied()).unwrap()
+    }
+
+    #[test]
+    fn parse_spec() {
+        let augmentation: Augmentation = "flip, rotate=10,brightness=0.1,saturation=0.2,noise=0.02"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            augmentation,
+            Augmentation {
+                flip: true,
+                rotation: 10.0,
+                brightness: 0.1,
+                saturation: 0.2,
+                noise: 0.02,
+            }
+        );
+
+        let none: Augmentation = " none ".parse().unwrap();
+        assert!(none.is_none());
+
+        let flip: Augmentation = "flip".parse().unwrap();
+        assert!(flip.flip && !flip.is_none());
+    }
+
+    #[test]
+    fn parse_spec_errors() {
+        for spec in [
+            "",
+            "mirror",
+            "rotate",
+            "rotate=",
+            "rotate=ten",
+            "brightness=-0.1",
+            "noise=NaN",
+            "saturation=inf",
+            "flip,none",
+        ] {
+            assert!(spec.parse::<Augmentation>().is_err(), "{:?} parsed", spec);
+        }
+    }
+
+    #[test]
+    fn flip_and_rotate() {
+        let image = luma(2, 2, &[0.0, 0.25, 0.5, 0.75]);
+
+        assert_eq!(image.flip_horizontal().brightness, [0.25, 0.0, 0.75, 0.5]);
+        assert_eq!(image.rotate(0.0).brightness, image.brightness);
+        assert_eq!(image.rotate(90.0).brightness, [0.5, 0.0, 0.75, 0.25]);
+    }
+
+    #[test]
+    fn apply_nothing() {
+        let image = luma(2, 2, &[0.0, 0.25, 0.5, 0.75]);
+        let mut rng = rand::thread_rng();
+
+        let applied = Augmentation::default().apply(&image, &mut rng);
+
+        assert_eq!(applied.brightness, image.brightness);
+    }
+}
@@ -0,0 +1,172 @@
+/*!
+ * BMP loading and saving for [super::data::ImageData].
+ *
+ * Uncompressed bitmaps with 1, 4 or 8 bits per pixel (through a palette), or
+ * with 24 or 32, can be loaded. Images are saved with 24 bits per pixel;
+ * bitmaps have no alpha, so it is left out on both ends.
+ */
+use super::data::ImageData;
+
+/// The size of the file header, before the DIB header.
+const FILE_HEADER_SIZE: usize = 14;
+
+/// The size of the BITMAPINFOHEADER, the DIB header written by [ImageData::to_bmp].
+const INFO_HEADER_SIZE: usize = 40;
+
+fn read_u16(bytes: &[u8], at: usize) -> Result<u16, String> {
+    bytes
+        .get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| "Unexpected end of BMP data".to_owned())
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Result<u32, String> {
+    bytes
+        .get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| "Unexpected end of BMP data".to_owned())
+}
+
+/// How many bytes a row of pixels takes, padded to a multiple of 4.
+fn row_size(width: usize, bits_per_pixel: usize) -> usize {
+    (width * bits_per_pixel).div_ceil(32) * 4
+}
+
+impl ImageData {
+    /// Loads ImageData from the contents of a BMP file.
+    pub fn from_bmp(bytes: &[u8]) -> Result<Self, String> {
+        if !bytes.starts_with(b"BM") {
+            return Err("Not a BMP file".to_owned());
+        }
+
+        let data_offset = read_u32(bytes, 10)? as usize;
+        let header_size = read_u32(bytes, FILE_HEADER_SIZE)? as usize;
+
+        if header_size < INFO_HEADER_SIZE {
+            return Err("OS/2 BMP headers are not supported".to_owned());
+        }
+
+        let width = read_u32(bytes, FILE_HEADER_SIZE + 4)? as i32;
+        let height = read_u32(bytes, FILE_HEADER_SIZE + 8)? as i32;
+        let bits_per_pixel = read_u16(bytes, FILE_HEADER_SIZE + 14)? as usize;
+        let compression = read_u32(bytes, FILE_HEADER_SIZE + 16)?;
+        let colours_used = read_u32(bytes, FILE_HEADER_SIZE + 32)? as usize;
+
+        if compression != 0 {
+            return Err("Compressed BMPs are not supported".to_owned());
+        }
+
+        // a negative height means rows are stored top to bottom
+        let top_down = height < 0;
+
+        let (width, height) = match (u16::try_from(width), u16::try_from(height.unsigned_abs())) {
+            (Ok(width), Ok(height)) if width > 0 && height > 0 => (width, height),
+            _ => return Err(format!("Unsupported BMP size {}x{}", width, height)),
+        };
+
+        let palette: Vec<[f64; 3]> = match bits_per_pixel {
+            1 | 4 | 8 => {
+                let size = if colours_used == 0 {
+                    1 << bits_per_pixel
+                } else {
+                    colours_used
+                };
+
+                let start = FILE_HEADER_SIZE + header_size;
+
+                bytes
+                    .get(start..start + size * 4)
+                    .ok_or("Unexpected end of BMP data")?
+                    .chunks_exact(4)
+                    .map(|bgra| {
+                        [bgra[2], bgra[1], bgra[0]].map(|value| value as f64 / u8::MAX as f64)
+                    })
+                    .collect()
+            }
+
+            24 | 32 => vec![],
+            _ => {
+                return Err(format!(
+                    "BMPs with {} bits per pixel are not supported",
+                    bits_per_pixel
+                ))
+            }
+        };
+
+        let row_size = row_size(width as usize, bits_per_pixel);
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+
+        for y in 0..height as usize {
+            let row_index = if top_down { y } else { height as usize - 1 - y };
+
+            let start = data_offset + row_index * row_size;
+            let row = bytes
+                .get(start..start + row_size)
+                .ok_or("Unexpected end of BMP data")?;
+
+            for x in 0..width as usize {
+                let pixel = match bits_per_pixel {
+                    24 | 32 => {
+                        let at = x * bits_per_pixel / 8;
+
+                        [row[at + 2], row[at + 1], row[at]]
+                            .map(|value| value as f64 / u8::MAX as f64)
+                    }
+
+                    _ => {
+                        let bit = x * bits_per_pixel;
+                        let mask = (1 << bits_per_pixel) - 1;
+                        let index =
+                            (row[bit / 8] >> (8 - bits_per_pixel - bit % 8)) as usize & mask;
+
+                        *palette.get(index).ok_or("BMP palette index out of range")?
+                    }
+                };
+
+                pixels.push(pixel);
+            }
+        }
+
+        ImageData::from_rgb(width, height, pixels)
+    }
+
+    /// Saves ImageData as an uncompressed BMP file, with 24 bits per pixel.
+    pub fn to_bmp(&self) -> Vec<u8> {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let row_size = row_size(width, 24);
+        let data_offset = FILE_HEADER_SIZE + INFO_HEADER_SIZE;
+        let file_size = data_offset + row_size * height;
+
+        let mut out = Vec::with_capacity(file_size);
+
+        // file header
+        out.extend_from_slice(b"BM");
+        out.extend((file_size as u32).to_le_bytes());
+        out.extend([0; 4]);
+        out.extend((data_offset as u32).to_le_bytes());
+
+        // BITMAPINFOHEADER
+        out.extend((INFO_HEADER_SIZE as u32).to_le_bytes());
+        out.extend((width as i32).to_le_bytes());
+        out.extend((height as i32).to_le_bytes());
+        out.extend(1_u16.to_le_bytes());
+        out.extend(24_u16.to_le_bytes());
+        out.extend([0; 24]);
+
+        let pixels = self.to_rgb();
+
+        // rows are stored bottom to top
+        for y in (0..height).rev() {
+            let row_start = out.len();
+
+            for [r, g, b] in &pixels[y * width..(y + 1) * width] {
+                out.extend([b, g, r].map(|value| (value * u8::MAX as f64).round() as u8));
+            }
+
+            out.resize(row_start + row_size, 0);
+        }
+
+        out
+    }
+}
@@ -2,11 +2,13 @@
  * The internal image data holder.
  */
 use crate::autoenc::prelude::*;
+use color_space::{FromRgb, Hsl, Rgb, ToRgb};
 
 /**
  * Image data, internally represented as separate Vecs
  * of H, S and L values.
  */
+#[derive(Clone)]
 pub struct ImageData {
     /// The brightnesses of each pixel.
     /// This is equivalent to the L (luminance) in the HSL
@@ -18,6 +20,10 @@ pub struct ImageData {
     /// that is, the H and S in HSL are assumed to always be 0.
     pub colour: Option<(Vec<f32>, Vec<f32>)>,
 
+    /// Optionally, the opacity of each pixel, from 0 (transparent) to 1
+    /// (opaque). If set to None, the image is fully opaque.
+    pub alpha: Option<Vec<f32>>,
+
     /// The width of this image.
     pub width: u16,
 
@@ -28,64 +34,197 @@ pub struct ImageData {
     pub area: u32,
 }
 
+impl ImageData {
+    /// Makes an opaque black image of the given size, in colour or in
+    /// grayscale.
+    pub fn blank(width: u16, height: u16, colour: bool) -> Self {
+        let area = width as u32 * height as u32;
+
+        ImageData {
+            brightness: vec![0.0; area as usize],
+            colour: colour.then(|| (vec![0.0; area as usize], vec![0.0; area as usize])),
+            alpha: None,
+            width,
+            height,
+            area,
+        }
+    }
+
+    /**
+     * Makes a colour image from the red, green and blue values of each pixel,
+     * between 0 and 1, row by row. There must be exactly as many pixels as
+     * the area of the image.
+     */
+    pub fn from_rgb(
+        width: u16,
+        height: u16,
+        pixels: impl IntoIterator<Item = [f64; 3]>,
+    ) -> Result<Self, String> {
+        let mut image = Self::blank(width, height, true);
+        let (hue, saturation) = image.colour.as_mut().unwrap();
+        let mut count = 0;
+
+        for (i, [r, g, b]) in pixels.into_iter().enumerate() {
+            if i >= image.area as usize {
+                return Err(format!("More pixels than fit in {}x{}", width, height));
+            }
+
+            let hsl = Hsl::from_rgb(&Rgb::new(r * 255.0, g * 255.0, b * 255.0));
+
+            image.brightness[i] = (hsl.l as f32).clamp(0.0, 1.0);
+            hue[i] = hsl.h as f32;
+            saturation[i] = (hsl.s as f32).clamp(0.0, 1.0);
+            count += 1;
+        }
+
+        if count < image.area {
+            return Err(format!("Fewer pixels than fill {}x{}", width, height));
+        }
+
+        Ok(image)
+    }
+
+    /**
+     * Makes a grayscale image from the brightness of each pixel, between 0
+     * and 1, row by row. There must be exactly as many pixels as the area of
+     * the image.
+     */
+    pub fn from_luma(
+        width: u16,
+        height: u16,
+        pixels: impl IntoIterator<Item = f64>,
+    ) -> Result<Self, String> {
+        let mut image = Self::blank(width, height, false);
+        let mut count = 0;
+
+        for (i, luma) in pixels.into_iter().enumerate() {
+            if i >= image.area as usize {
+                return Err(format!("More pixels than fit in {}x{}", width, height));
+            }
+
+            image.brightness[i] = (luma as f32).clamp(0.0, 1.0);
+            count += 1;
+        }
+
+        if count < image.area {
+            return Err(format!("Fewer pixels than fill {}x{}", width, height));
+        }
+
+        Ok(image)
+    }
+
+    /// The red, green and blue values of each pixel, between 0 and 1, row by
+    /// row.
+    pub fn to_rgb(&self) -> Vec<[f64; 3]> {
+        match &self.colour {
+            Some((hue, saturation)) => (0..self.area as usize)
+                .map(|i| {
+                    let rgb = Hsl::new(
+                        hue[i] as f64,
+                        saturation[i].clamp(0.0, 1.0) as f64,
+                        self.brightness[i].clamp(0.0, 1.0) as f64,
+                    )
+                    .to_rgb();
+
+                    [rgb.r / 255.0, rgb.g / 255.0, rgb.b / 255.0]
+                })
+                .collect(),
+
+            None => self
+                .brightness
+                .iter()
+                .map(|&luma| [luma.clamp(0.0, 1.0) as f64; 3])
+                .collect(),
+        }
+    }
+
+    /// How many planes of values this image has: brightness, then hue and
+    /// saturation if in colour, then alpha if any.
+    pub fn num_planes(&self) -> usize {
+        1 + 2 * self.colour.is_some() as usize + self.alpha.is_some() as usize
+    }
+
+    /// How many values this image is encoded into; see [Item::encode].
+    pub fn encoded_size(&self) -> usize {
+        self.area as usize * self.num_planes()
+    }
+}
+
 impl Item for ImageData {
-    /// Encodes an image into autoencoder data.
+    /// Encodes an image into autoencoder data: its brightnesses, then, if in
+    /// colour, its hues (scaled from degrees down to between 0 and 1) and
+    /// saturations, then its alpha values, if any.
     fn encode(&self) -> Result<Vec<f32>, &str> {
-        let area = self.area;
+        let area = self.area as usize;
 
-        let res_size: u32 = if self.colour.is_some() {
-            area * 3
-        } else {
-            area
-        };
-
-        let mut res: Vec<f32> = vec![0.0f32; res_size as usize];
+        let mut res: Vec<f32> = Vec::with_capacity(self.encoded_size());
 
         // encode brightnesses
-        res.copy_from_slice(&self.brightness);
+        res.extend_from_slice(&self.brightness[..area]);
 
         // encode colours
-        if self.colour.is_some() {
-            let colour = self.colour.as_ref().unwrap();
-
-            for i in 0..area as usize {
-                res[area as usize + i] = colour.0[i]
-            }
+        if let Some((hue, saturation)) = &self.colour {
+            res.extend(hue[..area].iter().map(|h| h / 360.0));
+            res.extend_from_slice(&saturation[..area]);
+        }
 
-            for i in 0..area as usize {
-                res[2 * area as usize + i] = colour.1[i]
-            }
+        // encode alpha
+        if let Some(alpha) = &self.alpha {
+            res.extend_from_slice(&alpha[..area]);
         }
 
         Ok(res)
     }
 
     /// Decodes an image from autoencoder output into the values of an ImageData.
+    /// Whether it is in colour and whether it has alpha are told by how many
+    /// planes of values there are.
     fn decode_from(&mut self, input: &[f32]) -> Result<(), String> {
-        let area = self.area;
+        let area = self.area as usize;
 
-        let has_colour: bool = if input.len() == area as usize * 3 {
-            true
-        } else if input.len() == area as usize {
-            false
+        let planes = if area > 0 && input.len().is_multiple_of(area) {
+            input.len() / area
         } else {
-            return Err("Incompatible size; array length must be equal to self.area for brightness values, or twice it for brightness and 'colour'".to_owned());
+            0
+        };
+
+        let (has_colour, has_alpha) = match planes {
+            1 => (false, false),
+            2 => (false, true),
+            3 => (true, false),
+            4 => (true, true),
+            _ => return Err("Incompatible size; array length must be equal to self.area for brightness values, twice it for brightness and alpha, thrice it for brightness and 'colour', or four times it for all of them".to_owned()),
         };
 
-        self.brightness.copy_from_slice(input);
+        self.brightness.copy_from_slice(&input[..area]);
 
         if has_colour {
-            let colour = self.colour.as_mut().unwrap();
+            let (hue, saturation) = self
+                .colour
+                .get_or_insert_with(|| (vec![0.0; area], vec![0.0; area]));
 
-            for i in 0..area as usize {
-                colour.0[i] = input[area as usize + i]
+            for (h, value) in hue.iter_mut().zip(&input[area..area * 2]) {
+                *h = value * 360.0;
             }
 
-            for i in 0..area as usize {
-                colour.1[i] = input[2 * area as usize + i]
-            }
+            saturation.copy_from_slice(&input[area * 2..area * 3]);
+        } else {
+            self.colour = None;
         }
 
+        self.alpha = has_alpha.then(|| input[input.len() - area..].to_vec());
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+impl ImageData {
+    /// A grayscale image for tests, whose pixels brighten from 0 towards 1,
+    /// row by row.
+    pub fn gradient(width: u16, height: u16) -> Self {
+        let area = width as usize * height as usize;
+
+        Self::from_luma(width, height, (0..area).map(|i| i as f64 / area as f64)).unwrap()
+    }
+}
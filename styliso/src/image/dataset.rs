@@ -0,0 +1,588 @@
+/*!
+ * Datasets of labeled images, loaded from directory trees where each
+ * subdirectory of the root is a label, or from manifests (see
+ * [super::manifest]).
+ */
+use super::augment::Augmentation;
+use super::data::ImageData;
+use super::io::ImageFormat;
+use super::labeled::LabeledImage;
+use super::transform::{FitMode, ResizeFilter};
+use crate::autoenc::assembly::ImageShape;
+use neurs::train::label::TrainingLabel;
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Options for loading datasets.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DatasetOptions {
+    /// If given, the width and height every image is fitted to as it is
+    /// loaded, so that images of any size can be used together.
+    pub size: Option<(u16, u16)>,
+
+    /// How images are fitted to [DatasetOptions::size].
+    pub fit: FitMode,
+
+    /// How images are resampled when fitted to [DatasetOptions::size].
+    pub filter: ResizeFilter,
+
+    /// If given, the augmentation to train with, overriding any the dataset
+    /// defines itself.
+    pub augmentation: Option<Augmentation>,
+
+    /// How many threads to load images with at once; 0, the default, for as
+    /// many as the machine can run in parallel.
+    pub threads: usize,
+}
+
+/// How much loading a set of image files took. See [Dataset::load_stats].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LoadStats {
+    /// How many files were read, whether or not they could be loaded.
+    pub files: usize,
+
+    /// How many bytes were read, in all.
+    pub bytes: u64,
+
+    /// How long reading and decoding the files took, from the first to the
+    /// last.
+    pub elapsed: Duration,
+}
+
+impl LoadStats {
+    /// How many files were loaded per second.
+    pub fn files_per_second(&self) -> f64 {
+        self.files as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// How many megabytes (of a million bytes) were loaded per second.
+    pub fn megabytes_per_second(&self) -> f64 {
+        self.bytes as f64 / 1e6 / self.elapsed.as_secs_f64()
+    }
+}
+
+/// A file which could not be loaded into a dataset, and why.
+pub struct SkippedFile {
+    /// The path of the file.
+    pub path: PathBuf,
+
+    /// Why it was skipped.
+    pub reason: String,
+}
+
+/**
+ * A set of labeled images, ready to train an autoencoder with, and the names
+ * of their labels, in the order of their indices.
+ *
+ * All images have the same size, are either all in colour or all in
+ * grayscale, and either all have alpha or none do. Their labels are made
+ * from the index of their name with [TrainingLabel::from_index].
+ */
+pub struct Dataset<L: TrainingLabel = usize> {
+    /// The labeled images.
+    pub images: Vec<LabeledImage<L>>,
+
+    /// How much each image weighs in training, in the order of the images.
+    pub weights: Vec<f32>,
+
+    /// The path each image was loaded from, in the order of the images.
+    pub paths: Vec<PathBuf>,
+
+    /// The name of each label.
+    pub labels: Vec<String>,
+
+    /// The files which could not be loaded, and were left out.
+    pub skipped: Vec<SkippedFile>,
+
+    /// The augmentation to train with, given by [DatasetOptions::augmentation]
+    /// or by the manifest; none by default.
+    pub augmentation: Augmentation,
+
+    /// How much loading the images took.
+    pub load_stats: LoadStats,
+}
+
+/// Whether a path has the extension of a supported image format.
+pub fn is_image_file(path: &Path) -> bool {
+    ImageFormat::from_path(path).is_some()
+}
+
+/// Lists the entries of a directory, sorted by name, for a stable order.
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut entries = fs::read_dir(dir)
+        .map_err(|err| format!("Could not read {}: {}", dir.display(), err))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("Could not read {}: {}", dir.display(), err))?;
+
+    entries.sort();
+
+    Ok(entries)
+}
+
+/**
+ * Lists the image files in a directory and, recursively, in its
+ * subdirectories. Directories which cannot be read are recorded as skipped.
+ */
+fn find_images(dir: &Path, found: &mut Vec<PathBuf>, skipped: &mut Vec<SkippedFile>) {
+    let entries = match sorted_entries(dir) {
+        Ok(entries) => entries,
+        Err(reason) => {
+            skipped.push(SkippedFile {
+                path: dir.to_owned(),
+                reason,
+            });
+            return;
+        }
+    };
+
+    for path in entries {
+        if path.is_dir() {
+            find_images(&path, found, skipped);
+        } else if is_image_file(&path) {
+            found.push(path);
+        }
+    }
+}
+
+/// Decodes the bytes of an image file, fitting the image to size if the
+/// options say so.
+fn decode_image(bytes: &[u8], path: &Path, options: &DatasetOptions) -> Result<ImageData, String> {
+    let format = ImageFormat::detect(bytes, path).ok_or("Not in a supported image format")?;
+    let image = ImageData::decode(bytes, format)?;
+
+    Ok(match options.size {
+        Some((width, height)) => image.fit(width, height, options.fit, options.filter),
+        None => image,
+    })
+}
+
+/**
+ * Loads image files on a pool of threads, as many as
+ * [DatasetOptions::threads] says, and calls `on_loaded` with each, in the
+ * order of `paths`, on the calling thread.
+ *
+ * The threads are only handed files a few past the first one not yet passed
+ * to `on_loaded`, so that they wait, rather than pile images up, while it is
+ * busy or while that file is slow to load.
+ */
+fn load_parallel(
+    paths: &[&Path],
+    options: &DatasetOptions,
+    mut on_loaded: impl FnMut(usize, Result<ImageData, String>),
+) -> LoadStats {
+    let threads = match options.threads {
+        0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        threads => threads,
+    }
+    .min(paths.len())
+    .max(1);
+
+    // how many files may be handed out past the first one not yet done
+    let window = threads * 2;
+
+    let start = Instant::now();
+    let mut bytes = 0;
+
+    let (work_sender, work_receiver) = mpsc::sync_channel::<usize>(window);
+    let work_receiver = Mutex::new(work_receiver);
+
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::sync_channel(window);
+
+        for _ in 0..threads {
+            let sender = sender.clone();
+            let work_receiver = &work_receiver;
+
+            scope.spawn(move || loop {
+                let Ok(index) = work_receiver.lock().unwrap().recv() else {
+                    break;
+                };
+
+                let path = paths[index];
+
+                let (size, loaded) = match fs::read(path) {
+                    Ok(bytes) => (bytes.len(), decode_image(&bytes, path, options)),
+                    Err(err) => (0, Err(err.to_string())),
+                };
+
+                if sender.send((index, size, loaded)).is_err() {
+                    break;
+                }
+            });
+        }
+
+        drop(sender);
+
+        // the threads finish files out of order, so those which come early
+        // wait here for the ones before them, each in the slot of its index
+        // modulo the window
+        let mut pending: Vec<Option<Result<ImageData, String>>> =
+            (0..window).map(|_| None).collect();
+        let mut work_sender = Some(work_sender);
+        let mut handed_out = 0;
+        let mut done = 0;
+
+        while done < paths.len() {
+            if let Some(sender) = &work_sender {
+                while handed_out < paths.len().min(done + window) {
+                    // never blocks, since no more than the window is handed
+                    // out at once
+                    let _ = sender.send(handed_out);
+                    handed_out += 1;
+                }
+
+                if handed_out == paths.len() {
+                    // lets the threads stop once there is nothing left to load
+                    work_sender = None;
+                }
+            }
+
+            let Ok((index, size, loaded)) = receiver.recv() else {
+                break;
+            };
+
+            bytes += size as u64;
+            pending[index % window] = Some(loaded);
+
+            while let Some(loaded) = pending[done % window].take() {
+                on_loaded(done, loaded);
+                done += 1;
+            }
+        }
+    });
+
+    LoadStats {
+        files: paths.len(),
+        bytes,
+        elapsed: start.elapsed(),
+    }
+}
+
+/**
+ * Loads every image in a directory and, recursively, in its subdirectories,
+ * regardless of labels, fitting them to size if the options say so. Returns
+ * them, in the order of their paths, along with the files which could not be
+ * loaded.
+ */
+pub fn load_images<P: AsRef<Path>>(
+    dir: P,
+    options: &DatasetOptions,
+) -> (Vec<ImageData>, Vec<SkippedFile>) {
+    let mut found = vec![];
+    let mut skipped = vec![];
+    find_images(dir.as_ref(), &mut found, &mut skipped);
+
+    let mut images = Vec::with_capacity(found.len());
+    let paths: Vec<&Path> = found.iter().map(PathBuf::as_path).collect();
+
+    load_parallel(&paths, options, |index, loaded| match loaded {
+        Ok(image) => images.push(image),
+        Err(reason) => skipped.push(SkippedFile {
+            path: found[index].clone(),
+            reason,
+        }),
+    });
+
+    (images, skipped)
+}
+
+/// Describes the size, colour and alpha of an image, for error messages.
+fn describe(image: &ImageData) -> String {
+    format!(
+        "{}x{} {}{}",
+        image.width,
+        image.height,
+        if image.colour.is_some() {
+            "colour"
+        } else {
+            "grayscale"
+        },
+        if image.alpha.is_some() {
+            " and alpha"
+        } else {
+            ""
+        }
+    )
+}
+
+/// Whether two images have the same size, colour and alpha.
+fn same_kind(a: &ImageData, b: &ImageData) -> bool {
+    (a.width, a.height, a.colour.is_some(), a.alpha.is_some())
+        == (b.width, b.height, b.colour.is_some(), b.alpha.is_some())
+}
+
+/// An image file to load into a dataset, with the indices of its labels and
+/// its weight.
+pub(super) struct DatasetFile {
+    pub path: PathBuf,
+    pub labels: Vec<usize>,
+    pub weight: f32,
+}
+
+impl<L: TrainingLabel> Dataset<L> {
+    /**
+     * Loads a list of image files into a dataset with the given labels,
+     * fitting them to size if the options say so. Files which cannot be
+     * loaded, or whose size, colour or alpha differs from that of the first
+     * image loaded, are added to `skipped`. Files are loaded in parallel, but
+     * added to the dataset in order.
+     */
+    pub(super) fn load_files(
+        files: Vec<DatasetFile>,
+        labels: Vec<String>,
+        mut skipped: Vec<SkippedFile>,
+        source: &Path,
+        options: &DatasetOptions,
+        mut on_progress: impl FnMut(usize, usize, &Path),
+    ) -> Result<Self, String> {
+        if labels.len() > L::num_labels() {
+            return Err(format!(
+                "Found {} labels in {}, but at most {} can be told apart",
+                labels.len(),
+                source.display(),
+                L::num_labels()
+            ));
+        }
+
+        let mut images: Vec<LabeledImage<L>> = Vec::with_capacity(files.len());
+        let mut weights = Vec::with_capacity(files.len());
+        let mut paths = Vec::with_capacity(files.len());
+
+        let file_paths: Vec<&Path> = files.iter().map(|file| file.path.as_path()).collect();
+
+        let load_stats = load_parallel(&file_paths, options, |index, loaded| {
+            let file = &files[index];
+
+            let loaded = loaded.and_then(|img| match images.first() {
+                Some(first) if !same_kind(&first.img, &img) => Err(format!(
+                    "Expected a {} image, like the first one, got {}",
+                    describe(&first.img),
+                    describe(&img)
+                )),
+
+                _ => Ok(img),
+            });
+
+            match loaded {
+                Ok(img) => {
+                    let mut image =
+                        LabeledImage::new(L::from_index(file.labels[0]), img, labels.len());
+
+                    image.extra_labels = file.labels[1..]
+                        .iter()
+                        .map(|&label| L::from_index(label))
+                        .collect();
+
+                    images.push(image);
+                    weights.push(file.weight);
+                    paths.push(file.path.clone());
+                }
+
+                Err(reason) => skipped.push(SkippedFile {
+                    path: file.path.clone(),
+                    reason,
+                }),
+            }
+
+            on_progress(index + 1, files.len(), &file.path);
+        });
+
+        if images.is_empty() {
+            return Err(format!(
+                "No images could be loaded from {}",
+                source.display()
+            ));
+        }
+
+        Ok(Dataset {
+            images,
+            weights,
+            paths,
+            labels,
+            skipped,
+            augmentation: options.augmentation.unwrap_or_default(),
+            load_stats,
+        })
+    }
+
+    /**
+     * Loads a dataset from a directory with a subdirectory per label, named
+     * after it, holding the images of that label, possibly in further
+     * subdirectories. Labels are indexed in the order of their names, and
+     * every image weighs 1.
+     *
+     * Files which cannot be loaded, or whose size (after fitting it to
+     * [DatasetOptions::size], if given), colour or alpha differs from that of
+     * the first image loaded, are skipped, and listed in [Dataset::skipped].
+     * `on_progress` is called after each file with how many have been
+     * processed so far, how many there are, and the path of the file.
+     *
+     * Images are read and decoded on several threads at once; see
+     * [DatasetOptions::threads].
+     *
+     * Fails if the directory cannot be read, if there are more labels than
+     * `L` can tell apart, or if no image could be loaded.
+     */
+    pub fn load<P: AsRef<Path>>(
+        dir: P,
+        options: &DatasetOptions,
+        on_progress: impl FnMut(usize, usize, &Path),
+    ) -> Result<Self, String> {
+        let dir = dir.as_ref();
+
+        let label_dirs: Vec<PathBuf> = sorted_entries(dir)?
+            .into_iter()
+            .filter(|path| path.is_dir())
+            .collect();
+
+        let labels: Vec<String> = label_dirs
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+
+        let mut skipped = vec![];
+        let mut files = vec![];
+
+        for (label, label_dir) in label_dirs.iter().enumerate() {
+            let mut found = vec![];
+            find_images(label_dir, &mut found, &mut skipped);
+
+            files.extend(found.into_iter().map(|path| DatasetFile {
+                path,
+                labels: vec![label],
+                weight: 1.0,
+            }));
+        }
+
+        Self::load_files(files, labels, skipped, dir, options, on_progress)
+    }
+
+    /**
+     * Loads a dataset from a directory like [Dataset::load], or from a
+     * manifest file like [Dataset::from_manifest], depending on what `path`
+     * is.
+     */
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        options: &DatasetOptions,
+        on_progress: impl FnMut(usize, usize, &Path),
+    ) -> Result<Self, String> {
+        if path.as_ref().is_dir() {
+            Self::load(path, options, on_progress)
+        } else {
+            Self::from_manifest(path, options, on_progress)
+        }
+    }
+
+    /// Loads a dataset like [Dataset::load], with the default options and
+    /// without reporting progress.
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self, String> {
+        Self::load(dir, &DatasetOptions::default(), |_, _, _| {})
+    }
+
+    /// The shape of the images of this dataset, to build an autoencoder for.
+    pub fn shape(&self) -> ImageShape {
+        ImageShape::of(&self.images[0])
+    }
+
+    /// How many images of each label there are, in the order of the labels.
+    pub fn label_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.labels.len()];
+
+        for label in self.images.iter().flat_map(LabeledImage::labels) {
+            counts[label.index()] += 1;
+        }
+
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh, empty directory for a test to write files into.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("styliso-{}-{}", name, std::process::id()));
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn load_parallel_in_order() {
+        let paths: Vec<PathBuf> = (0..50)
+            .map(|i| PathBuf::from(format!("/nonexistent/{}.png", i)))
+            .collect();
+        let paths: Vec<&Path> = paths.iter().map(PathBuf::as_path).collect();
+
+        let options = DatasetOptions {
+            threads: 3,
+            ..Default::default()
+        };
+
+        let mut indices = vec![];
+        let stats = load_parallel(&paths, &options, |index, loaded| {
+            assert!(loaded.is_err());
+            indices.push(index);
+        });
+
+        assert_eq!(indices, (0..50).collect::<Vec<_>>());
+        assert_eq!(stats.files, 50);
+        assert_eq!(stats.bytes, 0);
+    }
+
+    #[test]
+    fn load_images_in_file_order() {
+        let dir = test_dir("load-order");
+
+        // images of different sizes, so that they take different times to
+        // load, and are told apart by their width
+        for width in 1..=24_u16 {
+            let path = dir.join(format!("{:02}.png", width));
+
+            if width % 5 == 0 {
+                fs::write(path, b"not an image").unwrap();
+            } else {
+                let pixels = (0..width as usize * 16 * 16).map(|i| (i % 7) as f64 / 7.0);
+
+                ImageData::from_luma(width * 16, 16, pixels)
+                    .unwrap()
+                    .save(path)
+                    .unwrap();
+            }
+        }
+
+        let options = DatasetOptions {
+            threads: 4,
+            ..Default::default()
+        };
+
+        let (images, skipped) = load_images(&dir, &options);
+
+        let widths: Vec<u16> = images.iter().map(|image| image.width / 16).collect();
+        let skipped: Vec<PathBuf> = skipped.into_iter().map(|file| file.path).collect();
+
+        assert_eq!(
+            widths,
+            (1..=24).filter(|width| width % 5 != 0).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            skipped,
+            [5, 10, 15, 20]
+                .iter()
+                .map(|width| dir.join(format!("{:02}.png", width)))
+                .collect::<Vec<_>>()
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+/*!
+ * Unified image loading and saving, picking the decoder for each file by its
+ * contents or its extension, and the encoder by its extension.
+ *
+ * PNG and JPEG are always supported; BMP and WebP are behind the `bmp` and
+ * `webp` features respectively.
+ */
+use super::data::ImageData;
+use super::png::PngDepth;
+use std::fs;
+use std::path::Path;
+
+/// An image file format styliso can load.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+
+    #[cfg(feature = "bmp")]
+    Bmp,
+
+    #[cfg(feature = "webp")]
+    WebP,
+}
+
+impl ImageFormat {
+    /// The file extensions of each format.
+    pub const EXTENSIONS: &'static [(&'static str, ImageFormat)] = &[
+        ("png", ImageFormat::Png),
+        ("jpg", ImageFormat::Jpeg),
+        ("jpeg", ImageFormat::Jpeg),
+        #[cfg(feature = "bmp")]
+        ("bmp", ImageFormat::Bmp),
+        #[cfg(feature = "webp")]
+        ("webp", ImageFormat::WebP),
+    ];
+
+    /// Tells the format of a file from its extension, if it is supported.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?;
+
+        Self::EXTENSIONS
+            .iter()
+            .find(|(supported, _)| ext.eq_ignore_ascii_case(supported))
+            .map(|&(_, format)| format)
+    }
+
+    /// Tells the format of a file from the magic bytes at its start, if it is
+    /// supported.
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            return Some(ImageFormat::Png);
+        }
+
+        if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            return Some(ImageFormat::Jpeg);
+        }
+
+        #[cfg(feature = "bmp")]
+        if bytes.starts_with(b"BM") {
+            return Some(ImageFormat::Bmp);
+        }
+
+        #[cfg(feature = "webp")]
+        if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+            return Some(ImageFormat::WebP);
+        }
+
+        None
+    }
+
+    /// Tells the format of a file by its magic bytes, or, failing that, its
+    /// extension.
+    pub fn detect(bytes: &[u8], path: &Path) -> Option<Self> {
+        Self::sniff(bytes).or_else(|| Self::from_path(path))
+    }
+}
+
+/// Options for saving images, each applying to some of the formats.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SaveOptions {
+    /// How many bits each sample of a PNG is written with.
+    pub png_depth: PngDepth,
+}
+
+impl ImageData {
+    /// Loads ImageData from the contents of an image file in a given format.
+    pub fn decode(bytes: &[u8], format: ImageFormat) -> Result<Self, String> {
+        match format {
+            ImageFormat::Png => ImageData::from_png(bytes).map_err(|err| err.to_string()),
+            ImageFormat::Jpeg => ImageData::from_jpeg(bytes),
+
+            #[cfg(feature = "bmp")]
+            ImageFormat::Bmp => ImageData::from_bmp(bytes),
+
+            #[cfg(feature = "webp")]
+            ImageFormat::WebP => ImageData::from_webp(bytes),
+        }
+    }
+
+    /// Saves ImageData into the contents of an image file in a given format.
+    pub fn encode_as(&self, format: ImageFormat, options: &SaveOptions) -> Result<Vec<u8>, String> {
+        match format {
+            ImageFormat::Png => {
+                let mut out = vec![];
+                self.to_png_with_depth(&mut out, options.png_depth)
+                    .map_err(|err| err.to_string())?;
+
+                Ok(out)
+            }
+
+            ImageFormat::Jpeg => Err("Saving JPEG is not supported".to_owned()),
+
+            #[cfg(feature = "bmp")]
+            ImageFormat::Bmp => Ok(self.to_bmp()),
+
+            #[cfg(feature = "webp")]
+            ImageFormat::WebP => self.to_webp(),
+        }
+    }
+
+    /// Loads ImageData from an image file, in any supported format; see
+    /// [ImageFormat::detect].
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+
+        let bytes =
+            fs::read(path).map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+
+        let format = ImageFormat::detect(&bytes, path)
+            .ok_or_else(|| format!("{} is not in a supported image format", path.display()))?;
+
+        Self::decode(&bytes, format)
+            .map_err(|err| format!("Could not load {}: {}", path.display(), err))
+    }
+
+    /// Saves ImageData to an image file, in the format its extension names,
+    /// with the default [SaveOptions].
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        self.save_with(path, &SaveOptions::default())
+    }
+
+    /// Saves ImageData to an image file, in the format its extension names.
+    pub fn save_with<P: AsRef<Path>>(&self, path: P, options: &SaveOptions) -> Result<(), String> {
+        let path = path.as_ref();
+
+        let format = ImageFormat::from_path(path).ok_or_else(|| {
+            format!(
+                "Cannot tell which image format to save {} in",
+                path.display()
+            )
+        })?;
+
+        let bytes = self
+            .encode_as(format, options)
+            .map_err(|err| format!("Could not save {}: {}", path.display(), err))?;
+
+        fs::write(path, bytes).map_err(|err| format!("Could not save {}: {}", path.display(), err))
+    }
+}
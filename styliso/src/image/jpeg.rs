@@ -0,0 +1,63 @@
+/*!
+ * JPEG loading for [super::data::ImageData].
+ */
+use super::data::ImageData;
+use jpeg_decoder::{Decoder as JpegDecoder, PixelFormat};
+use std::io::Read;
+
+impl ImageData {
+    /// Loads ImageData from a JPEG file. Grayscale JPEGs load as grayscale.
+    pub fn from_jpeg<R: Read>(input: R) -> Result<Self, String> {
+        let mut decoder = JpegDecoder::new(input);
+
+        let pixels = decoder.decode().map_err(|err| err.to_string())?;
+        let info = decoder
+            .info()
+            .ok_or("JPEG decoded without image information")?;
+
+        let (width, height) = (info.width, info.height);
+
+        match info.pixel_format {
+            PixelFormat::L8 => ImageData::from_luma(
+                width,
+                height,
+                pixels.iter().map(|&luma| luma as f64 / u8::MAX as f64),
+            ),
+
+            PixelFormat::L16 => ImageData::from_luma(
+                width,
+                height,
+                pixels
+                    .chunks_exact(2)
+                    .map(|luma| u16::from_ne_bytes([luma[0], luma[1]]) as f64 / u16::MAX as f64),
+            ),
+
+            PixelFormat::RGB24 => ImageData::from_rgb(
+                width,
+                height,
+                pixels.chunks_exact(3).map(|rgb| {
+                    [
+                        rgb[0] as f64 / u8::MAX as f64,
+                        rgb[1] as f64 / u8::MAX as f64,
+                        rgb[2] as f64 / u8::MAX as f64,
+                    ]
+                }),
+            ),
+
+            PixelFormat::CMYK32 => ImageData::from_rgb(
+                width,
+                height,
+                pixels.chunks_exact(4).map(|cmyk| {
+                    let [c, m, y, k] = [cmyk[0], cmyk[1], cmyk[2], cmyk[3]]
+                        .map(|value| value as f64 / u8::MAX as f64);
+
+                    [
+                        (1.0 - c) * (1.0 - k),
+                        (1.0 - m) * (1.0 - k),
+                        (1.0 - y) * (1.0 - k),
+                    ]
+                }),
+            ),
+        }
+    }
+}
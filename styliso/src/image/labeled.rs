@@ -8,15 +8,63 @@ use neurs::train::label::TrainingLabel;
 
 /// An image which has been given a label.
 pub struct LabeledImage<LabelType: TrainingLabel> {
-    label: LabelType,
-    img: ImageData,
+    /// The label of this image.
+    pub label: LabelType,
+
+    /// Any other labels this image also has, in a multi-label dataset. They
+    /// are set in the one-hot vector along with [LabeledImage::label].
+    pub extra_labels: Vec<LabelType>,
+
+    /// The image itself.
+    pub img: ImageData,
+
+    /// How many labels there are in the dataset this image belongs to, i.e.
+    /// the length of the one-hot vector its label is encoded into.
+    ///
+    /// This is given explicitly, since label types such as `usize` do not
+    /// have a meaningful [TrainingLabel::num_labels].
+    pub num_labels: usize,
+}
+
+impl<LabelType: TrainingLabel> LabeledImage<LabelType> {
+    /// Gives a label to an image, out of `num_labels` labels.
+    pub fn new(label: LabelType, img: ImageData, num_labels: usize) -> Self {
+        LabeledImage {
+            label,
+            extra_labels: vec![],
+            img,
+            num_labels,
+        }
+    }
+
+    /// The same labels given to another image.
+    pub fn with_image(&self, img: ImageData) -> Self {
+        LabeledImage {
+            label: self.label.clone(),
+            extra_labels: self.extra_labels.clone(),
+            img,
+            num_labels: self.num_labels,
+        }
+    }
+
+    /// All labels of this image, the main one first.
+    pub fn labels(&self) -> impl Iterator<Item = &LabelType> {
+        std::iter::once(&self.label).chain(&self.extra_labels)
+    }
 }
 
 impl<LabelType: TrainingLabel> Item for LabeledImage<LabelType> {
     /// Vectorizes an image, along with label information, for autoencoding.
     fn encode(&self) -> Result<Vec<f32>, &str> {
-        let mut one_hot: Vec<f32> = vec![0.0; LabelType::num_labels() as usize];
-        one_hot[self.label.index() as usize] = 1.0;
+        let mut one_hot: Vec<f32> = vec![0.0; self.num_labels];
+
+        for label in self.labels() {
+            if label.index() >= self.num_labels {
+                return Err("Label index out of range of the number of labels");
+            }
+
+            one_hot[label.index()] = 1.0;
+        }
 
         let mut res = self.img.encode()?;
         res.append(&mut one_hot);
@@ -25,25 +73,68 @@ impl<LabelType: TrainingLabel> Item for LabeledImage<LabelType> {
     }
 
     /// De-vectorizes an image, along with label information, from autoencoder output,
-    /// into the values of a LabeledImage.
+    /// into the values of a LabeledImage. The highest one-hot value gives the
+    /// main label, and any other of at least 0.5 an extra label.
     fn decode_from(&mut self, input: &[f32]) -> Result<(), String> {
-        let img_data_len = input.len() - LabelType::num_labels() as usize;
+        if self.num_labels == 0 {
+            return Err("No labels to decode a main label from".to_owned());
+        }
 
-        let img_data = &input[..input.len() - img_data_len];
-        let label_data = &input[img_data_len..];
+        if input.len() <= self.num_labels {
+            return Err("Input too short to hold both image and label data".to_owned());
+        }
+
+        let img_data_len = input.len() - self.num_labels;
 
-        assert!(!label_data.is_empty());
+        let img_data = &input[..img_data_len];
+        let label_data = &input[img_data_len..];
 
+        // the label whose one-hot value is the highest
         let label_idx = label_data
             .iter()
             .enumerate()
-            .reduce(|best, curr| if curr.1 > best.1 { best } else { curr })
+            .reduce(|best, curr| if curr.1 > best.1 { curr } else { best })
             .unwrap()
             .0;
 
         self.label = LabelType::from_index(label_idx);
+        self.extra_labels = label_data
+            .iter()
+            .enumerate()
+            .filter(|&(idx, &value)| idx != label_idx && value >= 0.5)
+            .map(|(idx, _)| LabelType::from_index(idx))
+            .collect();
         self.img.decode_from(img_data)?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image() -> ImageData {
+        ImageData::from_luma(2, 2, [0.0, 0.25, 0.5, 1.0]).unwrap()
+    }
+
+    #[test]
+    fn decode_labels() {
+        let mut output = image().encode().unwrap();
+        output.extend([0.6, 0.1, 0.9, 0.2]);
+
+        let mut decoded = LabeledImage::new(1_usize, image(), 4);
+        decoded.decode_from(&output).unwrap();
+
+        assert_eq!(decoded.label, 2);
+        assert_eq!(decoded.extra_labels, [0]);
+    }
+
+    #[test]
+    fn decode_without_labels() {
+        let mut labeled = LabeledImage::new(0_usize, image(), 0);
+        let encoded = image().encode().unwrap();
+
+        assert!(labeled.decode_from(&encoded).is_err());
+    }
+}
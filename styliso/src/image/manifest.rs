@@ -0,0 +1,404 @@
+/*!
+ * Dataset manifests: files which list the images of a dataset, with their
+ * labels and weights, as an alternative to a directory per label. They allow
+ * images with several labels, images which weigh more or less than others in
+ * training, and datasets defined once and reproduced anywhere.
+ *
+ * A manifest is either a CSV file with a header row naming a `path` column, a
+ * `labels` (or `label`) column with labels separated by `;`, and optionally a
+ * `weight` column:
+ *
+ * ```text
+ * path,labels,weight
+ * cats/tabby.png,cat,1
+ * misc/both.png,cat;dog,0.5
+ * ```
+ *
+ * or a JSON file with a list of entries, or an object with such a list in
+ * `images`:
+ *
+ * ```json
+ * [
+ *     { "path": "cats/tabby.png", "label": "cat" },
+ *     { "path": "misc/both.png", "labels": ["cat", "dog"], "weight": 0.5 }
+ * ]
+ * ```
+ *
+ * Paths are relative to the directory of the manifest. Weights default to 1.
+ *
+ * A JSON object can also give the augmentation to train with in `augment`,
+ * written like [Augmentation]s are parsed, e.g.
+ * `{ "augment": "flip,rotate=10", "images": [...] }`.
+ */
+use super::augment::Augmentation;
+use super::dataset::{Dataset, DatasetFile, DatasetOptions, SkippedFile};
+use neurs::train::label::TrainingLabel;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// An entry of a manifest, before its labels are indexed.
+struct ManifestEntry {
+    path: PathBuf,
+    labels: Vec<String>,
+    weight: f32,
+}
+
+/// An entry of a manifest, or the path it gives and why it is invalid.
+type EntryResult = Result<ManifestEntry, (String, String)>;
+
+/// Checks an entry's labels and weight.
+fn check_entry(entry: &ManifestEntry) -> Result<(), String> {
+    if entry.labels.is_empty() {
+        return Err("No labels given".to_owned());
+    }
+
+    if !entry.weight.is_finite() || entry.weight <= 0.0 {
+        return Err(format!("Invalid weight {}", entry.weight));
+    }
+
+    Ok(())
+}
+
+/// Reads the entries of a CSV manifest.
+fn csv_entries(path: &Path) -> Result<Vec<EntryResult>, String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(file);
+
+    let headers = reader.headers().map_err(|err| err.to_string())?.clone();
+    let column = |name: &str| headers.iter().position(|header| header == name);
+
+    let path_column = column("path").ok_or("No path column")?;
+    let labels_column = column("labels")
+        .or_else(|| column("label"))
+        .ok_or("No labels column")?;
+    let weight_column = column("weight");
+
+    let mut entries = vec![];
+
+    for record in reader.records() {
+        let record = record.map_err(|err| err.to_string())?;
+        let image = record.get(path_column).unwrap_or("").to_owned();
+
+        let weight = match weight_column.and_then(|column| record.get(column)) {
+            None | Some("") => Ok(1.0),
+            Some(weight) => weight
+                .parse()
+                .map_err(|_| format!("Invalid weight {:?}", weight)),
+        };
+
+        entries.push(match weight {
+            Ok(weight) => Ok(ManifestEntry {
+                path: PathBuf::from(&image),
+                labels: record
+                    .get(labels_column)
+                    .unwrap_or("")
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|label| !label.is_empty())
+                    .map(str::to_owned)
+                    .collect(),
+                weight,
+            }),
+
+            Err(reason) => Err((image, reason)),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Reads an entry of a JSON manifest.
+fn json_entry(entry: &Value) -> Result<ManifestEntry, String> {
+    let path = entry["path"].as_str().ok_or("No path given")?;
+
+    let labels = match (&entry["labels"], &entry["label"]) {
+        (Value::Array(labels), _) => labels
+            .iter()
+            .map(|label| label.as_str().map(str::to_owned))
+            .collect::<Option<Vec<_>>>()
+            .ok_or("Labels must be strings")?,
+
+        (Value::Null, Value::String(label)) => vec![label.clone()],
+        (Value::Null, Value::Null) => vec![],
+        _ => return Err("Labels must be a string or a list of strings".to_owned()),
+    };
+
+    let weight = match &entry["weight"] {
+        Value::Null => 1.0,
+        weight => weight.as_f64().ok_or("Weight must be a number")? as f32,
+    };
+
+    Ok(ManifestEntry {
+        path: PathBuf::from(path),
+        labels,
+        weight,
+    })
+}
+
+/// Reads the entries of a JSON manifest, and the augmentation it gives, if
+/// any.
+fn json_entries(path: &Path) -> Result<(Vec<EntryResult>, Option<Augmentation>), String> {
+    let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let root: Value = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+
+    let augmentation = match &root["augment"] {
+        Value::Null => None,
+        Value::String(spec) => Some(spec.parse()?),
+        _ => return Err("Augmentation must be a string".to_owned()),
+    };
+
+    let entries = root
+        .get("images")
+        .unwrap_or(&root)
+        .as_array()
+        .ok_or("Expected a list of images")?;
+
+    let entries = entries
+        .iter()
+        .map(|entry| {
+            json_entry(entry).map_err(|reason| {
+                let image = entry["path"].as_str().unwrap_or("").to_owned();
+                (image, reason)
+            })
+        })
+        .collect();
+
+    Ok((entries, augmentation))
+}
+
+impl<L: TrainingLabel> Dataset<L> {
+    /**
+     * Loads a dataset from a CSV or JSON manifest, told apart by its
+     * extension. Labels are indexed in the order of their names.
+     *
+     * Entries which are invalid, such as those without labels, and images
+     * which cannot be loaded are skipped, like with [Dataset::load], and
+     * `options` and `on_progress` are used in the same way. The augmentation
+     * the manifest gives, if any, is used unless the options give one.
+     *
+     * Fails if the manifest cannot be read or parsed, if there are more
+     * labels than `L` can tell apart, or if no image could be loaded.
+     */
+    pub fn from_manifest<P: AsRef<Path>>(
+        manifest: P,
+        options: &DatasetOptions,
+        on_progress: impl FnMut(usize, usize, &Path),
+    ) -> Result<Self, String> {
+        let manifest = manifest.as_ref();
+
+        let extension = manifest
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+
+        let (entries, augmentation) = match extension.as_deref() {
+            Some("csv") => csv_entries(manifest).map(|entries| (entries, None)),
+            Some("json") => json_entries(manifest),
+            _ => Err("Expected a .csv or .json manifest".to_owned()),
+        }
+        .map_err(|err| format!("Could not read {}: {}", manifest.display(), err))?;
+
+        let base = manifest.parent().unwrap_or(Path::new(""));
+        let mut skipped = vec![];
+        let mut valid = vec![];
+
+        for entry in entries {
+            match entry.and_then(|entry| match check_entry(&entry) {
+                Ok(()) => Ok(entry),
+                Err(reason) => Err((entry.path.to_string_lossy().into_owned(), reason)),
+            }) {
+                Ok(entry) => valid.push(entry),
+
+                Err((image, reason)) => skipped.push(SkippedFile {
+                    path: base.join(image),
+                    reason,
+                }),
+            }
+        }
+
+        let labels: Vec<String> = valid
+            .iter()
+            .flat_map(|entry| entry.labels.iter().cloned())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        let files = valid
+            .into_iter()
+            .map(|entry| {
+                let mut indices: Vec<usize> = vec![];
+
+                for label in &entry.labels {
+                    let index = labels.binary_search(label).unwrap();
+
+                    if !indices.contains(&index) {
+                        indices.push(index);
+                    }
+                }
+
+                DatasetFile {
+                    path: base.join(entry.path),
+                    labels: indices,
+                    weight: entry.weight,
+                }
+            })
+            .collect();
+
+        let mut dataset = Self::load_files(files, labels, skipped, manifest, options, on_progress)?;
+
+        if let (None, Some(augmentation)) = (options.augmentation, augmentation) {
+            dataset.augmentation = augmentation;
+        }
+
+        Ok(dataset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::data::ImageData;
+    use serde_json::json;
+
+    /// A fresh directory with a small image in each of `images`, for a test
+    /// to write a manifest into.
+    fn test_dir(name: &str, images: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("styliso-{}-{}", name, std::process::id()));
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        for image in images {
+            ImageData::from_luma(2, 2, [0.0, 0.25, 0.5, 1.0])
+                .unwrap()
+                .save(dir.join(image))
+                .unwrap();
+        }
+
+        dir
+    }
+
+    fn skipped_paths(dataset: &Dataset) -> BTreeSet<PathBuf> {
+        dataset
+            .skipped
+            .iter()
+            .map(|file| file.path.clone())
+            .collect()
+    }
+
+    #[test]
+    fn load_csv_manifest() {
+        let dir = test_dir("manifest-csv", &["a.png", "b.png", "c.png", "d.png"]);
+
+        fs::write(
+            dir.join("manifest.csv"),
+            "path,labels,weight\n\
+             a.png,dog,2\n\
+             b.png, dog ; cat ,\n\
+             c.png,,1\n\
+             d.png,cat,heavy\n\
+             missing.png,cat,1\n",
+        )
+        .unwrap();
+
+        let dataset: Dataset =
+            Dataset::from_manifest(dir.join("manifest.csv"), &Default::default(), |_, _, _| {})
+                .unwrap();
+
+        // labels are indexed in the order of their names
+        assert_eq!(dataset.labels, ["cat", "dog"]);
+
+        assert_eq!(dataset.paths, [dir.join("a.png"), dir.join("b.png")]);
+        assert_eq!(dataset.weights, [2.0, 1.0]);
+
+        assert_eq!(dataset.images[0].label, 1);
+        assert!(dataset.images[0].extra_labels.is_empty());
+        assert_eq!(dataset.images[1].label, 1);
+        assert_eq!(dataset.images[1].extra_labels, [0]);
+
+        assert_eq!(
+            skipped_paths(&dataset),
+            ["c.png", "d.png", "missing.png"]
+                .iter()
+                .map(|image| dir.join(image))
+                .collect()
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn load_json_manifest() {
+        let dir = test_dir("manifest-json", &["a.png", "b.png", "c.png"]);
+
+        let manifest = json!({
+            "augment": "flip,noise=0.1",
+            "images": [
+                { "path": "a.png", "label": "zebra" },
+                { "path": "b.png", "labels": ["zebra", "ant"], "weight": 0.5 },
+                { "path": "c.png", "labels": [1] },
+                { "label": "ant" },
+            ],
+        });
+
+        fs::write(dir.join("manifest.json"), manifest.to_string()).unwrap();
+
+        let dataset: Dataset =
+            Dataset::from_manifest(dir.join("manifest.json"), &Default::default(), |_, _, _| {})
+                .unwrap();
+
+        assert_eq!(dataset.labels, ["ant", "zebra"]);
+        assert_eq!(dataset.paths, [dir.join("a.png"), dir.join("b.png")]);
+        assert_eq!(dataset.weights, [1.0, 0.5]);
+
+        assert_eq!(dataset.images[0].label, 1);
+        assert_eq!(dataset.images[1].label, 1);
+        assert_eq!(dataset.images[1].extra_labels, [0]);
+
+        assert_eq!(
+            skipped_paths(&dataset),
+            [dir.join("c.png"), dir.clone()].into_iter().collect()
+        );
+
+        assert!(dataset.augmentation.flip);
+        assert_eq!(dataset.augmentation.noise, 0.1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn json_entry_errors() {
+        assert!(json_entry(&json!({ "label": "cat" })).is_err());
+        assert!(json_entry(&json!({ "path": "a.png", "labels": "cat" })).is_err());
+        assert!(json_entry(&json!({ "path": "a.png", "label": "cat", "weight": "1" })).is_err());
+
+        let entry = json_entry(&json!({ "path": "a.png" })).unwrap();
+        assert!(check_entry(&entry).is_err());
+    }
+
+    #[test]
+    fn check_weights() {
+        let entry = |weight| ManifestEntry {
+            path: PathBuf::from("a.png"),
+            labels: vec!["cat".to_owned()],
+            weight,
+        };
+
+        assert!(check_entry(&entry(0.5)).is_ok());
+
+        for weight in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(check_entry(&entry(weight)).is_err());
+        }
+    }
+
+    #[test]
+    fn reject_unknown_extension() {
+        let result: Result<Dataset, _> =
+            Dataset::from_manifest("manifest.txt", &Default::default(), |_, _, _| {});
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,201 @@
+/*!
+ * Metrics of how alike two images are, e.g. an image and its reconstruction
+ * by an autoencoder: the mean squared error and peak signal-to-noise ratio of
+ * their red, green and blue values, and the structural similarity (SSIM) of
+ * their luma.
+ */
+use super::data::ImageData;
+
+/// The side of the square windows SSIM is measured over, unless the image
+/// is smaller.
+const SSIM_WINDOW: usize = 7;
+
+/// How alike two images are. See [ImageMetrics::compare].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImageMetrics {
+    /// The mean squared error of the red, green and blue values, from 0 to
+    /// 1; 0 if the images are the same.
+    pub mse: f64,
+
+    /// The peak signal-to-noise ratio, in decibels; the higher, the more
+    /// alike, and infinite if the images are the same.
+    pub psnr: f64,
+
+    /// The structural similarity of the images, from -1 to 1; 1 if the
+    /// images are the same.
+    pub ssim: f64,
+}
+
+/// The peak signal-to-noise ratio of a mean squared error, with values
+/// between 0 and 1.
+fn psnr(mse: f64) -> f64 {
+    -10.0 * mse.log10()
+}
+
+/// The luma of each pixel of an image, between 0 and 1.
+fn luma(image: &ImageData) -> Vec<f64> {
+    image
+        .to_rgb()
+        .iter()
+        .map(|[r, g, b]| 0.299 * r + 0.587 * g + 0.114 * b)
+        .collect()
+}
+
+/**
+ * The mean structural similarity of two planes of values between 0 and 1,
+ * over every square window of them, with uniform weights.
+ */
+fn ssim(a: &[f64], b: &[f64], width: usize, height: usize) -> f64 {
+    const C1: f64 = 0.01 * 0.01;
+    const C2: f64 = 0.03 * 0.03;
+
+    let window = SSIM_WINDOW.min(width).min(height);
+    let count = (window * window) as f64;
+
+    let mut total = 0.0;
+    let mut windows = 0;
+
+    for top in 0..=height - window {
+        for left in 0..=width - window {
+            let pixels = || {
+                (top..top + window)
+                    .flat_map(move |y| (left..left + window).map(move |x| y * width + x))
+            };
+
+            let mean_a = pixels().map(|i| a[i]).sum::<f64>() / count;
+            let mean_b = pixels().map(|i| b[i]).sum::<f64>() / count;
+
+            let (mut var_a, mut var_b, mut covar) = (0.0, 0.0, 0.0);
+
+            for i in pixels() {
+                let (da, db) = (a[i] - mean_a, b[i] - mean_b);
+
+                var_a += da * da;
+                var_b += db * db;
+                covar += da * db;
+            }
+
+            let (var_a, var_b, covar) = (var_a / count, var_b / count, covar / count);
+
+            total += ((2.0 * mean_a * mean_b + C1) * (2.0 * covar + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2));
+            windows += 1;
+        }
+    }
+
+    total / windows as f64
+}
+
+impl ImageMetrics {
+    /**
+     * Measures how alike two images of the same size are, in colour or in
+     * grayscale. Alpha is not taken into account.
+     */
+    pub fn compare(a: &ImageData, b: &ImageData) -> Result<Self, String> {
+        if (a.width, a.height) != (b.width, b.height) {
+            return Err(format!(
+                "Cannot compare a {}x{} image with a {}x{} one",
+                a.width, a.height, b.width, b.height
+            ));
+        }
+
+        if a.area == 0 {
+            return Err("Cannot compare empty images".to_owned());
+        }
+
+        let mse = a
+            .to_rgb()
+            .iter()
+            .zip(b.to_rgb())
+            .flat_map(|(a, b)| (0..3).map(move |channel| (a[channel] - b[channel]).powi(2)))
+            .sum::<f64>()
+            / (a.area as f64 * 3.0);
+
+        Ok(ImageMetrics {
+            mse,
+            psnr: psnr(mse),
+            ssim: ssim(&luma(a), &luma(b), a.width as usize, a.height as usize),
+        })
+    }
+
+    /**
+     * Averages the metrics of several pairs of images. The PSNR is that of
+     * the mean squared error, so that a single pair of identical images does
+     * not make it infinite.
+     */
+    pub fn mean<'a>(metrics: impl IntoIterator<Item = &'a ImageMetrics>) -> Option<Self> {
+        let (mut mse, mut ssim, mut count) = (0.0, 0.0, 0);
+
+        for metrics in metrics {
+            mse += metrics.mse;
+            ssim += metrics.ssim;
+            count += 1;
+        }
+
+        (count > 0).then(|| {
+            let mse = mse / count as f64;
+
+            ImageMetrics {
+                mse,
+                psnr: psnr(mse),
+                ssim: ssim / count as f64,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flat(width: u16, height: u16, luma: f64) -> ImageData {
+        ImageData::from_luma(width, height, vec![luma; width as usize * height as usize]).unwrap()
+    }
+
+    #[test]
+    fn identical_images() {
+        let image = ImageData::gradient(12, 10);
+        let metrics = ImageMetrics::compare(&image, &image).unwrap();
+
+        assert_eq!(metrics.mse, 0.0);
+        assert_eq!(metrics.psnr, f64::INFINITY);
+        assert!((metrics.ssim - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn known_mse() {
+        let metrics = ImageMetrics::compare(&flat(8, 8, 0.0), &flat(8, 8, 0.5)).unwrap();
+
+        assert!((metrics.mse - 0.25).abs() < 1e-9);
+        assert!((metrics.psnr - 10.0 * 4.0_f64.log10()).abs() < 1e-9);
+        assert!(metrics.ssim < 1.0);
+    }
+
+    #[test]
+    fn smaller_than_ssim_window() {
+        let (a, b) = (ImageData::gradient(3, 2), flat(3, 2, 0.5));
+
+        assert!((ImageMetrics::compare(&a, &a).unwrap().ssim - 1.0).abs() < 1e-9);
+
+        let ssim = ImageMetrics::compare(&a, &b).unwrap().ssim;
+        assert!(ssim.is_finite() && ssim < 1.0);
+    }
+
+    #[test]
+    fn compare_rejects_other_sizes() {
+        assert!(ImageMetrics::compare(&flat(4, 4, 0.0), &flat(4, 3, 0.0)).is_err());
+    }
+
+    #[test]
+    fn mean_of_metrics() {
+        assert_eq!(ImageMetrics::mean(&[]), None);
+
+        let same = ImageMetrics::compare(&flat(4, 4, 0.5), &flat(4, 4, 0.5)).unwrap();
+        let other = ImageMetrics::compare(&flat(4, 4, 0.0), &flat(4, 4, 0.5)).unwrap();
+        let mean = ImageMetrics::mean(&[same, other]).unwrap();
+
+        assert!((mean.mse - 0.125).abs() < 1e-9);
+        assert!(mean.psnr.is_finite());
+        assert!((mean.ssim - (same.ssim + other.ssim) / 2.0).abs() < 1e-9);
+    }
+}
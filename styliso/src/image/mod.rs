@@ -3,7 +3,18 @@
  * concerning PNG files and the implementation
  * of autoencoder traits for image data.
  */
+pub mod augment;
+#[cfg(feature = "bmp")]
+pub mod bmp;
 pub mod data;
+pub mod dataset;
+pub mod io;
+pub mod jpeg;
 pub mod labeled;
+pub mod manifest;
+pub mod metrics;
 pub mod png;
 pub mod prelude;
+pub mod transform;
+#[cfg(feature = "webp")]
+pub mod webp;
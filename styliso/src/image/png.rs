@@ -3,11 +3,14 @@
  * loading for [super::data::ImageData].
  */
 use super::data::ImageData;
+use std::fmt;
 use std::io::{Read, Write};
+use std::str::FromStr;
 
-use color_space::{FromRgb, Hsl, Rgb, ToRgb};
-use png::BitDepth::*;
-use png::{Decoder as PNGDecoder, DecodingError, Encoder as PNGEncoder, EncodingError};
+use png::{
+    BitDepth, ColorType, Decoder as PNGDecoder, DecodingError, Encoder as PNGEncoder,
+    EncodingError, Transformations,
+};
 
 /// A simple error class which encompasses both errors from the `png` crate and basic errors from this crate.
 pub enum GenericPngError {
@@ -21,51 +24,103 @@ pub enum GenericPngError {
     ImageDataError(String),
 }
 
-impl ImageData {
-    /// Writes ImageData into a PNG file.
-    pub fn to_png<W: Write>(&self, output: W) -> Result<(), GenericPngError> {
-        let mut writer = PNGEncoder::new(output, self.width as u32, self.height as u32);
+impl fmt::Display for GenericPngError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenericPngError::PngDecodeError(err) => write!(f, "{}", err),
+            GenericPngError::PngEncodeError(err) => write!(f, "{}", err),
+            GenericPngError::ImageDataError(err) => write!(f, "{}", err),
+        }
+    }
+}
 
-        writer.set_depth(Sixteen);
+/**
+ * How many bits each sample of a PNG is written with. 8 bits are plenty for
+ * viewing, and make for files half the size and more widely supported, so
+ * they are the default; 16 bits keep more of the precision of [ImageData].
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PngDepth {
+    #[default]
+    Eight,
+    Sixteen,
+}
 
-        /* PNGWriter::new(output, PartialInfo {
-            width: self.width,
-            height: self.height,
-            bit_depth: BitDepth.Eight,
-        }); */
+impl FromStr for PngDepth {
+    type Err = String;
+
+    fn from_str(bits: &str) -> Result<Self, String> {
+        match bits {
+            "8" => Ok(PngDepth::Eight),
+            "16" => Ok(PngDepth::Sixteen),
+            _ => Err(format!(
+                "Unsupported PNG depth {:?}; expected 8 or 16",
+                bits
+            )),
+        }
+    }
+}
 
-        let mut rgb_values = vec![0u16; self.area as usize * 3];
+impl ImageData {
+    /// Writes ImageData into a PNG file, with 8 bits per sample; see
+    /// [Self::to_png_with_depth].
+    pub fn to_png<W: Write>(&self, output: W) -> Result<(), GenericPngError> {
+        self.to_png_with_depth(output, PngDepth::default())
+    }
 
-        if self.colour.is_some() {
-            let colour = self.colour.as_ref().unwrap();
+    /**
+     * Writes ImageData into a PNG file, as RGB if it is in colour, or as
+     * grayscale otherwise, with an alpha channel if it has an alpha plane,
+     * and with the given number of bits per sample.
+     */
+    pub fn to_png_with_depth<W: Write>(
+        &self,
+        output: W,
+        depth: PngDepth,
+    ) -> Result<(), GenericPngError> {
+        let mut writer = PNGEncoder::new(output, self.width as u32, self.height as u32);
 
-            for i in 0..self.area as usize {
-                let rgb = Hsl {
-                    h: colour.0[i] as f64,
-                    s: colour.1[i] as f64,
-                    l: self.brightness[i] as f64,
+        writer.set_depth(match depth {
+            PngDepth::Eight => BitDepth::Eight,
+            PngDepth::Sixteen => BitDepth::Sixteen,
+        });
+        writer.set_color(match (self.colour.is_some(), self.alpha.is_some()) {
+            (true, false) => ColorType::Rgb,
+            (true, true) => ColorType::Rgba,
+            (false, false) => ColorType::Grayscale,
+            (false, true) => ColorType::GrayscaleAlpha,
+        });
+
+        let pixels: Vec<Vec<f64>> = match &self.colour {
+            Some(_) => self.to_rgb().into_iter().map(Vec::from).collect(),
+            None => self
+                .brightness
+                .iter()
+                .map(|&luma| vec![luma as f64])
+                .collect(),
+        };
+
+        let mut data = Vec::with_capacity(self.area as usize * 8);
+
+        let mut push_sample = |value: f64| {
+            let value = value.clamp(0.0, 1.0);
+
+            match depth {
+                PngDepth::Eight => data.push((value * u8::MAX as f64).round() as u8),
+                PngDepth::Sixteen => {
+                    data.extend(((value * u16::MAX as f64).round() as u16).to_be_bytes())
                 }
-                .to_rgb();
-
-                rgb_values[i * 3] = (rgb.r as f64 * u16::MAX as f64) as u16;
-                rgb_values[i * 3 + 1] = (rgb.g as f64 * u16::MAX as f64) as u16;
-                rgb_values[i * 3 + 2] = (rgb.b as f64 * u16::MAX as f64) as u16;
             }
-        } else {
-            for i in 0..self.area as usize {
-                let val = (self.brightness[i] as f64 * u16::MAX as f64) as u16;
+        };
 
-                rgb_values[i * 3] = val;
-                rgb_values[i * 3 + 1] = val;
-                rgb_values[i * 3 + 2] = val;
+        for (i, pixel) in pixels.into_iter().enumerate() {
+            for value in pixel {
+                push_sample(value);
             }
-        }
-
-        let mut rgb_data = vec![0u8; self.area as usize * 6];
 
-        for (idx, val) in rgb_values.iter().enumerate() {
-            rgb_data[idx * 2] = (val & 0xFF00 >> 8) as u8;
-            rgb_data[idx * 2 + 1] = (val & 0x00FF) as u8;
+            if let Some(alpha) = &self.alpha {
+                push_sample(alpha[i] as f64);
+            }
         }
 
         let mut datawriter = writer
@@ -73,7 +128,7 @@ impl ImageData {
             .map_err(GenericPngError::PngEncodeError)?;
 
         datawriter
-            .write_image_data(&rgb_data)
+            .write_image_data(&data)
             .map_err(GenericPngError::PngEncodeError)?;
         datawriter
             .finish()
@@ -84,101 +139,252 @@ impl ImageData {
 
     //---
 
-    /// Loads ImageData from a PNG file.
+    /**
+     * Loads ImageData from a PNG file, of any colour type and bit depth.
+     * Grayscale images load as grayscale, i.e. with no colour planes.
+     * Palette-indexed images are expanded through their palette, and
+     * grayscale images of less than 8 bits per sample are scaled up. The
+     * alpha channel, if any, including palette transparency, is loaded into
+     * the alpha plane.
+     */
     pub fn from_png<R: Read>(input: R) -> Result<Self, GenericPngError> {
-        let reader = PNGDecoder::new(input);
+        let mut reader = PNGDecoder::new(input);
+
+        // leaves only 8 or 16 bit RGB or grayscale, with or without alpha
+        reader.set_transformations(Transformations::EXPAND);
 
         let mut datareader = reader
             .read_info()
             .map_err(GenericPngError::PngDecodeError)?;
 
+        let (colour_type, depth) = datareader.output_color_type();
         let info = datareader.info();
 
-        let width = info.width;
-        let height = info.height;
-        let area = width * height;
-        let depth = info.bit_depth;
+        let (width, height) = match (u16::try_from(info.width), u16::try_from(info.height)) {
+            (Ok(width), Ok(height)) => (width, height),
+            _ => {
+                return Err(GenericPngError::ImageDataError(format!(
+                    "PNG too large: {}x{}",
+                    info.width, info.height
+                )))
+            }
+        };
 
-        let mut brightness = vec![0.0f32; area as usize];
-        let mut hue = vec![0.0f32; area as usize];
-        let mut saturation = vec![0.0f32; area as usize];
+        if colour_type == ColorType::Indexed
+            || (depth != BitDepth::Eight && depth != BitDepth::Sixteen)
+        {
+            return Err(GenericPngError::ImageDataError(
+                "PNG could not be expanded to 8 or 16 bits per sample".to_owned(),
+            ));
+        }
 
-        let mut startidx = 0;
+        let mut buffer = vec![0; datareader.output_buffer_size()];
+        let frame = datareader
+            .next_frame(&mut buffer)
+            .map_err(GenericPngError::PngDecodeError)?;
 
-        while let Some(row) = datareader
-            .next_row()
-            .map_err(GenericPngError::PngDecodeError)?
-        {
-            let rowdata = row.data();
-
-            for x in 0..width as usize {
-                let rgb = match depth {
-                    Eight => Rgb::new(
-                        rowdata[x * 3] as f64 / u8::MAX as f64,
-                        rowdata[x * 3 + 1] as f64 / u8::MAX as f64,
-                        rowdata[x * 3 + 2] as f64 / u8::MAX as f64,
-                    ),
-
-                    Sixteen => Rgb::new(
-                        (rowdata[x * 6] as u16 | (rowdata[x * 6 + 1] as u16) << 8) as f64
-                            / u16::MAX as f64,
-                        (rowdata[x * 6 + 2] as u16 | (rowdata[x * 6 + 3] as u16) << 8) as f64
-                            / u16::MAX as f64,
-                        (rowdata[x * 6 + 4] as u16 | (rowdata[x * 6 + 5] as u16) << 8) as f64
-                            / u16::MAX as f64,
-                    ),
-
-                    Four => {
-                        if x % 2 == 0 {
-                            let bx = x * 3 / 2;
-
-                            Rgb::new(
-                                (rowdata[bx] as u8 & 0xF0 >> 4) as f64 / 16_f64,
-                                (rowdata[bx] as u8 & 0x0F) as f64 / 16_f64,
-                                (rowdata[bx + 1] as u8 & 0xF0 >> 4) as f64 / 16_f64,
-                            )
-                        } else {
-                            let bx = 1 + ((x - 1) * 3 / 2);
-
-                            Rgb::new(
-                                (rowdata[bx] as u8 & 0x0F) as f64 / 16_f64,
-                                (rowdata[bx] as u8 & 0xF0 >> 4) as f64 / 16_f64,
-                                (rowdata[bx + 1] as u8 & 0x0F) as f64 / 16_f64,
-                            )
-                        }
-                    }
-
-                    Two => {
-                        return Err(GenericPngError::ImageDataError(
-                            "PNGs with bit depth 2 not supported".to_owned(),
-                        ));
-                    }
-
-                    One => {
-                        return Err(GenericPngError::ImageDataError(
-                            "PNGs with bit depth 1 not supported".to_owned(),
-                        ));
-                    }
-                };
-
-                let hsl = Hsl::from_rgb(&rgb);
-
-                brightness[startidx + x] = hsl.l as f32;
-                hue[startidx + x] = hsl.h as f32;
-                saturation[startidx + x] = hsl.s as f32;
+        // each sample, between 0 and 1
+        let samples: Vec<f64> = match depth {
+            BitDepth::Sixteen => buffer[..frame.buffer_size()]
+                .chunks_exact(2)
+                .map(|sample| u16::from_be_bytes([sample[0], sample[1]]) as f64 / u16::MAX as f64)
+                .collect(),
+
+            _ => buffer[..frame.buffer_size()]
+                .iter()
+                .map(|&sample| sample as f64 / u8::MAX as f64)
+                .collect(),
+        };
+
+        let channels = colour_type.samples();
+        let pixels = samples.chunks_exact(channels);
+
+        let mut image = match colour_type {
+            ColorType::Rgb | ColorType::Rgba => {
+                ImageData::from_rgb(width, height, pixels.clone().map(|p| [p[0], p[1], p[2]]))
+            }
+
+            _ => ImageData::from_luma(width, height, pixels.clone().map(|p| p[0])),
+        }
+        .map_err(GenericPngError::ImageDataError)?;
+
+        if matches!(colour_type, ColorType::Rgba | ColorType::GrayscaleAlpha) {
+            image.alpha = Some(pixels.map(|p| p[channels - 1] as f32).collect());
+        }
+
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes raw PNG data, with a palette and its transparency if given.
+    fn encode_raw(
+        (width, height): (u32, u32),
+        colour: ColorType,
+        depth: BitDepth,
+        palette: Option<(&[u8], &[u8])>,
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut bytes = vec![];
+        let mut encoder = PNGEncoder::new(&mut bytes, width, height);
+
+        encoder.set_color(colour);
+        encoder.set_depth(depth);
+
+        if let Some((palette, transparency)) = palette {
+            encoder.set_palette(palette.to_vec());
+            encoder.set_trns(transparency.to_vec());
+        }
+
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(data).unwrap();
+        writer.finish().unwrap();
+
+        bytes
+    }
+
+    fn assert_close(a: &[[f64; 3]], b: &[[f64; 3]], tolerance: f64) {
+        assert_eq!(a.len(), b.len());
+
+        for (a, b) in a.iter().zip(b) {
+            for channel in 0..3 {
+                assert!(
+                    (a[channel] - b[channel]).abs() <= tolerance,
+                    "{:?} != {:?}",
+                    a,
+                    b
+                );
             }
+        }
+    }
+
+    #[test]
+    fn load_palette() {
+        // red, green and a half transparent blue, 2 bits per index
+        let palette = [255, 0, 0, 0, 255, 0, 0, 0, 255];
+        let transparency = [255, 255, 128];
+
+        let bytes = encode_raw(
+            (3, 1),
+            ColorType::Indexed,
+            BitDepth::Two,
+            Some((&palette, &transparency)),
+            &[0b00_01_10_00],
+        );
+
+        let image = ImageData::from_png(&bytes[..]).ok().unwrap();
+
+        assert!(image.colour.is_some());
+        assert_close(
+            &image.to_rgb(),
+            &[[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            1e-6,
+        );
+
+        let alpha = image.alpha.unwrap();
+        assert_eq!(alpha[..2], [1.0, 1.0]);
+        assert!((alpha[2] - 128.0 / 255.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn load_low_depth_grayscale() {
+        let bytes = encode_raw(
+            (4, 1),
+            ColorType::Grayscale,
+            BitDepth::Two,
+            None,
+            &[0b00_01_10_11],
+        );
+
+        let image = ImageData::from_png(&bytes[..]).ok().unwrap();
+
+        assert!(image.colour.is_none());
+        assert_eq!(image.brightness, [0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0]);
+    }
+
+    /// The colour type and bit depth a PNG was written with.
+    fn header(bytes: &[u8]) -> (ColorType, BitDepth) {
+        let reader = PNGDecoder::new(bytes).read_info().unwrap();
+        let info = reader.info();
+
+        (info.color_type, info.bit_depth)
+    }
+
+    #[test]
+    fn grayscale_roundtrip() {
+        let image = ImageData::from_luma(2, 2, [0.0, 0.2, 0.6, 1.0]).unwrap();
+
+        let mut bytes = vec![];
+        image.to_png(&mut bytes).ok().unwrap();
+
+        assert_eq!(header(&bytes).0, ColorType::Grayscale);
+
+        let loaded = ImageData::from_png(&bytes[..]).ok().unwrap();
 
-            startidx += height as usize;
+        assert!(loaded.colour.is_none());
+        assert!(loaded.alpha.is_none());
+
+        for (a, b) in loaded.brightness.iter().zip(&image.brightness) {
+            assert!((a - b).abs() <= 0.5 / 255.0);
+        }
+    }
+
+    #[test]
+    fn grayscale_alpha_roundtrip() {
+        let mut image = ImageData::from_luma(2, 1, [0.25, 0.75]).unwrap();
+        image.alpha = Some(vec![1.0, 0.0]);
+
+        let mut bytes = vec![];
+        image.to_png(&mut bytes).ok().unwrap();
+
+        assert_eq!(header(&bytes).0, ColorType::GrayscaleAlpha);
+
+        let loaded = ImageData::from_png(&bytes[..]).ok().unwrap();
+
+        assert!(loaded.colour.is_none());
+        assert_eq!(loaded.alpha, Some(vec![1.0, 0.0]));
+    }
+
+    /// A colour image with values which 8 bits per sample cannot hold.
+    fn fine_colour_image() -> ImageData {
+        ImageData::from_rgb(2, 1, [[0.1234, 0.5678, 0.9012], [0.3456, 0.789, 0.0123]]).unwrap()
+    }
+
+    #[test]
+    fn depth_roundtrip() {
+        let image = fine_colour_image();
+
+        for (depth, bit_depth, tolerance) in [
+            (PngDepth::Eight, BitDepth::Eight, 0.5 / 255.0),
+            (PngDepth::Sixteen, BitDepth::Sixteen, 0.5 / 65535.0),
+        ] {
+            let mut bytes = vec![];
+            image.to_png_with_depth(&mut bytes, depth).ok().unwrap();
+
+            assert_eq!(header(&bytes), (ColorType::Rgb, bit_depth));
+
+            let loaded = ImageData::from_png(&bytes[..]).ok().unwrap();
+
+            // the HSL planes of ImageData are single precision
+            assert_close(&loaded.to_rgb(), &image.to_rgb(), tolerance + 1e-6);
         }
+    }
 
-        Ok(ImageData {
-            area,
-            brightness,
+    #[test]
+    fn default_depth_is_eight() {
+        let mut bytes = vec![];
+        fine_colour_image().to_png(&mut bytes).ok().unwrap();
 
-            width: width as u16,
-            height: height as u16,
+        assert_eq!(header(&bytes).1, BitDepth::Eight);
+    }
 
-            colour: Some((hue, saturation)),
-        })
+    #[test]
+    fn parse_depth() {
+        assert_eq!("8".parse(), Ok(PngDepth::Eight));
+        assert_eq!("16".parse(), Ok(PngDepth::Sixteen));
+        assert!("12".parse::<PngDepth>().is_err());
     }
 }
@@ -1,3 +1,8 @@
 //! A set of useful imports related to images.
+pub use super::augment::*;
 pub use super::data::*;
+pub use super::dataset::*;
+pub use super::io::*;
 pub use super::labeled::*;
+pub use super::metrics::*;
+pub use super::transform::*;
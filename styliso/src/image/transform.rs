@@ -0,0 +1,400 @@
+/*!
+ * Geometric transforms of [ImageData]: resizing, cropping and padding, and
+ * fitting images to a fixed size with them, since autoencoders only work
+ * with images of one size.
+ */
+use super::data::ImageData;
+use std::str::FromStr;
+
+/// How to sample pixels when resizing an image.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResizeFilter {
+    /// Take the nearest pixel; fast, and keeps edges sharp.
+    Nearest,
+
+    /// Blend the four nearest pixels; smoother.
+    #[default]
+    Bilinear,
+}
+
+impl FromStr for ResizeFilter {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name {
+            "nearest" => Ok(ResizeFilter::Nearest),
+            "bilinear" => Ok(ResizeFilter::Bilinear),
+            _ => Err(format!(
+                "Unknown resize filter {:?}; expected nearest or bilinear",
+                name
+            )),
+        }
+    }
+}
+
+/// How to fit an image to a size of another aspect ratio.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FitMode {
+    /// Resize to the size exactly, stretching the image.
+    #[default]
+    Stretch,
+
+    /// Resize to cover the size, keeping the aspect ratio, then crop the
+    /// center.
+    Crop,
+
+    /// Resize to fit within the size, keeping the aspect ratio, then pad
+    /// around it.
+    Pad,
+}
+
+impl FromStr for FitMode {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name {
+            "stretch" => Ok(FitMode::Stretch),
+            "crop" => Ok(FitMode::Crop),
+            "pad" => Ok(FitMode::Pad),
+            _ => Err(format!(
+                "Unknown fit mode {:?}; expected stretch, crop or pad",
+                name
+            )),
+        }
+    }
+}
+
+/**
+ * Resamples a plane of values from one size to another. Hues are circular,
+ * in degrees, and so are blended along the shortest way around.
+ */
+fn resample(
+    plane: &[f32],
+    (src_width, src_height): (usize, usize),
+    (width, height): (usize, usize),
+    filter: ResizeFilter,
+    circular: bool,
+) -> Vec<f32> {
+    let scale_x = src_width as f32 / width as f32;
+    let scale_y = src_height as f32 / height as f32;
+
+    let mut out = Vec::with_capacity(width * height);
+
+    for y in 0..height {
+        for x in 0..width {
+            let value = match filter {
+                ResizeFilter::Nearest => {
+                    let sx = (((x as f32 + 0.5) * scale_x) as usize).min(src_width - 1);
+                    let sy = (((y as f32 + 0.5) * scale_y) as usize).min(src_height - 1);
+
+                    plane[sy * src_width + sx]
+                }
+
+                ResizeFilter::Bilinear => {
+                    let sx = ((x as f32 + 0.5) * scale_x - 0.5).clamp(0.0, (src_width - 1) as f32);
+                    let sy = ((y as f32 + 0.5) * scale_y - 0.5).clamp(0.0, (src_height - 1) as f32);
+
+                    let (x0, y0) = (sx as usize, sy as usize);
+                    let (x1, y1) = ((x0 + 1).min(src_width - 1), (y0 + 1).min(src_height - 1));
+                    let (fx, fy) = (sx - x0 as f32, sy - y0 as f32);
+
+                    let samples = [
+                        (plane[y0 * src_width + x0], (1.0 - fx) * (1.0 - fy)),
+                        (plane[y0 * src_width + x1], fx * (1.0 - fy)),
+                        (plane[y1 * src_width + x0], (1.0 - fx) * fy),
+                        (plane[y1 * src_width + x1], fx * fy),
+                    ];
+
+                    if circular {
+                        let (sin, cos) = samples.iter().fold((0.0, 0.0), |(sin, cos), (h, w)| {
+                            let angle = h.to_radians();
+                            (sin + angle.sin() * w, cos + angle.cos() * w)
+                        });
+
+                        f32::atan2(sin, cos).to_degrees().rem_euclid(360.0)
+                    } else {
+                        samples.iter().map(|(value, w)| value * w).sum()
+                    }
+                }
+            };
+
+            out.push(value);
+        }
+    }
+
+    out
+}
+
+/// Copies a rectangle out of a plane of values.
+fn crop_plane(
+    plane: &[f32],
+    src_width: usize,
+    (left, top): (usize, usize),
+    (width, height): (usize, usize),
+) -> Vec<f32> {
+    (top..top + height)
+        .flat_map(|y| &plane[y * src_width + left..y * src_width + left + width])
+        .copied()
+        .collect()
+}
+
+/// Places a plane of values within a larger one, filled with `fill`.
+fn pad_plane(
+    plane: &[f32],
+    (src_width, src_height): (usize, usize),
+    (left, top): (usize, usize),
+    (width, height): (usize, usize),
+    fill: f32,
+) -> Vec<f32> {
+    let mut out = vec![fill; width * height];
+
+    for y in 0..src_height {
+        let start = (top + y) * width + left;
+        out[start..start + src_width].copy_from_slice(&plane[y * src_width..(y + 1) * src_width]);
+    }
+
+    out
+}
+
+impl ImageData {
+    /// Makes a new image of a size, by transforming each plane of this one.
+    pub(super) fn map_planes(
+        &self,
+        width: u16,
+        height: u16,
+        f: impl Fn(&[f32], bool) -> Vec<f32>,
+    ) -> Self {
+        ImageData {
+            brightness: f(&self.brightness, false),
+            colour: self
+                .colour
+                .as_ref()
+                .map(|(hue, saturation)| (f(hue, true), f(saturation, false))),
+            alpha: self.alpha.as_ref().map(|alpha| f(alpha, false)),
+            width,
+            height,
+            area: width as u32 * height as u32,
+        }
+    }
+
+    /// Resizes this image to another size, with a filter. Images with no
+    /// pixels cannot be resized, and are returned as they are.
+    pub fn resize(&self, width: u16, height: u16, filter: ResizeFilter) -> Self {
+        if self.area == 0 || width == 0 || height == 0 {
+            return self.map_planes(width, height, |_, _| {
+                vec![0.0; width as usize * height as usize]
+            });
+        }
+
+        let src = (self.width as usize, self.height as usize);
+        let dst = (width as usize, height as usize);
+
+        self.map_planes(width, height, |plane, circular| {
+            resample(plane, src, dst, filter, circular)
+        })
+    }
+
+    /// Crops a rectangle of a size out of this image, from its top left
+    /// corner. The rectangle must be within the image.
+    pub fn crop(&self, left: u16, top: u16, width: u16, height: u16) -> Result<Self, String> {
+        if left as u32 + width as u32 > self.width as u32
+            || top as u32 + height as u32 > self.height as u32
+        {
+            return Err(format!(
+                "Cannot crop {}x{} at {},{} out of a {}x{} image",
+                width, height, left, top, self.width, self.height
+            ));
+        }
+
+        Ok(self.map_planes(width, height, |plane, _| {
+            crop_plane(
+                plane,
+                self.width as usize,
+                (left as usize, top as usize),
+                (width as usize, height as usize),
+            )
+        }))
+    }
+
+    /// Crops a rectangle of a size out of the center of this image, which
+    /// must be at least as large.
+    pub fn center_crop(&self, width: u16, height: u16) -> Result<Self, String> {
+        if width > self.width || height > self.height {
+            return Err(format!(
+                "Cannot crop {}x{} out of a {}x{} image",
+                width, height, self.width, self.height
+            ));
+        }
+
+        self.crop(
+            (self.width - width) / 2,
+            (self.height - height) / 2,
+            width,
+            height,
+        )
+    }
+
+    /**
+     * Pads this image, which must be at most as large as the size given, to
+     * that size, keeping it centered. The padding is black, and transparent
+     * if the image has an alpha plane.
+     */
+    pub fn pad(&self, width: u16, height: u16) -> Result<Self, String> {
+        if width < self.width || height < self.height {
+            return Err(format!(
+                "Cannot pad a {}x{} image to {}x{}",
+                self.width, self.height, width, height
+            ));
+        }
+
+        let src = (self.width as usize, self.height as usize);
+        let dst = (width as usize, height as usize);
+        let offset = ((dst.0 - src.0) / 2, (dst.1 - src.1) / 2);
+
+        Ok(self.map_planes(width, height, |plane, _| {
+            pad_plane(plane, src, offset, dst, 0.0)
+        }))
+    }
+
+    /// Fits this image to a size, resizing it with a filter, and cropping or
+    /// padding it depending on the mode.
+    pub fn fit(&self, width: u16, height: u16, mode: FitMode, filter: ResizeFilter) -> Self {
+        if (self.width, self.height) == (width, height) {
+            return self.clone();
+        }
+
+        let scale_x = width as f64 / self.width.max(1) as f64;
+        let scale_y = height as f64 / self.height.max(1) as f64;
+
+        let scaled = |scale: f64| {
+            (
+                ((self.width as f64 * scale).round() as u16).max(1),
+                ((self.height as f64 * scale).round() as u16).max(1),
+            )
+        };
+
+        match mode {
+            FitMode::Stretch => self.resize(width, height, filter),
+
+            FitMode::Crop => {
+                let (w, h) = scaled(scale_x.max(scale_y));
+
+                self.resize(w.max(width), h.max(height), filter)
+                    .center_crop(width, height)
+                    .unwrap()
+            }
+
+            FitMode::Pad => {
+                let (w, h) = scaled(scale_x.min(scale_y));
+
+                self.resize(w.min(width), h.min(height), filter)
+                    .pad(width, height)
+                    .unwrap()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resize_nearest() {
+        let image = ImageData::from_luma(2, 2, [0.0, 0.25, 0.5, 1.0]).unwrap();
+        let resized = image.resize(4, 2, ResizeFilter::Nearest);
+
+        assert_eq!((resized.width, resized.height, resized.area), (4, 2, 8));
+        assert_eq!(
+            resized.brightness,
+            [0.0, 0.0, 0.25, 0.25, 0.5, 0.5, 1.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn resize_bilinear() {
+        let image = ImageData::from_luma(2, 1, [0.0, 1.0]).unwrap();
+        let resized = image.resize(4, 1, ResizeFilter::Bilinear);
+
+        assert_eq!(resized.brightness, [0.0, 0.25, 0.75, 1.0]);
+    }
+
+    #[test]
+    fn resize_blends_hues_around() {
+        let mut image = ImageData::from_rgb(2, 1, [[1.0, 0.0, 0.0]; 2]).unwrap();
+        image
+            .colour
+            .as_mut()
+            .unwrap()
+            .0
+            .copy_from_slice(&[350.0, 10.0]);
+
+        let resized = image.resize(1, 1, ResizeFilter::Bilinear);
+        let hue = resized.colour.unwrap().0[0];
+
+        // halfway between 350 and 10 degrees, not between 10 and 350
+        assert!(!(1e-3..=360.0 - 1e-3).contains(&hue), "hue is {}", hue);
+    }
+
+    #[test]
+    fn crop() {
+        let image = ImageData::gradient(4, 3);
+        let cropped = image.crop(1, 1, 2, 2).unwrap();
+
+        assert_eq!(
+            cropped.brightness,
+            [5.0 / 12.0, 6.0 / 12.0, 9.0 / 12.0, 10.0 / 12.0]
+        );
+        assert_eq!(
+            image.center_crop(2, 1).unwrap().brightness,
+            [5.0 / 12.0, 6.0 / 12.0]
+        );
+
+        assert!(image.crop(3, 0, 2, 1).is_err());
+        assert!(image.center_crop(5, 1).is_err());
+    }
+
+    #[test]
+    fn pad() {
+        let mut image = ImageData::from_luma(1, 1, [1.0]).unwrap();
+        image.alpha = Some(vec![1.0]);
+
+        let padded = image.pad(3, 2).unwrap();
+
+        assert_eq!(padded.brightness, [0.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(padded.alpha.unwrap(), [0.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+        assert!(image.pad(0, 1).is_err());
+    }
+
+    #[test]
+    fn fit() {
+        let image = ImageData::from_luma(4, 2, [1.0; 8]).unwrap();
+
+        for mode in [FitMode::Stretch, FitMode::Crop, FitMode::Pad] {
+            let fitted = image.fit(2, 2, mode, ResizeFilter::Nearest);
+
+            assert_eq!((fitted.width, fitted.height), (2, 2));
+
+            let lit = fitted
+                .brightness
+                .iter()
+                .filter(|luma| **luma == 1.0)
+                .count();
+
+            // only padding leaves black bars above and below
+            assert_eq!(lit, if mode == FitMode::Pad { 2 } else { 4 });
+        }
+    }
+
+    #[test]
+    fn parse_names() {
+        assert_eq!("nearest".parse(), Ok(ResizeFilter::Nearest));
+        assert_eq!("bilinear".parse(), Ok(ResizeFilter::Bilinear));
+        assert!("bicubic".parse::<ResizeFilter>().is_err());
+
+        assert_eq!("stretch".parse(), Ok(FitMode::Stretch));
+        assert_eq!("crop".parse(), Ok(FitMode::Crop));
+        assert_eq!("pad".parse(), Ok(FitMode::Pad));
+        assert!("zoom".parse::<FitMode>().is_err());
+    }
+}
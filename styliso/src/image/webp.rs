@@ -0,0 +1,91 @@
+/*!
+ * WebP loading and saving for [super::data::ImageData].
+ */
+use super::data::ImageData;
+use image_webp::{ColorType, WebPDecoder, WebPEncoder};
+use std::io::Cursor;
+
+impl ImageData {
+    /// Loads ImageData from the contents of a WebP file, with an alpha plane
+    /// if it has alpha.
+    pub fn from_webp(bytes: &[u8]) -> Result<Self, String> {
+        let mut decoder = WebPDecoder::new(Cursor::new(bytes)).map_err(|err| err.to_string())?;
+
+        let (width, height) = decoder.dimensions();
+        let channels = if decoder.has_alpha() { 4 } else { 3 };
+
+        let (width, height) = match (u16::try_from(width), u16::try_from(height)) {
+            (Ok(width), Ok(height)) => (width, height),
+            _ => return Err(format!("WebP too large: {}x{}", width, height)),
+        };
+
+        let mut buffer = vec![0; width as usize * height as usize * channels];
+        decoder
+            .read_image(&mut buffer)
+            .map_err(|err| err.to_string())?;
+
+        let mut image = ImageData::from_rgb(
+            width,
+            height,
+            buffer.chunks_exact(channels).map(|pixel| {
+                [pixel[0], pixel[1], pixel[2]].map(|value| value as f64 / u8::MAX as f64)
+            }),
+        )?;
+
+        if channels == 4 {
+            image.alpha = Some(
+                buffer
+                    .chunks_exact(4)
+                    .map(|pixel| pixel[3] as f32 / u8::MAX as f32)
+                    .collect(),
+            );
+        }
+
+        Ok(image)
+    }
+
+    /// Saves ImageData as a lossless WebP file, with alpha if it has an alpha
+    /// plane.
+    pub fn to_webp(&self) -> Result<Vec<u8>, String> {
+        let to_byte = |value: f64| (value.clamp(0.0, 1.0) * u8::MAX as f64).round() as u8;
+
+        let pixels: Vec<Vec<u8>> = match &self.colour {
+            Some(_) => self
+                .to_rgb()
+                .into_iter()
+                .map(|rgb| rgb.map(to_byte).to_vec())
+                .collect(),
+
+            None => self
+                .brightness
+                .iter()
+                .map(|&luma| vec![to_byte(luma as f64)])
+                .collect(),
+        };
+
+        let mut data = Vec::with_capacity(self.area as usize * 4);
+
+        for (i, pixel) in pixels.into_iter().enumerate() {
+            data.extend(pixel);
+
+            if let Some(alpha) = &self.alpha {
+                data.push(to_byte(alpha[i] as f64));
+            }
+        }
+
+        let colour_type = match (self.colour.is_some(), self.alpha.is_some()) {
+            (true, false) => ColorType::Rgb8,
+            (true, true) => ColorType::Rgba8,
+            (false, false) => ColorType::L8,
+            (false, true) => ColorType::La8,
+        };
+
+        let mut out = vec![];
+
+        WebPEncoder::new(&mut out)
+            .encode(&data, self.width as u32, self.height as u32, colour_type)
+            .map_err(|err| err.to_string())?;
+
+        Ok(out)
+    }
+}
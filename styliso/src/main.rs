@@ -19,7 +19,19 @@
  * of them in the input.
  */
 pub mod autoenc;
+mod cli;
 pub mod image;
 pub mod prelude;
 
-pub fn main() {}
+use clap::Parser;
+use std::process::ExitCode;
+
+pub fn main() -> ExitCode {
+    match cli::run(cli::Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("ERROR: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
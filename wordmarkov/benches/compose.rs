@@ -0,0 +1,86 @@
+//! Measures how fast sentences are composed from a large chain.
+//!
+//! Run with `cargo bench -p wordmarkov --bench compose`.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::time::Instant;
+use wordmarkov::prelude::*;
+
+const VOCABULARY: usize = 2000;
+const SENTENCES: usize = 20000;
+const COMPOSED: usize = 5000;
+
+/// Makes up a corpus of sentences, with some words much more frequent than
+/// others, like in natural text.
+fn corpus(rng: &mut StdRng) -> Vec<String> {
+    let words: Vec<String> = (0..VOCABULARY).map(|i| format!("w{}", i)).collect();
+
+    (0..SENTENCES)
+        .map(|_| {
+            let len = rng.gen_range(4..20);
+
+            (0..len)
+                .map(|_| {
+                    let rank = (rng.gen::<f64>().powi(3) * VOCABULARY as f64) as usize;
+                    words[rank].as_str()
+                })
+                .collect::<Vec<&str>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+/// Composes sentences from a chain, and prints how fast it went.
+fn compose(chain: &MarkovChain, rng: &mut StdRng, label: &str) {
+    let mut selector = WeightedRandomSelector;
+    let mut words = 0;
+
+    let start = Instant::now();
+
+    for _ in 0..COMPOSED {
+        let sentence = chain
+            .compose_sentence_with_rng(MarkovSeed::Random, &mut selector, Some(400), rng)
+            .unwrap();
+
+        words += sentence.num_words();
+    }
+
+    let composing = start.elapsed();
+
+    println!(
+        "composed {} sentences ({} words) {} in {:?}: {:.0} sentences/s",
+        COMPOSED,
+        words,
+        label,
+        composing,
+        COMPOSED as f64 / composing.as_secs_f64()
+    );
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(6046);
+    let corpus = corpus(&mut rng);
+
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    let start = Instant::now();
+
+    for sentence in &corpus {
+        chain.parse_sentence(sentence);
+    }
+
+    let parsing = start.elapsed();
+
+    println!(
+        "parsed {} sentences ({} textlets) in {:?}",
+        SENTENCES,
+        chain.num_textlets(),
+        parsing
+    );
+
+    compose(&chain, &mut rng, "without a sampling cache");
+
+    chain.set_sampling_cache(true);
+
+    compose(&chain, &mut rng, "with a sampling cache");
+}
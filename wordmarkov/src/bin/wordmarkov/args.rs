@@ -0,0 +1,208 @@
+//! Command line arguments of the wordmarkov binary.
+
+use super::config::ProfileConfig;
+use clap::{Parser, ValueEnum};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use wordmarkov::prelude::*;
+
+/// Learns from each line typed, and replies with a sentence composed from it.
+#[derive(Parser)]
+#[command(version)]
+pub struct Args {
+    /// Files to parse into the chain, one sentence per line; `-` for standard
+    /// input. Files ending in .gz or .zst are decompressed.
+    pub corpora: Vec<String>,
+
+    /// The format of the corpora: plain text, or a chat log, whose
+    /// timestamps, nicknames and markup are left out.
+    #[arg(long, value_enum, default_value_t = Format::Plain)]
+    pub format: Format,
+
+    /// Start from a chain saved before.
+    #[arg(long, value_name = "PATH")]
+    pub load: Option<String>,
+
+    /// Use the chain of this profile, e.g. of a channel or persona: it is
+    /// loaded from the profile directory if it was saved before, and saved
+    /// there on exit. Profiles can be switched with :switch at the prompt.
+    #[arg(long, value_name = "NAME", conflicts_with = "load")]
+    pub profile: Option<String>,
+
+    /// The directory profiles are saved in.
+    #[arg(long, value_name = "PATH", default_value = "profiles")]
+    pub profile_dir: PathBuf,
+
+    /// The profiles defined in the config file.
+    #[arg(skip)]
+    pub profiles: HashMap<String, ProfileConfig>,
+
+    /// Read defaults for these options from a TOML file; see the README for
+    /// which. Options given in the command line override them.
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<String>,
+
+    /// Save the chain when the input ends.
+    #[arg(long, value_name = "PATH")]
+    pub save_on_exit: Option<String>,
+
+    /// The most tokens (words and punctuation) a reply can have.
+    #[arg(long, default_value_t = 450)]
+    pub max_len: usize,
+
+    /// How to pick each next word: weighted, best, naive or temperature=X.
+    #[arg(long, default_value = "weighted")]
+    pub selector: SelectorKind,
+
+    /// Compose every reply from this word, rather than from a word of the
+    /// line typed.
+    #[arg(long, visible_alias = "seed", value_name = "WORD")]
+    pub seed_word: Option<String>,
+
+    /// Do not learn from the lines typed.
+    #[arg(long)]
+    pub no_learn: bool,
+
+    /// Rather than replying to each line typed, list the K words most likely
+    /// to come next after it, like autocompletion, without learning from it.
+    #[arg(
+        long,
+        value_name = "K",
+        num_args = 0..=1,
+        default_missing_value = "5",
+        conflicts_with_all = ["generate", "serve", "watch"]
+    )]
+    pub suggest: Option<usize>,
+
+    /// Keep the history of the lines typed in this file, across runs.
+    #[arg(long, value_name = "PATH")]
+    pub history: Option<String>,
+
+    /// Seed the random number generator, so that the same chain composes
+    /// the same sentences every time, e.g. to reproduce odd ones.
+    #[arg(long, value_name = "SEED")]
+    pub rng_seed: Option<u64>,
+
+    /// Print each sentence composed as a JSON object, with the seed word it
+    /// was composed from, its length and how long it took.
+    #[arg(long)]
+    pub json: bool,
+
+    /// Print this many sentences, one per line, and exit, rather than
+    /// prompting.
+    #[arg(long, value_name = "N")]
+    pub generate: Option<usize>,
+
+    /// Parse the corpora, report how many lines and tokens were parsed per
+    /// second and how much memory the chain takes, and exit.
+    #[arg(long, conflicts_with_all = ["generate", "serve", "watch", "suggest"])]
+    pub bench_parse: bool,
+
+    /// Serve the chain over HTTP at this address, e.g. 127.0.0.1:8080,
+    /// rather than prompting; see the README for the endpoints. Can be
+    /// combined with --watch.
+    #[arg(long, value_name = "ADDR", conflicts_with = "generate")]
+    pub serve: Option<String>,
+
+    /// Learn each line appended to this file as soon as it is written, e.g. a
+    /// live chat log, rather than prompting (unless serving).
+    #[arg(long, value_name = "PATH", conflicts_with = "generate")]
+    pub watch: Option<String>,
+
+    /// Save the chain to this file every so often while watching, if it
+    /// learned anything.
+    #[arg(long, value_name = "PATH")]
+    pub autosave: Option<String>,
+
+    /// How many seconds to wait between autosaves.
+    #[arg(long, value_name = "SECS", default_value_t = 300)]
+    pub autosave_interval: u64,
+}
+
+/// A format of corpus files. See [Args::format].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// One sentence per line.
+    Plain,
+
+    /// An IRC log, one message or event per line.
+    Irc,
+
+    /// A JSON export of a Discord channel, e.g. by DiscordChatExporter.
+    DiscordJson,
+
+    /// A JSON export of Telegram Desktop.
+    TelegramJson,
+}
+
+/// A selector to compose replies with. See [Args::selector].
+#[derive(Clone, Copy, Debug)]
+pub enum SelectorKind {
+    Weighted,
+    Best,
+    Naive,
+    Temperature(f32),
+}
+
+impl SelectorKind {
+    pub fn build(self) -> Box<dyn MarkovSelector> {
+        match self {
+            SelectorKind::Weighted => Box::new(WeightedRandomSelector),
+            SelectorKind::Best => Box::new(StaticBestSelector),
+            SelectorKind::Naive => Box::new(NaiveRandomSelector),
+            SelectorKind::Temperature(temperature) => Box::new(TemperatureSelector { temperature }),
+        }
+    }
+}
+
+impl FromStr for SelectorKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name {
+            "weighted" => Ok(SelectorKind::Weighted),
+            "best" => Ok(SelectorKind::Best),
+            "naive" => Ok(SelectorKind::Naive),
+
+            _ => match name.strip_prefix("temperature=") {
+                Some(temperature) => temperature
+                    .parse()
+                    .map(SelectorKind::Temperature)
+                    .map_err(|_| format!("Invalid temperature {:?}", temperature)),
+
+                None => Err(format!(
+                    "Unknown selector {:?}; expected weighted, best, naive or temperature=X",
+                    name
+                )),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_selectors() {
+        assert!(matches!("weighted".parse(), Ok(SelectorKind::Weighted)));
+        assert!(matches!("best".parse(), Ok(SelectorKind::Best)));
+        assert!(matches!("naive".parse(), Ok(SelectorKind::Naive)));
+        assert!(matches!(
+            "temperature=0.75".parse(),
+            Ok(SelectorKind::Temperature(t)) if t == 0.75
+        ));
+
+        for name in [
+            "",
+            "Best",
+            "worst",
+            "temperature",
+            "temperature=",
+            "temperature=hot",
+        ] {
+            assert!(name.parse::<SelectorKind>().is_err(), "{:?} parsed", name);
+        }
+    }
+}
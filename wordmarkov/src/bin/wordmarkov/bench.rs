@@ -0,0 +1,136 @@
+//! The ingestion benchmark of the wordmarkov binary, to measure how fast the
+//! lexer and the chain parse a corpus.
+
+use super::args::Args;
+use super::import::import;
+use super::open_corpus;
+use serde_json::json;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use wordmarkov::prelude::*;
+
+/// How much of a corpus was parsed, and how long it took.
+#[derive(Default)]
+struct Measure {
+    lines: usize,
+    tokens: usize,
+    time: Duration,
+}
+
+impl Measure {
+    fn per_sec(&self, count: usize) -> f64 {
+        count as f64 / self.time.as_secs_f64().max(f64::EPSILON)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} lines, {} tokens in {:.3}s ({:.0} lines/s, {:.0} tokens/s)",
+            self.lines,
+            self.tokens,
+            self.time.as_secs_f64(),
+            self.per_sec(self.lines),
+            self.per_sec(self.tokens)
+        )
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "lines": self.lines,
+            "tokens": self.tokens,
+            "seconds": self.time.as_secs_f64(),
+            "lines_per_sec": self.per_sec(self.lines),
+            "tokens_per_sec": self.per_sec(self.tokens),
+        })
+    }
+}
+
+/**
+ * Parses a corpus into a chain, timing only the parsing: the corpus is read
+ * (and decompressed) into memory, and its tokens counted, beforehand.
+ */
+fn measure(chain: &mut MarkovChain, args: &Args, path: &str) -> Result<Measure, String> {
+    let mut lines = vec![];
+
+    import(args.format, open_corpus(path)?, |sentence| {
+        lines.push(sentence.to_owned())
+    })?;
+
+    let tokens = lines
+        .iter()
+        .flat_map(|line| Lexer::new(line))
+        .filter(|token| match token {
+            Token::Word(_) => true,
+            Token::Punct(punct) => !punct.is_empty(),
+            _ => false,
+        })
+        .count();
+
+    let start = Instant::now();
+
+    for line in &lines {
+        chain.parse_sentence(line);
+    }
+
+    Ok(Measure {
+        lines: lines.len(),
+        tokens,
+        time: start.elapsed(),
+    })
+}
+
+/**
+ * Parses each corpus into a chain, and reports how many lines and tokens were
+ * parsed per second, and how much memory the chain takes in the end.
+ */
+pub fn bench_parse(chain: &mut MarkovChain, args: &Args) -> ExitCode {
+    if args.corpora.is_empty() {
+        eprintln!("ERROR: No corpus to benchmark parsing with");
+        return ExitCode::FAILURE;
+    }
+
+    let mut total = Measure::default();
+    let mut corpora = vec![];
+
+    for path in &args.corpora {
+        let measure = match measure(chain, args, path) {
+            Ok(measure) => measure,
+            Err(err) => {
+                eprintln!("ERROR: Error reading file {}: {}", path, err);
+                return ExitCode::FAILURE;
+            }
+        };
+
+        total.lines += measure.lines;
+        total.tokens += measure.tokens;
+        total.time += measure.time;
+
+        if args.json {
+            let mut value = measure.to_json();
+            value["path"] = json!(path);
+            corpora.push(value);
+        } else {
+            println!("{}: {}", path, measure.describe());
+        }
+    }
+
+    if args.json {
+        let mut value = total.to_json();
+        value["corpora"] = json!(corpora);
+        value["words"] = json!(chain.num_words());
+        value["edges"] = json!(chain.num_edges());
+        value["memory_bytes"] = json!(chain.memory_usage());
+
+        println!("{}", value);
+    } else {
+        println!("Total: {}", total.describe());
+        println!(
+            "Chain: {} words, {} textlets, {} edges, about {} KiB",
+            chain.num_words(),
+            chain.num_textlets(),
+            chain.num_edges(),
+            chain.memory_usage() / 1024
+        );
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,215 @@
+//! The config file of the wordmarkov binary, in TOML, which gives defaults for
+//! the command line options, so that daemons need not be run with giant
+//! command lines.
+
+use super::args::{Args, Format};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, ValueEnum};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// A profile defined in the config file. See [Args::profile].
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileConfig {
+    /// The file the profile is saved in, rather than one named after it in
+    /// the profile directory.
+    pub path: Option<PathBuf>,
+
+    /// Corpora to parse into the profile the first time it is used, i.e.
+    /// before it was ever saved.
+    pub corpora: Vec<String>,
+}
+
+/// The options a config file can give. See [Args] for what each does.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    corpora: Vec<String>,
+    format: Option<String>,
+    selector: Option<String>,
+    max_len: Option<usize>,
+    serve: Option<String>,
+    watch: Option<String>,
+    autosave: Option<String>,
+    autosave_interval: Option<u64>,
+    profile: Option<String>,
+    profile_dir: Option<PathBuf>,
+    profiles: HashMap<String, ProfileConfig>,
+}
+
+impl Config {
+    /// Reads a config file.
+    pub fn load(path: &str) -> Result<Config, String> {
+        let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+
+        toml::from_str(&text).map_err(|err| err.to_string())
+    }
+
+    /**
+     * Fills in the options which were not given in the command line, as
+     * matched in `matches`, with those of this config.
+     */
+    pub fn apply(self, args: &mut Args, matches: &ArgMatches) -> Result<(), String> {
+        let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        if !given("corpora") && !self.corpora.is_empty() {
+            args.corpora = self.corpora;
+        }
+
+        // options with a default value
+        if let Some(format) = self.format.filter(|_| !given("format")) {
+            args.format = Format::from_str(&format, false)
+                .map_err(|_| format!("Unknown format {:?}", format))?;
+        }
+
+        if let Some(selector) = self.selector.filter(|_| !given("selector")) {
+            args.selector = selector.parse()?;
+        }
+
+        if let Some(max_len) = self.max_len.filter(|_| !given("max_len")) {
+            args.max_len = max_len;
+        }
+
+        if let Some(interval) = self
+            .autosave_interval
+            .filter(|_| !given("autosave_interval"))
+        {
+            args.autosave_interval = interval;
+        }
+
+        if let Some(dir) = self.profile_dir.filter(|_| !given("profile_dir")) {
+            args.profile_dir = dir;
+        }
+
+        // options without one, which are only missing if not given; clap does
+        // not check those of the config for conflicts, so they are skipped if
+        // the command line chose a mode they conflict with
+        if !["generate", "suggest", "bench_parse"]
+            .into_iter()
+            .any(given)
+        {
+            args.serve = args.serve.take().or(self.serve);
+            args.watch = args.watch.take().or(self.watch);
+        }
+
+        args.autosave = args.autosave.take().or(self.autosave);
+
+        // a chain loaded in the command line is used rather than a profile
+        if args.load.is_none() {
+            args.profile = args.profile.take().or(self.profile);
+        }
+
+        args.profiles = self.profiles;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::SelectorKind;
+    use clap::{CommandFactory, FromArgMatches};
+
+    /// Parses a command line, and fills it in with a config in TOML.
+    fn apply(command_line: &[&str], config: &str) -> Result<Args, String> {
+        let matches = Args::command()
+            .try_get_matches_from(["wordmarkov"].iter().chain(command_line))
+            .unwrap();
+        let mut args = Args::from_arg_matches(&matches).unwrap();
+
+        let config: Config = toml::from_str(config).map_err(|err| err.to_string())?;
+        config.apply(&mut args, &matches)?;
+
+        Ok(args)
+    }
+
+    const CONFIG: &str = r#"
+        corpora = ["seed.txt"]
+        format = "irc"
+        selector = "temperature=0.5"
+        max_len = 20
+        serve = "127.0.0.1:8080"
+        watch = "live.log"
+        autosave = "chain.bin"
+        autosave_interval = 60
+        profile = "general"
+        profile_dir = "chains"
+
+        [profiles.general]
+        corpora = ["general.txt"]
+    "#;
+
+    #[test]
+    fn config_fills_in_defaults() {
+        let args = apply(&[], CONFIG).unwrap();
+
+        assert_eq!(args.corpora, ["seed.txt"]);
+        assert_eq!(args.format, Format::Irc);
+        assert!(matches!(args.selector, SelectorKind::Temperature(t) if t == 0.5));
+        assert_eq!(args.max_len, 20);
+        assert_eq!(args.serve.as_deref(), Some("127.0.0.1:8080"));
+        assert_eq!(args.watch.as_deref(), Some("live.log"));
+        assert_eq!(args.autosave.as_deref(), Some("chain.bin"));
+        assert_eq!(args.autosave_interval, 60);
+        assert_eq!(args.profile.as_deref(), Some("general"));
+        assert_eq!(args.profile_dir, PathBuf::from("chains"));
+        assert_eq!(args.profiles["general"].corpora, ["general.txt"]);
+    }
+
+    #[test]
+    fn command_line_overrides_config() {
+        let args = apply(
+            &[
+                "other.txt",
+                "--format",
+                "plain",
+                "--selector",
+                "best",
+                "--max-len",
+                "50",
+                "--serve",
+                "0.0.0.0:80",
+                "--autosave-interval",
+                "300",
+                "--load",
+                "saved.bin",
+            ],
+            CONFIG,
+        )
+        .unwrap();
+
+        assert_eq!(args.corpora, ["other.txt"]);
+        assert_eq!(args.format, Format::Plain);
+        assert!(matches!(args.selector, SelectorKind::Best));
+        assert_eq!(args.max_len, 50);
+        assert_eq!(args.serve.as_deref(), Some("0.0.0.0:80"));
+        assert_eq!(args.watch.as_deref(), Some("live.log"));
+
+        // even if given the default value
+        assert_eq!(args.autosave_interval, 300);
+
+        // the loaded chain is used rather than the profile
+        assert_eq!(args.profile, None);
+    }
+
+    #[test]
+    fn config_modes_skipped_if_conflicting() {
+        for command_line in [&["--generate", "3"][..], &["--suggest"], &["--bench-parse"]] {
+            let args = apply(command_line, CONFIG).unwrap();
+
+            assert_eq!(args.serve, None, "{:?}", command_line);
+            assert_eq!(args.watch, None, "{:?}", command_line);
+        }
+    }
+
+    #[test]
+    fn config_errors() {
+        assert!(apply(&[], "format = \"xml\"").is_err());
+        assert!(apply(&[], "selector = \"worst\"").is_err());
+        assert!(apply(&[], "colour = true").is_err());
+    }
+}
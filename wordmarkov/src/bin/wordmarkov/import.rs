@@ -0,0 +1,434 @@
+//! Importers of the wordmarkov binary, which pick the text out of chat logs
+//! exported in common formats, leaving out timestamps, nicknames and markup
+//! which would otherwise be learned as words.
+
+use super::args::Format;
+use serde_json::Value;
+use std::io::BufRead;
+
+/// Removes mIRC formatting codes, for bold, colours, italics and the like.
+fn strip_irc_formatting(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            // a colour code, with up to two digits of foreground, then
+            // optionally of background
+            '\x03' => {
+                for _ in 0..2 {
+                    chars.next_if(char::is_ascii_digit);
+                }
+
+                let mut ahead = chars.clone();
+
+                if ahead.next() == Some(',') && ahead.peek().is_some_and(char::is_ascii_digit) {
+                    chars.next();
+
+                    for _ in 0..2 {
+                        chars.next_if(char::is_ascii_digit);
+                    }
+                }
+            }
+
+            '\x02' | '\x0f' | '\x11' | '\x16' | '\x1d' | '\x1e' | '\x1f' => {}
+            c => text.push(c),
+        }
+    }
+
+    text
+}
+
+/// Whether a word of a log line is a timestamp, or part of one, e.g.
+/// `12:34:56` or `2024-01-31`.
+fn is_timestamp(word: &str) -> bool {
+    word.contains(|c: char| c.is_ascii_digit())
+        && word
+            .chars()
+            .all(|c| c.is_ascii_digit() || ":-./T+Z".contains(c))
+}
+
+/**
+ * Picks the message out of a line of an IRC log, as written by most clients
+ * and bouncers: `[12:34] <nick> message`, `12:34 * nick action`, or WeeChat's
+ * `2024-01-31 12:34:56\tnick\tmessage`. Joins, parts, topic changes and other
+ * events, which have no nick, are skipped.
+ */
+fn irc_message(line: &str) -> Option<String> {
+    let line = strip_irc_formatting(line);
+    let mut rest = line.trim();
+
+    loop {
+        if let Some((stamp, after)) = rest.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+            if stamp.contains(|c: char| c.is_ascii_digit()) {
+                rest = after.trim_start();
+                continue;
+            }
+        }
+
+        match rest.split_once(char::is_whitespace) {
+            Some((word, after)) if is_timestamp(word) => rest = after.trim_start(),
+            _ => break,
+        }
+    }
+
+    let message = if let Some(rest) = rest.strip_prefix('<') {
+        rest.split_once('>')?.1
+    } else if let Some(action) = rest.strip_prefix("* ") {
+        action.split_once(' ')?.1
+    } else {
+        let (nick, message) = rest.split_once('\t')?;
+
+        match nick.trim() {
+            "*" => message.split_once(' ')?.1,
+
+            // WeeChat marks events with arrows and dashes in place of a nick
+            nick if !nick.contains(char::is_alphanumeric) => return None,
+            _ => message,
+        }
+    };
+
+    let message = message.trim();
+    (!message.is_empty()).then(|| message.to_owned())
+}
+
+/**
+ * Removes Discord markup from a message: mentions of users, roles and
+ * channels, custom emoji, timestamps, quotes and Markdown emphasis.
+ */
+fn strip_discord_markup(content: &str) -> String {
+    let content = content
+        .replace("||", "")
+        .replace("~~", "")
+        .replace("__", "");
+    let content = content.replace(['*', '`'], "");
+
+    let lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            let line = line.trim_start();
+            let line = line
+                .strip_prefix(">>> ")
+                .or_else(|| line.strip_prefix("> "))
+                .unwrap_or(line);
+
+            let mut text = String::with_capacity(line.len());
+
+            for word in line.split_whitespace() {
+                let word = if ["<@", "<#", "<:", "<a:", "<t:"]
+                    .iter()
+                    .any(|prefix| word.starts_with(prefix))
+                {
+                    // keep any punctuation right after the markup, as if it
+                    // followed the word before
+                    if let Some((_, after)) = word.split_once('>') {
+                        text.push_str(after);
+                    }
+
+                    continue;
+                } else if word.starts_with('@') {
+                    continue;
+                } else if let Some(link) = word.strip_prefix('<').and_then(|w| w.strip_suffix('>'))
+                {
+                    link
+                } else {
+                    word
+                };
+
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+
+                text.push_str(word);
+            }
+
+            text
+        })
+        .collect();
+
+    lines.join("\n")
+}
+
+/**
+ * Lists the text of each message of a Discord export, as made by
+ * DiscordChatExporter (an object with a `messages` list) or by the API (a
+ * list of messages). Pins, joins and other system messages are skipped.
+ */
+fn discord_messages(root: &Value) -> Result<Vec<String>, String> {
+    let messages = root
+        .get("messages")
+        .unwrap_or(root)
+        .as_array()
+        .ok_or("Expected a list of messages")?;
+
+    Ok(messages
+        .iter()
+        .filter(|message| match &message["type"] {
+            Value::Null => true,
+            Value::String(kind) => kind == "Default" || kind == "Reply",
+            Value::Number(kind) => kind.as_u64() == Some(0) || kind.as_u64() == Some(19),
+            _ => false,
+        })
+        .filter_map(|message| message["content"].as_str())
+        .map(strip_discord_markup)
+        .collect())
+}
+
+/**
+ * Lists the text of each message of a Telegram Desktop export, of a single
+ * chat or of all of them. Service messages, such as joins, and mentions are
+ * skipped.
+ */
+fn telegram_messages(root: &Value) -> Result<Vec<String>, String> {
+    let chats = match root["chats"]["list"].as_array() {
+        Some(chats) => chats.iter().collect(),
+        None => vec![root],
+    };
+
+    let mut texts = vec![];
+
+    for chat in chats {
+        let messages = chat["messages"]
+            .as_array()
+            .ok_or("Expected a list of messages")?;
+
+        for message in messages {
+            if message["type"] != "message" {
+                continue;
+            }
+
+            // formatted text is a list of plain strings and of entities
+            let text = match &message["text"] {
+                Value::String(text) => text.clone(),
+                Value::Array(parts) => parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        Value::String(text) => Some(text.as_str()),
+                        Value::Object(entity) => match entity.get("type")?.as_str()? {
+                            "mention" | "mention_name" | "bot_command" => None,
+                            _ => entity.get("text")?.as_str(),
+                        },
+                        _ => None,
+                    })
+                    .collect(),
+                _ => continue,
+            };
+
+            texts.push(text);
+        }
+    }
+
+    Ok(texts)
+}
+
+/**
+ * Cleans up a line of a corpus in a line-based format, or returns `None` if
+ * it has nothing to learn from.
+ */
+pub fn clean_line(format: Format, line: &str) -> Option<String> {
+    match format {
+        Format::Irc => irc_message(line),
+
+        _ => {
+            let sentence = line.trim();
+            (!sentence.is_empty()).then(|| sentence.to_owned())
+        }
+    }
+}
+
+/**
+ * Reads each sentence out of a corpus in a format, passing it to `learn`:
+ * each line, or, in the JSON formats, each line of each message, cleaned up.
+ * Returns how many sentences there were.
+ */
+pub fn import(
+    format: Format,
+    reader: impl BufRead,
+    mut learn: impl FnMut(&str),
+) -> Result<usize, String> {
+    let mut count = 0;
+
+    let messages = match format {
+        Format::Plain | Format::Irc => {
+            for line in reader.lines() {
+                let line = line.map_err(|err| err.to_string())?;
+
+                if let Some(sentence) = clean_line(format, &line) {
+                    learn(&sentence);
+                    count += 1;
+                }
+            }
+
+            return Ok(count);
+        }
+
+        Format::DiscordJson | Format::TelegramJson => {
+            let root: Value = serde_json::from_reader(reader).map_err(|err| err.to_string())?;
+
+            if format == Format::DiscordJson {
+                discord_messages(&root)?
+            } else {
+                telegram_messages(&root)?
+            }
+        }
+    };
+
+    for message in &messages {
+        for line in message.lines() {
+            let sentence = line.trim();
+
+            if !sentence.is_empty() {
+                learn(sentence);
+                count += 1;
+            }
+        }
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn irc_formatting() {
+        assert_eq!(
+            strip_irc_formatting("\x02bold\x02 \x0304red\x03 \x0312,01blue\x0f \x1ditalic\x1d"),
+            "bold red blue italic"
+        );
+
+        // a comma after a colour, but not a background
+        assert_eq!(strip_irc_formatting("\x034, then"), ", then");
+        assert_eq!(strip_irc_formatting("\x03123 apples"), "3 apples");
+    }
+
+    #[test]
+    fn irc_messages() {
+        for (line, message) in [
+            ("[12:34] <alice> hello there", Some("hello there")),
+            ("[2024-01-31] [12:34:56] <@bob> hi", Some("hi")),
+            ("12:34 <carol> what's up?", Some("what's up?")),
+            ("12:34 * dave waves", Some("waves")),
+            (
+                "2024-01-31 12:34:56\teve\tgood morning",
+                Some("good morning"),
+            ),
+            ("2024-01-31 12:34:56\t *\tfrank shrugs", Some("shrugs")),
+            ("<alice> \x02loud\x02 noises", Some("loud noises")),
+            ("[12:34] [nick] <alice> odd prefix", None),
+            ("[12:34] -!- bob has joined #channel", None),
+            ("2024-01-31 12:34:56\t-->\tbob has joined", None),
+            ("[12:34] <alice>   ", None),
+            ("", None),
+        ] {
+            assert_eq!(irc_message(line).as_deref(), message, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn discord_markup() {
+        assert_eq!(
+            strip_discord_markup("hey <@123456>, see <#789> **now** <:wave:42>!"),
+            "hey, see now!"
+        );
+        assert_eq!(
+            strip_discord_markup("> quoted ||spoiler|| ~~struck~~ __under__ `code`"),
+            "quoted spoiler struck under code"
+        );
+        assert_eq!(
+            strip_discord_markup(">>> link <https://example.com>\n@everyone second line"),
+            "link https://example.com\nsecond line"
+        );
+        assert_eq!(strip_discord_markup("at <t:1700000000:R>."), "at.");
+    }
+
+    #[test]
+    fn discord_exports() {
+        let exporter = json!({
+            "messages": [
+                {"type": "Default", "content": "hello <@1>"},
+                {"type": "Reply", "content": "hi"},
+                {"type": "ChannelPinnedMessage", "content": "pinned"},
+                {"type": "Default"},
+            ]
+        });
+
+        assert_eq!(discord_messages(&exporter).unwrap(), ["hello", "hi"]);
+
+        let api = json!([
+            {"type": 0, "content": "first"},
+            {"type": 7, "content": "joined"},
+            {"type": 19, "content": "reply"},
+            {"content": "untyped"},
+        ]);
+
+        assert_eq!(
+            discord_messages(&api).unwrap(),
+            ["first", "reply", "untyped"]
+        );
+
+        assert!(discord_messages(&json!({"messages": 3})).is_err());
+    }
+
+    #[test]
+    fn telegram_exports() {
+        let chat = json!({
+            "messages": [
+                {"type": "message", "text": "plain text"},
+                {"type": "service", "action": "join_group_by_link"},
+                {"type": "message", "text": [
+                    "hey ",
+                    {"type": "mention", "text": "@bob"},
+                    ", look at ",
+                    {"type": "link", "text": "example.com"},
+                    {"type": "bold"},
+                ]},
+                {"type": "message", "photo": "photo.jpg"},
+            ]
+        });
+
+        assert_eq!(
+            telegram_messages(&chat).unwrap(),
+            ["plain text", "hey , look at example.com"]
+        );
+
+        let all = json!({
+            "chats": {"list": [
+                {"messages": [{"type": "message", "text": "one"}]},
+                {"messages": [{"type": "message", "text": "two"}]},
+            ]}
+        });
+
+        assert_eq!(telegram_messages(&all).unwrap(), ["one", "two"]);
+        assert!(telegram_messages(&json!({"name": "chat"})).is_err());
+    }
+
+    #[test]
+    fn import_lines() {
+        let mut sentences = vec![];
+
+        let count = import(
+            Format::Irc,
+            "[12:34] <alice> hello\n[12:35] -!- bob has quit\n\n[12:36] <bob> bye\n".as_bytes(),
+            |sentence| sentences.push(sentence.to_owned()),
+        )
+        .unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(sentences, ["hello", "bye"]);
+
+        let mut sentences = vec![];
+
+        let count = import(
+            Format::DiscordJson,
+            r#"[{"content": "two\n\nlines"}, {"content": "  "}]"#.as_bytes(),
+            |sentence| sentences.push(sentence.to_owned()),
+        )
+        .unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(sentences, ["two", "lines"]);
+    }
+}
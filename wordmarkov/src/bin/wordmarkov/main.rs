@@ -0,0 +1,567 @@
+mod args;
+mod bench;
+mod config;
+mod import;
+mod profile;
+mod serve;
+mod watch;
+
+use args::Args;
+use clap::{CommandFactory, FromArgMatches};
+use config::Config;
+use flate2::read::MultiGzDecoder;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use serde_json::json;
+use std::fs;
+use std::io::{self, BufRead, Read};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use wordmarkov::prelude::*;
+
+fn parse(chain: &mut MarkovChain, prompt: &str) {
+    if !prompt.is_empty() {
+        chain.parse_sentence(prompt);
+    }
+}
+
+/// The seed word to reply to a prompt with: a word of the prompt, if any.
+fn seed<'a>(args: &'a Args, prompt: &'a str, rng: &mut StdRng) -> MarkovSeed<'a> {
+    if let Some(word) = &args.seed_word {
+        MarkovSeed::Word(word)
+    } else if !prompt.is_empty() {
+        let lexed = Lexer::new(prompt);
+        let words: Vec<&str> = lexed
+            .filter_map(|lex| {
+                if let Token::Word(w) = lex {
+                    Some(w)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        if words.is_empty() {
+            MarkovSeed::Random
+        } else {
+            MarkovSeed::Word(words[rng.gen_range(0..words.len())])
+        }
+    } else {
+        MarkovSeed::Random
+    }
+}
+
+/// A composed sentence, and how it was composed.
+struct Composed {
+    text: String,
+
+    /// The word it was composed from, if not the begin or end token.
+    seed: Option<String>,
+
+    /// How many words and punctuation textlets it has.
+    tokens: usize,
+    words: usize,
+    time: Duration,
+}
+
+impl Composed {
+    /// The sentence, or, with --json, a JSON object describing it.
+    fn format(&self, args: &Args) -> String {
+        if !args.json {
+            return self.text.clone();
+        }
+
+        json!({
+            "text": self.text,
+            "seed": self.seed,
+            "tokens": self.tokens,
+            "words": self.words,
+            "compose_time_ms": self.time.as_secs_f64() * 1000.0,
+        })
+        .to_string()
+    }
+}
+
+/// An error, or, with --json, a JSON object describing it.
+fn format_error(args: &Args, err: &MarkovError) -> String {
+    if args.json {
+        json!({ "error": err.to_string() }).to_string()
+    } else {
+        format!("{{ ERROR: {} }}", err)
+    }
+}
+
+/// Composes a sentence with the options given in the command line.
+fn compose(
+    chain: &MarkovChain,
+    args: &Args,
+    seed: MarkovSeed,
+    rng: &mut StdRng,
+) -> Result<Composed, MarkovError> {
+    let start = Instant::now();
+
+    // resolved first, to tell which word a random seed picked
+    let from = chain.resolve_seed(seed, rng)?;
+
+    let mut selector = args.selector.build();
+    let sentence = chain.compose_sentence_with_rng(
+        MarkovSeed::Id(from),
+        selector.as_mut(),
+        Some(args.max_len),
+        rng,
+    )?;
+
+    let seed = match chain.get_textlet(from) {
+        Some(MarkovToken::Textlet(word)) => Some(word.to_owned()),
+        _ => None,
+    };
+
+    Ok(Composed {
+        text: sentence.to_string(),
+        seed,
+        tokens: sentence
+            .iter()
+            .filter(|token| matches!(token, MarkovToken::Textlet(text) if !text.is_empty()))
+            .count(),
+        words: sentence.num_words(),
+        time: start.elapsed(),
+    })
+}
+
+fn produce(chain: &MarkovChain, args: &Args, prompt: &str, rng: &mut StdRng) -> String {
+    match compose(chain, args, seed(args, prompt, rng), rng) {
+        Ok(composed) => composed.format(args),
+        Err(err) => format_error(args, &err),
+    }
+}
+
+/**
+ * Opens a corpus file to be read line by line; or standard input, if the path
+ * is `-`. Files ending in `.gz` or `.zst` are decompressed as they are read.
+ */
+fn open_corpus(path: &str) -> Result<Box<dyn BufRead>, String> {
+    if path == "-" {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+
+    let file = fs::File::open(path).map_err(|err| err.to_string())?;
+
+    let reader: Box<dyn Read> = if path.ends_with(".gz") {
+        Box::new(MultiGzDecoder::new(file))
+    } else if path.ends_with(".zst") {
+        Box::new(zstd::Decoder::new(file).map_err(|err| err.to_string())?)
+    } else {
+        Box::new(file)
+    };
+
+    Ok(Box::new(io::BufReader::new(reader)))
+}
+
+/**
+ * Parses a corpus file in the format given in the command line, e.g. one
+ * sentence per line; see [open_corpus].
+ */
+fn parse_file(chain: &mut MarkovChain, args: &Args, path: &str) -> Result<(), String> {
+    import::import(args.format, open_corpus(path)?, |sentence| {
+        chain.parse_sentence(sentence)
+    })?;
+
+    Ok(())
+}
+
+/// The words found the most times, and how many times each was.
+fn top_words(chain: &MarkovChain, count: usize) -> Vec<(&str, usize)> {
+    let mut words: Vec<(&str, usize)> = (0..chain.num_textlets())
+        .filter_map(|idx| match chain.get_textlet(idx)? {
+            MarkovToken::Textlet(word) if chain.frequency(idx) > 0 => {
+                Some((word, chain.frequency(idx)))
+            }
+
+            _ => None,
+        })
+        .collect();
+
+    words.sort_by_key(|(_, hits)| std::cmp::Reverse(*hits));
+    words.truncate(count);
+
+    words
+}
+
+/// Describes a chain: its size and most common words.
+fn stats(chain: &MarkovChain) -> String {
+    let top: Vec<String> = top_words(chain, 10)
+        .into_iter()
+        .map(|(word, hits)| format!("{} ({})", word, hits))
+        .collect();
+
+    format!(
+        "{} words, {} textlets, {} edges, about {} KiB\nTop words: {}",
+        chain.num_words(),
+        chain.num_textlets(),
+        chain.num_edges(),
+        chain.memory_usage() / 1024,
+        top.join(", ")
+    )
+}
+
+/// Lists the `k` words most likely to follow a seed, one per line.
+fn top(chain: &MarkovChain, seed: MarkovSeed, k: usize) -> Result<String, String> {
+    let suggestions = chain.suggest_next(seed, k, MarkovTraverseDir::Forward)?;
+
+    let lines: Vec<String> = suggestions
+        .iter()
+        .map(|(next, _, probability)| {
+            let next = match next {
+                MarkovToken::End => "<end>",
+                next => next.into(),
+            };
+
+            format!("{:5.1}% {}", probability * 100.0, next)
+        })
+        .collect();
+
+    Ok(lines.join("\n"))
+}
+
+/**
+ * Suggests the words most likely to come next in a partial sentence: after
+ * its last word, or at the beginning of a sentence if it has none.
+ */
+fn suggest(chain: &MarkovChain, args: &Args, prompt: &str, k: usize) -> String {
+    let seed = match Lexer::new(prompt)
+        .filter(|lex| matches!(lex, Token::Word(_)))
+        .last()
+    {
+        Some(Token::Word(word)) => MarkovSeed::Word(word),
+        _ => MarkovSeed::Begin,
+    };
+
+    if !args.json {
+        return top(chain, seed, k).unwrap_or_else(|err| format!("{{ ERROR: {} }}", err));
+    }
+
+    match chain.suggest_next(seed, k, MarkovTraverseDir::Forward) {
+        Ok(suggestions) => {
+            let suggestions: Vec<_> = suggestions
+                .iter()
+                .map(|(next, _, probability)| {
+                    let word = match next {
+                        MarkovToken::Textlet(word) => Some(*word),
+                        _ => None,
+                    };
+
+                    json!({ "word": word, "probability": probability })
+                })
+                .collect();
+
+            json!({ "suggestions": suggestions }).to_string()
+        }
+
+        Err(err) => format_error(args, &err),
+    }
+}
+
+/**
+ * Runs a prompt command, i.e. a line starting with `:`. `profile` is the name
+ * of the profile being used, if any.
+ */
+fn command(
+    chain: &mut MarkovChain,
+    args: &Args,
+    profile: &mut Option<String>,
+    line: &str,
+) -> Result<String, String> {
+    let (name, arg) = line.split_once(' ').unwrap_or((line, ""));
+    let arg = arg.trim();
+
+    match name {
+        ":save" | ":load" if arg.is_empty() => Err(format!("Usage: {} <path>", name)),
+        ":top" | ":forget" if arg.is_empty() => Err(format!("Usage: {} <word>", name)),
+        ":switch" if arg.is_empty() => Err(format!("Usage: {} <profile>", name)),
+        ":switch" if profile.as_deref() == Some(arg) => {
+            Ok(format!("Already using profile {}", arg))
+        }
+
+        ":save" => {
+            chain.save_to_file(arg)?;
+            Ok(format!("Saved to {}", arg))
+        }
+
+        ":load" => {
+            *chain = MarkovChain::load_from_file(arg)?;
+            Ok(format!("Loaded {} ({} words)", arg, chain.num_words()))
+        }
+
+        ":stats" => Ok(stats(chain)),
+        ":top" => top(chain, MarkovSeed::Word(arg), 10),
+
+        ":forget" if chain.remove_word(arg) => Ok(format!("Forgot {}", arg)),
+        ":forget" => Err(format!("{} is not in the chain", arg)),
+
+        ":clear" => {
+            *chain = MarkovChain::with_order(chain.order());
+            Ok("Cleared the chain".to_owned())
+        }
+
+        ":switch" => {
+            // loaded first, so that nothing is saved if it fails
+            let next = profile::load_profile(args, arg)?;
+
+            if let Some(name) = profile {
+                profile::save_profile(chain, args, name)?;
+            }
+
+            *chain = next;
+            *profile = Some(arg.to_owned());
+
+            Ok(format!(
+                "Switched to profile {} ({} words)",
+                arg,
+                chain.num_words()
+            ))
+        }
+
+        _ => Err(format!(
+            "Unknown command {}; expected :save, :load, :stats, :top, :forget, :clear or :switch",
+            name
+        )),
+    }
+}
+
+fn main() -> ExitCode {
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+    if let Some(path) = args.config.clone() {
+        if let Err(err) = Config::load(&path).and_then(|config| config.apply(&mut args, &matches)) {
+            eprintln!("ERROR: Could not load config {}: {}", path, err);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let mut profile = args.profile.clone();
+
+    // not started empty on error, lest the chain or profile be overwritten on
+    // exit; only one which was never saved starts empty
+    let mut chain = match (&args.load, &profile) {
+        (Some(path), _) if !Path::new(path).exists() => MarkovChain::new(),
+
+        (Some(path), _) => match MarkovChain::load_from_file(path) {
+            Ok(chain) => chain,
+            Err(err) => {
+                eprintln!("ERROR: Could not load chain {}: {}", path, err);
+                return ExitCode::FAILURE;
+            }
+        },
+
+        (None, Some(name)) => match profile::load_profile(&args, name) {
+            Ok(chain) => chain,
+            Err(err) => {
+                eprintln!("ERROR: {}", err);
+                return ExitCode::FAILURE;
+            }
+        },
+
+        (None, None) => MarkovChain::new(),
+    };
+
+    if args.bench_parse {
+        return bench::bench_parse(&mut chain, &args);
+    }
+
+    // Read files from command args to parse into the chain.
+    for path in &args.corpora {
+        if let Err(err) = parse_file(&mut chain, &args, path) {
+            eprintln!("WARN: Error reading file {}: {}", path, err);
+        }
+    }
+
+    let mut rng = match args.rng_seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let status = match (args.generate, &args.serve, &args.watch) {
+        (Some(count), ..) => generate(&chain, &args, count, &mut rng),
+        (None, None, None) => prompt(&mut chain, &args, &mut profile, &mut rng),
+
+        (None, serve, watch) => {
+            let server = match serve.as_deref().map(serve::bind).transpose() {
+                Ok(server) => server.map(Arc::new),
+                Err(err) => {
+                    eprintln!("ERROR: {}", err);
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            let shared = SharedMarkovChain::new(chain);
+            let stop = Arc::new(AtomicBool::new(false));
+
+            // stop serving and watching when interrupted or terminated, rather
+            // than exiting at once, so that the chain is saved
+            let handler = {
+                let (stop, server) = (stop.clone(), server.clone());
+
+                move || {
+                    stop.store(true, Ordering::Relaxed);
+
+                    if let Some(server) = &server {
+                        for _ in 0..serve::num_workers() {
+                            server.unblock();
+                        }
+                    }
+                }
+            };
+
+            if let Err(err) = ctrlc::set_handler(handler) {
+                eprintln!("WARN: Could not handle interruption: {}", err);
+            }
+
+            let status = thread::scope(|scope| match (&server, watch) {
+                (Some(server), Some(path)) => {
+                    scope.spawn(|| watch::watch(&shared, &args, path, &stop));
+
+                    let status = serve::serve(&shared, &args, server, &mut rng);
+                    stop.store(true, Ordering::Relaxed);
+                    status
+                }
+
+                (Some(server), None) => serve::serve(&shared, &args, server, &mut rng),
+                (None, Some(path)) => watch::watch(&shared, &args, path, &stop),
+                (None, None) => unreachable!(),
+            });
+
+            // every thread is done with the chain by now, so it can be
+            // unshared; were it not, it is saved from under the lock instead
+            return match shared.try_into_inner() {
+                Ok(chain) => save_on_exit(&chain, &args, profile.as_deref(), status),
+                Err(shared) => save_on_exit(&shared.read(), &args, profile.as_deref(), status),
+            };
+        }
+    };
+
+    save_on_exit(&chain, &args, profile.as_deref(), status)
+}
+
+/**
+ * Saves a chain once done with it, to its profile and to
+ * [Args::save_on_exit], if given. Returns the status to exit with: `status`,
+ * unless saving failed.
+ */
+fn save_on_exit(
+    chain: &MarkovChain,
+    args: &Args,
+    profile: Option<&str>,
+    status: ExitCode,
+) -> ExitCode {
+    if let Some(name) = profile {
+        if let Err(err) = profile::save_profile(chain, args, name) {
+            eprintln!("ERROR: {}", err);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if let Some(path) = &args.save_on_exit {
+        if let Err(err) = chain.save_to_file(path) {
+            eprintln!("ERROR: Could not save chain to {}: {}", path, err);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    status
+}
+
+/// Prints sentences, one per line, and fails on the first which cannot be
+/// composed.
+fn generate(chain: &MarkovChain, args: &Args, count: usize, rng: &mut StdRng) -> ExitCode {
+    for _ in 0..count {
+        match compose(chain, args, seed(args, "", rng), rng) {
+            Ok(composed) => println!("{}", composed.format(args)),
+            Err(err) if args.json => {
+                println!("{}", format_error(args, &err));
+                return ExitCode::FAILURE;
+            }
+            Err(err) => {
+                eprintln!("ERROR: {}", err);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+/**
+ * Learns from each line typed and replies to it, until the input ends (e.g.
+ * with Ctrl-D). Lines can be edited, and earlier ones recalled or searched
+ * (with Ctrl-R); Ctrl-C discards the line being typed.
+ */
+fn prompt(
+    chain: &mut MarkovChain,
+    args: &Args,
+    profile: &mut Option<String>,
+    rng: &mut StdRng,
+) -> ExitCode {
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            eprintln!("ERROR: Could not start the prompt: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Some(path) = &args.history {
+        // there is no history yet the first time
+        let _ = editor.load_history(path);
+    }
+
+    loop {
+        // the profile being used, if any, is shown in the prompt
+        let line = match editor.readline(&format!("{}> ", profile.as_deref().unwrap_or(""))) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                eprintln!("ERROR: Could not read the prompt: {}", err);
+                break;
+            }
+        };
+
+        let trimmed = line.trim();
+
+        if !trimmed.is_empty() {
+            let _ = editor.add_history_entry(trimmed);
+        }
+
+        let reply = if trimmed.starts_with(':') {
+            command(chain, args, profile, trimmed).unwrap_or_else(|err| format!("ERROR: {}", err))
+        } else if let Some(k) = args.suggest {
+            suggest(chain, args, trimmed, k)
+        } else {
+            if !args.no_learn {
+                parse(chain, trimmed);
+            }
+
+            produce(chain, args, trimmed, rng)
+        };
+
+        println!("{}\n", reply);
+    }
+
+    if let Some(path) = &args.history {
+        if let Err(err) = editor.save_history(path) {
+            eprintln!(
+                "WARN: Could not save the prompt history to {}: {}",
+                path, err
+            );
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,68 @@
+//! Named chain profiles of the wordmarkov binary, e.g. one per channel or
+//! persona, each saved in its own file of the profile directory.
+
+use super::args::Args;
+use super::parse_file;
+use std::fs;
+use std::path::PathBuf;
+use wordmarkov::prelude::*;
+
+/**
+ * The file a profile is saved in: the one given in its definition in the
+ * config file, if any, or else one in the profile directory. Names are made of
+ * letters, digits, `-` and `_`, so that they cannot lead out of it.
+ */
+fn profile_path(args: &Args, name: &str) -> Result<PathBuf, String> {
+    if let Some(path) = args.profiles.get(name).and_then(|def| def.path.clone()) {
+        return Ok(path);
+    }
+
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid profile name {:?}; expected letters, digits, - and _",
+            name
+        ));
+    }
+
+    Ok(args.profile_dir.join(format!("{}.chain", name)))
+}
+
+/**
+ * Loads a profile, or, if it was never saved, starts a chain from the corpora
+ * given in its definition in the config file, if any.
+ */
+pub fn load_profile(args: &Args, name: &str) -> Result<MarkovChain, String> {
+    let path = profile_path(args, name)?;
+
+    if !path.exists() {
+        let mut chain = MarkovChain::new();
+
+        for corpus in args.profiles.get(name).map_or(&[][..], |def| &def.corpora) {
+            if let Err(err) = parse_file(&mut chain, args, corpus) {
+                eprintln!("WARN: Error reading file {}: {}", corpus, err);
+            }
+        }
+
+        return Ok(chain);
+    }
+
+    MarkovChain::load_from_file(&path)
+        .map_err(|err| format!("Could not load profile {}: {}", name, err))
+}
+
+/// Saves a profile, creating its directory if need be.
+pub fn save_profile(chain: &MarkovChain, args: &Args, name: &str) -> Result<(), String> {
+    let path = profile_path(args, name)?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+
+    chain
+        .save_to_file(&path)
+        .map_err(|err| format!("Could not save profile {}: {}", name, err))
+}
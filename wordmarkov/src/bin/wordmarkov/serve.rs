@@ -0,0 +1,207 @@
+//! The HTTP server of the wordmarkov binary, for chat bridges to learn from
+//! and compose with a chain without linking to it.
+//!
+//! * `POST /learn` parses the text in the body into the chain;
+//! * `GET /generate` composes a sentence, from the word in the `seed` query
+//!   parameter if given;
+//! * `GET /stats` describes the chain, in JSON.
+
+use super::args::Args;
+use super::{compose, format_error, seed, top_words};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::json;
+use std::io::Read;
+use std::process::ExitCode;
+use std::thread;
+use tiny_http::{Header, Method, Request, Response, Server};
+use wordmarkov::prelude::*;
+
+/// The most bytes of text learned at once.
+const MAX_BODY: u64 = 1 << 20;
+
+/// A status code, content type and body to respond with.
+type Reply = (u16, &'static str, String);
+
+fn text(status: u16, body: impl Into<String>) -> Reply {
+    (status, "text/plain; charset=utf-8", body.into())
+}
+
+fn json(value: serde_json::Value) -> Reply {
+    (200, "application/json", value.to_string())
+}
+
+/// Decodes a component of a URL, with `+` for spaces and `%XX` escapes.
+fn decode(component: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(component.len());
+    let mut iter = component.bytes();
+
+    while let Some(byte) = iter.next() {
+        bytes.push(match byte {
+            b'+' => b' ',
+            b'%' => {
+                let hex = [iter.next()?, iter.next()?];
+                u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?
+            }
+            byte => byte,
+        });
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+/// Finds a parameter of a URL query, decoded.
+fn query_param(query: &str, name: &str) -> Result<Option<String>, Reply> {
+    for pair in query.split('&') {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+
+        if key == name {
+            return match decode(value) {
+                Some(value) => Ok(Some(value)),
+                None => Err(text(400, format!("Malformed {} parameter", name))),
+            };
+        }
+    }
+
+    Ok(None)
+}
+
+fn learn(request: &mut Request, chain: &SharedMarkovChain, args: &Args) -> Reply {
+    if args.no_learn {
+        return text(403, "Learning is disabled");
+    }
+
+    let mut body = String::new();
+
+    if let Err(err) = request
+        .as_reader()
+        .take(MAX_BODY + 1)
+        .read_to_string(&mut body)
+    {
+        return text(400, err.to_string());
+    }
+
+    if body.len() as u64 > MAX_BODY {
+        return text(
+            413,
+            format!("At most {} bytes can be learned at once", MAX_BODY),
+        );
+    }
+
+    json(json!({ "learned": chain.parse_text(&body) }))
+}
+
+fn generate(query: &str, chain: &SharedMarkovChain, args: &Args, rng: &mut StdRng) -> Reply {
+    let word = match query_param(query, "seed") {
+        Ok(word) => word,
+        Err(reply) => return reply,
+    };
+
+    let seed = match &word {
+        Some(word) => MarkovSeed::Word(word),
+        None => seed(args, "", rng),
+    };
+
+    match compose(&chain.read(), args, seed, rng) {
+        Ok(composed) if args.json => (200, "application/json", composed.format(args)),
+        Ok(composed) => text(200, composed.text),
+
+        Err(err) => {
+            let status = match err {
+                MarkovError::UnknownSeedWord(_) => 404,
+                MarkovError::EmptyChain => 503,
+                _ => 500,
+            };
+
+            if args.json {
+                (status, "application/json", format_error(args, &err))
+            } else {
+                text(status, err.to_string())
+            }
+        }
+    }
+}
+
+fn stats(chain: &SharedMarkovChain) -> Reply {
+    let chain = chain.read();
+
+    let top: Vec<_> = top_words(&chain, 10)
+        .into_iter()
+        .map(|(word, count)| json!({ "word": word, "count": count }))
+        .collect();
+
+    json(json!({
+        "words": chain.num_words(),
+        "textlets": chain.num_textlets(),
+        "edges": chain.num_edges(),
+        "memory_bytes": chain.memory_usage(),
+        "top_words": top,
+    }))
+}
+
+fn handle(
+    request: &mut Request,
+    chain: &SharedMarkovChain,
+    args: &Args,
+    rng: &mut StdRng,
+) -> Reply {
+    let url = request.url().to_owned();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+
+    match (request.method(), path) {
+        (Method::Post, "/learn") => learn(request, chain, args),
+        (Method::Get, "/generate") => generate(query, chain, args, rng),
+        (Method::Get, "/stats") => stats(chain),
+        (_, "/learn" | "/generate" | "/stats") => text(405, "Method not allowed"),
+        _ => text(404, "Not found"),
+    }
+}
+
+/// Starts a server at an address, for [serve] to serve with.
+pub fn bind(addr: &str) -> Result<Server, String> {
+    let server =
+        Server::http(addr).map_err(|err| format!("Could not serve at {}: {}", addr, err))?;
+
+    eprintln!("Serving at http://{}", addr);
+
+    Ok(server)
+}
+
+/// How many threads [serve] serves on: as many as there are cores.
+pub fn num_workers() -> usize {
+    thread::available_parallelism().map_or(4, usize::from)
+}
+
+/**
+ * Serves a chain over HTTP with a server, on [num_workers] threads, until the
+ * server fails or is unblocked once for each thread. Each thread draws random
+ * numbers from its own generator, seeded from `rng`.
+ */
+pub fn serve(
+    chain: &SharedMarkovChain,
+    args: &Args,
+    server: &Server,
+    rng: &mut StdRng,
+) -> ExitCode {
+    thread::scope(|scope| {
+        for _ in 0..num_workers() {
+            let mut rng = StdRng::seed_from_u64(rng.gen());
+
+            scope.spawn(move || {
+                while let Ok(mut request) = server.recv() {
+                    let (status, content_type, body) = handle(&mut request, chain, args, &mut rng);
+
+                    let response = Response::from_string(body)
+                        .with_status_code(status)
+                        .with_header(Header::from_bytes("Content-Type", content_type).unwrap());
+
+                    if let Err(err) = request.respond(response) {
+                        eprintln!("WARN: Could not respond: {}", err);
+                    }
+                }
+            });
+        }
+    });
+
+    ExitCode::SUCCESS
+}
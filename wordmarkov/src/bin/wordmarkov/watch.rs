@@ -0,0 +1,129 @@
+//! Continuous learning from a growing file, such as a live chat log, for the
+//! wordmarkov binary to keep learning as a daemon.
+
+use super::args::{Args, Format};
+use super::import::clean_line;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use wordmarkov::prelude::*;
+
+/// How often the watched file is checked for new lines.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The end of a file being watched, and what was read of a line not yet
+/// written whole.
+struct Tail {
+    pos: u64,
+    partial: Vec<u8>,
+}
+
+impl Tail {
+    /**
+     * Reads the lines written to a file since last time. If the file shrank,
+     * it is assumed to have been truncated or replaced, as when logs are
+     * rotated, and is read from the start.
+     */
+    fn read_lines(&mut self, path: &str) -> io::Result<Vec<String>> {
+        let mut file = File::open(path)?;
+
+        if file.metadata()?.len() < self.pos {
+            self.pos = 0;
+            self.partial.clear();
+        }
+
+        file.seek(SeekFrom::Start(self.pos))?;
+        self.pos += file.read_to_end(&mut self.partial)? as u64;
+
+        let complete = match self.partial.iter().rposition(|byte| *byte == b'\n') {
+            Some(end) => end + 1,
+            None => return Ok(vec![]),
+        };
+
+        let rest = self.partial.split_off(complete);
+        let lines = String::from_utf8_lossy(&self.partial)
+            .lines()
+            .map(str::to_owned)
+            .collect();
+
+        self.partial = rest;
+
+        Ok(lines)
+    }
+}
+
+/// Saves a chain, if an autosave path was given.
+fn autosave(chain: &SharedMarkovChain, args: &Args) {
+    if let Some(path) = &args.autosave {
+        if let Err(err) = chain.read().save_to_file(path) {
+            eprintln!("WARN: Could not save chain to {}: {}", path, err);
+        }
+    }
+}
+
+/**
+ * Parses each line appended to a file into a chain as soon as it is written,
+ * starting from the end of the file, until `stop` is set. Saves the chain
+ * every so often if anything was learned, and once more when stopped, if
+ * [Args::autosave] is given.
+ */
+pub fn watch(chain: &SharedMarkovChain, args: &Args, path: &str, stop: &AtomicBool) -> ExitCode {
+    if !matches!(args.format, Format::Plain | Format::Irc) {
+        eprintln!("ERROR: Only plain and IRC logs can be watched");
+        return ExitCode::FAILURE;
+    }
+
+    let pos = match File::open(path).and_then(|file| file.metadata()) {
+        Ok(metadata) => metadata.len(),
+        Err(err) => {
+            eprintln!("ERROR: Could not watch {}: {}", path, err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    eprintln!("Watching {}", path);
+
+    let mut tail = Tail {
+        pos,
+        partial: vec![],
+    };
+
+    let interval = Duration::from_secs(args.autosave_interval);
+    let mut last_save = Instant::now();
+    let mut unsaved = 0;
+
+    while !stop.load(Ordering::Relaxed) {
+        match tail.read_lines(path) {
+            Ok(lines) => {
+                for line in lines {
+                    if let Some(sentence) = clean_line(args.format, &line) {
+                        chain.parse_sentence(&sentence);
+                        unsaved += 1;
+                    }
+                }
+            }
+
+            // the file is being rotated
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+
+            Err(err) => eprintln!("WARN: Could not read {}: {}", path, err),
+        }
+
+        if unsaved > 0 && last_save.elapsed() >= interval {
+            autosave(chain, args);
+            last_save = Instant::now();
+            unsaved = 0;
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    if unsaved > 0 {
+        autosave(chain, args);
+    }
+
+    ExitCode::SUCCESS
+}
@@ -2,14 +2,35 @@
  * Actual Markov chain container.
  */
 
+use super::case::CaseFolding;
+use super::compose::{ComposeDirection, ComposeOptions};
+use super::error::MarkovError;
+use super::limits::ChainLimits;
+use super::log::ChainLog;
+use super::metadata::MetadataTables;
+use super::sampling::SamplingCache;
+use super::seed::{RandomSeeding, SeedFallback};
 use super::selectors::interface::MarkovSelector;
 use super::selectors::interface::SelectionType;
 use super::token::*;
-use crate::sentence::lex::{Lexer, Token as LexedToken};
+use crate::sentence::lex::{Lexer, LexerConfig, Token as LexedToken};
+use crate::sentence::split::split_sentences;
 use rand::{distributions::Uniform, prelude::*};
-use std::collections::HashMap;
-use std::collections::LinkedList;
-use std::rc::Rc;
+use std::collections::{HashMap, HashSet};
+use std::io::BufRead;
+use std::sync::Arc;
+
+/**
+ * The log probability [MarkovChain::score_sentence] adds for each word never
+ * found after the word before it.
+ */
+pub const DEFAULT_UNSEEN_PENALTY: f64 = -20.0;
+
+/// The index of the [MarkovTokenOwned::Begin] textlet, always the first.
+pub(crate) const BEGIN: usize = 0;
+
+/// The index of the [MarkovTokenOwned::End] textlet, always the second.
+pub(crate) const END: usize = 1;
 
 /// The direction in which to traverse the Markov chain.
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
@@ -23,9 +44,14 @@ pub enum MarkovSeed<'a> {
     Word(&'a str),
     Id(usize),
     Random,
+
+    /// The beginning of a sentence; composing forward from it makes a whole
+    /// sentence.
+    Begin,
 }
 
 /// An edge linking two words in the Markov chain.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Edge {
     /// The word this edge comes from.
     pub src_idx: usize,
@@ -36,6 +62,10 @@ pub struct Edge {
     /// How many times this edge has been found.
     pub hits: usize,
 
+    /// How many sentences had been parsed into the chain when this edge was
+    /// last found. See [crate::chain::limits::EvictionPolicy].
+    pub last_seen: usize,
+
     /// The punctuation in this edge.
     pub pct_idx: usize,
 }
@@ -57,17 +87,72 @@ impl Edge {
     }
 }
 
+/**
+ * The edges found after (or, in reverse, before) each context of a
+ * higher-order chain, and how many times each was found in it.
+ *
+ * Contexts are sequences of 2 or more words; see [MarkovChain::with_order].
+ */
+pub(crate) type ContextTable = HashMap<Vec<usize>, Vec<(usize, usize)>>;
+
 /**
  * A graph that links tokens together.
+ *
+ * By default, the chain is first-order; the next word only depends on the
+ * current one. See [Self::with_order] for higher-order chains.
+ *
+ * A chain is [Send] and [Sync], so it can be shared between threads, e.g.
+ * behind an [Arc]; composing sentences only needs a shared reference, so any
+ * number of threads can compose from the same chain at once, each with its
+ * own selector. Parsing sentences needs a mutable reference, and thus a lock
+ * if the chain is shared.
  */
 pub struct MarkovChain {
-    textlet_bag: Vec<MarkovTokenOwned>,
-    textlet_indices: HashMap<Rc<str>, usize>,
-    words: Vec<usize>,
+    pub(crate) textlet_bag: Vec<MarkovTokenOwned>,
+    textlet_indices: HashMap<Arc<str>, usize>,
+    pub(crate) words: Vec<usize>,
+
+    /// The same textlet indices as [Self::words], to look them up without
+    /// scanning it.
+    word_set: HashSet<usize>,
+
+    pub(crate) edge_list: Vec<Edge>,
+
+    /// The index of each edge by its source, destination and punctuation;
+    /// there is only ever one edge for each.
+    edge_keys: HashMap<(usize, usize, usize), usize>,
+
+    /// The edges from and toward each textlet, by textlet index, so that
+    /// walking the chain needs no hashing.
+    edges: Vec<Vec<usize>>,
+    reverse_edges: Vec<Vec<usize>>,
+
+    /// How many times each textlet was found, by textlet index; the sum of
+    /// the hits of the edges toward it.
+    frequencies: Vec<usize>,
+
+    pub(crate) order: usize,
+    pub(crate) forward_contexts: ContextTable,
+    pub(crate) reverse_contexts: ContextTable,
 
-    edge_list: Vec<Edge>,
-    edges: HashMap<usize, Vec<usize>>,
-    reverse_edges: HashMap<usize, Vec<usize>>,
+    pub(crate) sentences_parsed: usize,
+    pub(crate) limits: Option<ChainLimits>,
+
+    pub(crate) case_folding: CaseFolding,
+
+    /// How sentences are split into words and punctuation, unless given a
+    /// [LexerConfig] of their own; see [Self::set_lexer_config].
+    pub(crate) lexer_config: LexerConfig,
+
+    pub(crate) surface_forms: HashMap<Arc<str>, Vec<(Arc<str>, usize)>>,
+    pub(crate) capitalized: HashMap<Arc<str>, Arc<str>>,
+
+    pub(crate) seed_fallback: SeedFallback,
+    pub(crate) random_seeding: RandomSeeding,
+
+    pub(crate) log: Option<ChainLog>,
+    pub(crate) metadata: MetadataTables,
+    pub(crate) sampling: Option<SamplingCache>,
 }
 
 impl Default for MarkovChain {
@@ -81,15 +166,191 @@ impl MarkovChain {
      * Makes a new empty [MarkovChain].
      */
     pub fn new() -> MarkovChain {
+        Self::with_order(1)
+    }
+
+    /**
+     * Makes a new chain of the given order; the next word is picked based
+     * on the last `order` words, rather than only the current one, which
+     * makes for more coherent sentences given a large enough corpus.
+     *
+     * When the last `order` words were never found together, the chain backs
+     * off to fewer of them, down to only the current word.
+     *
+     * An order of 0 is treated as 1.
+     */
+    pub fn with_order(order: usize) -> MarkovChain {
         MarkovChain {
             textlet_bag: vec![MarkovTokenOwned::Begin, MarkovTokenOwned::End],
             textlet_indices: HashMap::new(),
             words: Vec::new(),
+            word_set: HashSet::new(),
 
             edge_list: Vec::new(),
-            edges: HashMap::new(),
-            reverse_edges: HashMap::new(),
+            edge_keys: HashMap::new(),
+            edges: Vec::new(),
+            reverse_edges: Vec::new(),
+            frequencies: Vec::new(),
+
+            order: order.max(1),
+            forward_contexts: HashMap::new(),
+            reverse_contexts: HashMap::new(),
+
+            sentences_parsed: 0,
+            limits: None,
+
+            case_folding: CaseFolding::Off,
+            lexer_config: LexerConfig::default(),
+            surface_forms: HashMap::new(),
+            capitalized: HashMap::new(),
+
+            seed_fallback: SeedFallback::default(),
+            random_seeding: RandomSeeding::default(),
+
+            log: None,
+            metadata: HashMap::new(),
+            sampling: None,
+        }
+    }
+
+    /// The order of this chain. See [Self::with_order].
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /**
+     * Makes a chain out of its textlets, seed words and edges, rebuilding the
+     * lookup tables from them.
+     *
+     * Fails if the textlets do not start with [MarkovTokenOwned::Begin] and
+     * [MarkovTokenOwned::End], if a textlet is repeated, or if an index is out
+     * of bounds.
+     */
+    pub(crate) fn from_parts(
+        textlet_bag: Vec<MarkovTokenOwned>,
+        words: Vec<usize>,
+        edge_list: Vec<Edge>,
+    ) -> Result<MarkovChain, String> {
+        use MarkovTokenOwned::*;
+
+        if textlet_bag.len() < 2 || textlet_bag[0] != Begin || textlet_bag[1] != End {
+            return Err("The first two textlets must be the begin and end tokens".to_owned());
+        }
+
+        let num_textlets = textlet_bag.len();
+        let mut textlet_indices = HashMap::new();
+
+        for (i, textlet) in textlet_bag.iter().enumerate().skip(2) {
+            match textlet {
+                Textlet(word) => {
+                    if textlet_indices.insert(word.clone(), i).is_some() {
+                        return Err(format!("Textlet {:?} is repeated", word));
+                    }
+                }
+
+                _ => return Err(format!("Textlet {} is a misplaced begin or end token", i)),
+            }
+        }
+
+        if let Some(word) = words.iter().find(|w| **w >= num_textlets) {
+            return Err(format!("Word index {} is out of bounds", word));
+        }
+
+        let mut chain = MarkovChain {
+            textlet_bag,
+            textlet_indices,
+            word_set: words.iter().copied().collect(),
+            words,
+
+            edge_list: Vec::with_capacity(edge_list.len()),
+            edge_keys: HashMap::with_capacity(edge_list.len()),
+            edges: vec![Vec::new(); num_textlets],
+            reverse_edges: vec![Vec::new(); num_textlets],
+            frequencies: vec![0; num_textlets],
+
+            order: 1,
+            forward_contexts: HashMap::new(),
+            reverse_contexts: HashMap::new(),
+
+            sentences_parsed: 0,
+            limits: None,
+
+            case_folding: CaseFolding::Off,
+            lexer_config: LexerConfig::default(),
+            surface_forms: HashMap::new(),
+            capitalized: HashMap::new(),
+
+            seed_fallback: SeedFallback::default(),
+            random_seeding: RandomSeeding::default(),
+
+            log: None,
+            metadata: HashMap::new(),
+            sampling: None,
+        };
+
+        for edge in edge_list {
+            if [edge.src_idx, edge.dst_idx, edge.pct_idx]
+                .iter()
+                .any(|i| *i >= num_textlets)
+            {
+                return Err(format!(
+                    "Edge {} -> {} is out of bounds",
+                    edge.src_idx, edge.dst_idx
+                ));
+            }
+
+            if chain
+                .find_edge(edge.src_idx, edge.dst_idx, edge.pct_idx)
+                .is_some()
+            {
+                return Err(format!(
+                    "Edge {} -> {} is repeated",
+                    edge.src_idx, edge.dst_idx
+                ));
+            }
+
+            chain.edge_list.push(edge);
+            chain.link_edge(chain.edge_list.len() - 1);
+        }
+
+        Ok(chain)
+    }
+
+    /**
+     * Sets the order and higher-order contexts of a chain made with
+     * [Self::from_parts].
+     *
+     * Fails if a context is too long or too short for the order, or if an
+     * index is out of bounds.
+     */
+    #[cfg(feature = "serde")]
+    pub(crate) fn with_contexts(
+        mut self,
+        order: usize,
+        forward_contexts: ContextTable,
+        reverse_contexts: ContextTable,
+    ) -> Result<MarkovChain, String> {
+        for (context, edges) in forward_contexts.iter().chain(reverse_contexts.iter()) {
+            if context.len() < 2 || context.len() > order {
+                return Err(format!(
+                    "Context of {} words does not fit an order {} chain",
+                    context.len(),
+                    order
+                ));
+            }
+
+            if context.iter().any(|w| *w >= self.textlet_bag.len())
+                || edges.iter().any(|(e, _)| *e >= self.edge_list.len())
+            {
+                return Err("Context is out of bounds".to_owned());
+            }
         }
+
+        self.order = order.max(1);
+        self.forward_contexts = forward_contexts;
+        self.reverse_contexts = reverse_contexts;
+
+        Ok(self)
     }
 
     /**
@@ -97,16 +358,18 @@ impl MarkovChain {
      * makes a new one and returns that instead.
      */
     pub fn ensure_textlet_index(&mut self, word: &str) -> usize {
-        match self.textlet_indices.get(word) {
+        let word = self.fold_case(word);
+
+        match self.textlet_indices.get(&*word) {
             Some(a) => *a,
             None => {
                 let i = self.textlet_bag.len();
-                let rcword: Rc<str> = Rc::from(word);
+                let arcword: Arc<str> = Arc::from(word);
 
                 self.textlet_bag
-                    .push(MarkovTokenOwned::Textlet(rcword.clone()));
+                    .push(MarkovTokenOwned::Textlet(arcword.clone()));
 
-                self.textlet_indices.insert(rcword, i);
+                self.textlet_indices.insert(arcword, i);
 
                 i
             }
@@ -133,58 +396,81 @@ impl MarkovChain {
      * If the textlet is not registered, returns None.
      */
     pub fn try_get_textlet_index(&self, word: &str) -> Option<usize> {
-        self.textlet_indices.get(word).copied()
+        self.textlet_indices.get(&*self.fold_case(word)).copied()
     }
 
     /**
      * Gets the [MarkovToken] of a textlet by its index.
+     *
+     * With [CaseFolding::MostFrequent], words are in their most frequent
+     * form.
      */
     pub fn get_textlet(&self, index: usize) -> Option<MarkovToken<'_>> {
-        self.textlet_bag.get(index).map(MarkovToken::from)
+        self.textlet_bag.get(index).map(|textlet| match textlet {
+            MarkovTokenOwned::Textlet(word) => MarkovToken::Textlet(self.surface_form(word)),
+            _ => MarkovToken::from(textlet),
+        })
     }
 
-    fn push_new_edge(
-        &mut self,
-        from: usize,
-        to: usize,
-        punct: usize,
-        hits: Option<usize>,
-    ) -> usize {
-        let edge = Edge {
+    fn push_new_edge(&mut self, from: usize, to: usize, punct: usize, hits: usize) -> usize {
+        self.edge_list.push(Edge {
             src_idx: from,
             dst_idx: to,
-            hits: hits.unwrap_or(1),
+            hits,
+            last_seen: self.sentences_parsed,
             pct_idx: punct,
-        };
+        });
 
-        let idx = self.edge_list.len();
-        self.edge_list.push(edge);
+        let idx = self.edge_list.len() - 1;
+        self.link_edge(idx);
 
         idx
     }
 
-    fn add_reverse_edge(&mut self, edge_idx: usize) {
-        let edge = &self.edge_list[edge_idx];
-
-        match self.reverse_edges.get_mut(&edge.dst_idx) {
-            None => {
-                let rev_vec = vec![edge_idx];
+    /// Adds an edge of `self.edge_list` to the lookup tables.
+    fn link_edge(&mut self, idx: usize) {
+        let edge = &self.edge_list[idx];
 
-                self.reverse_edges.insert(edge.dst_idx, rev_vec);
-            }
+        self.edge_keys
+            .insert((edge.src_idx, edge.dst_idx, edge.pct_idx), idx);
 
-            Some(rev_vec) => {
-                for oedge in rev_vec.iter() {
-                    let oedge = self.edge_list.get(*oedge).unwrap();
+        adjacent_mut(&mut self.edges, edge.src_idx).push(idx);
+        adjacent_mut(&mut self.reverse_edges, edge.dst_idx).push(idx);
 
-                    if edge.src_idx == oedge.src_idx && edge.pct_idx == oedge.pct_idx {
-                        return;
-                    }
-                }
+        let (src_idx, dst_idx, hits) = (edge.src_idx, edge.dst_idx, edge.hits);
+        self.count_frequency(dst_idx, hits);
+        self.invalidate_sampling(src_idx, dst_idx);
+    }
 
-                rev_vec.push(edge_idx);
-            }
+    /// Counts up how many times a textlet was found.
+    fn count_frequency(&mut self, textlet: usize, hits: usize) {
+        if self.frequencies.len() <= textlet {
+            self.frequencies.resize(textlet + 1, 0);
         }
+
+        self.frequencies[textlet] += hits;
+    }
+
+    /**
+     * How many times a word (or any textlet) was found in the sentences
+     * parsed into this chain, after any decay; the sum of the hits of the
+     * edges toward it. For the end token, that is the number of sentences.
+     */
+    pub fn frequency(&self, textlet: usize) -> usize {
+        self.frequencies.get(textlet).copied().unwrap_or(0)
+    }
+
+    /**
+     * Finds the edge between two words with the given punctuation, as
+     * registered by [Self::register_edge], and returns its index.
+     */
+    pub fn find_edge(&self, from: usize, to: usize, punct: usize) -> Option<usize> {
+        self.edge_keys.get(&(from, to, punct)).copied()
+    }
+
+    /// Gets an edge by its index.
+    pub fn get_edge(&self, index: usize) -> Option<&Edge> {
+        self.edge_list.get(index)
     }
 
     /**
@@ -195,382 +481,1395 @@ impl MarkovChain {
      *
      * For both `from` and `to`, if the index is not found in the
      * `self.words` list, it will be added to it.
+     *
+     * If an edge between the same words with the same punctuation was
+     * already registered, its hits are counted up instead.
+     *
+     * Returns the index of the edge.
+     */
+    pub fn register_edge(&mut self, from: usize, to: usize, punct: usize) -> usize {
+        self.register_edge_hits(from, to, punct, 1)
+    }
+
+    /**
+     * Register an edge like [Self::register_edge], as if it had been found
+     * `hits` times.
      */
-    pub fn register_edge(&mut self, from: usize, to: usize, punct: usize) {
+    pub fn register_edge_hits(
+        &mut self,
+        from: usize,
+        to: usize,
+        punct: usize,
+        hits: usize,
+    ) -> usize {
         for item in [from, to] {
-            if !self.words.contains(&item) {
-                self.words.push(item);
-            }
+            self.add_word(item);
         }
 
-        if let Some(edgevec) = self.edges.get_mut(&from) {
-            for edge in edgevec.iter() {
-                let edge: &mut Edge = self.edge_list.get_mut(*edge).unwrap();
+        if let Some(idx) = self.find_edge(from, to, punct) {
+            let edge = &mut self.edge_list[idx];
 
-                if edge.dst_idx == to && edge.pct_idx == punct {
-                    edge.hits += 1;
-                    return;
-                }
-            }
-        }
+            edge.hits += hits;
+            edge.last_seen = self.sentences_parsed;
 
-        let idx = self.push_new_edge(from, to, punct, None);
-        self.edges.insert(from, vec![idx]);
+            self.count_frequency(to, hits);
+            self.invalidate_sampling(from, to);
 
-        if let Some(edgevec) = self.edges.get_mut(&from) {
-            edgevec.push(idx);
-        } else {
-            self.edges.insert(from, vec![idx]);
+            return idx;
         }
 
-        self.add_reverse_edge(idx);
+        self.push_new_edge(from, to, punct, hits)
     }
 
-    fn get_seed<T: Rng>(&self, seed: MarkovSeed, rng: &mut T) -> Result<usize, String> {
-        use MarkovSeed::*;
-
-        match seed {
-            Word(seed) => {
-                let from = self.try_get_textlet_index(seed);
-
-                if from.is_none() {
-                    return Err(format!(
-                        "Seed word {:?} not found in this Markov chain!",
-                        seed
-                    ));
+    /**
+     * Registers the higher-order contexts in which each edge of a sentence
+     * was found.
+     *
+     * `words` are the words of the sentence, starting with
+     * [MarkovTokenOwned::Begin] and ending with [MarkovTokenOwned::End], and
+     * `edges` the edges between each of them, found `hits` times.
+     */
+    fn register_contexts(&mut self, words: &[usize], edges: &[usize], hits: usize) {
+        for (i, edge) in edges.iter().enumerate() {
+            for len in 2..=self.order {
+                if len <= i + 1 {
+                    add_context_hits(
+                        &mut self.forward_contexts,
+                        words[i + 1 - len..=i].to_vec(),
+                        *edge,
+                        hits,
+                    );
                 }
 
-                Ok(from.unwrap())
-            }
-
-            Id(seed) => Ok(seed),
-
-            Random => {
-                let from: usize = Uniform::new(0, self.words.len()).sample(rng);
-                Ok(self.words[from])
-            }
-        }
-    }
-
-    fn _weighted_select<R>(
-        &self,
-        sel_type: SelectionType,
-        edges: &[usize],
-        weights: &[f32],
-        rng: &mut R,
-    ) -> &Edge
-    where
-        R: Rng,
-    {
-        match sel_type {
-            SelectionType::Lowest => {
-                edges
-                    .iter()
-                    .map(|e| &self.edge_list[*e])
-                    .zip(weights.iter())
-                    .reduce(|ewc, ewn| if ewc.1 < ewn.1 { ewc } else { ewn })
-                    .unwrap()
-                    .0
-            }
-
-            SelectionType::Highest => {
-                edges
-                    .iter()
-                    .map(|e| &self.edge_list[*e])
-                    .zip(weights.iter())
-                    .reduce(|ewc, ewn| if ewc.1 > ewn.1 { ewc } else { ewn })
-                    .unwrap()
-                    .0
-            }
-
-            SelectionType::WeightedRandom => {
-                let total: f32 = weights.iter().sum();
-                let pick = Uniform::new(0.0_f32, total).sample(rng);
-
-                let mut curr = 0.0;
-                let mut res = None;
-
-                for (edge, weight) in edges
-                    .iter()
-                    .map(|e| &self.edge_list[*e])
-                    .zip(weights.iter())
-                {
-                    curr += weight;
-
-                    if curr >= pick {
-                        res = Some(edge);
-                        break;
-                    }
+                if i + len < words.len() {
+                    add_context_hits(
+                        &mut self.reverse_contexts,
+                        words[i + 1..=i + len].to_vec(),
+                        *edge,
+                        hits,
+                    );
                 }
-
-                res.unwrap()
             }
         }
     }
 
     /**
-     * Selects the word following the current one (`from`) based om the
-     * criteria of a [MarkovSelector] (`selector`).
-     *
-     * Returns a tuple (`dest`, `inbetween`, `dest_idx`, `inbetween_idx`).
-     * The first two items can be converted into strings because MarkovToken
-     * has Into<&str>. The last two items are the corresponding internal
-     * indices, which can be reused in functions which take `usize`.
+     * Merges another chain into this one, e.g. to combine chains built from
+     * different sources without parsing them again.
      *
-     * `inbetween` is all of the whitespace and punctuation lying between
-     * `from` and `dest`. Simply concatenate `from` with `inbetween.into()`
-     * with `dest.into()`, in that order.
+     * Adds the textlets, edges and contexts of `other` to this chain,
+     * summing the hits of the edges found in both. Contexts longer than the
+     * order of this chain are left out. The limits of this chain, if any,
+     * are then enforced (see [Self::set_limits]).
      */
-    pub fn select_next_word(
-        &self,
-        seed: MarkovSeed,
-        selector: &mut dyn MarkovSelector,
-        direction: MarkovTraverseDir,
-    ) -> Result<(MarkovToken<'_>, MarkovToken<'_>, usize, usize), String> {
-        use MarkovTraverseDir::*;
-
-        let mut rng = thread_rng();
-
-        let from: usize = self.get_seed(seed, &mut rng)?;
-
-        let edges = match direction {
-            MarkovTraverseDir::Forward => self.edges.get(&from),
-            MarkovTraverseDir::Reverse => self.reverse_edges.get(&from),
-        };
-
-        if edges.is_none() {
-            return Err(format!(
-                "Seed textlet {:?} is not connected to anything in this Markov chain!",
-                self.get_textlet(from)
+    pub fn merge(&mut self, other: &MarkovChain) {
+        let textlets: Vec<usize> = other
+            .textlet_bag
+            .iter()
+            .map(|textlet| match textlet {
+                MarkovTokenOwned::Begin => 0,
+                MarkovTokenOwned::End => 1,
+                MarkovTokenOwned::Textlet(word) => self.ensure_textlet_index(word),
+            })
+            .collect();
+
+        let mut edges = Vec::with_capacity(other.edge_list.len());
+
+        for edge in &other.edge_list {
+            edges.push(self.register_edge_hits(
+                textlets[edge.src_idx],
+                textlets[edge.dst_idx],
+                textlets[edge.pct_idx],
+                edge.hits,
             ));
         }
 
-        let edges = edges.unwrap();
-
-        if edges.is_empty() {
-            return Err(format!("Seed textlet {:?} is not connected to anything in this Markov chain, but in a weird way!", self.get_textlet(from)));
+        for word in &other.words {
+            self.add_word(textlets[*word]);
         }
 
-        let mut weights: Vec<f32> = vec![0.0; edges.len()];
-
-        selector.reset(direction);
-
-        for (edge, weight) in edges
-            .iter()
-            .map(|e| &self.edge_list[*e])
-            .zip(weights.iter_mut())
-        {
-            *weight = selector.weight(
-                &edge.get_source(self),
-                &edge.get_dest(self),
-                &edge.get_punct(self),
-                edge.hits,
-            );
+        for forms in other.surface_forms.values() {
+            for (form, hits) in forms {
+                self.add_surface_form(form, *hits);
+            }
         }
 
-        let sel_type = selector.selection_type();
+        let order = self.order;
 
-        let best_edge: &Edge = self._weighted_select(sel_type, edges, &weights, &mut rng);
+        for (table, other_table) in [
+            (&mut self.forward_contexts, &other.forward_contexts),
+            (&mut self.reverse_contexts, &other.reverse_contexts),
+        ] {
+            for (context, hits) in other_table {
+                if context.len() > order {
+                    continue;
+                }
 
-        match direction {
-            Forward => Ok((
-                best_edge.get_dest(self),
-                best_edge.get_punct(self),
-                best_edge.dst_idx,
-                best_edge.pct_idx,
-            )),
+                let context: Vec<usize> = context.iter().map(|w| textlets[*w]).collect();
 
-            Reverse => Ok((
-                best_edge.get_source(self),
-                best_edge.get_punct(self),
-                best_edge.src_idx,
-                best_edge.pct_idx,
-            )),
+                for (edge, count) in hits {
+                    add_context_hits(table, context.clone(), edges[*edge], *count);
+                }
+            }
         }
+
+        self.enforce_limits();
     }
 
     /**
-     * The number of words in this chain.
-     *
-     * Includes the internal tokens [MarkovTokenOwned::Begin] and
-     * [MarkovTokenOwned::End].
+     * Removes the edges found fewer than `min_hits` times, such as those left
+     * by one-off typos, and removes the words left without any edges from
+     * the seed words. Returns how many edges were removed.
      *
-     * Should be smaller than or, in extreme cases, equal to,
-     * [Self::num_textlets()].
+     * Textlets are kept; see [Self::prune_orphan_textlets].
      */
-    pub fn num_words(&self) -> usize {
-        self.words.len()
+    pub fn prune_edges(&mut self, min_hits: usize) -> usize {
+        self.retain_edges(|_, edge| edge.hits >= min_hits)
     }
 
     /**
-     * The number of textlets in this chain.
+     * Removes a word from this chain, along with every edge and context it
+     * is part of, and renumbers the remaining textlets; e.g. to purge an
+     * offensive word. Returns false if the word is not in the chain.
      *
-     * Includes unique instances of whitespace or punctuation, as well as the
-     * internal tokens [MarkovTokenOwned::Begin] and [MarkovTokenOwned::End].
+     * Textlets left unused are removed as well, as with
+     * [Self::prune_orphan_textlets]. Words which were only ever found next to
+     * the removed word may be left with no edges in one direction.
      */
-    pub fn num_textlets(&self) -> usize {
-        self.textlet_bag.len()
+    pub fn remove_word(&mut self, word: &str) -> bool {
+        let idx = match self.try_get_textlet_index(word) {
+            Some(idx) => idx,
+            None => return false,
+        };
+
+        self.retain_edges(|_, edge| ![edge.src_idx, edge.dst_idx, edge.pct_idx].contains(&idx));
+        self.words.retain(|word| *word != idx);
+
+        for table in [&mut self.forward_contexts, &mut self.reverse_contexts] {
+            table.retain(|context, _| !context.contains(&idx));
+        }
+
+        self.prune_orphan_textlets();
+
+        true
     }
 
     /**
-     * The number of [Edge]s registered in this chain.
+     * Removes the edge between two words with the given punctuation, as
+     * registered by [Self::register_edge]. Returns false if there is no such
+     * edge.
      *
-     * Includes edges connected to the internal tokens
-     * [MarkovTokenOwned::Begin] and [MarkovTokenOwned::End].
+     * Textlets are kept; see [Self::prune_orphan_textlets].
      */
-    pub fn num_edges(&self) -> usize {
-        self.edge_list.len()
+    pub fn remove_edge(&mut self, from: usize, to: usize, punct: usize) -> bool {
+        match self.find_edge(from, to, punct) {
+            Some(idx) => self.retain_edges(|i, _| i != idx) > 0,
+            None => false,
+        }
     }
 
     /**
-     * Parse a sentence, registering textlets and edges
-     * for it.
+     * Removes the edges for which `keep` returns false, given their index,
+     * and the words left without any edges from the seed words. Returns how
+     * many edges were removed.
      */
-    pub fn parse_sentence(&mut self, sentence: &str) {
-        let mut lexer = Lexer::new(sentence);
-        let mut curr_token = lexer.next();
+    pub(crate) fn retain_edges<F>(&mut self, mut keep: F) -> usize
+    where
+        F: FnMut(usize, &Edge) -> bool,
+    {
+        let mut new_indices = Vec::with_capacity(self.edge_list.len());
+        let mut kept = Vec::with_capacity(self.edge_list.len());
+
+        for (idx, edge) in std::mem::take(&mut self.edge_list).into_iter().enumerate() {
+            if keep(idx, &edge) {
+                new_indices.push(Some(kept.len()));
+                kept.push(edge);
+            } else {
+                new_indices.push(None);
+            }
+        }
 
-        let mut to_register: Vec<(LexedToken, LexedToken, LexedToken)> = vec![];
+        let removed = new_indices.len() - kept.len();
+        self.edge_list = kept;
 
-        if sentence.is_empty() {
-            return;
+        for table in [&mut self.forward_contexts, &mut self.reverse_contexts] {
+            table.retain(|_, edges| {
+                edges.retain_mut(|(edge, _)| match new_indices[*edge] {
+                    Some(new_index) => {
+                        *edge = new_index;
+                        true
+                    }
+
+                    None => false,
+                });
+
+                !edges.is_empty()
+            });
         }
 
-        loop {
-            if curr_token.is_none() {
-                panic!("Found a none token prematurely!");
-            }
+        self.rebuild_edge_tables();
 
-            let token = curr_token.unwrap();
+        let (edges, reverse_edges) = (&self.edges, &self.reverse_edges);
+        let connected = |table: &Vec<Vec<usize>>, word: usize| {
+            table.get(word).is_some_and(|edges| !edges.is_empty())
+        };
 
-            let punct = lexer.next();
-            let next_token = lexer.next();
+        self.words
+            .retain(|word| connected(edges, *word) || connected(reverse_edges, *word));
+        self.sync_word_set();
 
-            if punct.is_none() || next_token.is_none() {
-                return;
-            }
+        removed
+    }
 
-            let punct = punct.unwrap();
-            let next_token = next_token.unwrap();
+    /**
+     * Removes the textlets which are no longer used by any edge, e.g. after
+     * [Self::prune_edges], and renumbers the remaining ones. Returns how many
+     * textlets were removed.
+     */
+    pub fn prune_orphan_textlets(&mut self) -> usize {
+        let mut used = vec![false; self.textlet_bag.len()];
 
-            to_register.push((token, punct, next_token.clone()));
+        // the begin and end tokens must stay in place
+        used[0] = true;
+        used[1] = true;
 
-            if next_token == LexedToken::End {
-                break;
+        for edge in &self.edge_list {
+            for idx in [edge.src_idx, edge.dst_idx, edge.pct_idx] {
+                used[idx] = true;
             }
+        }
 
-            curr_token = Some(next_token);
+        for idx in self
+            .words
+            .iter()
+            .chain(self.forward_contexts.keys().flatten())
+            .chain(self.reverse_contexts.keys().flatten())
+        {
+            used[*idx] = true;
         }
 
-        for (src, pct, dst) in to_register {
-            let src = self.ensure_textlet_from_token(src);
-            let pct = self.ensure_textlet_from_token(pct);
-            let dst = self.ensure_textlet_from_token(dst);
+        let mut new_indices = vec![0; self.textlet_bag.len()];
+        let mut kept = Vec::with_capacity(self.textlet_bag.len());
 
-            self.register_edge(src, dst, pct);
+        for (i, textlet) in std::mem::take(&mut self.textlet_bag)
+            .into_iter()
+            .enumerate()
+        {
+            if used[i] {
+                new_indices[i] = kept.len();
+                kept.push(textlet);
+            }
         }
-    }
 
-    /// Get the textlet identifier for [MarkovTokenOwned::Begin].
-    pub fn begin(&self) -> usize {
-        self.textlet_bag
-            .iter()
-            .position(|a| a == &MarkovTokenOwned::Begin)
-            .unwrap()
-    }
+        let removed = used.len() - kept.len();
 
-    /// Get the textlet identifier for [MarkovTokenOwned::End].
-    pub fn end(&self) -> usize {
-        self.textlet_bag
-            .iter()
-            .position(|a| a == &MarkovTokenOwned::End)
-            .unwrap()
-    }
+        self.renumber_metadata(&|i| {
+            used.get(i)
+                .copied()
+                .unwrap_or(false)
+                .then(|| new_indices[i])
+        });
 
-    /// Returns whether the chain is empty – has no words in it.
-    pub fn is_empty(&self) -> bool {
-        self.words.is_empty()
-    }
+        self.textlet_bag = kept;
 
-    /**
-     * Composes a sentence by traversing this chain forward and backward from a
-     * given 'seed word'.
+        self.textlet_indices = self
+            .textlet_bag
+            .iter()
+            .enumerate()
+            .filter_map(|(i, textlet)| match textlet {
+                MarkovTokenOwned::Textlet(word) => Some((word.clone(), i)),
+                _ => None,
+            })
+            .collect();
+
+        let textlet_indices = &self.textlet_indices;
+        self.surface_forms
+            .retain(|word, _| textlet_indices.contains_key(word));
+        self.capitalized
+            .retain(|word, _| textlet_indices.contains_key(word));
+
+        for edge in &mut self.edge_list {
+            edge.src_idx = new_indices[edge.src_idx];
+            edge.dst_idx = new_indices[edge.dst_idx];
+            edge.pct_idx = new_indices[edge.pct_idx];
+        }
+
+        for word in &mut self.words {
+            *word = new_indices[*word];
+        }
+
+        self.sync_word_set();
+
+        for table in [&mut self.forward_contexts, &mut self.reverse_contexts] {
+            *table = std::mem::take(table)
+                .into_iter()
+                .map(|(context, edges)| {
+                    (context.into_iter().map(|w| new_indices[w]).collect(), edges)
+                })
+                .collect();
+        }
+
+        self.rebuild_edge_tables();
+
+        removed
+    }
+
+    /**
+     * Scales the hits of every edge (and context) by `factor`, normally
+     * between 0 and 1, so that older associations fade as new sentences are
+     * parsed; e.g. decaying by 0.99 once a day halves the weight of a
+     * sentence in about 70 days.
+     *
+     * Scaled hits are rounded up or down at random, with odds in proportion
+     * to their fractional part, so that rarely seen edges fade too, on
+     * average. Edges whose hits reach zero are removed, as with
+     * [Self::prune_edges], whose return value this returns.
+     *
+     * Fails, leaving the chain untouched, if `factor` is negative or not
+     * finite.
      */
-    pub fn compose_sentence<'a>(
-        &'a self,
+    pub fn decay_hits(&mut self, factor: f32) -> Result<usize, String> {
+        if !factor.is_finite() || factor < 0.0 {
+            return Err(format!(
+                "Decay factor must be finite and non-negative, got {}",
+                factor
+            ));
+        }
+
+        let mut rng = thread_rng();
+        let factor = factor as f64;
+
+        let mut decay = |hits: &mut usize| {
+            let scaled = *hits as f64 * factor;
+            let round_up = rng.gen_bool(scaled.fract());
+
+            *hits = scaled.floor() as usize + round_up as usize;
+        };
+
+        for edge in &mut self.edge_list {
+            decay(&mut edge.hits);
+        }
+
+        for table in [&mut self.forward_contexts, &mut self.reverse_contexts] {
+            table.retain(|_, edges| {
+                for (_, hits) in edges.iter_mut() {
+                    decay(hits);
+                }
+
+                edges.retain(|(_, hits)| *hits > 0);
+                !edges.is_empty()
+            });
+        }
+
+        Ok(self.prune_edges(1))
+    }
+
+    /// Adds a textlet to the seed words, unless it already is one.
+    fn add_word(&mut self, word: usize) {
+        if self.word_set.insert(word) {
+            self.words.push(word);
+        }
+    }
+
+    /// Rebuilds [Self::word_set] after the seed words were changed in bulk.
+    fn sync_word_set(&mut self) {
+        self.word_set = self.words.iter().copied().collect();
+    }
+
+    /// Rebuilds the lookup tables of edges by source and destination.
+    fn rebuild_edge_tables(&mut self) {
+        self.edge_keys.clear();
+        self.frequencies = vec![0; self.textlet_bag.len()];
+        self.edges = vec![Vec::new(); self.textlet_bag.len()];
+        self.reverse_edges = vec![Vec::new(); self.textlet_bag.len()];
+        self.clear_sampling_cache();
+
+        for idx in 0..self.edge_list.len() {
+            self.link_edge(idx);
+        }
+    }
+
+    /// The error for a textlet not connected to anything.
+    pub(crate) fn dead_end(&self, from: usize) -> MarkovError {
+        MarkovError::DeadEnd(
+            self.get_textlet(from)
+                .map_or_else(String::new, |textlet| <&str>::from(&textlet).to_owned()),
+        )
+    }
+
+    pub(crate) fn get_seed<T: Rng>(
+        &self,
+        seed: MarkovSeed,
+        rng: &mut T,
+    ) -> Result<usize, MarkovError> {
+        use MarkovSeed::*;
+
+        match seed {
+            Word(word) => {
+                let from = self
+                    .try_get_textlet_index(word)
+                    .or_else(|| self.seed_variant(word));
+
+                match from {
+                    Some(from) => Ok(from),
+                    None if self.seed_fallback.random => self.get_seed(Random, rng),
+                    None => Err(MarkovError::UnknownSeedWord(word.to_owned())),
+                }
+            }
+
+            Id(seed) => Ok(seed),
+
+            Begin => Ok(self.begin()),
+
+            Random => {
+                if self.words.is_empty() {
+                    return Err(MarkovError::EmptyChain);
+                }
+
+                if let Some(from) = self.random_seed(rng) {
+                    return Ok(from);
+                }
+
+                let from: usize = Uniform::new(0, self.words.len()).sample(rng);
+                Ok(self.words[from])
+            }
+        }
+    }
+
+    /**
+     * Selects the word following the current one (`from`) based om the
+     * criteria of a [MarkovSelector] (`selector`).
+     *
+     * Returns a tuple (`dest`, `inbetween`, `dest_idx`, `inbetween_idx`).
+     * The first two items can be converted into strings because MarkovToken
+     * has Into<&str>. The last two items are the corresponding internal
+     * indices, which can be reused in functions which take `usize`.
+     *
+     * `inbetween` is all of the whitespace and punctuation lying between
+     * `from` and `dest`. Simply concatenate `from` with `inbetween.into()`
+     * with `dest.into()`, in that order.
+     */
+    pub fn select_next_word(
+        &self,
         seed: MarkovSeed,
         selector: &mut dyn MarkovSelector,
-        max_len: Option<usize>,
-    ) -> Result<TokenList<'a>, String> {
-        use MarkovSeed::Id;
-        use MarkovToken::*;
+        direction: MarkovTraverseDir,
+    ) -> Result<(MarkovToken<'_>, MarkovToken<'_>, usize, usize), MarkovError> {
+        self.select_next_word_with_rng(seed, selector, direction, &mut thread_rng())
+    }
+
+    /**
+     * Selects the next word like [Self::select_next_word], drawing any random
+     * numbers from `rng`, e.g. a seeded one for reproducible results.
+     */
+    pub fn select_next_word_with_rng<R: Rng>(
+        &self,
+        seed: MarkovSeed,
+        selector: &mut dyn MarkovSelector,
+        direction: MarkovTraverseDir,
+        rng: &mut R,
+    ) -> Result<(MarkovToken<'_>, MarkovToken<'_>, usize, usize), MarkovError> {
+        let from: usize = self.get_seed(seed, rng)?;
+
+        let edges = self.adjacent(from, direction);
+
+        if edges.is_empty() {
+            return Err(self.dead_end(from));
+        }
+
+        let edge = match self.sample_with_cache(from, selector, direction, rng) {
+            Some(edge) => edge,
+            None => {
+                let edges: Vec<(usize, usize)> = edges
+                    .iter()
+                    .map(|e| (*e, self.edge_list[*e].hits))
+                    .collect();
+
+                self.select_edge(&edges, selector, direction, None, rng)
+                    .ok_or_else(|| self.dead_end(from))?
+            }
+        };
+
+        Ok(self.step(edge, direction))
+    }
+
+    /**
+     * Selects the word following (or, in reverse, preceding) a context of
+     * words, based on the criteria of a [MarkovSelector] (`selector`).
+     *
+     * Going forward, the context is the words up to and including the
+     * current one, in order; in reverse, the words from the current one on.
+     * As many of them are used as the order of this chain allows (see
+     * [Self::with_order]), backing off to fewer when they were never found
+     * together.
+     *
+     * Returns the same as [Self::select_next_word].
+     */
+    pub fn select_next_word_in_context(
+        &self,
+        context: &[usize],
+        selector: &mut dyn MarkovSelector,
+        direction: MarkovTraverseDir,
+    ) -> Result<(MarkovToken<'_>, MarkovToken<'_>, usize, usize), MarkovError> {
+        self.select_next_word_where(context, selector, direction, None, &mut thread_rng())
+    }
+
+    /**
+     * Selects the next word like [Self::select_next_word_in_context], with
+     * the weight of each edge scaled by `scale`, if given. Edges scaled to
+     * zero are never walked.
+     *
+     * Backs off to fewer words of context when none of the edges found
+     * after the whole context can be walked.
+     */
+    pub(crate) fn select_next_word_where<R: Rng>(
+        &self,
+        context: &[usize],
+        selector: &mut dyn MarkovSelector,
+        direction: MarkovTraverseDir,
+        scale: Option<&dyn Fn(&Edge) -> f32>,
+        rng: &mut R,
+    ) -> Result<(MarkovToken<'_>, MarkovToken<'_>, usize, usize), MarkovError> {
+        let edge = self.select_edge_where(context, selector, direction, scale, rng)?;
+
+        Ok(self.step(edge, direction))
+    }
+
+    /**
+     * Selects the next word like [Self::select_next_word_where], but returns
+     * the index of the edge walked to it.
+     */
+    pub(crate) fn select_edge_where<R: Rng>(
+        &self,
+        context: &[usize],
+        selector: &mut dyn MarkovSelector,
+        direction: MarkovTraverseDir,
+        scale: Option<&dyn Fn(&Edge) -> f32>,
+        rng: &mut R,
+    ) -> Result<usize, MarkovError> {
         use MarkovTraverseDir::*;
 
-        let mut rng = thread_rng();
+        let walkable = |edge: usize| match scale {
+            Some(scale) => scale(&self.edge_list[edge]) > 0.0,
+            None => true,
+        };
+
+        let (table, from) = match direction {
+            Forward => (&self.forward_contexts, context.last()),
+            Reverse => (&self.reverse_contexts, context.first()),
+        };
+
+        let from = *from.ok_or(MarkovError::EmptyContext)?;
+
+        for len in (2..=self.order.min(context.len())).rev() {
+            let key = match direction {
+                Forward => &context[context.len() - len..],
+                Reverse => &context[..len],
+            };
+
+            if let Some(edges) = table.get(key) {
+                let edges: Vec<(usize, usize)> = edges
+                    .iter()
+                    .copied()
+                    .filter(|(e, _)| walkable(*e))
+                    .collect();
+
+                // back off if the selector filtered out all of them, too
+                if let Some(edge) = self.select_edge(&edges, selector, direction, scale, rng) {
+                    return Ok(edge);
+                }
+            }
+        }
+
+        if scale.is_none() {
+            if let Some(edge) = self.sample_with_cache(from, selector, direction, rng) {
+                return Ok(edge);
+            }
+        }
+
+        let edges: Vec<(usize, usize)> = self
+            .adjacent(from, direction)
+            .iter()
+            .map(|e| (*e, self.edge_list[*e].hits))
+            .filter(|(e, _)| walkable(*e))
+            .collect();
+
+        self.select_edge(&edges, selector, direction, scale, rng)
+            .ok_or_else(|| self.dead_end(from))
+    }
 
-        if self.is_empty() {
-            return Err("Cannot compose a sentence from an empty chain".into());
+    /**
+     * Selects one of a list of (edge index, hits) pairs, and returns the
+     * index of the selected edge; or None if there are none, or if the
+     * selector filtered out all of them (see [select_weighted]).
+     *
+     * The weight of each edge is scaled by `scale`, if given, such that a
+     * smaller scale makes an edge less likely to be selected, whichever the
+     * [SelectionType].
+     */
+    fn select_edge<R: Rng>(
+        &self,
+        edges: &[(usize, usize)],
+        selector: &mut dyn MarkovSelector,
+        direction: MarkovTraverseDir,
+        scale: Option<&dyn Fn(&Edge) -> f32>,
+        rng: &mut R,
+    ) -> Option<usize> {
+        let mut weights: Vec<f32> = vec![0.0; edges.len()];
+
+        for ((edge, hits), weight) in edges
+            .iter()
+            .map(|(e, hits)| (&self.edge_list[*e], *hits))
+            .zip(weights.iter_mut())
+        {
+            *weight = selector.weight_edge(self, edge, hits);
         }
 
-        let seed = self.get_seed(seed, &mut rng)?;
+        let sel_type = selector.selection_type();
 
-        let mut sentence: LinkedList<MarkovToken<'a>> =
-            LinkedList::from([self.get_textlet(seed).unwrap()]);
+        if let Some(scale) = scale {
+            for ((e, _), weight) in edges.iter().zip(weights.iter_mut()) {
+                *weight = match sel_type {
+                    SelectionType::Lowest => *weight / scale(&self.edge_list[*e]),
+                    _ => *weight * scale(&self.edge_list[*e]),
+                };
+            }
+        }
+
+        let unfiltered = weights.clone();
+        selector.filter_weights(&mut weights);
 
-        let mut len = self.get_textlet(seed).unwrap().len();
+        let best_edge = edges[select_weighted(sel_type, &unfiltered, &weights, rng)?].0;
 
-        let mut curr_backward = seed;
-        let mut curr_forward = seed;
+        selector.feedback(&self.step(best_edge, direction).0);
 
-        let capped = max_len.is_some();
-        let max_half_len: Option<usize> = max_len.map(|x| x / 2);
+        Some(best_edge)
+    }
 
-        while curr_backward != self.begin() {
-            let (prev, punct, prvidx, _) =
-                self.select_next_word(Id(curr_backward), selector, Reverse)?;
+    /**
+     * The word an edge leads to in a direction, the punctuation before it,
+     * and their indices; as returned by [Self::select_next_word].
+     */
+    fn step(
+        &self,
+        edge: usize,
+        direction: MarkovTraverseDir,
+    ) -> (MarkovToken<'_>, MarkovToken<'_>, usize, usize) {
+        let edge = &self.edge_list[edge];
 
-            let new_len = len + punct.len() + prev.len();
+        match direction {
+            MarkovTraverseDir::Forward => (
+                edge.get_dest(self),
+                edge.get_punct(self),
+                edge.dst_idx,
+                edge.pct_idx,
+            ),
+
+            MarkovTraverseDir::Reverse => (
+                edge.get_source(self),
+                edge.get_punct(self),
+                edge.src_idx,
+                edge.pct_idx,
+            ),
+        }
+    }
+
+    /// The edges walked from a word in a direction, as indices into
+    /// `self.edge_list`.
+    pub(crate) fn adjacent(&self, from: usize, direction: MarkovTraverseDir) -> &[usize] {
+        match direction {
+            MarkovTraverseDir::Forward => self.edges.get(from),
+            MarkovTraverseDir::Reverse => self.reverse_edges.get(from),
+        }
+        .map_or(&[][..], |edges| edges.as_slice())
+    }
+
+    /**
+     * Selects one of the edges walked from a word from the sampling cache of
+     * this chain (see [Self::set_sampling_cache]), if it has one and the
+     * selector weighs edges by their hits alone, and returns its index.
+     */
+    fn sample_with_cache<R: Rng>(
+        &self,
+        from: usize,
+        selector: &mut dyn MarkovSelector,
+        direction: MarkovTraverseDir,
+        rng: &mut R,
+    ) -> Option<usize> {
+        if self.sampling.is_none() || !selector.weighs_by_hits() {
+            return None;
+        }
+
+        let edge = self.sample_edge(from, direction, rng)?;
+
+        selector.feedback(&self.step(edge, direction).0);
+
+        Some(edge)
+    }
+
+    /**
+     * The edges walked from a word in a direction, as indices into
+     * `self.edge_list`, and the sum of their hits.
+     */
+    fn edges_toward(&self, from: usize, direction: MarkovTraverseDir) -> (&[usize], usize) {
+        let edges = self.adjacent(from, direction);
+
+        let total = edges.iter().map(|e| self.edge_list[*e].hits).sum();
+
+        (edges, total)
+    }
+
+    /**
+     * The probability that the word `to` follows the word `from`, whichever
+     * the punctuation between them, based on how many times each word was
+     * found after `from`.
+     *
+     * Returns 0 if `to` was never found after `from`.
+     */
+    pub fn probability(&self, from: usize, to: usize) -> f32 {
+        let (edges, total) = self.edges_toward(from, MarkovTraverseDir::Forward);
+
+        let hits: usize = edges
+            .iter()
+            .map(|e| &self.edge_list[*e])
+            .filter(|edge| edge.dst_idx == to)
+            .map(|edge| edge.hits)
+            .sum();
+
+        hits as f32 / total.max(1) as f32
+    }
+
+    /**
+     * Lists the words found after the word `from`, as tuples (`dest`,
+     * `inbetween`, `probability`); see [Self::select_next_word] and
+     * [Self::probability].
+     *
+     * Each punctuation between the same two words is listed separately.
+     */
+    pub fn transitions(
+        &self,
+        from: usize,
+    ) -> impl Iterator<Item = (MarkovToken<'_>, MarkovToken<'_>, f32)> + '_ {
+        self.transitions_toward(from, MarkovTraverseDir::Forward)
+    }
+
+    /**
+     * Scores how likely this chain is to produce a sentence, as the sum of
+     * the log probabilities of each word following the one before it (see
+     * [Self::probability]; or the ones before it, in higher-order chains),
+     * from the beginning to the end of the sentence. Higher (closer to 0) is
+     * more likely.
+     *
+     * The sentence is split into words with this chain's [LexerConfig] (see
+     * [Self::set_lexer_config]), as it was when parsed. Words never found
+     * after the word before them, including unknown words, are penalized by [DEFAULT_UNSEEN_PENALTY] each; see
+     * [Self::score_sentence_with_penalty].
+     */
+    pub fn score_sentence(&self, sentence: &str) -> f64 {
+        self.score_sentence_with_penalty(sentence, DEFAULT_UNSEEN_PENALTY)
+    }
+
+    /**
+     * Scores how likely this chain is to produce a sentence; see
+     * [Self::score_sentence]. Words never found after the word before them
+     * add `unseen_penalty`, a log probability, to the score.
+     */
+    pub fn score_sentence_with_penalty(&self, sentence: &str, unseen_penalty: f64) -> f64 {
+        self.log_likelihood(sentence, unseen_penalty).0
+    }
+
+    /**
+     * Computes the perplexity of this chain over a corpus of sentences it has
+     * not learned from, e.g. to compare chains of different orders, or
+     * pruned differently; lower is better.
+     *
+     * The perplexity is the exponential of the negated average log
+     * probability of each word in the corpus (see [Self::score_sentence]).
+     * Returns infinity if the corpus has no words.
+     */
+    pub fn perplexity<I, S>(&self, lines: I) -> f64
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let (total, count) = lines
+            .into_iter()
+            .map(|line| self.log_likelihood(line.as_ref(), DEFAULT_UNSEEN_PENALTY))
+            .fold((0.0, 0), |(total, count), (score, words)| {
+                (total + score, count + words)
+            });
+
+        if count == 0 {
+            return f64::INFINITY;
+        }
+
+        (-total / count as f64).exp()
+    }
 
-            if capped && new_len > max_half_len.unwrap() {
-                break;
+    /**
+     * The log probability of a sentence (see [Self::score_sentence]), and
+     * how many word transitions it has.
+     *
+     * In higher-order chains, each word's probability is based on as many
+     * of the words before it as the order allows, backing off as
+     * [Self::select_next_word_in_context] does.
+     */
+    fn log_likelihood(&self, sentence: &str, unseen_penalty: f64) -> (f64, usize) {
+        let index = |token: LexedToken| match token {
+            LexedToken::Begin => Some(self.begin()),
+            LexedToken::End => Some(self.end()),
+            LexedToken::Punct(word) | LexedToken::Word(word) => self.try_get_textlet_index(word),
+        };
+
+        let triples = lex_sentence(sentence, &self.lexer_config).unwrap_or_default();
+        let count = triples.len();
+
+        // the known words walked so far, since the last unknown one
+        let mut context: Vec<usize> = vec![];
+        let mut total = 0.0;
+
+        for (src, _, dst) in triples {
+            match index(src) {
+                Some(src) if context.is_empty() => context.push(src),
+                Some(_) => {}
+                None => context.clear(),
             }
 
-            len = new_len;
+            let dst = index(dst);
+
+            let probability = match dst {
+                Some(dst) if !context.is_empty() => self.context_probability(&context, dst),
+                _ => 0.0,
+            };
 
-            sentence.push_front(punct);
+            total += if probability > 0.0 {
+                (probability as f64).ln()
+            } else {
+                unseen_penalty
+            };
 
-            if prev == Begin {
-                break;
+            match dst {
+                Some(dst) => context.push(dst),
+                None => context.clear(),
             }
+        }
+
+        (total, count)
+    }
 
-            sentence.push_front(prev);
+    /**
+     * The probability that the word `to` follows a context of words, which
+     * must not be empty; see [Self::select_next_word_in_context].
+     */
+    fn context_probability(&self, context: &[usize], to: usize) -> f32 {
+        for len in (2..=self.order.min(context.len())).rev() {
+            if let Some(edges) = self.forward_contexts.get(&context[context.len() - len..]) {
+                let total: usize = edges.iter().map(|(_, hits)| hits).sum();
+
+                let hits: usize = edges
+                    .iter()
+                    .filter(|(e, _)| self.edge_list[*e].dst_idx == to)
+                    .map(|(_, hits)| hits)
+                    .sum();
 
-            curr_backward = prvidx;
+                return hits as f32 / total.max(1) as f32;
+            }
         }
 
-        while curr_forward != self.begin() {
-            let (next, punct, nxtidx, _) =
-                self.select_next_word(Id(curr_forward), selector, Forward)?;
+        self.probability(context[context.len() - 1], to)
+    }
+
+    /**
+     * Suggests the `k` most likely words to follow (or, in reverse, precede)
+     * a word, e.g. for autocompletion, as tuples (`dest`, `inbetween`,
+     * `probability`), most likely first; see [Self::transitions].
+     */
+    pub fn suggest_next(
+        &self,
+        seed: MarkovSeed,
+        k: usize,
+        direction: MarkovTraverseDir,
+    ) -> Result<Vec<(MarkovToken<'_>, MarkovToken<'_>, f32)>, MarkovError> {
+        let from = self.get_seed(seed, &mut thread_rng())?;
+
+        let mut suggestions: Vec<_> = self.transitions_toward(from, direction).collect();
+
+        suggestions.sort_by(|a, b| b.2.total_cmp(&a.2));
+        suggestions.truncate(k);
+
+        Ok(suggestions)
+    }
+
+    /**
+     * Lists the words found after (or, in reverse, before) the word `from`;
+     * see [Self::transitions].
+     */
+    pub(crate) fn transitions_toward(
+        &self,
+        from: usize,
+        direction: MarkovTraverseDir,
+    ) -> impl Iterator<Item = (MarkovToken<'_>, MarkovToken<'_>, f32)> + '_ {
+        let (edges, total) = self.edges_toward(from, direction);
+
+        edges.iter().map(move |e| {
+            let edge = &self.edge_list[*e];
+
+            let next = match direction {
+                MarkovTraverseDir::Forward => edge.get_dest(self),
+                MarkovTraverseDir::Reverse => edge.get_source(self),
+            };
+
+            (
+                next,
+                edge.get_punct(self),
+                edge.hits as f32 / total.max(1) as f32,
+            )
+        })
+    }
+
+    /**
+     * The number of words in this chain.
+     *
+     * Includes the internal tokens [MarkovTokenOwned::Begin] and
+     * [MarkovTokenOwned::End].
+     *
+     * Should be smaller than or, in extreme cases, equal to,
+     * [Self::num_textlets()].
+     */
+    pub fn num_words(&self) -> usize {
+        self.words.len()
+    }
+
+    /**
+     * The number of textlets in this chain.
+     *
+     * Includes unique instances of whitespace or punctuation, as well as the
+     * internal tokens [MarkovTokenOwned::Begin] and [MarkovTokenOwned::End].
+     */
+    pub fn num_textlets(&self) -> usize {
+        self.textlet_bag.len()
+    }
+
+    /**
+     * The number of [Edge]s registered in this chain.
+     *
+     * Includes edges connected to the internal tokens
+     * [MarkovTokenOwned::Begin] and [MarkovTokenOwned::End].
+     */
+    pub fn num_edges(&self) -> usize {
+        self.edge_list.len()
+    }
+
+    /**
+     * An estimate of how many bytes of memory this chain takes up, for its
+     * textlets, edges and lookup tables; e.g. to pick capacity limits (see
+     * [Self::set_limits]). Metadata and any log are not counted.
+     */
+    pub fn memory_usage(&self) -> usize {
+        let strings: usize = self
+            .textlet_bag
+            .iter()
+            .map(|textlet| match textlet {
+                MarkovTokenOwned::Textlet(text) => text.len(),
+                _ => 0,
+            })
+            .sum();
+
+        let adjacency =
+            |table: &Vec<Vec<usize>>| vec_bytes(table) + table.iter().map(vec_bytes).sum::<usize>();
+
+        let contexts = |table: &ContextTable| {
+            map_bytes(table)
+                + table
+                    .iter()
+                    .map(|(context, edges)| vec_bytes(context) + vec_bytes(edges))
+                    .sum::<usize>()
+        };
+
+        let surface_forms: usize = self.surface_forms.values().map(vec_bytes).sum();
+
+        std::mem::size_of::<Self>()
+            + vec_bytes(&self.textlet_bag)
+            + strings
+            + map_bytes(&self.textlet_indices)
+            + vec_bytes(&self.words)
+            + set_bytes(&self.word_set)
+            + vec_bytes(&self.edge_list)
+            + map_bytes(&self.edge_keys)
+            + adjacency(&self.edges)
+            + adjacency(&self.reverse_edges)
+            + vec_bytes(&self.frequencies)
+            + contexts(&self.forward_contexts)
+            + contexts(&self.reverse_contexts)
+            + map_bytes(&self.surface_forms)
+            + surface_forms
+            + map_bytes(&self.capitalized)
+            + self
+                .sampling
+                .as_ref()
+                .map_or(0, SamplingCache::memory_usage)
+    }
+
+    /**
+     * Sets how this chain splits sentences into words and punctuation, when
+     * parsing them (unless given another [LexerConfig]) and scoring them.
+     *
+     * Must be set before anything is parsed; fails if the chain already has
+     * textlets.
+     */
+    pub fn set_lexer_config(&mut self, config: LexerConfig) -> Result<(), String> {
+        if self.num_textlets() > 2 {
+            return Err("Cannot change the lexer config of a chain which is not empty".to_owned());
+        }
+
+        self.lexer_config = config;
+
+        Ok(())
+    }
+
+    /// How this chain splits sentences into words and punctuation. See
+    /// [Self::set_lexer_config].
+    pub fn lexer_config(&self) -> &LexerConfig {
+        &self.lexer_config
+    }
+
+    /// Runs `body` with this chain's [LexerConfig], set aside so that the
+    /// chain can still be borrowed mutably.
+    pub(crate) fn with_own_lexer_config<T>(
+        &mut self,
+        body: impl FnOnce(&mut Self, &LexerConfig) -> T,
+    ) -> T {
+        let config = std::mem::take(&mut self.lexer_config);
+        let result = body(self, &config);
+        self.lexer_config = config;
+
+        result
+    }
+
+    /**
+     * Parse a sentence, registering textlets and edges
+     * for it, with this chain's [LexerConfig].
+     */
+    pub fn parse_sentence(&mut self, sentence: &str) {
+        self.with_own_lexer_config(|chain, config| {
+            chain.parse_sentence_with_config(sentence, config)
+        });
+    }
+
+    /**
+     * Parse a sentence like [Self::parse_sentence], splitting it into words
+     * and punctuation according to a [LexerConfig].
+     */
+    pub fn parse_sentence_with_config(&mut self, sentence: &str, config: &LexerConfig) {
+        self.parse_weighted(sentence, config, 1);
+    }
+
+    /**
+     * Parse a sentence as if it had been parsed `weight` times, e.g. to
+     * ingest a deduplicated corpus along with the count of each sentence,
+     * or to give some sources more importance than others.
+     *
+     * Does nothing if `weight` is zero.
+     */
+    pub fn parse_sentence_weighted(&mut self, sentence: &str, weight: usize) {
+        self.with_own_lexer_config(|chain, config| chain.parse_weighted(sentence, config, weight));
+    }
+
+    pub(crate) fn parse_weighted(&mut self, sentence: &str, config: &LexerConfig, weight: usize) {
+        if weight == 0 {
+            return;
+        }
+
+        let to_register = match lex_sentence(sentence, config) {
+            Some(to_register) => to_register,
+            None => return,
+        };
+
+        self.sentences_parsed += 1;
+
+        if let Some(log) = &mut self.log {
+            log.append(sentence, weight);
+        }
+
+        let mut words = vec![];
+        let mut edges = vec![];
+
+        for (src, pct, dst) in to_register {
+            if let LexedToken::Word(word) = dst {
+                self.add_surface_form(word, weight);
+            }
 
-            let new_len = len + punct.len() + next.len();
+            let src = self.ensure_textlet_from_token(src);
+            let pct = self.ensure_textlet_from_token(pct);
+            let dst = self.ensure_textlet_from_token(dst);
 
-            if capped && new_len > max_len.unwrap() {
-                break;
+            if words.is_empty() {
+                words.push(src);
             }
 
-            len = new_len;
+            words.push(dst);
+            edges.push(self.register_edge_hits(src, dst, pct, weight));
+        }
+
+        if self.order > 1 {
+            self.register_contexts(&words, &edges, weight);
+        }
 
-            sentence.push_back(punct);
+        self.enforce_limits();
+    }
+
+    /**
+     * Splits some text into sentences (see [split_sentences]) and parses
+     * each of them, so that the end of one sentence is not connected to the
+     * start of the next. Returns how many sentences were parsed.
+     */
+    pub fn parse_text(&mut self, text: &str) -> usize {
+        self.with_own_lexer_config(|chain, config| chain.parse_text_with_config(text, config))
+    }
+
+    /**
+     * Parses some text like [Self::parse_text], splitting each sentence
+     * according to a [LexerConfig].
+     */
+    pub fn parse_text_with_config(&mut self, text: &str, config: &LexerConfig) -> usize {
+        let sentences = split_sentences(text);
 
-            if next == End {
-                break;
+        for sentence in &sentences {
+            self.parse_sentence_with_config(sentence, config);
+        }
+
+        sentences.len()
+    }
+
+    /**
+     * Parses every line read from a reader as a sentence, one at a time, so
+     * that large files need not be loaded into memory at once.
+     *
+     * Lines are trimmed, and empty lines are skipped. Returns how many
+     * sentences were parsed, or the first read error.
+     */
+    pub fn parse_reader<R: BufRead>(&mut self, mut reader: R) -> Result<usize, String> {
+        let mut line = String::new();
+        let mut parsed = 0;
+
+        loop {
+            line.clear();
+
+            if reader.read_line(&mut line).map_err(|err| err.to_string())? == 0 {
+                return Ok(parsed);
             }
 
-            sentence.push_back(next);
+            let sentence = line.trim();
 
-            curr_forward = nxtidx;
+            if !sentence.is_empty() {
+                self.parse_sentence(sentence);
+                parsed += 1;
+            }
         }
+    }
+
+    /// Get the textlet identifier for [MarkovTokenOwned::Begin].
+    pub fn begin(&self) -> usize {
+        BEGIN
+    }
+
+    /// Get the textlet identifier for [MarkovTokenOwned::End].
+    pub fn end(&self) -> usize {
+        END
+    }
 
-        Ok(TokenList(sentence))
+    /// Returns whether the chain is empty – has no words in it.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /**
+     * Composes a sentence by traversing this chain forward and backward from a
+     * given 'seed word'.
+     */
+    pub fn compose_sentence<'a>(
+        &'a self,
+        seed: MarkovSeed,
+        selector: &mut dyn MarkovSelector,
+        max_len: Option<usize>,
+    ) -> Result<TokenList<'a>, MarkovError> {
+        self.compose_sentence_directed(seed, ComposeDirection::Both, selector, max_len)
+    }
+
+    /**
+     * Composes a sentence like [Self::compose_sentence], drawing any random
+     * numbers from `rng`; with the same seeded RNG and selector, the same
+     * sentence is composed every time.
+     */
+    pub fn compose_sentence_with_rng<'a, R: Rng>(
+        &'a self,
+        seed: MarkovSeed,
+        selector: &mut dyn MarkovSelector,
+        max_len: Option<usize>,
+        rng: &mut R,
+    ) -> Result<TokenList<'a>, MarkovError> {
+        let options = ComposeOptions {
+            max_len,
+            ..Default::default()
+        };
+
+        self.compose_with_rng(seed, selector, &options, rng)
+    }
+
+    /**
+     * Composes a sentence by traversing this chain from a given 'seed word'
+     * in the given directions only, e.g. forward to complete a sentence
+     * which starts with the seed.
+     *
+     * With [MarkovSeed::Begin], the sentence is composed forward from its
+     * beginning.
+     */
+    pub fn compose_sentence_directed<'a>(
+        &'a self,
+        seed: MarkovSeed,
+        direction: ComposeDirection,
+        selector: &mut dyn MarkovSelector,
+        max_len: Option<usize>,
+    ) -> Result<TokenList<'a>, MarkovError> {
+        let options = ComposeOptions {
+            direction,
+            max_len,
+            ..Default::default()
+        };
+
+        self.compose(seed, selector, &options)
+    }
+}
+
+/// The bytes allocated by a vector.
+pub(crate) fn vec_bytes<T>(vec: &Vec<T>) -> usize {
+    vec.capacity() * std::mem::size_of::<T>()
+}
+
+/// An estimate of the bytes allocated by a hash map.
+fn map_bytes<K, V>(map: &HashMap<K, V>) -> usize {
+    // a control byte per bucket
+    map.capacity() * (std::mem::size_of::<(K, V)>() + 1)
+}
+
+/// An estimate of the bytes allocated by a hash set.
+fn set_bytes<T>(set: &HashSet<T>) -> usize {
+    // a control byte per bucket
+    set.capacity() * (std::mem::size_of::<T>() + 1)
+}
+
+/**
+ * Lexes a sentence into (word, punctuation, next word) triples, from the
+ * begin token to the end token. Returns None if the sentence is empty or
+ * cut short.
+ */
+fn lex_sentence<'a>(
+    sentence: &'a str,
+    config: &'a LexerConfig,
+) -> Option<Vec<(LexedToken<'a>, LexedToken<'a>, LexedToken<'a>)>> {
+    let mut lexer = Lexer::new_with_config(sentence, config);
+    let mut curr_token = lexer.next();
+
+    let mut triples = vec![];
+
+    if sentence.is_empty() {
+        return None;
+    }
+
+    loop {
+        if curr_token.is_none() {
+            panic!("Found a none token prematurely!");
+        }
+
+        let token = curr_token.unwrap();
+
+        let punct = lexer.next()?;
+        let next_token = lexer.next()?;
+
+        triples.push((token, punct, next_token.clone()));
+
+        if next_token == LexedToken::End {
+            break;
+        }
+
+        curr_token = Some(next_token);
+    }
+
+    Some(triples)
+}
+
+/// Adds hits to an edge in a context, registering either if needed.
+fn add_context_hits(table: &mut ContextTable, context: Vec<usize>, edge: usize, hits: usize) {
+    let edges = table.entry(context).or_default();
+
+    match edges.iter_mut().find(|(e, _)| *e == edge) {
+        Some((_, count)) => *count += hits,
+        None => edges.push((edge, hits)),
+    }
+}
+
+/// The edges of a textlet in an adjacency table, growing it if needed.
+fn adjacent_mut(table: &mut Vec<Vec<usize>>, textlet: usize) -> &mut Vec<usize> {
+    if table.len() <= textlet {
+        table.resize_with(textlet + 1, Vec::new);
+    }
+
+    &mut table[textlet]
+}
+
+/**
+ * Picks an item according to the weights a [MarkovSelector] gave them, as per
+ * its [SelectionType]. Returns the index of the picked item.
+ *
+ * `unfiltered` are the weights before [MarkovSelector::filter_weights]; the
+ * items it zeroed out are never picked, even when every other item weighs
+ * zero too. Returns None if no item is left to pick.
+ */
+pub(crate) fn select_weighted<R: Rng>(
+    sel_type: SelectionType,
+    unfiltered: &[f32],
+    weights: &[f32],
+    rng: &mut R,
+) -> Option<usize> {
+    let candidates: Vec<(usize, f32)> = weights
+        .iter()
+        .zip(unfiltered)
+        .enumerate()
+        .filter(|(_, (weight, before))| **weight != 0.0 || **before == 0.0)
+        .map(|(i, (weight, _))| (i, *weight))
+        .collect();
+
+    let indexed = candidates.iter().copied();
+
+    match sel_type {
+        SelectionType::Lowest => indexed
+            .reduce(|ewc, ewn| if ewc.1 < ewn.1 { ewc } else { ewn })
+            .map(|ew| ew.0),
+
+        SelectionType::Highest => indexed
+            .reduce(|ewc, ewn| if ewc.1 > ewn.1 { ewc } else { ewn })
+            .map(|ew| ew.0),
+
+        SelectionType::WeightedRandom => {
+            let (last, _) = *candidates.last()?;
+            let total: f32 = candidates.iter().map(|ew| ew.1).sum();
+
+            if total <= 0.0 {
+                return Some(candidates[Uniform::new(0, candidates.len()).sample(rng)].0);
+            }
+            let pick = Uniform::new(0.0_f32, total).sample(rng);
+
+            let mut curr = 0.0;
+
+            for (i, weight) in indexed {
+                curr += weight;
+
+                if curr > pick {
+                    return Some(i);
+                }
+            }
+
+            Some(last)
+        }
     }
 }
@@ -0,0 +1,163 @@
+/*!
+ * Case-insensitive chains.
+ *
+ * With [CaseFolding] enabled, words which only differ in case, such as
+ * "Hello" and "hello", are the same word in the chain; they are stored in
+ * lowercase, along with every form in which they were found (their surface
+ * forms), and how many times.
+ */
+
+use super::body::MarkovChain;
+use super::token::{MarkovToken, TokenList};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Whether and how a chain matches words case-insensitively.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CaseFolding {
+    /// Words which differ in case are different words.
+    #[default]
+    Off,
+
+    /// Words are matched case-insensitively, and composed in the form in
+    /// which they were found the most.
+    MostFrequent,
+
+    /// Words are matched case-insensitively, and composed in lowercase,
+    /// except for the first word of a sentence, which is capitalized.
+    CapitalizeFirst,
+}
+
+/// Capitalizes the first letter of a word.
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Whether a textlet is punctuation (or whitespace) rather than a word.
+fn is_punct(text: &str) -> bool {
+    text.chars()
+        .all(|c| c.is_ascii_punctuation() || c.is_whitespace())
+}
+
+impl MarkovChain {
+    /**
+     * Sets whether and how this chain matches words case-insensitively.
+     *
+     * Must be set before anything is parsed; fails if the chain already has
+     * textlets.
+     */
+    pub fn set_case_folding(&mut self, case_folding: CaseFolding) -> Result<(), String> {
+        if self.num_textlets() > 2 {
+            return Err("Cannot change the case folding of a chain which is not empty".to_owned());
+        }
+
+        self.case_folding = case_folding;
+
+        Ok(())
+    }
+
+    /// Whether and how this chain matches words case-insensitively.
+    pub fn case_folding(&self) -> CaseFolding {
+        self.case_folding
+    }
+
+    /**
+     * Lists the forms in which a word was found, and how many times each,
+     * most frequent first. Empty unless case folding is enabled.
+     */
+    pub fn surface_forms(&self, word: &str) -> Vec<(&str, usize)> {
+        self.surface_forms
+            .get(&*self.fold_case(word))
+            .map(|forms| forms.iter().map(|(form, hits)| (&**form, *hits)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Folds the case of a textlet, if case folding is enabled.
+    pub(crate) fn fold_case<'a>(&self, word: &'a str) -> Cow<'a, str> {
+        match self.case_folding {
+            CaseFolding::Off => Cow::Borrowed(word),
+            _ => Cow::Owned(word.to_lowercase()),
+        }
+    }
+
+    /**
+     * Registers that a word was found in some form, `hits` times, if case
+     * folding is enabled. The word must already be a textlet.
+     */
+    pub(crate) fn add_surface_form(&mut self, form: &str, hits: usize) {
+        if self.case_folding == CaseFolding::Off {
+            return;
+        }
+
+        let folded: Arc<str> = Arc::from(self.fold_case(form));
+
+        if self.case_folding == CaseFolding::CapitalizeFirst {
+            self.capitalized
+                .entry(folded.clone())
+                .or_insert_with(|| Arc::from(capitalize(&folded)));
+        }
+
+        let forms = self.surface_forms.entry(folded).or_default();
+
+        let mut idx = match forms.iter().position(|(f, _)| &**f == form) {
+            Some(idx) => idx,
+            None => {
+                forms.push((Arc::from(form), 0));
+                forms.len() - 1
+            }
+        };
+
+        forms[idx].1 += hits;
+
+        // keep the most frequent form first
+        while idx > 0 && forms[idx].1 > forms[idx - 1].1 {
+            forms.swap(idx, idx - 1);
+            idx -= 1;
+        }
+    }
+
+    /// The form in which a folded textlet should be composed.
+    pub(crate) fn surface_form<'a>(&'a self, folded: &'a str) -> &'a str {
+        match self.case_folding {
+            CaseFolding::MostFrequent => self
+                .surface_forms
+                .get(folded)
+                .and_then(|forms| forms.first())
+                .map_or(folded, |(form, _)| form),
+
+            _ => folded,
+        }
+    }
+
+    /**
+     * Capitalizes the first word of a composed sentence, if case folding is
+     * [CaseFolding::CapitalizeFirst] and the sentence was walked back to its
+     * beginning.
+     */
+    pub(crate) fn capitalize_first<'a>(&'a self, sentence: &mut TokenList<'a>) {
+        if self.case_folding != CaseFolding::CapitalizeFirst {
+            return;
+        }
+
+        let mut tokens = sentence.0.iter_mut();
+
+        // sentences walked back to their beginning start with punctuation,
+        // often empty, rather than with a word
+        match tokens.next() {
+            Some(MarkovToken::Textlet(first)) if is_punct(first) => {}
+            _ => return,
+        }
+
+        if let Some(MarkovToken::Textlet(word)) = tokens.next() {
+            if let Some(capitalized) = self.capitalized.get(*word) {
+                *word = capitalized;
+            }
+        }
+    }
+}
@@ -0,0 +1,646 @@
+/*!
+ * A compact, read-only on-disk format for [MarkovChain]s.
+ *
+ * Unlike the serde format (see the `serde` feature), a chain in this format
+ * can be used as it lies, without deserializing it first; a [CompactChain]
+ * reads textlets and edges straight out of a byte slice. Combined with a
+ * memory map (see `MappedChain`, with the `mmap` feature), even very large
+ * chains can be loaded almost instantly, and only the parts which are
+ * actually walked are ever read from disk.
+ *
+ * The format, with all numbers in little endian, is as follows:
+ *
+ * * a header: the magic bytes `WMKC`, the format version (u32), and the
+ *   number of textlets, seed words and edges, and the length of the string
+ *   table (u64 each);
+ * * the offset of every textlet in the string table, plus the end of the
+ *   last one (u64 each);
+ * * every textlet other than the begin and end tokens, sorted by their
+ *   text, for lookups (u32 each);
+ * * the seed words (u32 each);
+ * * the edges, sorted by the textlet they come from; each is the source,
+ *   destination and punctuation textlets (u32 each), and the hits (u64);
+ * * for every textlet, where its outgoing edges start, plus the end of the
+ *   last ones (u32 each);
+ * * the edges again, sorted by the textlet they lead into (u32 each);
+ * * for every textlet, where its incoming edges start in the above, plus
+ *   the end of the last ones (u32 each);
+ * * the string table; the text of all textlets, in UTF-8.
+ *
+ * Textlets keep the indices they have in the original chain, and the first
+ * two are the begin and end tokens.
+ *
+ * Only the first-order graph is stored; the contexts of higher-order chains
+ * (see [MarkovChain::with_order]) are not.
+ */
+
+use super::body::{select_weighted, Edge, MarkovChain, MarkovSeed, MarkovTraverseDir};
+use super::compose::{compose_around, ComposeOptions};
+use super::error::MarkovError;
+use super::selectors::interface::MarkovSelector;
+use super::token::*;
+use rand::{distributions::Uniform, prelude::*};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The magic bytes which start a compact chain.
+pub const COMPACT_MAGIC: &[u8; 4] = b"WMKC";
+
+/// The version of the compact chain format.
+pub const COMPACT_FORMAT_VERSION: u32 = 1;
+
+const HEADER_SIZE: usize = 40;
+const EDGE_SIZE: usize = 20;
+
+fn u32_at(section: &[u8], index: usize) -> usize {
+    u32::from_le_bytes(section[index * 4..index * 4 + 4].try_into().unwrap()) as usize
+}
+
+fn u64_at(section: &[u8], index: usize) -> u64 {
+    u64::from_le_bytes(section[index * 8..index * 8 + 8].try_into().unwrap())
+}
+
+fn to_u32(value: usize, what: &str) -> Result<u32, String> {
+    u32::try_from(value).map_err(|_| format!("Too many {} for the compact format", what))
+}
+
+/**
+ * A [MarkovChain] in the compact format, read directly from a byte slice.
+ *
+ * Read-only; to keep training a chain, convert it back with
+ * [Self::to_chain].
+ */
+#[derive(Clone, Copy)]
+pub struct CompactChain<'a> {
+    num_textlets: usize,
+    num_words: usize,
+    num_edges: usize,
+
+    textlet_offsets: &'a [u8],
+    sorted_textlets: &'a [u8],
+    words: &'a [u8],
+    edges: &'a [u8],
+    forward_offsets: &'a [u8],
+    reverse_edges: &'a [u8],
+    reverse_offsets: &'a [u8],
+    strings: &'a [u8],
+}
+
+impl<'a> CompactChain<'a> {
+    /**
+     * Reads a compact chain from a byte slice.
+     *
+     * Only the header and the size of the data are checked here, so that
+     * this is fast no matter how large the chain is; corrupt data is
+     * reported later on, as it is read.
+     */
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<CompactChain<'a>, String> {
+        if bytes.len() < HEADER_SIZE || &bytes[0..4] != COMPACT_MAGIC {
+            return Err("Not a compact Markov chain".to_owned());
+        }
+
+        let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+
+        if version != COMPACT_FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported compact chain format version {}",
+                version
+            ));
+        }
+
+        let header = &bytes[8..HEADER_SIZE];
+        let count = |i: usize| {
+            usize::try_from(u64_at(header, i)).map_err(|_| "Compact chain is too large".to_owned())
+        };
+
+        let (num_textlets, num_words, num_edges, string_len) =
+            (count(0)?, count(1)?, count(2)?, count(3)?);
+
+        if num_textlets < 2 {
+            return Err("Compact chain is missing the begin and end tokens".to_owned());
+        }
+
+        let sizes = [
+            (num_textlets + 1).checked_mul(8),
+            (num_textlets - 2).checked_mul(4),
+            num_words.checked_mul(4),
+            num_edges.checked_mul(EDGE_SIZE),
+            (num_textlets + 1).checked_mul(4),
+            num_edges.checked_mul(4),
+            (num_textlets + 1).checked_mul(4),
+            Some(string_len),
+        ];
+
+        let mut sections: [&'a [u8]; 8] = [&[]; 8];
+        let mut rest = &bytes[HEADER_SIZE..];
+
+        for (section, size) in sections.iter_mut().zip(sizes) {
+            let size = size
+                .filter(|size| *size <= rest.len())
+                .ok_or_else(|| "Compact chain is truncated".to_owned())?;
+
+            (*section, rest) = rest.split_at(size);
+        }
+
+        if !rest.is_empty() {
+            return Err("Compact chain has trailing data".to_owned());
+        }
+
+        let [textlet_offsets, sorted_textlets, words, edges, forward_offsets, reverse_edges, reverse_offsets, strings] =
+            sections;
+
+        Ok(CompactChain {
+            num_textlets,
+            num_words,
+            num_edges,
+
+            textlet_offsets,
+            sorted_textlets,
+            words,
+            edges,
+            forward_offsets,
+            reverse_edges,
+            reverse_offsets,
+            strings,
+        })
+    }
+
+    /// The number of words in this chain. See [MarkovChain::num_words].
+    pub fn num_words(&self) -> usize {
+        self.num_words
+    }
+
+    /// The number of textlets in this chain. See [MarkovChain::num_textlets].
+    pub fn num_textlets(&self) -> usize {
+        self.num_textlets
+    }
+
+    /// The number of edges in this chain. See [MarkovChain::num_edges].
+    pub fn num_edges(&self) -> usize {
+        self.num_edges
+    }
+
+    /// Returns whether the chain is empty – has no words in it.
+    pub fn is_empty(&self) -> bool {
+        self.num_words == 0
+    }
+
+    /// Get the textlet identifier for [MarkovToken::Begin].
+    pub fn begin(&self) -> usize {
+        0
+    }
+
+    /// Get the textlet identifier for [MarkovToken::End].
+    pub fn end(&self) -> usize {
+        1
+    }
+
+    /**
+     * Gets the [MarkovToken] of a textlet by its index.
+     *
+     * Returns None if there is no such textlet, or if its text is corrupt.
+     */
+    pub fn get_textlet(&self, index: usize) -> Option<MarkovToken<'a>> {
+        match index {
+            0 => Some(MarkovToken::Begin),
+            1 => Some(MarkovToken::End),
+            _ if index >= self.num_textlets => None,
+
+            _ => {
+                let start = usize::try_from(u64_at(self.textlet_offsets, index)).ok()?;
+                let end = usize::try_from(u64_at(self.textlet_offsets, index + 1)).ok()?;
+
+                let text = std::str::from_utf8(self.strings.get(start..end)?).ok()?;
+
+                Some(MarkovToken::Textlet(text))
+            }
+        }
+    }
+
+    fn textlet(&self, index: usize) -> Result<MarkovToken<'a>, MarkovError> {
+        self.get_textlet(index)
+            .ok_or_else(|| MarkovError::Corrupt(format!("Textlet {} is missing or corrupt", index)))
+    }
+
+    /**
+     * Tries to get the index of a textlet in this chain.
+     *
+     * If the textlet is not registered, returns None.
+     */
+    pub fn try_get_textlet_index(&self, word: &str) -> Option<usize> {
+        let (mut low, mut high) = (0, self.num_textlets - 2);
+
+        while low < high {
+            let middle = (low + high) / 2;
+            let index = u32_at(self.sorted_textlets, middle);
+
+            let text: &str = match self.get_textlet(index)? {
+                MarkovToken::Textlet(text) => text,
+                _ => return None,
+            };
+
+            match text.cmp(word) {
+                std::cmp::Ordering::Equal => return Some(index),
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+            }
+        }
+
+        None
+    }
+
+    fn edge(&self, index: usize) -> Result<Edge, MarkovError> {
+        let record = self
+            .edges
+            .get(index * EDGE_SIZE..(index + 1) * EDGE_SIZE)
+            .ok_or_else(|| MarkovError::Corrupt(format!("Edge {} is out of bounds", index)))?;
+
+        let edge = Edge {
+            src_idx: u32_at(record, 0),
+            dst_idx: u32_at(record, 1),
+            pct_idx: u32_at(record, 2),
+            hits: u64_at(&record[12..], 0) as usize,
+            last_seen: 0,
+        };
+
+        if [edge.src_idx, edge.dst_idx, edge.pct_idx]
+            .iter()
+            .any(|i| *i >= self.num_textlets)
+        {
+            return Err(MarkovError::Corrupt(format!("Edge {} is corrupt", index)));
+        }
+
+        Ok(edge)
+    }
+
+    fn edges_of(
+        &self,
+        from: usize,
+        direction: MarkovTraverseDir,
+    ) -> Result<Vec<Edge>, MarkovError> {
+        if from >= self.num_textlets {
+            return Err(MarkovError::Corrupt(format!(
+                "Textlet {} is out of bounds",
+                from
+            )));
+        }
+
+        let offsets = match direction {
+            MarkovTraverseDir::Forward => self.forward_offsets,
+            MarkovTraverseDir::Reverse => self.reverse_offsets,
+        };
+
+        let (start, end) = (u32_at(offsets, from), u32_at(offsets, from + 1));
+
+        if start > end || end > self.num_edges {
+            return Err(MarkovError::Corrupt(format!(
+                "Edges of textlet {} are corrupt",
+                from
+            )));
+        }
+
+        (start..end)
+            .map(|i| match direction {
+                MarkovTraverseDir::Forward => self.edge(i),
+                MarkovTraverseDir::Reverse => self.edge(u32_at(self.reverse_edges, i)),
+            })
+            .collect()
+    }
+
+    fn get_seed<T: Rng>(&self, seed: MarkovSeed, rng: &mut T) -> Result<usize, MarkovError> {
+        match seed {
+            MarkovSeed::Word(seed) => self
+                .try_get_textlet_index(seed)
+                .ok_or_else(|| MarkovError::UnknownSeedWord(seed.to_owned())),
+
+            MarkovSeed::Id(seed) => Ok(seed),
+
+            MarkovSeed::Begin => Ok(self.begin()),
+
+            MarkovSeed::Random => {
+                let from: usize = Uniform::new(0, self.num_words).sample(rng);
+                Ok(u32_at(self.words, from))
+            }
+        }
+    }
+
+    /**
+     * Selects the word following the current one (`from`) based on the
+     * criteria of a [MarkovSelector] (`selector`).
+     *
+     * See [MarkovChain::select_next_word].
+     */
+    pub fn select_next_word(
+        &self,
+        seed: MarkovSeed,
+        selector: &mut dyn MarkovSelector,
+        direction: MarkovTraverseDir,
+    ) -> Result<(MarkovToken<'a>, MarkovToken<'a>, usize, usize), MarkovError> {
+        self.select_next_word_with_rng(seed, selector, direction, &mut thread_rng())
+    }
+
+    /**
+     * Selects the next word like [Self::select_next_word], drawing any random
+     * numbers from `rng`. See [MarkovChain::select_next_word_with_rng].
+     */
+    pub fn select_next_word_with_rng<R: Rng>(
+        &self,
+        seed: MarkovSeed,
+        selector: &mut dyn MarkovSelector,
+        direction: MarkovTraverseDir,
+        rng: &mut R,
+    ) -> Result<(MarkovToken<'a>, MarkovToken<'a>, usize, usize), MarkovError> {
+        let from = self.get_seed(seed, rng)?;
+        let edges = self.edges_of(from, direction)?;
+
+        if edges.is_empty() {
+            return Err(MarkovError::DeadEnd(
+                self.get_textlet(from)
+                    .map_or_else(String::new, |textlet| <&str>::from(&textlet).to_owned()),
+            ));
+        }
+
+        let mut weights: Vec<f32> = Vec::with_capacity(edges.len());
+
+        for edge in &edges {
+            weights.push(selector.weight(
+                &self.textlet(edge.src_idx)?,
+                &self.textlet(edge.dst_idx)?,
+                &self.textlet(edge.pct_idx)?,
+                edge.hits,
+            ));
+        }
+
+        let sel_type = selector.selection_type();
+        let unfiltered = weights.clone();
+        selector.filter_weights(&mut weights);
+
+        let best_edge = match select_weighted(sel_type, &unfiltered, &weights, rng) {
+            Some(idx) => &edges[idx],
+            None => {
+                return Err(MarkovError::DeadEnd(
+                    self.get_textlet(from)
+                        .map_or_else(String::new, |textlet| <&str>::from(&textlet).to_owned()),
+                ))
+            }
+        };
+
+        let next_idx = match direction {
+            MarkovTraverseDir::Forward => best_edge.dst_idx,
+            MarkovTraverseDir::Reverse => best_edge.src_idx,
+        };
+
+        let next = self.textlet(next_idx)?;
+
+        selector.feedback(&next);
+
+        Ok((
+            next,
+            self.textlet(best_edge.pct_idx)?,
+            next_idx,
+            best_edge.pct_idx,
+        ))
+    }
+
+    /**
+     * Composes a sentence by traversing this chain forward and backward from a
+     * given 'seed word'. See [MarkovChain::compose_sentence].
+     */
+    pub fn compose_sentence(
+        &self,
+        seed: MarkovSeed,
+        selector: &mut dyn MarkovSelector,
+        max_len: Option<usize>,
+    ) -> Result<TokenList<'a>, MarkovError> {
+        self.compose_sentence_with_rng(seed, selector, max_len, &mut thread_rng())
+    }
+
+    /**
+     * Composes a sentence like [Self::compose_sentence], drawing any random
+     * numbers from `rng`. See [MarkovChain::compose_sentence_with_rng].
+     */
+    pub fn compose_sentence_with_rng<R: Rng>(
+        &self,
+        seed: MarkovSeed,
+        selector: &mut dyn MarkovSelector,
+        max_len: Option<usize>,
+        rng: &mut R,
+    ) -> Result<TokenList<'a>, MarkovError> {
+        if self.is_empty() {
+            return Err(MarkovError::EmptyChain);
+        }
+
+        let seed = self.get_seed(seed, rng)?;
+        let seed_token = self.textlet(seed)?;
+
+        selector.reset(if seed_token == MarkovToken::Begin {
+            MarkovTraverseDir::Forward
+        } else {
+            MarkovTraverseDir::Reverse
+        });
+        selector.feedback(&seed_token);
+
+        compose_around(
+            seed,
+            seed_token,
+            &ComposeOptions {
+                max_len,
+                ..Default::default()
+            },
+            |context, selection| {
+                let direction = selection.direction;
+
+                let from = match direction {
+                    MarkovTraverseDir::Forward => context[context.len() - 1],
+                    MarkovTraverseDir::Reverse => context[0],
+                };
+
+                selector.prepare(selection);
+
+                self.select_next_word_with_rng(MarkovSeed::Id(from), selector, direction, rng)
+            },
+        )
+        .map(|(sentence, _)| sentence)
+    }
+
+    /**
+     * Reads this whole chain into a [MarkovChain], e.g. to keep training
+     * it.
+     */
+    pub fn to_chain(&self) -> Result<MarkovChain, String> {
+        let textlets = (0..self.num_textlets)
+            .map(|i| {
+                Ok(match self.textlet(i)? {
+                    MarkovToken::Begin => MarkovTokenOwned::Begin,
+                    MarkovToken::End => MarkovTokenOwned::End,
+                    MarkovToken::Textlet(text) => MarkovTokenOwned::Textlet(Arc::from(text)),
+                })
+            })
+            .collect::<Result<Vec<_>, MarkovError>>()?;
+
+        let words = (0..self.num_words).map(|i| u32_at(self.words, i)).collect();
+
+        let edges = (0..self.num_edges)
+            .map(|i| self.edge(i))
+            .collect::<Result<Vec<_>, MarkovError>>()?;
+
+        MarkovChain::from_parts(textlets, words, edges)
+    }
+}
+
+impl MarkovChain {
+    /**
+     * Writes this chain, in the compact format, to a writer. See
+     * [CompactChain].
+     */
+    pub fn write_compact<W: Write>(&self, mut writer: W) -> Result<(), String> {
+        let num_textlets = self.num_textlets();
+        let num_edges = self.edge_list.len();
+
+        to_u32(num_textlets, "textlets")?;
+        to_u32(num_edges, "edges")?;
+
+        let texts: Vec<&str> = self.textlet_bag.iter().map(<&str>::from).collect();
+
+        let mut textlet_offsets = Vec::with_capacity(num_textlets + 1);
+        let mut offset = 0_u64;
+
+        for text in &texts {
+            textlet_offsets.push(offset);
+            offset += text.len() as u64;
+        }
+
+        textlet_offsets.push(offset);
+
+        let mut sorted_textlets: Vec<usize> = (2..num_textlets).collect();
+        sorted_textlets.sort_by_key(|i| texts[*i]);
+
+        let mut forward: Vec<usize> = (0..num_edges).collect();
+        forward.sort_by_key(|i| self.edge_list[*i].src_idx);
+
+        // where each edge ends up once sorted by source
+        let mut position = vec![0; num_edges];
+
+        for (new, old) in forward.iter().enumerate() {
+            position[*old] = new;
+        }
+
+        let mut reverse: Vec<usize> = (0..num_edges).collect();
+        reverse.sort_by_key(|i| self.edge_list[*i].dst_idx);
+
+        let offsets_by = |key: &dyn Fn(&Edge) -> usize| {
+            let mut offsets = vec![0_u32; num_textlets + 1];
+
+            for edge in &self.edge_list {
+                offsets[key(edge) + 1] += 1;
+            }
+
+            for i in 0..num_textlets {
+                offsets[i + 1] += offsets[i];
+            }
+
+            offsets
+        };
+
+        let forward_offsets = offsets_by(&|edge| edge.src_idx);
+        let reverse_offsets = offsets_by(&|edge| edge.dst_idx);
+
+        let mut put = |bytes: &[u8]| writer.write_all(bytes).map_err(|err| err.to_string());
+
+        put(COMPACT_MAGIC)?;
+        put(&COMPACT_FORMAT_VERSION.to_le_bytes())?;
+
+        for count in [num_textlets, self.words.len(), num_edges] {
+            put(&(count as u64).to_le_bytes())?;
+        }
+
+        put(&offset.to_le_bytes())?;
+
+        for offset in textlet_offsets {
+            put(&offset.to_le_bytes())?;
+        }
+
+        for index in sorted_textlets
+            .into_iter()
+            .chain(self.words.iter().copied())
+        {
+            put(&(index as u32).to_le_bytes())?;
+        }
+
+        for edge in forward.iter().map(|i| &self.edge_list[*i]) {
+            for index in [edge.src_idx, edge.dst_idx, edge.pct_idx] {
+                put(&(index as u32).to_le_bytes())?;
+            }
+
+            put(&(edge.hits as u64).to_le_bytes())?;
+        }
+
+        for offset in forward_offsets {
+            put(&offset.to_le_bytes())?;
+        }
+
+        for edge in reverse {
+            put(&(position[edge] as u32).to_le_bytes())?;
+        }
+
+        for offset in reverse_offsets {
+            put(&offset.to_le_bytes())?;
+        }
+
+        for text in texts {
+            put(text.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Writes this chain, in the compact format, to a file. See
+     * [Self::write_compact].
+     */
+    pub fn save_compact_file<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let file = File::create(path).map_err(|err| err.to_string())?;
+        let mut writer = BufWriter::new(file);
+
+        self.write_compact(&mut writer)?;
+        writer.flush().map_err(|err| err.to_string())
+    }
+}
+
+/**
+ * A compact chain file, mapped into memory.
+ *
+ * Only available with the `mmap` feature.
+ */
+#[cfg(feature = "mmap")]
+pub struct MappedChain {
+    mmap: memmap2::Mmap,
+}
+
+#[cfg(feature = "mmap")]
+impl MappedChain {
+    /**
+     * Maps a compact chain file (see [MarkovChain::save_compact_file]) into
+     * memory.
+     *
+     * The file must not be changed while it is mapped.
+     */
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<MappedChain, String> {
+        let file = File::open(path).map_err(|err| err.to_string())?;
+
+        // SAFETY: the map is only ever read from, and the file is expected
+        // not to change while it is mapped, as documented above.
+        let mmap = unsafe { memmap2::Mmap::map(&file) }.map_err(|err| err.to_string())?;
+
+        CompactChain::from_bytes(&mmap)?;
+
+        Ok(MappedChain { mmap })
+    }
+
+    /// The mapped chain.
+    pub fn chain(&self) -> CompactChain<'_> {
+        CompactChain::from_bytes(&self.mmap).unwrap()
+    }
+}
@@ -0,0 +1,517 @@
+/*!
+ * Composition of sentences, and the options which control their shape.
+ *
+ * [MarkovChain::compose] walks a chain from a seed word, backward to the
+ * beginning of a sentence and forward to its end, within the limits of a
+ * [ComposeOptions].
+ */
+
+use super::body::{Edge, MarkovChain, MarkovSeed, MarkovTraverseDir};
+use super::error::MarkovError;
+use super::selectors::interface::{MarkovSelector, SelectionContext};
+use super::token::{MarkovToken, TokenList};
+use rand::{thread_rng, Rng};
+use std::collections::{HashMap, HashSet, LinkedList, VecDeque};
+
+/**
+ * Which directions to compose a sentence in from its seed. See
+ * [MarkovChain::compose_sentence_directed].
+ */
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub enum ComposeDirection {
+    /// Walk backward to the beginning of the sentence, then forward to its
+    /// end.
+    #[default]
+    Both,
+
+    /// Only walk forward, so that the sentence starts with the seed.
+    Forward,
+
+    /// Only walk backward, so that the sentence ends with the seed.
+    Backward,
+}
+
+/**
+ * How to keep a sentence from walking in circles, such as "and the and the
+ * and the...", in chains with tight cycles. See [ComposeOptions::loop_guard].
+ *
+ * Edges are told apart by their punctuation, too.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LoopGuard {
+    /// Walk edges as many times as they are selected.
+    #[default]
+    Off,
+
+    /// Never walk the same edge twice in a sentence. Attempts which can only
+    /// go on by walking an edge again fail.
+    Forbid,
+
+    /// Scale the weight of an edge by this factor, between 0 and 1, for
+    /// each time it was already walked in the sentence.
+    Penalize(f32),
+}
+
+impl LoopGuard {
+    /// How much to scale the weight of an edge walked `visits` times.
+    pub fn scale(&self, visits: usize) -> f32 {
+        match self {
+            LoopGuard::Off => 1.0,
+            LoopGuard::Forbid if visits > 0 => 0.0,
+            LoopGuard::Forbid => 1.0,
+            LoopGuard::Penalize(factor) => factor.powi(visits as i32),
+        }
+    }
+}
+
+/**
+ * How to compose a sentence. See [MarkovChain::compose].
+ *
+ * Lengths are in characters, and count punctuation and whitespace; word
+ * counts include the seed. When composing in both directions, the backward
+ * walk gets half of the overall limits, unless it has limits of its own.
+ */
+#[derive(Clone, Debug)]
+pub struct ComposeOptions {
+    /// Which directions to walk from the seed in.
+    pub direction: ComposeDirection,
+
+    /// The maximum length of the sentence, if any.
+    pub max_len: Option<usize>,
+
+    /// The maximum length of the part of the sentence walked backward,
+    /// including the seed, if any.
+    pub max_backward_len: Option<usize>,
+
+    /// The maximum number of words in the sentence, if any.
+    pub max_words: Option<usize>,
+
+    /// The maximum number of words walked backward, not including the seed,
+    /// if any.
+    pub max_backward_words: Option<usize>,
+
+    /// The maximum number of words walked forward, not including the seed,
+    /// if any.
+    pub max_forward_words: Option<usize>,
+
+    /// The minimum number of words in the sentence. Shorter sentences are
+    /// composed again.
+    pub min_words: usize,
+
+    /// Words which must all be in the sentence. When the seed is random,
+    /// sentences are composed from these words instead.
+    pub required_words: Vec<String>,
+
+    /// Words which must not be in the sentence; they are never walked into.
+    pub banned_words: Vec<String>,
+
+    /// How to keep the sentence from walking in circles.
+    pub loop_guard: LoopGuard,
+
+    /// How many times to try composing a sentence which satisfies these
+    /// options before giving up.
+    pub max_attempts: usize,
+}
+
+impl Default for ComposeOptions {
+    fn default() -> Self {
+        Self {
+            direction: ComposeDirection::Both,
+            max_len: None,
+            max_backward_len: None,
+            max_words: None,
+            max_backward_words: None,
+            max_forward_words: None,
+            min_words: 0,
+            required_words: vec![],
+            banned_words: vec![],
+            loop_guard: LoopGuard::Off,
+            max_attempts: 1,
+        }
+    }
+}
+
+/**
+ * How to compose a paragraph of several sentences. See
+ * [MarkovChain::compose_paragraph].
+ */
+#[derive(Clone, Debug)]
+pub struct ParagraphOptions {
+    /// How many sentences to compose.
+    pub sentences: usize,
+
+    /// Whether to seed each sentence after the first with the last word of
+    /// the sentence before it, rather than with a random word.
+    pub chain_seeds: bool,
+
+    /// The punctuation which ends sentences that do not end with any of
+    /// their own.
+    pub terminal: String,
+
+    /// How to compose each sentence.
+    pub compose: ComposeOptions,
+}
+
+impl Default for ParagraphOptions {
+    fn default() -> Self {
+        Self {
+            sentences: 3,
+            chain_seeds: false,
+            terminal: ".".to_owned(),
+            compose: ComposeOptions::default(),
+        }
+    }
+}
+
+/// Whether a word is a word, rather than punctuation and whitespace.
+fn is_word(text: &str) -> bool {
+    text.chars()
+        .any(|c| !(c.is_ascii_punctuation() || c.is_whitespace()))
+}
+
+/// Whether a count would exceed a limit, if any.
+fn exceeds(count: usize, limit: Option<usize>) -> bool {
+    limit.is_some_and(|limit| count > limit)
+}
+
+/// How much room is left under a limit, if any.
+fn remaining(count: usize, limit: Option<usize>) -> Option<usize> {
+    limit.map(|limit| limit.saturating_sub(count))
+}
+
+impl MarkovChain {
+    /**
+     * Composes a sentence by traversing this chain from a given 'seed word',
+     * within the limits of some [ComposeOptions].
+     *
+     * With a random seed, each attempt starts from a different word. Fails
+     * if a required word is not in this chain, or if no sentence which
+     * satisfies the options was composed within
+     * [ComposeOptions::max_attempts] attempts.
+     */
+    pub fn compose<'a>(
+        &'a self,
+        seed: MarkovSeed,
+        selector: &mut dyn MarkovSelector,
+        options: &ComposeOptions,
+    ) -> Result<TokenList<'a>, MarkovError> {
+        self.compose_with_rng(seed, selector, options, &mut thread_rng())
+    }
+
+    /**
+     * Composes a sentence like [Self::compose], drawing any random numbers
+     * from `rng`, e.g. a seeded one for reproducible results.
+     */
+    pub fn compose_with_rng<'a, R: Rng>(
+        &'a self,
+        seed: MarkovSeed,
+        selector: &mut dyn MarkovSelector,
+        options: &ComposeOptions,
+        rng: &mut R,
+    ) -> Result<TokenList<'a>, MarkovError> {
+        if self.is_empty() {
+            return Err(MarkovError::EmptyChain);
+        }
+
+        let required = options
+            .required_words
+            .iter()
+            .map(|word| {
+                self.try_get_textlet_index(word)
+                    .ok_or_else(|| MarkovError::UnknownRequiredWord(word.clone()))
+            })
+            .collect::<Result<Vec<usize>, MarkovError>>()?;
+
+        let banned: HashSet<usize> = options
+            .banned_words
+            .iter()
+            .filter_map(|word| self.try_get_textlet_index(word))
+            .collect();
+
+        let allowed = |edge: &Edge| {
+            banned.is_empty() || !banned.contains(&edge.src_idx) && !banned.contains(&edge.dst_idx)
+        };
+
+        let mut error = MarkovError::Unsatisfiable;
+
+        for attempt in 0..options.max_attempts.max(1) {
+            let from = match seed {
+                MarkovSeed::Random if !required.is_empty() => required[attempt % required.len()],
+                _ => self.get_seed(seed.clone(), rng)?,
+            };
+
+            if banned.contains(&from) {
+                error = MarkovError::Unsatisfiable;
+                continue;
+            }
+
+            // how many times each (source, destination, punctuation) edge
+            // was walked
+            let mut visits: HashMap<(usize, usize, usize), usize> = HashMap::new();
+
+            let seed_token = self.get_textlet(from).unwrap();
+
+            selector.reset(
+                if options.direction == ComposeDirection::Forward
+                    || seed_token == MarkovToken::Begin
+                {
+                    MarkovTraverseDir::Forward
+                } else {
+                    MarkovTraverseDir::Reverse
+                },
+            );
+            selector.feedback(&seed_token);
+
+            let composed = compose_around(from, seed_token, options, |context, selection| {
+                let direction = selection.direction;
+
+                let scale = |edge: &Edge| {
+                    if !allowed(edge) {
+                        return 0.0;
+                    }
+
+                    if options.loop_guard == LoopGuard::Off {
+                        return 1.0;
+                    }
+
+                    let key = (edge.src_idx, edge.dst_idx, edge.pct_idx);
+
+                    options
+                        .loop_guard
+                        .scale(visits.get(&key).copied().unwrap_or(0))
+                };
+
+                selector.prepare(selection);
+
+                // without anything to scale, the chain may sample from its cache
+                let scale = (!banned.is_empty() || options.loop_guard != LoopGuard::Off)
+                    .then_some(&scale as &dyn Fn(&Edge) -> f32);
+
+                let next = self.select_next_word_where(context, selector, direction, scale, rng)?;
+
+                let key = match direction {
+                    MarkovTraverseDir::Forward => (context[context.len() - 1], next.2, next.3),
+                    MarkovTraverseDir::Reverse => (next.2, context[0], next.3),
+                };
+
+                *visits.entry(key).or_default() += 1;
+
+                Ok(next)
+            });
+
+            let (mut sentence, words) = match composed {
+                Ok(composed) => composed,
+                Err(err) => {
+                    error = err;
+                    continue;
+                }
+            };
+
+            if words < options.min_words {
+                error = MarkovError::LengthExhausted;
+                continue;
+            }
+
+            if !self.contains_all(&sentence, &required) {
+                error = MarkovError::Unsatisfiable;
+                continue;
+            }
+
+            self.capitalize_first(&mut sentence);
+
+            return Ok(sentence);
+        }
+
+        Err(error)
+    }
+
+    /**
+     * Composes a paragraph of several sentences, the first of them from a
+     * seed word, and joins them with spaces.
+     *
+     * Sentences which do not end with terminal punctuation (`.`, `!` or
+     * `?`) are ended with [ParagraphOptions::terminal].
+     */
+    pub fn compose_paragraph(
+        &self,
+        seed: MarkovSeed,
+        selector: &mut dyn MarkovSelector,
+        options: &ParagraphOptions,
+    ) -> Result<String, MarkovError> {
+        let mut paragraph = String::new();
+        let mut seed = seed;
+
+        for _ in 0..options.sentences {
+            let sentence = self.compose(seed, selector, &options.compose)?;
+            let text = sentence.to_string();
+            let text = text.trim();
+
+            if !paragraph.is_empty() {
+                paragraph.push(' ');
+            }
+
+            paragraph.push_str(text);
+
+            if !text.ends_with(['.', '!', '?']) {
+                paragraph.push_str(&options.terminal);
+            }
+
+            let last_word = sentence.iter().rev().find_map(|token| match token {
+                MarkovToken::Textlet(text) if is_word(text) => Some(*text),
+                _ => None,
+            });
+
+            seed = match last_word {
+                Some(word) if options.chain_seeds => MarkovSeed::Word(word),
+                _ => MarkovSeed::Random,
+            };
+        }
+
+        Ok(paragraph)
+    }
+
+    /// Whether a composed sentence contains every one of some words.
+    fn contains_all(&self, sentence: &TokenList, words: &[usize]) -> bool {
+        let found: HashSet<usize> = sentence
+            .0
+            .iter()
+            .filter_map(|token| match token {
+                MarkovToken::Textlet(text) => self.try_get_textlet_index(text),
+                _ => None,
+            })
+            .collect();
+
+        words.iter().all(|word| found.contains(word))
+    }
+}
+
+/**
+ * Composes a sentence by walking backward and then forward from a seed
+ * textlet, within the limits of some [ComposeOptions], using `select_next`
+ * to pick each next word given the words walked so far (as
+ * [MarkovChain::select_next_word_in_context] does) and a [SelectionContext]
+ * for the selector.
+ *
+ * Returns the sentence, and how many words are in it.
+ *
+ * The begin and end tokens are not part of the sentence, even as seeds; no
+ * walking happens backward from the begin token, nor forward from the end
+ * token.
+ */
+pub(crate) fn compose_around<'a, F>(
+    seed: usize,
+    seed_token: MarkovToken<'a>,
+    options: &ComposeOptions,
+    mut select_next: F,
+) -> Result<(TokenList<'a>, usize), MarkovError>
+where
+    F: FnMut(
+        &[usize],
+        &SelectionContext<'_, 'a>,
+    ) -> Result<(MarkovToken<'a>, MarkovToken<'a>, usize, usize), MarkovError>,
+{
+    use MarkovToken::*;
+    use MarkovTraverseDir::*;
+
+    let backward = options.direction != ComposeDirection::Forward && seed_token != Begin;
+    let forward = options.direction != ComposeDirection::Backward && seed_token != End;
+
+    let mut sentence = TokenList(LinkedList::new());
+    let mut len = 0;
+    let mut words = 0;
+
+    if let Textlet(_) = seed_token {
+        len = seed_token.len();
+        words = 1;
+        sentence.0.push_back(seed_token);
+    }
+
+    // the words walked so far, including the begin token if reached
+    let mut context: VecDeque<usize> = VecDeque::from([seed]);
+
+    // leave half the room for the forward walk, if any
+    let halve = |limit: Option<usize>| if forward { limit.map(|x| x / 2) } else { limit };
+
+    let max_backward_len = options.max_backward_len.or(halve(options.max_len));
+    let max_backward_words = options
+        .max_backward_words
+        .map(|limit| limit + words)
+        .or(halve(options.max_words));
+
+    if backward {
+        loop {
+            let selection = SelectionContext {
+                sentence: &sentence,
+                direction: Reverse,
+                remaining_len: remaining(len, max_backward_len),
+                remaining_words: remaining(words, max_backward_words),
+            };
+
+            let (prev, punct, prvidx, _) = select_next(context.make_contiguous(), &selection)?;
+
+            let new_len = len + punct.len() + prev.len();
+            let new_words = words + usize::from(prev != Begin);
+
+            if exceeds(new_len, max_backward_len) || exceeds(new_words, max_backward_words) {
+                break;
+            }
+
+            len = new_len;
+            words = new_words;
+
+            sentence.0.push_front(punct);
+            context.push_front(prvidx);
+
+            if prev == Begin {
+                break;
+            }
+
+            sentence.0.push_front(prev);
+        }
+    }
+
+    let backward_words = words;
+
+    if forward {
+        loop {
+            let selection = SelectionContext {
+                sentence: &sentence,
+                direction: Forward,
+                remaining_len: remaining(len, options.max_len),
+                remaining_words: [
+                    remaining(words, options.max_words),
+                    remaining(words - backward_words, options.max_forward_words),
+                ]
+                .into_iter()
+                .flatten()
+                .min(),
+            };
+
+            let (next, punct, nxtidx, _) = select_next(context.make_contiguous(), &selection)?;
+
+            let new_len = len + punct.len() + next.len();
+            let new_words = words + usize::from(next != End);
+
+            if exceeds(new_len, options.max_len)
+                || exceeds(new_words, options.max_words)
+                || exceeds(new_words - backward_words, options.max_forward_words)
+            {
+                break;
+            }
+
+            len = new_len;
+            words = new_words;
+
+            sentence.0.push_back(punct);
+            context.push_back(nxtidx);
+
+            if next == End {
+                break;
+            }
+
+            sentence.0.push_back(next);
+        }
+    }
+
+    Ok((sentence, words))
+}
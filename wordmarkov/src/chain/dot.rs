@@ -0,0 +1,106 @@
+/*!
+ * Export of [MarkovChain]s to GraphViz DOT graphs, for inspecting them
+ * visually; e.g. to find out why a chain keeps walking in a loop.
+ *
+ * Words are nodes, and edges are labelled with their punctuation (quoted)
+ * and hits. The begin and end tokens are drawn as boxes.
+ */
+
+use super::body::{Edge, MarkovChain};
+use super::token::MarkovToken;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// Which parts of a chain to include in a DOT graph.
+#[derive(Clone, Debug, Default)]
+pub struct DotOptions {
+    /// Only include edges found at least this many times.
+    pub min_hits: usize,
+
+    /// Only include the words at most this many edges away from this word,
+    /// in either direction.
+    pub neighborhood: Option<(String, usize)>,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl MarkovChain {
+    /**
+     * Renders this chain, or part of it, as a GraphViz DOT graph.
+     *
+     * Fails if the word at the center of the neighborhood is not in the
+     * chain.
+     */
+    pub fn to_dot(&self, options: &DotOptions) -> Result<String, String> {
+        let mut edges: Vec<&Edge> = self
+            .edge_list
+            .iter()
+            .filter(|edge| edge.hits >= options.min_hits)
+            .collect();
+
+        let mut nodes: HashSet<usize> = HashSet::new();
+
+        if let Some((word, radius)) = &options.neighborhood {
+            let center = self
+                .try_get_textlet_index(word)
+                .ok_or_else(|| format!("Word {:?} not found in this Markov chain!", word))?;
+
+            nodes.insert(center);
+
+            for _ in 0..*radius {
+                let reached: Vec<usize> = edges
+                    .iter()
+                    .filter_map(|edge| {
+                        match (nodes.contains(&edge.src_idx), nodes.contains(&edge.dst_idx)) {
+                            (true, false) => Some(edge.dst_idx),
+                            (false, true) => Some(edge.src_idx),
+                            _ => None,
+                        }
+                    })
+                    .collect();
+
+                nodes.extend(reached);
+            }
+
+            edges.retain(|edge| nodes.contains(&edge.src_idx) && nodes.contains(&edge.dst_idx));
+        } else {
+            nodes.extend(edges.iter().flat_map(|edge| [edge.src_idx, edge.dst_idx]));
+        }
+
+        let mut nodes: Vec<usize> = nodes.into_iter().collect();
+        nodes.sort_unstable();
+
+        let mut dot = String::from("digraph markov {\n");
+
+        for node in nodes {
+            let _ = match self.get_textlet(node).unwrap() {
+                MarkovToken::Begin => writeln!(dot, "    n{} [label=\"BEGIN\", shape=box];", node),
+                MarkovToken::End => writeln!(dot, "    n{} [label=\"END\", shape=box];", node),
+                MarkovToken::Textlet(text) => {
+                    writeln!(dot, "    n{} [label=\"{}\"];", node, escape(text))
+                }
+            };
+        }
+
+        for edge in edges {
+            let punct: &str = (&self.textlet_bag[edge.pct_idx]).into();
+            let label = format!("{:?} ({})", punct, edge.hits);
+
+            let _ = writeln!(
+                dot,
+                "    n{} -> n{} [label=\"{}\"];",
+                edge.src_idx,
+                edge.dst_idx,
+                escape(&label)
+            );
+        }
+
+        dot.push_str("}\n");
+
+        Ok(dot)
+    }
+}
@@ -0,0 +1,82 @@
+/*!
+ * The ways in which walking a Markov chain, i.e. seeding, selecting words and
+ * composing sentences, can fail.
+ */
+
+use std::fmt::{Display, Formatter};
+
+/**
+ * Why a sentence could not be composed, or a next word selected, from a
+ * Markov chain.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MarkovError {
+    /// The chain has no words to compose a sentence from.
+    EmptyChain,
+
+    /// The seed word is not in the chain, nor any fallback for it (see
+    /// [super::seed::SeedFallback]).
+    UnknownSeedWord(String),
+
+    /// A word which a sentence must contain is not in the chain.
+    UnknownRequiredWord(String),
+
+    /// The textlet walked from is not connected to anything in the
+    /// direction walked, or the selector filtered out everything it is
+    /// connected to.
+    DeadEnd(String),
+
+    /// A context of words to select the next word after was empty.
+    EmptyContext,
+
+    /// No sentence of at least the minimum number of words could be
+    /// composed within the length limits and attempts given.
+    LengthExhausted,
+
+    /// No sentence with every required word and no banned word could be
+    /// composed within the attempts given.
+    Unsatisfiable,
+
+    /// The data of a compact chain is corrupt.
+    Corrupt(String),
+}
+
+impl Display for MarkovError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), std::fmt::Error> {
+        use MarkovError::*;
+
+        match self {
+            EmptyChain => write!(fmt, "Cannot compose a sentence from an empty chain"),
+            UnknownSeedWord(word) => {
+                write!(fmt, "Seed word {:?} not found in this Markov chain!", word)
+            }
+            UnknownRequiredWord(word) => {
+                write!(
+                    fmt,
+                    "Required word {:?} not found in this Markov chain!",
+                    word
+                )
+            }
+            DeadEnd(textlet) => write!(
+                fmt,
+                "Textlet {:?} is not connected to anything in this Markov chain!",
+                textlet
+            ),
+            EmptyContext => write!(fmt, "The context is empty"),
+            LengthExhausted => write!(fmt, "Could not compose a sentence long enough"),
+            Unsatisfiable => write!(
+                fmt,
+                "Could not compose a sentence with every required word and no banned word"
+            ),
+            Corrupt(what) => write!(fmt, "Compact chain is corrupt: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for MarkovError {}
+
+impl From<MarkovError> for String {
+    fn from(err: MarkovError) -> String {
+        err.to_string()
+    }
+}
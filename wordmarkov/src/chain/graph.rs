@@ -0,0 +1,210 @@
+/*!
+ * Structured graphs of [MarkovChain]s, for editing or generating chains
+ * with external tooling.
+ *
+ * A [ChainGraph] lists the words of a chain as nodes, and its edges with
+ * their punctuation and hits. With the `serde` feature, it can be written in
+ * any serde format; with the `json` feature, [MarkovChain::to_json] and
+ * [MarkovChain::from_json] do so in JSON.
+ *
+ * Only the first-order graph is included; the contexts of higher-order
+ * chains (see [MarkovChain::with_order]) are not.
+ */
+
+use super::body::{Edge, MarkovChain};
+use super::token::MarkovTokenOwned;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// What a [GraphNode] stands for.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum GraphNodeKind {
+    /// The beginning of a sentence.
+    Begin,
+
+    /// The end of a sentence.
+    End,
+
+    /// A word.
+    Word,
+}
+
+/// A word of a chain.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphNode {
+    /// The identifier of this node, unique within its graph.
+    pub id: usize,
+
+    /// What this node stands for.
+    pub kind: GraphNodeKind,
+
+    /// The text of the word; empty for the begin and end nodes.
+    pub text: String,
+}
+
+/// An edge of a chain, between two [GraphNode]s.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphEdge {
+    /// The identifier of the node this edge comes from.
+    pub source: usize,
+
+    /// The identifier of the node this edge leads into.
+    pub target: usize,
+
+    /// The punctuation and whitespace between the two words.
+    pub punct: String,
+
+    /// How many times this edge has been found.
+    pub hits: usize,
+}
+
+/// The words and edges of a chain. See [MarkovChain::to_graph].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChainGraph {
+    /// The words of the chain, including the begin and end nodes.
+    pub nodes: Vec<GraphNode>,
+
+    /// The edges between the words.
+    pub edges: Vec<GraphEdge>,
+}
+
+impl MarkovChain {
+    /**
+     * Lists the words and edges of this chain as a [ChainGraph].
+     *
+     * Node identifiers are the textlet indices of the words.
+     */
+    pub fn to_graph(&self) -> ChainGraph {
+        let mut used = vec![false; self.textlet_bag.len()];
+
+        for edge in &self.edge_list {
+            used[edge.src_idx] = true;
+            used[edge.dst_idx] = true;
+        }
+
+        let nodes = self
+            .textlet_bag
+            .iter()
+            .enumerate()
+            .filter(|(id, _)| used[*id])
+            .map(|(id, textlet)| GraphNode {
+                id,
+                kind: match textlet {
+                    MarkovTokenOwned::Begin => GraphNodeKind::Begin,
+                    MarkovTokenOwned::End => GraphNodeKind::End,
+                    MarkovTokenOwned::Textlet(_) => GraphNodeKind::Word,
+                },
+                text: <&str>::from(textlet).to_owned(),
+            })
+            .collect();
+
+        let edges = self
+            .edge_list
+            .iter()
+            .map(|edge| GraphEdge {
+                source: edge.src_idx,
+                target: edge.dst_idx,
+                punct: <&str>::from(&self.textlet_bag[edge.pct_idx]).to_owned(),
+                hits: edge.hits,
+            })
+            .collect();
+
+        ChainGraph { nodes, edges }
+    }
+
+    /**
+     * Makes a chain out of a [ChainGraph], e.g. one made by
+     * [Self::to_graph] and then edited.
+     *
+     * Fails if a node identifier is repeated or missing, or if two word
+     * nodes have the same text.
+     */
+    pub fn from_graph(graph: &ChainGraph) -> Result<MarkovChain, String> {
+        let mut textlet_bag = vec![MarkovTokenOwned::Begin, MarkovTokenOwned::End];
+        let mut textlets: HashMap<&str, usize> = HashMap::new();
+        let mut nodes: HashMap<usize, usize> = HashMap::new();
+
+        for node in &graph.nodes {
+            let idx = match node.kind {
+                GraphNodeKind::Begin => 0,
+                GraphNodeKind::End => 1,
+
+                GraphNodeKind::Word => {
+                    if textlets.contains_key(node.text.as_str()) {
+                        return Err(format!("Word {:?} is repeated", node.text));
+                    }
+
+                    textlet_bag.push(MarkovTokenOwned::Textlet(Arc::from(node.text.as_str())));
+                    textlets.insert(&node.text, textlet_bag.len() - 1);
+                    textlet_bag.len() - 1
+                }
+            };
+
+            if nodes.insert(node.id, idx).is_some() {
+                return Err(format!("Node {} is repeated", node.id));
+            }
+        }
+
+        let node = |id: usize| {
+            nodes
+                .get(&id)
+                .copied()
+                .ok_or_else(|| format!("Node {} not found", id))
+        };
+
+        let mut words = vec![];
+        let mut edge_list = vec![];
+
+        for edge in &graph.edges {
+            let (src_idx, dst_idx) = (node(edge.source)?, node(edge.target)?);
+
+            let pct_idx = *textlets.entry(&edge.punct).or_insert_with(|| {
+                textlet_bag.push(MarkovTokenOwned::Textlet(Arc::from(edge.punct.as_str())));
+                textlet_bag.len() - 1
+            });
+
+            for word in [src_idx, dst_idx] {
+                if !words.contains(&word) {
+                    words.push(word);
+                }
+            }
+
+            edge_list.push(Edge {
+                src_idx,
+                dst_idx,
+                hits: edge.hits,
+                last_seen: 0,
+                pct_idx,
+            });
+        }
+
+        MarkovChain::from_parts(textlet_bag, words, edge_list)
+    }
+
+    /**
+     * Writes the graph of this chain (see [Self::to_graph]) as JSON.
+     *
+     * Only available with the `json` feature.
+     */
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(&self.to_graph()).map_err(|err| err.to_string())
+    }
+
+    /**
+     * Reads a chain from the JSON of its graph (see [Self::from_graph]).
+     *
+     * Only available with the `json` feature.
+     */
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str) -> Result<MarkovChain, String> {
+        let graph: ChainGraph = serde_json::from_str(json).map_err(|err| err.to_string())?;
+
+        Self::from_graph(&graph)
+    }
+}
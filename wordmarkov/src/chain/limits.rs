@@ -0,0 +1,141 @@
+/*!
+ * Capacity limits for [MarkovChain]s.
+ *
+ * A chain which keeps learning, such as a chat bot's, grows without bound.
+ * With [ChainLimits], the chain instead evicts its least useful edges and
+ * textlets whenever it grows past a limit, so that it can run indefinitely
+ * in a fixed amount of memory.
+ */
+
+use super::body::MarkovChain;
+use std::collections::HashSet;
+
+/// Which edges and textlets a bounded chain evicts first.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EvictionPolicy {
+    /// Evict those found the fewest times.
+    LowestHits,
+
+    /// Evict those found the longest ago.
+    LeastRecentlySeen,
+}
+
+/**
+ * The capacity limits of a chain. See [MarkovChain::set_limits].
+ *
+ * When a limit is exceeded, enough is evicted to get a tenth under it, so
+ * that eviction does not need to happen for every new sentence.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ChainLimits {
+    /// The maximum number of textlets, if any.
+    pub max_textlets: Option<usize>,
+
+    /// The maximum number of edges, if any.
+    pub max_edges: Option<usize>,
+
+    /// Which edges and textlets to evict first.
+    pub policy: EvictionPolicy,
+}
+
+/// How many items to evict to get a tenth under a limit, if it is exceeded.
+fn excess(len: usize, limit: Option<usize>) -> usize {
+    match limit {
+        Some(limit) if len > limit => len - (limit - limit / 10),
+        _ => 0,
+    }
+}
+
+/// The indices of the `count` lowest scores.
+fn lowest(scores: &[usize], count: usize) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..scores.len()).collect();
+
+    indices.sort_by_key(|i| scores[*i]);
+    indices.truncate(count);
+
+    indices
+}
+
+impl MarkovChain {
+    /**
+     * Sets the capacity limits of this chain, or removes them if `None`.
+     *
+     * The limits are enforced right away, and then whenever sentences are
+     * parsed or chains are merged into this one.
+     */
+    pub fn set_limits(&mut self, limits: Option<ChainLimits>) {
+        self.limits = limits;
+        self.enforce_limits();
+    }
+
+    /// The capacity limits of this chain, if any. See [Self::set_limits].
+    pub fn limits(&self) -> Option<ChainLimits> {
+        self.limits
+    }
+
+    /// Evicts edges and textlets until this chain is within its limits.
+    pub(crate) fn enforce_limits(&mut self) {
+        let limits = match self.limits {
+            Some(limits) => limits,
+            None => return,
+        };
+
+        let score = |hits: usize, last_seen: usize| match limits.policy {
+            EvictionPolicy::LowestHits => hits,
+            EvictionPolicy::LeastRecentlySeen => last_seen,
+        };
+
+        let num_textlets = self.num_textlets();
+        let textlet_excess = excess(num_textlets, limits.max_textlets);
+
+        if textlet_excess > 0 {
+            // a textlet is worth as much as all of its edges put together, or
+            // as recent as the most recent of them
+            let mut scores = vec![0_usize; num_textlets];
+
+            for edge in &self.edge_list {
+                for idx in [edge.src_idx, edge.dst_idx, edge.pct_idx] {
+                    scores[idx] = match limits.policy {
+                        EvictionPolicy::LowestHits => scores[idx].saturating_add(edge.hits),
+                        EvictionPolicy::LeastRecentlySeen => scores[idx].max(edge.last_seen),
+                    };
+                }
+            }
+
+            // the begin and end tokens are never evicted
+            scores[0] = usize::MAX;
+            scores[1] = usize::MAX;
+
+            let evicted: HashSet<usize> = lowest(&scores, textlet_excess).into_iter().collect();
+
+            self.retain_edges(|_, edge| {
+                [edge.src_idx, edge.dst_idx, edge.pct_idx]
+                    .iter()
+                    .all(|idx| !evicted.contains(idx))
+            });
+
+            self.words.retain(|word| !evicted.contains(word));
+
+            for table in [&mut self.forward_contexts, &mut self.reverse_contexts] {
+                table.retain(|context, _| context.iter().all(|idx| !evicted.contains(idx)));
+            }
+
+            self.prune_orphan_textlets();
+        }
+
+        let edge_excess = excess(self.num_edges(), limits.max_edges);
+
+        if edge_excess > 0 {
+            let scores: Vec<usize> = self
+                .edge_list
+                .iter()
+                .map(|edge| score(edge.hits, edge.last_seen))
+                .collect();
+
+            let evicted: HashSet<usize> = lowest(&scores, edge_excess).into_iter().collect();
+
+            self.retain_edges(|idx, _| !evicted.contains(&idx));
+            self.prune_orphan_textlets();
+        }
+    }
+}
@@ -0,0 +1,187 @@
+/*!
+ * Append-only logs of the sentences parsed into a chain.
+ *
+ * A chain which keeps learning, such as a chat bot's, would otherwise have to
+ * be saved whole every so often, losing whatever it learned since whenever it
+ * crashes. With [MarkovChain::open_log], each sentence parsed is appended to
+ * a file as soon as it is parsed, and [MarkovChain::replay] parses them all
+ * again on startup; e.g. on top of the last full snapshot, after which the log
+ * can be started anew.
+ *
+ * Each line of a log is a sentence, as `<hits>\t<sentence>`, with
+ * backslashes, tabs and line breaks in the sentence escaped.
+ */
+
+use super::body::MarkovChain;
+use crate::sentence::lex::LexerConfig;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+/// An open log file. See [MarkovChain::open_log].
+pub(crate) struct ChainLog {
+    file: File,
+
+    /// The first error writing to the file, after which nothing else is.
+    error: Option<String>,
+}
+
+impl ChainLog {
+    /// Appends a sentence parsed `hits` times, unless writing already failed.
+    pub(crate) fn append(&mut self, sentence: &str, hits: usize) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let mut line = format!("{}\t", hits);
+
+        for c in sentence.chars() {
+            match c {
+                '\\' => line.push_str("\\\\"),
+                '\t' => line.push_str("\\t"),
+                '\n' => line.push_str("\\n"),
+                '\r' => line.push_str("\\r"),
+                _ => line.push(c),
+            }
+        }
+
+        line.push('\n');
+
+        // written all at once, so that a crash can only cut the last line
+        // short
+        if let Err(err) = self.file.write_all(line.as_bytes()) {
+            self.error = Some(err.to_string());
+        }
+    }
+}
+
+/// Parses a line of a log into a sentence and its hits.
+fn parse_line(line: &str) -> Option<(String, usize)> {
+    let (hits, escaped) = line.split_once('\t')?;
+    let hits = hits.parse().ok()?;
+
+    let mut sentence = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+
+    while let Some(c) = chars.next() {
+        sentence.push(match c {
+            '\\' => match chars.next()? {
+                '\\' => '\\',
+                't' => '\t',
+                'n' => '\n',
+                'r' => '\r',
+                _ => return None,
+            },
+
+            c => c,
+        });
+    }
+
+    Some((sentence, hits))
+}
+
+impl MarkovChain {
+    /**
+     * Opens a log file, creating it if needed, and appends every sentence
+     * parsed into this chain from now on to it. See [Self::replay].
+     *
+     * Only sentences are logged; other changes to the chain, such as merging
+     * chains into it, pruning or decaying it, are not, nor are sentences
+     * parsed in parallel (which are merged into the chain).
+     *
+     * Each sentence is written to the file as soon as it is parsed, so that
+     * no more than the sentence being written is lost if the program
+     * crashes. If writing fails, nothing else is written; see
+     * [Self::log_error].
+     */
+    pub fn open_log<P: AsRef<Path>>(&mut self, path: P) -> Result<(), String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| err.to_string())?;
+
+        self.log = Some(ChainLog { file, error: None });
+
+        Ok(())
+    }
+
+    /// Stops logging the sentences parsed into this chain.
+    pub fn close_log(&mut self) {
+        self.log = None;
+    }
+
+    /// Whether the sentences parsed into this chain are being logged.
+    pub fn is_logging(&self) -> bool {
+        self.log.is_some()
+    }
+
+    /**
+     * The error which stopped the log from being written to, if any. The
+     * sentences parsed since were not logged.
+     */
+    pub fn log_error(&self) -> Option<&str> {
+        self.log.as_ref().and_then(|log| log.error.as_deref())
+    }
+
+    /**
+     * Parses every sentence in a log file (see [Self::open_log]) into this
+     * chain again, with the chain's [LexerConfig], and returns how many
+     * there were.
+     *
+     * The chain must be configured as it was when the sentences were first
+     * parsed (e.g. its order and case folding) to learn the same from them.
+     * Replayed sentences are not logged again.
+     *
+     * A last line cut short, as by a crash, is skipped; any other malformed
+     * line fails the replay, leaving the sentences before it parsed.
+     */
+    pub fn replay<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, String> {
+        self.with_own_lexer_config(|chain, config| chain.replay_with_config(path, config))
+    }
+
+    /**
+     * Replays a log file like [Self::replay], lexing its sentences according
+     * to a [LexerConfig]; the one they were first parsed with.
+     */
+    pub fn replay_with_config<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        config: &LexerConfig,
+    ) -> Result<usize, String> {
+        let file = File::open(path).map_err(|err| err.to_string())?;
+        let mut reader = BufReader::new(file);
+
+        let log = self.log.take();
+        let mut line = String::new();
+        let mut replayed = 0;
+
+        let result = loop {
+            line.clear();
+
+            match reader.read_line(&mut line) {
+                Ok(0) => break Ok(replayed),
+                Err(err) => break Err(err.to_string()),
+                Ok(_) => {}
+            }
+
+            let line = match line.strip_suffix('\n') {
+                Some(line) => line,
+                None => break Ok(replayed),
+            };
+
+            match parse_line(line) {
+                Some((sentence, hits)) => {
+                    self.parse_weighted(&sentence, config, hits);
+                    replayed += 1;
+                }
+
+                None => break Err(format!("Line {} of the log is malformed", replayed + 1)),
+            }
+        };
+
+        self.log = log;
+
+        result
+    }
+}
@@ -0,0 +1,105 @@
+/*!
+ * Arbitrary metadata attached to the textlets of a chain.
+ *
+ * Each type of metadata (e.g. a part-of-speech tag, the corpus a word came
+ * from, or when it was first seen) is kept in its own side table, keyed by
+ * textlet index. Selectors can query it while weighing edges, through
+ * [MarkovSelector::weight_edge]; e.g. to prefer nouns after articles.
+ *
+ * [MarkovSelector::weight_edge]: super::selectors::interface::MarkovSelector::weight_edge
+ */
+
+use super::body::MarkovChain;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// A side table of one type of metadata, with its type erased.
+pub(crate) trait MetadataTable: Any + Send + Sync {
+    /// Renumbers the textlets, dropping those mapped to None.
+    fn renumber(&mut self, new_index: &dyn Fn(usize) -> Option<usize>);
+
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Send + Sync + 'static> MetadataTable for HashMap<usize, T> {
+    fn renumber(&mut self, new_index: &dyn Fn(usize) -> Option<usize>) {
+        *self = std::mem::take(self)
+            .into_iter()
+            .filter_map(|(textlet, value)| Some((new_index(textlet)?, value)))
+            .collect();
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// The side tables of metadata of a chain, by type.
+pub(crate) type MetadataTables = HashMap<TypeId, Box<dyn MetadataTable>>;
+
+impl MarkovChain {
+    /// The side table of metadata of type `T`, if any was ever set.
+    fn metadata_table<T: Send + Sync + 'static>(&self) -> Option<&HashMap<usize, T>> {
+        self.metadata
+            .get(&TypeId::of::<T>())
+            .and_then(|table| table.as_any().downcast_ref())
+    }
+
+    fn metadata_table_mut<T: Send + Sync + 'static>(&mut self) -> &mut HashMap<usize, T> {
+        self.metadata
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(HashMap::<usize, T>::new()))
+            .as_any_mut()
+            .downcast_mut()
+            .unwrap()
+    }
+
+    /**
+     * Attaches metadata of type `T` to a textlet, by index, replacing and
+     * returning any it had of that type. A textlet can have metadata of any
+     * number of types, one of each.
+     *
+     * Metadata is kept when textlets are renumbered (e.g. by
+     * [Self::prune_orphan_textlets]), and dropped along with its textlet.
+     * It is not saved, nor merged into other chains.
+     */
+    pub fn set_metadata<T: Send + Sync + 'static>(
+        &mut self,
+        textlet: usize,
+        value: T,
+    ) -> Option<T> {
+        self.metadata_table_mut().insert(textlet, value)
+    }
+
+    /// The metadata of type `T` of a textlet, if it has any.
+    pub fn metadata<T: Send + Sync + 'static>(&self, textlet: usize) -> Option<&T> {
+        self.metadata_table()?.get(&textlet)
+    }
+
+    /// The metadata of type `T` of a textlet, mutably, if it has any.
+    pub fn metadata_mut<T: Send + Sync + 'static>(&mut self, textlet: usize) -> Option<&mut T> {
+        self.metadata_table_mut().get_mut(&textlet)
+    }
+
+    /// Removes and returns the metadata of type `T` of a textlet, if any.
+    pub fn remove_metadata<T: Send + Sync + 'static>(&mut self, textlet: usize) -> Option<T> {
+        self.metadata_table_mut().remove(&textlet)
+    }
+
+    /// Removes the metadata of type `T` of every textlet.
+    pub fn clear_metadata<T: Send + Sync + 'static>(&mut self) {
+        self.metadata.remove(&TypeId::of::<T>());
+    }
+
+    /// Renumbers the textlets of every side table of metadata.
+    pub(crate) fn renumber_metadata(&mut self, new_index: &dyn Fn(usize) -> Option<usize>) {
+        for table in self.metadata.values_mut() {
+            table.renumber(new_index);
+        }
+    }
+}
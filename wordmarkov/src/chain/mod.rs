@@ -4,7 +4,27 @@
  */
 
 pub mod body;
+pub mod case;
+pub mod compact;
+pub mod compose;
+pub mod dot;
+pub mod error;
+pub mod graph;
+pub mod limits;
+pub mod log;
+pub mod metadata;
+pub mod sampling;
+pub mod scorer;
+pub mod seed;
 pub mod selectors;
+pub mod shared;
 pub mod token;
+pub mod walk;
+
+#[cfg(feature = "parallel")]
+pub mod parallel;
+
+#[cfg(feature = "serde")]
+pub mod serial;
 
 pub mod prelude;
@@ -0,0 +1,47 @@
+/*!
+ * Parsing of large corpora on multiple threads.
+ *
+ * Only available with the `parallel` feature. Each thread parses its share
+ * of the corpus into a partial chain of its own, and the partial chains are
+ * then merged together, so threads never wait on each other.
+ */
+
+use super::body::MarkovChain;
+use rayon::prelude::*;
+
+impl MarkovChain {
+    /**
+     * Parses many sentences (e.g. the lines of a chat log) on multiple
+     * threads, registering textlets and edges for them.
+     *
+     * Registers the same textlets and edges as calling
+     * [Self::parse_sentence] on each sentence, although textlets may be
+     * numbered differently.
+     */
+    pub fn parse_corpus_parallel<I, S>(&mut self, lines: I)
+    where
+        I: IntoParallelIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let order = self.order();
+
+        let partial = lines
+            .into_par_iter()
+            .fold(
+                || MarkovChain::with_order(order),
+                |mut chain, line| {
+                    chain.parse_sentence(line.as_ref());
+                    chain
+                },
+            )
+            .reduce(
+                || MarkovChain::with_order(order),
+                |mut chain, other| {
+                    chain.merge(&other);
+                    chain
+                },
+            );
+
+        self.merge(&partial);
+    }
+}
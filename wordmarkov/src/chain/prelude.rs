@@ -1,5 +1,16 @@
 //! Useful imports for chain.
 
 pub use super::body::*;
+pub use super::case::*;
+pub use super::compact::*;
+pub use super::compose::*;
+pub use super::dot::*;
+pub use super::error::*;
+pub use super::graph::*;
+pub use super::limits::*;
+pub use super::scorer::*;
+pub use super::seed::*;
 pub use super::selectors::prelude::*;
+pub use super::shared::*;
 pub use super::token::*;
+pub use super::walk::*;
@@ -0,0 +1,140 @@
+/*!
+ * A cache of the cumulative hits of the edges from and toward each textlet,
+ * for fast weighted random selection.
+ *
+ * Selecting the next word normally weighs every edge from the current one,
+ * which takes longer the more edges it has. For selectors which weigh edges by
+ * their hits alone (see [MarkovSelector::weighs_by_hits]), such as
+ * [WeightedRandomSelector], a chain with a sampling cache (see
+ * [MarkovChain::set_sampling_cache]) instead binary searches the running sums
+ * of the hits of those edges. The sums of each textlet are computed the first
+ * time they are needed, and discarded whenever its edges change; so the cache
+ * pays off for chains which are composed from much more than they are parsed
+ * into.
+ *
+ * [MarkovSelector::weighs_by_hits]: super::selectors::interface::MarkovSelector::weighs_by_hits
+ * [WeightedRandomSelector]: super::selectors::random::WeightedRandomSelector
+ */
+
+use super::body::{vec_bytes, MarkovChain, MarkovTraverseDir};
+use rand::Rng;
+use std::sync::OnceLock;
+
+/// The running sums of the hits of the edges of each textlet, by index.
+type RunningSums = Vec<OnceLock<Box<[usize]>>>;
+
+/// See [MarkovChain::set_sampling_cache].
+pub(crate) struct SamplingCache {
+    forward: RunningSums,
+    reverse: RunningSums,
+}
+
+impl SamplingCache {
+    /// The bytes allocated by this cache.
+    pub(crate) fn memory_usage(&self) -> usize {
+        [&self.forward, &self.reverse]
+            .into_iter()
+            .map(|sums| {
+                vec_bytes(sums)
+                    + sums
+                        .iter()
+                        .filter_map(OnceLock::get)
+                        .map(|sums| std::mem::size_of_val(&**sums))
+                        .sum::<usize>()
+            })
+            .sum()
+    }
+}
+
+/// Discards the running sums of a textlet, making room for it if needed.
+fn invalidate(sums: &mut RunningSums, textlet: usize) {
+    if sums.len() <= textlet {
+        sums.resize_with(textlet + 1, OnceLock::new);
+    }
+
+    sums[textlet] = OnceLock::new();
+}
+
+impl MarkovChain {
+    /**
+     * Enables or disables the sampling cache of this chain, which makes
+     * selecting the next word by weighted random faster at the cost of
+     * memory; see [super::sampling].
+     *
+     * The cache only applies to the edges from (or toward) a single word;
+     * edges found after several words, in higher-order chains, and edges
+     * scaled while composing, e.g. by banned words or a loop guard, are still
+     * weighed one by one.
+     */
+    pub fn set_sampling_cache(&mut self, enabled: bool) {
+        let textlets = self.textlet_bag.len();
+
+        self.sampling = enabled.then(|| SamplingCache {
+            forward: (0..textlets).map(|_| OnceLock::new()).collect(),
+            reverse: (0..textlets).map(|_| OnceLock::new()).collect(),
+        });
+    }
+
+    /// Whether this chain has a sampling cache. See [Self::set_sampling_cache].
+    pub fn has_sampling_cache(&self) -> bool {
+        self.sampling.is_some()
+    }
+
+    /// Discards the cached sums of the edges from `from` and toward `to`.
+    pub(crate) fn invalidate_sampling(&mut self, from: usize, to: usize) {
+        if let Some(cache) = &mut self.sampling {
+            invalidate(&mut cache.forward, from);
+            invalidate(&mut cache.reverse, to);
+        }
+    }
+
+    /// Discards every cached sum, such as when edges are renumbered.
+    pub(crate) fn clear_sampling_cache(&mut self) {
+        if self.sampling.is_some() {
+            self.set_sampling_cache(true);
+        }
+    }
+
+    /**
+     * Picks one of the edges from (or toward) a textlet at random, weighted by
+     * hits, and returns its index; or None if this chain has no sampling
+     * cache, or the textlet has no edges with any hits.
+     */
+    pub(crate) fn sample_edge<R: Rng>(
+        &self,
+        textlet: usize,
+        direction: MarkovTraverseDir,
+        rng: &mut R,
+    ) -> Option<usize> {
+        let cache = self.sampling.as_ref()?;
+
+        let sums = match direction {
+            MarkovTraverseDir::Forward => &cache.forward,
+            MarkovTraverseDir::Reverse => &cache.reverse,
+        };
+
+        let edges = self.adjacent(textlet, direction);
+
+        let sums = sums.get(textlet)?.get_or_init(|| {
+            let mut total = 0;
+
+            edges
+                .iter()
+                .map(|edge| {
+                    total += self.edge_list[*edge].hits;
+                    total
+                })
+                .collect()
+        });
+
+        let total = *sums.last()?;
+
+        if total == 0 {
+            return None;
+        }
+
+        let pick = rng.gen_range(0..total);
+
+        Some(edges[sums.partition_point(|sum| *sum <= pick)])
+    }
+}
@@ -0,0 +1,117 @@
+/*!
+ * Reranking of composed sentences.
+ *
+ * [MarkovChain::compose_best] composes several candidate sentences and keeps
+ * the one which a [SentenceScorer] scores the highest; e.g. the most likely
+ * one, the one closest to a length, or the best according to a neural
+ * network.
+ */
+
+use super::body::{MarkovChain, MarkovSeed};
+use super::compose::ComposeOptions;
+use super::error::MarkovError;
+use super::selectors::interface::MarkovSelector;
+use super::token::TokenList;
+
+/**
+ * An object which scores composed sentences; higher is better.
+ *
+ * Implemented for closures taking the chain and the sentence.
+ */
+pub trait SentenceScorer {
+    /// Scores a sentence composed from a chain.
+    fn score(&mut self, chain: &MarkovChain, sentence: &TokenList<'_>) -> f64;
+}
+
+impl<F: FnMut(&MarkovChain, &TokenList<'_>) -> f64> SentenceScorer for F {
+    fn score(&mut self, chain: &MarkovChain, sentence: &TokenList<'_>) -> f64 {
+        self(chain, sentence)
+    }
+}
+
+/// Prefers sentences with a number of words closest to a target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LengthScorer {
+    /// The ideal number of words.
+    pub target_words: usize,
+}
+
+impl SentenceScorer for LengthScorer {
+    fn score(&mut self, _chain: &MarkovChain, sentence: &TokenList<'_>) -> f64 {
+        -(sentence.num_words().abs_diff(self.target_words) as f64)
+    }
+}
+
+/**
+ * Prefers the sentences which the chain is the most likely to produce, by
+ * their average log probability per word (see [MarkovChain::perplexity]),
+ * so that longer sentences are not penalized for their length alone.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LikelihoodScorer;
+
+impl SentenceScorer for LikelihoodScorer {
+    fn score(&mut self, chain: &MarkovChain, sentence: &TokenList<'_>) -> f64 {
+        -chain.perplexity([sentence.to_string()]).ln()
+    }
+}
+
+impl MarkovChain {
+    /**
+     * Composes `n_candidates` sentences from a seed and returns the one with
+     * the highest score, according to `scorer`.
+     *
+     * Candidates which fail to be composed are skipped; fails only if every
+     * one of them does (or with [MarkovError::Unsatisfiable] if there are no
+     * candidates).
+     */
+    pub fn compose_best<'a>(
+        &'a self,
+        seed: MarkovSeed,
+        selector: &mut dyn MarkovSelector,
+        scorer: &mut dyn SentenceScorer,
+        n_candidates: usize,
+    ) -> Result<TokenList<'a>, MarkovError> {
+        self.compose_best_with_options(
+            seed,
+            selector,
+            &ComposeOptions::default(),
+            scorer,
+            n_candidates,
+        )
+    }
+
+    /**
+     * Composes `n_candidates` sentences like [Self::compose_best], within
+     * the limits of some [ComposeOptions].
+     */
+    pub fn compose_best_with_options<'a>(
+        &'a self,
+        seed: MarkovSeed,
+        selector: &mut dyn MarkovSelector,
+        options: &ComposeOptions,
+        scorer: &mut dyn SentenceScorer,
+        n_candidates: usize,
+    ) -> Result<TokenList<'a>, MarkovError> {
+        let mut best: Option<(f64, TokenList<'a>)> = None;
+        let mut error = MarkovError::Unsatisfiable;
+
+        for _ in 0..n_candidates {
+            let candidate = match self.compose(seed.clone(), selector, options) {
+                Ok(candidate) => candidate,
+                Err(err) => {
+                    error = err;
+                    continue;
+                }
+            };
+
+            let score = scorer.score(self, &candidate);
+
+            if best.as_ref().is_none_or(|(best, _)| score > *best) {
+                best = Some((score, candidate));
+            }
+        }
+
+        best.map(|(_, sentence)| sentence).ok_or(error)
+    }
+}
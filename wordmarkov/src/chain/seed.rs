@@ -0,0 +1,231 @@
+/*!
+ * Fallbacks for seed words which are not in a chain, and how random seed
+ * words are picked.
+ *
+ * By default, composing a sentence from a seed word which the chain does not
+ * know fails. With a [SeedFallback], the chain instead tries variants of the
+ * word, and then, optionally, a random word.
+ *
+ * Random seed words are picked uniformly by default, so that sentences
+ * mostly pivot on the most common words, such as "the" or "of". With
+ * [RandomSeeding], a chain can skip stop words, or favor rarer words.
+ */
+
+use super::body::{MarkovChain, MarkovSeed, BEGIN, END};
+use super::error::MarkovError;
+use super::token::MarkovTokenOwned;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use std::collections::HashSet;
+
+/**
+ * What a chain tries when a seed word is not found. See
+ * [MarkovChain::set_seed_fallback].
+ *
+ * Fallbacks are tried in the order of these fields.
+ */
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SeedFallback {
+    /// Try the word in lowercase.
+    pub lowercase: bool,
+
+    /// Try words with the same stem, e.g. "walked" for "walking"; see
+    /// [stem].
+    pub stem: bool,
+
+    /// Pick a random word, as [crate::chain::body::MarkovSeed::Random] would.
+    pub random: bool,
+}
+
+impl SeedFallback {
+    /// Every fallback, ending with a random word.
+    pub const ALL: SeedFallback = SeedFallback {
+        lowercase: true,
+        stem: true,
+        random: true,
+    };
+}
+
+/**
+ * How random seed words are weighed, by how many times each was found (see
+ * [MarkovChain::frequency]). See [RandomSeeding].
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SeedWeighting {
+    /// Every word is as likely.
+    #[default]
+    Uniform,
+
+    /// Words are less likely the more often they were found.
+    InverseFrequency,
+
+    /// Words are likelier the closer they are to the median frequency, so
+    /// that neither the most common words nor one-off typos are favored.
+    MidFrequency,
+}
+
+/**
+ * How a chain picks random seed words, as for
+ * [crate::chain::body::MarkovSeed::Random]. See
+ * [MarkovChain::set_random_seeding].
+ *
+ * With anything but the default, the begin and end tokens are never picked.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RandomSeeding {
+    /// How words are weighed.
+    pub weighting: SeedWeighting,
+
+    /// Words never to pick, such as [ENGLISH_STOP_WORDS]; unless no other
+    /// word can be.
+    pub stop_words: Vec<String>,
+}
+
+/// Common English words which make for dull seed words.
+pub const ENGLISH_STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "he",
+    "her", "his", "i", "if", "in", "is", "it", "its", "me", "my", "not", "of", "on", "or", "she",
+    "so", "that", "the", "their", "them", "they", "this", "to", "was", "we", "were", "what",
+    "when", "which", "who", "will", "with", "you", "your",
+];
+
+/**
+ * A crude stem of a lowercase English word, made by stripping a common
+ * suffix, such as "-ing" or "-s", as long as at least three letters are
+ * left.
+ */
+pub fn stem(word: &str) -> &str {
+    for suffix in ["ing", "ed", "es", "ly", "s"] {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            if stem.chars().count() >= 3 {
+                return stem;
+            }
+        }
+    }
+
+    word
+}
+
+impl MarkovChain {
+    /// Sets what this chain tries when a seed word is not found.
+    pub fn set_seed_fallback(&mut self, fallback: SeedFallback) {
+        self.seed_fallback = fallback;
+    }
+
+    /// What this chain tries when a seed word is not found. See
+    /// [Self::set_seed_fallback].
+    pub fn seed_fallback(&self) -> SeedFallback {
+        self.seed_fallback
+    }
+
+    /// Sets how this chain picks random seed words.
+    pub fn set_random_seeding(&mut self, seeding: RandomSeeding) {
+        self.random_seeding = seeding;
+    }
+
+    /// How this chain picks random seed words. See
+    /// [Self::set_random_seeding].
+    pub fn random_seeding(&self) -> &RandomSeeding {
+        &self.random_seeding
+    }
+
+    /**
+     * Finds the textlet, by index, which a sentence composed from a seed
+     * would start from: the seed word itself, a fallback for it (see
+     * [SeedFallback]), or a random word; e.g. to tell which word a random
+     * seed picked, and then compose from it with [MarkovSeed::Id].
+     */
+    pub fn resolve_seed<R: Rng>(
+        &self,
+        seed: MarkovSeed,
+        rng: &mut R,
+    ) -> Result<usize, MarkovError> {
+        self.get_seed(seed, rng)
+    }
+
+    /**
+     * Picks a random seed word according to the [RandomSeeding] of this
+     * chain, or returns None if it is the default, or if every word is
+     * excluded.
+     */
+    pub(crate) fn random_seed<R: Rng>(&self, rng: &mut R) -> Option<usize> {
+        let seeding = &self.random_seeding;
+
+        if *seeding == RandomSeeding::default() {
+            return None;
+        }
+
+        let stop_words: HashSet<usize> = seeding
+            .stop_words
+            .iter()
+            .filter_map(|word| self.try_get_textlet_index(word))
+            .chain([BEGIN, END])
+            .collect();
+
+        let candidates: Vec<usize> = self
+            .words
+            .iter()
+            .copied()
+            .filter(|word| !stop_words.contains(word))
+            .collect();
+
+        let frequency = |word: usize| self.frequency(word).max(1) as f64;
+
+        let weights: Vec<f64> = match seeding.weighting {
+            SeedWeighting::Uniform => vec![1.0; candidates.len()],
+
+            SeedWeighting::InverseFrequency => candidates
+                .iter()
+                .map(|word| 1.0 / frequency(*word))
+                .collect(),
+
+            SeedWeighting::MidFrequency => {
+                let mut frequencies: Vec<f64> =
+                    candidates.iter().map(|word| frequency(*word)).collect();
+
+                frequencies.sort_by(f64::total_cmp);
+
+                let median = frequencies.get(frequencies.len() / 2)?.ln();
+
+                // a bell curve over the logarithm of the frequency
+                candidates
+                    .iter()
+                    .map(|word| (-(frequency(*word).ln() - median).powi(2) / 2.0).exp())
+                    .collect()
+            }
+        };
+
+        let index = WeightedIndex::new(&weights).ok()?.sample(rng);
+
+        Some(candidates[index])
+    }
+
+    /**
+     * Finds a word to seed a sentence with in place of one which is not in
+     * this chain, according to its [SeedFallback], other than a random one.
+     */
+    pub(crate) fn seed_variant(&self, word: &str) -> Option<usize> {
+        let lower = word.to_lowercase();
+
+        if self.seed_fallback.lowercase {
+            if let Some(idx) = self.try_get_textlet_index(&lower) {
+                return Some(idx);
+            }
+        }
+
+        if self.seed_fallback.stem {
+            let wanted = stem(&lower);
+
+            return self
+                .words
+                .iter()
+                .copied()
+                .find(|idx| match &self.textlet_bag[*idx] {
+                    MarkovTokenOwned::Textlet(word) => stem(&word.to_lowercase()) == wanted,
+                    _ => false,
+                });
+        }
+
+        None
+    }
+}
@@ -25,9 +25,9 @@ impl MarkovSelector for StaticBestSelector {
     }
 }
 
-pub struct NaiveRandomSelector;
+pub struct StaticWorstSelector;
 
-impl MarkovSelector for NaiveRandomSelector {
+impl MarkovSelector for StaticWorstSelector {
     fn reset(&mut self, _dir: MarkovTraverseDir) {}
 
     fn weight<'a>(
@@ -0,0 +1,71 @@
+//! A weighted random selector which favors less common words.
+
+use crate::prelude::{Edge, MarkovChain, MarkovTraverseDir};
+
+use super::super::token::MarkovToken;
+use super::interface::{MarkovSelector, SelectionContext, SelectionType};
+
+/**
+ * Picks at random, weighted by hits, but divides the weight of each edge by
+ * how many times the word it leads to was found in the whole chain (see
+ * [MarkovChain::frequency]), raised to `strength`; like TF-IDF, this favors
+ * words which are common after the current one, but not everywhere, for
+ * less generic sentences.
+ *
+ * A `strength` of 0 weighs by hits alone; 1 weighs each word by how much
+ * likelier it is after the current word than anywhere else. The begin and
+ * end tokens are weighed by hits alone, so as not to make sentences longer.
+ *
+ * Chains which do not tell selectors about their edges, such as
+ * [crate::chain::compact::CompactChain], are weighed by hits alone.
+ */
+pub struct InverseFrequencySelector {
+    pub strength: f32,
+    direction: MarkovTraverseDir,
+}
+
+impl InverseFrequencySelector {
+    pub fn new(strength: f32) -> Self {
+        Self {
+            strength,
+            direction: MarkovTraverseDir::Forward,
+        }
+    }
+}
+
+impl MarkovSelector for InverseFrequencySelector {
+    fn reset(&mut self, dir: MarkovTraverseDir) {
+        self.direction = dir;
+    }
+
+    fn prepare(&mut self, context: &SelectionContext<'_, '_>) {
+        self.direction = context.direction;
+    }
+
+    fn weight<'a>(
+        &mut self,
+        _from: &MarkovToken<'a>,
+        _to: &MarkovToken<'a>,
+        _punct: &MarkovToken<'a>,
+        hits: usize,
+    ) -> f32 {
+        hits as f32
+    }
+
+    fn weight_edge(&mut self, chain: &MarkovChain, edge: &Edge, hits: usize) -> f32 {
+        let word = match self.direction {
+            MarkovTraverseDir::Forward => edge.dst_idx,
+            MarkovTraverseDir::Reverse => edge.src_idx,
+        };
+
+        if word == chain.begin() || word == chain.end() {
+            return hits as f32;
+        }
+
+        hits as f32 / (chain.frequency(word).max(1) as f32).powf(self.strength)
+    }
+
+    fn selection_type(&mut self) -> SelectionType {
+        SelectionType::WeightedRandom
+    }
+}
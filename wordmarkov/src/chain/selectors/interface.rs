@@ -3,7 +3,7 @@
  * deciding the next state of a Markov chain.
  */
 
-use crate::prelude::{MarkovToken, MarkovTraverseDir};
+use crate::prelude::{Edge, MarkovChain, MarkovToken, MarkovTraverseDir, TokenList};
 
 /**
  * The way in which the weights returned by [MarkovSelector::weight] should be
@@ -14,6 +14,25 @@ pub enum SelectionType {
     Highest,
     Lowest,
 }
+
+/**
+ * What is known of the sentence being composed when its next word is about to
+ * be selected. See [MarkovSelector::prepare].
+ */
+pub struct SelectionContext<'s, 'a> {
+    /// The tokens chosen so far, in the order they will be in the sentence.
+    pub sentence: &'s TokenList<'a>,
+
+    /// The direction the next word is selected in.
+    pub direction: MarkovTraverseDir,
+
+    /// How many more characters may be added in this direction, if limited.
+    pub remaining_len: Option<usize>,
+
+    /// How many more words may be added in this direction, if limited.
+    pub remaining_words: Option<usize>,
+}
+
 /**
  * An object which can be used as a selector for a Markov chain.
  */
@@ -21,10 +40,29 @@ pub trait MarkovSelector {
     /**
      * Reset the state of this MarkovSelector.
      *
-     * Must always be called before composing a new sentence.
+     * Must always be called before composing a new sentence, with the
+     * direction walked first. Composing a sentence does so.
      */
     fn reset(&mut self, direction: MarkovTraverseDir);
 
+    /**
+     * Told the seed of each sentence being composed, and then each word
+     * selected (including the begin and end tokens), so that stateful
+     * selectors can keep track of the sentence.
+     *
+     * Does nothing by default.
+     */
+    fn feedback(&mut self, _chosen: &MarkovToken<'_>) {}
+
+    /**
+     * Told the sentence composed so far before each next word is weighed,
+     * so that selectors can take the whole sentence, the direction walked,
+     * and how much room is left into account, rather than only each edge.
+     *
+     * Does nothing by default.
+     */
+    fn prepare(&mut self, _context: &SelectionContext<'_, '_>) {}
+
     /**
      * The weight of a particular link.
      *
@@ -43,9 +81,49 @@ pub trait MarkovSelector {
         occurrences: usize,
     ) -> f32;
 
+    /**
+     * The weight of a particular edge of a [MarkovChain], found `occurrences`
+     * times (after the words walked last, in higher-order chains); this is
+     * what [MarkovChain] asks for, so that selectors can look into the chain,
+     * e.g. at the metadata of each word (see [MarkovChain::metadata]).
+     *
+     * Calls [Self::weight] by default.
+     */
+    fn weight_edge(&mut self, chain: &MarkovChain, edge: &Edge, occurrences: usize) -> f32 {
+        self.weight(
+            &edge.get_source(chain),
+            &edge.get_dest(chain),
+            &edge.get_punct(chain),
+            occurrences,
+        )
+    }
+
     /**
      * Returns the [SelectionType] of this Selector; this will decide how the
      * weight returned by [Self::weight()] should be interpreted.
      */
     fn selection_type(&mut self) -> SelectionType;
+
+    /**
+     * Adjusts the weights of every possible next state at once, after they
+     * were all weighed; e.g. to zero out all but the best few of them.
+     * States whose weight this sets to zero are never selected, whatever the
+     * [SelectionType]; if they all are, there is no next state.
+     *
+     * Does nothing by default.
+     */
+    fn filter_weights(&mut self, _weights: &mut [f32]) {}
+
+    /**
+     * Whether this selector weighs each edge by its hits alone, for
+     * [SelectionType::WeightedRandom], and filters no weights; if so, a
+     * [MarkovChain] with a sampling cache (see
+     * [MarkovChain::set_sampling_cache]) may select edges without asking for
+     * their weights at all, only giving feedback.
+     *
+     * False by default.
+     */
+    fn weighs_by_hits(&self) -> bool {
+        false
+    }
 }
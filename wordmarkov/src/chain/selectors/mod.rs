@@ -6,7 +6,10 @@
  */
 
 pub mod fixed;
+pub mod frequency;
 pub mod interface;
 pub mod random;
+pub mod repetition;
+pub mod truncated;
 
 pub mod prelude;
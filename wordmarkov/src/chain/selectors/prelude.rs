@@ -1,5 +1,8 @@
 //! Useful imports for selectors.
 
 pub use super::fixed::*;
+pub use super::frequency::*;
 pub use super::interface::*;
 pub use super::random::*;
+pub use super::repetition::*;
+pub use super::truncated::*;
@@ -23,6 +23,10 @@ impl MarkovSelector for WeightedRandomSelector {
     fn selection_type(&mut self) -> SelectionType {
         SelectionType::WeightedRandom
     }
+
+    fn weighs_by_hits(&self) -> bool {
+        true
+    }
 }
 
 pub struct NaiveRandomSelector;
@@ -44,3 +48,53 @@ impl MarkovSelector for NaiveRandomSelector {
         SelectionType::WeightedRandom
     }
 }
+
+/**
+ * Picks at random, weighted by hits raised to the power of `1 / temperature`;
+ * a temperature below 1 favors the most frequent edges, above 1 flattens the
+ * odds, and 1 is the same as [WeightedRandomSelector]. A temperature of 0 (or
+ * below) always picks the most frequent edge.
+ */
+pub struct TemperatureSelector {
+    pub temperature: f32,
+}
+
+impl MarkovSelector for TemperatureSelector {
+    fn reset(&mut self, _dir: MarkovTraverseDir) {}
+
+    fn weight<'a>(
+        &mut self,
+        _from: &MarkovToken<'a>,
+        _to: &MarkovToken<'a>,
+        _punct: &MarkovToken<'a>,
+        hits: usize,
+    ) -> f32 {
+        hits as f32
+    }
+
+    fn selection_type(&mut self) -> SelectionType {
+        if self.temperature > 0.0 {
+            SelectionType::WeightedRandom
+        } else {
+            SelectionType::Highest
+        }
+    }
+
+    fn filter_weights(&mut self, weights: &mut [f32]) {
+        if self.temperature <= 0.0 {
+            return;
+        }
+
+        let max = weights.iter().copied().fold(0.0, f32::max);
+
+        if max <= 0.0 {
+            return;
+        }
+
+        // relative to the highest weight, so that low temperatures cannot
+        // overflow
+        for weight in weights {
+            *weight = (*weight / max).powf(1.0 / self.temperature);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! A weighted random selector which avoids repeating words.
+
+use std::collections::HashMap;
+
+use crate::prelude::MarkovTraverseDir;
+
+use super::super::token::MarkovToken;
+use super::interface::{MarkovSelector, SelectionContext, SelectionType};
+
+/**
+ * Picks at random, weighted by hits, but scales the weight of each edge by
+ * `penalty` (between 0 and 1) for every time the word it leads to was
+ * already in the sentence, so that phrases are seldom repeated.
+ *
+ * When every edge leads to a repeated word, they are weighed by hits alone.
+ */
+pub struct AntiRepetitionSelector {
+    pub penalty: f32,
+    seen: HashMap<String, i32>,
+    direction: MarkovTraverseDir,
+
+    /// The hits of each edge weighed in this step.
+    weighed: Vec<f32>,
+}
+
+impl AntiRepetitionSelector {
+    pub fn new(penalty: f32) -> Self {
+        Self {
+            penalty,
+            seen: HashMap::new(),
+            direction: MarkovTraverseDir::Forward,
+            weighed: vec![],
+        }
+    }
+
+    fn times_seen(&self, token: &MarkovToken<'_>) -> i32 {
+        match token {
+            MarkovToken::Textlet(word) => self.seen.get(*word).copied().unwrap_or(0),
+            _ => 0,
+        }
+    }
+}
+
+impl MarkovSelector for AntiRepetitionSelector {
+    fn reset(&mut self, dir: MarkovTraverseDir) {
+        self.seen.clear();
+        self.direction = dir;
+        self.weighed.clear();
+    }
+
+    fn feedback(&mut self, chosen: &MarkovToken<'_>) {
+        if let MarkovToken::Textlet(word) = chosen {
+            *self.seen.entry(word.to_string()).or_default() += 1;
+        }
+    }
+
+    fn prepare(&mut self, context: &SelectionContext<'_, '_>) {
+        self.direction = context.direction;
+    }
+
+    fn weight<'a>(
+        &mut self,
+        from: &MarkovToken<'a>,
+        to: &MarkovToken<'a>,
+        _punct: &MarkovToken<'a>,
+        hits: usize,
+    ) -> f32 {
+        // the word each edge leads to, in the direction walked
+        let seen = match self.direction {
+            MarkovTraverseDir::Forward => self.times_seen(to),
+            MarkovTraverseDir::Reverse => self.times_seen(from),
+        };
+
+        self.weighed.push(hits as f32);
+
+        hits as f32 * self.penalty.powi(seen)
+    }
+
+    fn selection_type(&mut self) -> SelectionType {
+        SelectionType::WeightedRandom
+    }
+
+    fn filter_weights(&mut self, weights: &mut [f32]) {
+        let hits = std::mem::take(&mut self.weighed);
+
+        if hits.len() == weights.len() && weights.iter().all(|weight| *weight <= 0.0) {
+            weights.copy_from_slice(&hits);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Weighted random selectors restricted to the most frequent edges.
+
+use crate::prelude::MarkovTraverseDir;
+
+use super::super::token::MarkovToken;
+use super::interface::{MarkovSelector, SelectionType};
+
+/// The indices of some weights, from the highest to the lowest.
+fn by_weight(weights: &[f32]) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..weights.len()).collect();
+
+    indices.sort_by(|a, b| weights[*b].total_cmp(&weights[*a]));
+
+    indices
+}
+
+/**
+ * Picks at random, weighted by hits, among only the `k` edges found the most
+ * times (top-k sampling).
+ */
+pub struct TopKSelector {
+    pub k: usize,
+}
+
+impl MarkovSelector for TopKSelector {
+    fn reset(&mut self, _dir: MarkovTraverseDir) {}
+
+    fn weight<'a>(
+        &mut self,
+        _from: &MarkovToken<'a>,
+        _to: &MarkovToken<'a>,
+        _punct: &MarkovToken<'a>,
+        hits: usize,
+    ) -> f32 {
+        hits as f32
+    }
+
+    fn selection_type(&mut self) -> SelectionType {
+        SelectionType::WeightedRandom
+    }
+
+    fn filter_weights(&mut self, weights: &mut [f32]) {
+        for idx in by_weight(weights).into_iter().skip(self.k.max(1)) {
+            weights[idx] = 0.0;
+        }
+    }
+}
+
+/**
+ * Picks at random, weighted by hits, among only the fewest most frequent
+ * edges whose probabilities add up to at least `p` (top-p, or nucleus,
+ * sampling).
+ */
+pub struct TopPSelector {
+    pub p: f32,
+}
+
+impl MarkovSelector for TopPSelector {
+    fn reset(&mut self, _dir: MarkovTraverseDir) {}
+
+    fn weight<'a>(
+        &mut self,
+        _from: &MarkovToken<'a>,
+        _to: &MarkovToken<'a>,
+        _punct: &MarkovToken<'a>,
+        hits: usize,
+    ) -> f32 {
+        hits as f32
+    }
+
+    fn selection_type(&mut self) -> SelectionType {
+        SelectionType::WeightedRandom
+    }
+
+    fn filter_weights(&mut self, weights: &mut [f32]) {
+        let total: f32 = weights.iter().sum();
+        let mut cumulative = 0.0;
+
+        for idx in by_weight(weights) {
+            // always keep the most frequent edge
+            if cumulative > 0.0 && cumulative >= self.p * total {
+                weights[idx] = 0.0;
+            } else {
+                cumulative += weights[idx];
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+/*!
+ * Serialization of [MarkovChain]s.
+ *
+ * Only available with the `serde` feature. A chain is serialized as its list
+ * of textlets, its list of seed words, its list of edges, its order and
+ * higher-order contexts, its case folding and surface forms, and its lexer
+ * config; the lookup
+ * tables are rebuilt when it is deserialized, and each textlet is stored
+ * only once.
+ *
+ * [MarkovChain::save_to] and [MarkovChain::load_from] use bincode, a compact
+ * binary encoding, so that a chain does not need to be parsed from its
+ * corpus again every time it is needed.
+ */
+
+use super::body::{ContextTable, Edge, MarkovChain};
+use super::case::CaseFolding;
+use super::token::MarkovTokenOwned;
+use crate::sentence::lex::LexerConfig;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+#[derive(Serialize)]
+struct ChainDataRef<'a> {
+    textlets: &'a [MarkovTokenOwned],
+    words: &'a [usize],
+    edges: &'a [Edge],
+    order: usize,
+    forward_contexts: &'a ContextTable,
+    reverse_contexts: &'a ContextTable,
+    sentences_parsed: usize,
+    case_folding: CaseFolding,
+    surface_forms: Vec<(&'a str, usize)>,
+    lexer_config: &'a LexerConfig,
+}
+
+#[derive(Deserialize)]
+struct ChainData {
+    textlets: Vec<MarkovTokenOwned>,
+    words: Vec<usize>,
+    edges: Vec<Edge>,
+    order: usize,
+    forward_contexts: ContextTable,
+    reverse_contexts: ContextTable,
+    sentences_parsed: usize,
+    case_folding: CaseFolding,
+    surface_forms: Vec<(String, usize)>,
+    lexer_config: LexerConfig,
+}
+
+impl Serialize for MarkovChain {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ChainDataRef {
+            textlets: &self.textlet_bag,
+            words: &self.words,
+            edges: &self.edge_list,
+            order: self.order,
+            forward_contexts: &self.forward_contexts,
+            reverse_contexts: &self.reverse_contexts,
+            sentences_parsed: self.sentences_parsed,
+            case_folding: self.case_folding,
+            surface_forms: self
+                .surface_forms
+                .values()
+                .flatten()
+                .map(|(form, hits)| (&**form, *hits))
+                .collect(),
+            lexer_config: &self.lexer_config,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MarkovChain {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = ChainData::deserialize(deserializer)?;
+
+        MarkovChain::from_parts(data.textlets, data.words, data.edges)
+            .and_then(|chain| {
+                chain.with_contexts(data.order, data.forward_contexts, data.reverse_contexts)
+            })
+            .map(|mut chain| {
+                chain.sentences_parsed = data.sentences_parsed;
+                chain.case_folding = data.case_folding;
+                chain.lexer_config = data.lexer_config;
+
+                for (form, hits) in &data.surface_forms {
+                    chain.add_surface_form(form, *hits);
+                }
+
+                chain
+            })
+            .map_err(D::Error::custom)
+    }
+}
+
+impl MarkovChain {
+    /**
+     * Writes this chain, in bincode, to a writer.
+     */
+    pub fn save_to<W: Write>(&self, writer: W) -> Result<(), String> {
+        bincode::serialize_into(writer, self).map_err(|err| err.to_string())
+    }
+
+    /**
+     * Reads a chain, in bincode, from a reader.
+     */
+    pub fn load_from<R: Read>(reader: R) -> Result<MarkovChain, String> {
+        bincode::deserialize_from(reader).map_err(|err| err.to_string())
+    }
+
+    /**
+     * Writes this chain to a file. See [Self::save_to].
+     */
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let file = File::create(path).map_err(|err| err.to_string())?;
+        let mut writer = BufWriter::new(file);
+
+        self.save_to(&mut writer)?;
+        writer.flush().map_err(|err| err.to_string())
+    }
+
+    /**
+     * Reads a chain from a file. See [Self::load_from].
+     */
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<MarkovChain, String> {
+        let file = File::open(path).map_err(|err| err.to_string())?;
+
+        Self::load_from(BufReader::new(file))
+    }
+}
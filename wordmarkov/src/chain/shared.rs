@@ -0,0 +1,134 @@
+/*!
+ * A chain shared between threads which both learn from it and compose from
+ * it, such as those of a chat bot serving many users at once.
+ *
+ * A [MarkovChain] can already be composed from by any number of threads
+ * behind an [Arc], but parsing into it needs it to be locked. A
+ * [SharedMarkovChain] does the locking: any number of threads can compose
+ * from it at once, and each sentence parsed into it locks it only for as long
+ * as that sentence takes, so that composing threads are never kept waiting
+ * for long, not even while a whole text is parsed.
+ */
+
+use super::body::{MarkovChain, MarkovSeed};
+use super::compose::ComposeOptions;
+use super::error::MarkovError;
+use super::selectors::interface::MarkovSelector;
+use crate::sentence::lex::LexerConfig;
+use crate::sentence::split::split_sentences;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/**
+ * A [MarkovChain] behind a read-write lock, which can be cloned and sent to
+ * other threads; every clone refers to the same chain.
+ *
+ * Composed sentences are returned as strings, since they cannot outlive the
+ * lock. For anything else, [Self::read] and [Self::write] lock the chain
+ * itself.
+ *
+ * If a thread panics while parsing into the chain, the chain is still
+ * usable, but the sentence may have been only partly parsed.
+ */
+#[derive(Clone, Default)]
+pub struct SharedMarkovChain {
+    chain: Arc<RwLock<MarkovChain>>,
+}
+
+impl From<MarkovChain> for SharedMarkovChain {
+    fn from(chain: MarkovChain) -> Self {
+        Self::new(chain)
+    }
+}
+
+impl SharedMarkovChain {
+    /// Shares a chain.
+    pub fn new(chain: MarkovChain) -> SharedMarkovChain {
+        SharedMarkovChain {
+            chain: Arc::new(RwLock::new(chain)),
+        }
+    }
+
+    /**
+     * Locks the chain for reading, waiting for any thread parsing into it,
+     * e.g. to score sentences or walk it.
+     */
+    pub fn read(&self) -> RwLockReadGuard<'_, MarkovChain> {
+        self.chain.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /**
+     * Locks the chain for writing, waiting for every thread using it, e.g. to
+     * prune or decay it. Keep it locked for as short as possible.
+     */
+    pub fn write(&self) -> RwLockWriteGuard<'_, MarkovChain> {
+        self.chain.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /**
+     * Unshares the chain, if this is the last clone referring to it;
+     * otherwise, returns this clone back.
+     */
+    pub fn try_into_inner(self) -> Result<MarkovChain, SharedMarkovChain> {
+        match Arc::try_unwrap(self.chain) {
+            Ok(lock) => Ok(lock.into_inner().unwrap_or_else(PoisonError::into_inner)),
+            Err(chain) => Err(SharedMarkovChain { chain }),
+        }
+    }
+
+    /// Composes a sentence, as [MarkovChain::compose_sentence] does.
+    pub fn compose_sentence(
+        &self,
+        seed: MarkovSeed,
+        selector: &mut dyn MarkovSelector,
+        max_len: Option<usize>,
+    ) -> Result<String, MarkovError> {
+        let chain = self.read();
+        let sentence = chain.compose_sentence(seed, selector, max_len)?;
+
+        Ok(sentence.to_string())
+    }
+
+    /// Composes a sentence within the limits of some [ComposeOptions], as
+    /// [MarkovChain::compose] does.
+    pub fn compose(
+        &self,
+        seed: MarkovSeed,
+        selector: &mut dyn MarkovSelector,
+        options: &ComposeOptions,
+    ) -> Result<String, MarkovError> {
+        let chain = self.read();
+        let sentence = chain.compose(seed, selector, options)?;
+
+        Ok(sentence.to_string())
+    }
+
+    /// Parses a sentence, as [MarkovChain::parse_sentence] does.
+    pub fn parse_sentence(&self, sentence: &str) {
+        self.write().parse_sentence(sentence);
+    }
+
+    /**
+     * Parses some text, as [MarkovChain::parse_text] does, locking the chain
+     * for each sentence in turn rather than for the whole text. Returns how
+     * many sentences were parsed.
+     */
+    pub fn parse_text(&self, text: &str) -> usize {
+        let config = self.read().lexer_config().clone();
+
+        self.parse_text_with_config(text, &config)
+    }
+
+    /**
+     * Parses some text like [Self::parse_text], splitting each sentence
+     * according to a [LexerConfig].
+     */
+    pub fn parse_text_with_config(&self, text: &str, config: &LexerConfig) -> usize {
+        let sentences = split_sentences(text);
+
+        for sentence in &sentences {
+            self.write().parse_sentence_with_config(sentence, config);
+        }
+
+        sentences.len()
+    }
+}
@@ -4,7 +4,7 @@ use crate::sentence::token::Token as LexedToken;
 use std::collections::LinkedList;
 use std::fmt::Display;
 use std::fmt::Formatter;
-use std::rc::Rc;
+use std::sync::Arc;
 
 /// A Markov token.
 #[derive(Eq, PartialEq, Debug)]
@@ -54,10 +54,11 @@ impl<'a> From<&LexedToken<'a>> for MarkovToken<'a> {
 
 /// A Markov token, but owned. Only used from MarkovChain.
 #[derive(Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MarkovTokenOwned {
     Begin,
     End,
-    Textlet(Rc<str>),
+    Textlet(Arc<str>),
 }
 
 impl<'a> From<&'a MarkovTokenOwned> for MarkovToken<'a> {
@@ -91,7 +92,7 @@ impl<'a> From<&'a MarkovToken<'a>> for &'a str {
 pub struct TokenList<'a>(pub LinkedList<MarkovToken<'a>>);
 
 impl<'a> TokenList<'a> {
-    pub fn iter(&self) -> std::collections::linked_list::Iter<MarkovToken> {
+    pub fn iter(&self) -> std::collections::linked_list::Iter<'_, MarkovToken<'a>> {
         self.0.iter()
     }
 
@@ -102,6 +103,18 @@ impl<'a> TokenList<'a> {
     pub fn len(&self) -> usize {
         self.iter().map(|x| x.len()).sum()
     }
+
+    /// The number of words in this list; textlets which are not only
+    /// punctuation and whitespace.
+    pub fn num_words(&self) -> usize {
+        self.iter()
+            .filter(|x| {
+                x.string_ref()
+                    .chars()
+                    .any(|c| !(c.is_ascii_punctuation() || c.is_whitespace()))
+            })
+            .count()
+    }
 }
 
 impl<'a> Display for TokenList<'a> {
@@ -0,0 +1,138 @@
+/*!
+ * Raw traversal of a chain, one step at a time.
+ *
+ * [MarkovChain::walk] selects words like composing a sentence does, but only
+ * yields the index of each word walked to and of the edge walked there, so
+ * that applications can stitch, stream or analyze walks their own way.
+ */
+
+use super::body::{MarkovChain, MarkovSeed, MarkovTraverseDir, BEGIN, END};
+use super::error::MarkovError;
+use super::selectors::interface::MarkovSelector;
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, Rng};
+use std::collections::VecDeque;
+
+/**
+ * A walk through a chain, from a seed word in one direction. See
+ * [MarkovChain::walk].
+ *
+ * Yields (textlet index, edge index) pairs: each word walked to and the edge
+ * walked to get there, whose punctuation is the one between the word and the
+ * one before it. Ends after walking to the end token (or, walking backward,
+ * the begin token), after the maximum number of steps, or when a word is not
+ * connected to anything.
+ */
+pub struct Walk<'c, 's, R: Rng = ThreadRng> {
+    chain: &'c MarkovChain,
+    selector: &'s mut dyn MarkovSelector,
+    direction: MarkovTraverseDir,
+    rng: R,
+
+    /// The words walked last, as many as the order of the chain.
+    context: VecDeque<usize>,
+
+    steps_left: Option<usize>,
+    done: bool,
+}
+
+impl<R: Rng> Iterator for Walk<'_, '_, R> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        if self.done || self.steps_left == Some(0) {
+            return None;
+        }
+
+        let edge = match self.chain.select_edge_where(
+            self.context.make_contiguous(),
+            self.selector,
+            self.direction,
+            None,
+            &mut self.rng,
+        ) {
+            Ok(edge) => edge,
+            Err(_) => {
+                self.done = true;
+                return None;
+            }
+        };
+
+        let edge_ref = &self.chain.edge_list[edge];
+
+        let next = match self.direction {
+            MarkovTraverseDir::Forward => {
+                self.context.push_back(edge_ref.dst_idx);
+                edge_ref.dst_idx
+            }
+
+            MarkovTraverseDir::Reverse => {
+                self.context.push_front(edge_ref.src_idx);
+                edge_ref.src_idx
+            }
+        };
+
+        if self.context.len() > self.chain.order {
+            match self.direction {
+                MarkovTraverseDir::Forward => self.context.pop_front(),
+                MarkovTraverseDir::Reverse => self.context.pop_back(),
+            };
+        }
+
+        self.steps_left = self.steps_left.map(|steps| steps - 1);
+        self.done = next == END || next == BEGIN;
+
+        Some((next, edge))
+    }
+}
+
+impl MarkovChain {
+    /**
+     * Walks this chain from a seed word in one direction, selecting each
+     * next word with a [MarkovSelector], for at most `max_steps` steps if
+     * given. See [Walk].
+     *
+     * The selector is reset and told the seed and each word walked to, as
+     * when composing a sentence, but no sentence is built; nor is the
+     * selector told a [super::selectors::interface::SelectionContext].
+     *
+     * Fails if the seed does, e.g. if the seed word is not in this chain.
+     */
+    pub fn walk<'c, 's>(
+        &'c self,
+        seed: MarkovSeed,
+        direction: MarkovTraverseDir,
+        selector: &'s mut dyn MarkovSelector,
+        max_steps: Option<usize>,
+    ) -> Result<Walk<'c, 's>, MarkovError> {
+        self.walk_with_rng(seed, direction, selector, max_steps, thread_rng())
+    }
+
+    /**
+     * Walks this chain like [Self::walk], drawing any random numbers from
+     * `rng`, e.g. a seeded one for reproducible walks.
+     */
+    pub fn walk_with_rng<'c, 's, R: Rng>(
+        &'c self,
+        seed: MarkovSeed,
+        direction: MarkovTraverseDir,
+        selector: &'s mut dyn MarkovSelector,
+        max_steps: Option<usize>,
+        mut rng: R,
+    ) -> Result<Walk<'c, 's, R>, MarkovError> {
+        let from = self.get_seed(seed, &mut rng)?;
+
+        selector.reset(direction);
+        selector.feedback(&self.get_textlet(from).ok_or_else(|| self.dead_end(from))?);
+
+        Ok(Walk {
+            chain: self,
+            selector,
+            direction,
+            rng,
+            context: VecDeque::from([from]),
+            steps_left: max_steps,
+            done: false,
+        })
+    }
+}
@@ -7,4 +7,7 @@
 pub mod chain;
 pub mod sentence;
 
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 pub mod prelude;
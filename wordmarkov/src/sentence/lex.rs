@@ -3,6 +3,166 @@
  */
 pub use super::token::Token;
 
+/**
+ * Kinds of text which a [Lexer] can keep whole, as a single [Token::Word],
+ * rather than splitting them at their punctuation.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AtomKind {
+    /// A web address, such as `https://example.com/page?id=1`, or one
+    /// starting with `www.`.
+    Url,
+
+    /// A mention of a user, such as `@someone`.
+    Mention,
+
+    /// A hashtag, such as `#rustlang`.
+    Hashtag,
+
+    /// An emoji code, such as `:smile:`.
+    Emoji,
+}
+
+impl AtomKind {
+    /// Every kind of atom.
+    pub const ALL: [AtomKind; 4] = [
+        AtomKind::Url,
+        AtomKind::Mention,
+        AtomKind::Hashtag,
+        AtomKind::Emoji,
+    ];
+
+    /// The word which replaces atoms of this kind when
+    /// [LexerConfig::placeholders] is set.
+    pub fn placeholder(&self) -> &'static str {
+        match self {
+            AtomKind::Url => "<url>",
+            AtomKind::Mention => "<mention>",
+            AtomKind::Hashtag => "<hashtag>",
+            AtomKind::Emoji => "<emoji>",
+        }
+    }
+
+    /// The length, in bytes, of the atom of this kind at the start of some
+    /// text, if there is one.
+    fn match_len(&self, text: &str) -> Option<usize> {
+        let name_len = |text: &str| {
+            text.find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(text.len())
+        };
+
+        match self {
+            AtomKind::Url => {
+                let lower = text.get(..8).unwrap_or(text).to_ascii_lowercase();
+
+                let prefix = ["https://", "http://", "www."]
+                    .into_iter()
+                    .find(|prefix| lower.starts_with(prefix))?;
+
+                let end = text.find(char::is_whitespace).unwrap_or(text.len());
+
+                // punctuation right after an address most likely ends the
+                // sentence or clause, rather than belonging to it
+                let len = text[..end]
+                    .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '}', '\'', '"'])
+                    .len();
+
+                (len > prefix.len()).then_some(len)
+            }
+
+            AtomKind::Mention => {
+                let len = name_len(text.strip_prefix('@')?);
+
+                (len > 0).then_some(len + 1)
+            }
+
+            AtomKind::Hashtag => {
+                let tag = text.strip_prefix('#')?;
+                let len = name_len(tag);
+
+                // "#1" is more likely a number than a tag
+                tag[..len]
+                    .contains(|c: char| !c.is_ascii_digit())
+                    .then_some(len + 1)
+            }
+
+            AtomKind::Emoji => {
+                let code = text.strip_prefix(':')?;
+                let len = code
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || "_+-".contains(c)))
+                    .unwrap_or(code.len());
+
+                (len > 0 && code[len..].starts_with(':')).then_some(len + 2)
+            }
+        }
+    }
+}
+
+/**
+ * Which characters a [Lexer] considers part of words, rather than
+ * punctuation, and which kinds of [AtomKind] it keeps whole.
+ *
+ * By default, ASCII punctuation and whitespace are punctuation, every other
+ * character is part of words, and no atoms are recognized.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LexerConfig {
+    /// Characters which are always part of words, such as `_`.
+    pub word_chars: Vec<char>,
+
+    /// Characters which are part of words only between two word characters,
+    /// such as `'` in "don't" or `-` in "high-priest".
+    pub word_internal_chars: Vec<char>,
+
+    /// Characters which are always punctuation.
+    pub punct_chars: Vec<char>,
+
+    /// Kinds of atoms which are kept whole, as single words.
+    pub atoms: Vec<AtomKind>,
+
+    /// Whether atoms are replaced by their [AtomKind::placeholder], so that
+    /// e.g. every URL is the same word.
+    pub placeholders: bool,
+}
+
+static DEFAULT_CONFIG: LexerConfig = LexerConfig {
+    word_chars: Vec::new(),
+    word_internal_chars: Vec::new(),
+    punct_chars: Vec::new(),
+    atoms: Vec::new(),
+    placeholders: false,
+};
+
+impl LexerConfig {
+    /// Whether a character is punctuation, regardless of its neighbours.
+    pub fn is_punct(&self, char: char) -> bool {
+        if self.word_chars.contains(&char) {
+            return false;
+        }
+
+        self.punct_chars.contains(&char) || char.is_ascii_punctuation() || char.is_whitespace()
+    }
+
+    /**
+     * Finds which atom, if any, starts some text, and its length in bytes.
+     *
+     * Atoms must be followed by punctuation, or by the end of the text.
+     */
+    pub fn match_atom(&self, text: &str) -> Option<(AtomKind, usize)> {
+        self.atoms.iter().find_map(|kind| {
+            let len = kind.match_len(text)?;
+
+            text[len..]
+                .chars()
+                .next()
+                .is_none_or(|c| self.is_punct(c))
+                .then_some((*kind, len))
+        })
+    }
+}
+
 #[derive(PartialEq, Debug)]
 enum LexingType {
     Begin,
@@ -13,6 +173,7 @@ enum LexingType {
 
     PreEnd,
     PostBegin,
+    Atom(AtomKind, usize),
 }
 
 /**
@@ -23,6 +184,7 @@ pub struct Lexer<'a> {
     start: usize,
     head: usize,
     state: LexingType,
+    config: &'a LexerConfig,
 }
 
 impl<'a> Lexer<'a> {
@@ -30,11 +192,19 @@ impl<'a> Lexer<'a> {
      * Make a new Lexer state from a string.
      */
     pub fn new(from: &'a str) -> Lexer<'a> {
+        Self::new_with_config(from, &DEFAULT_CONFIG)
+    }
+
+    /**
+     * Make a new Lexer state from a string, with custom character classes.
+     */
+    pub fn new_with_config(from: &'a str, config: &'a LexerConfig) -> Lexer<'a> {
         Lexer {
             from,
             start: 0,
             head: 0,
             state: LexingType::Begin,
+            config,
         }
     }
 
@@ -51,6 +221,7 @@ impl<'a> Lexer<'a> {
             // special cases
             LexingType::PostBegin => Token::Punct(s),
             LexingType::PreEnd => Token::Punct(s),
+            LexingType::Atom(..) => Token::Word(s),
         }
     }
 
@@ -66,7 +237,7 @@ impl<'a> Lexer<'a> {
         self.state_wrap(&self.from[self.start..self.head])
     }
 
-    fn char_type(&self, char: Option<char>) -> LexingType {
+    fn char_type(&self, char: Option<char>, following: Option<char>) -> LexingType {
         if char.is_none() {
             if self.state == LexingType::Punct {
                 return LexingType::End;
@@ -77,7 +248,14 @@ impl<'a> Lexer<'a> {
 
         let char = char.unwrap();
 
-        if char.is_ascii_punctuation() || char.is_whitespace() {
+        if self.state == LexingType::Word
+            && self.config.word_internal_chars.contains(&char)
+            && following.is_some_and(|c| !self.config.is_punct(c))
+        {
+            return LexingType::Word;
+        }
+
+        if self.config.is_punct(char) {
             LexingType::Punct
         } else {
             LexingType::Word
@@ -108,11 +286,37 @@ impl<'a> Iterator for Lexer<'a> {
             return Some(Token::Punct(""));
         }
 
+        if let LexingType::Atom(kind, end) = self.state {
+            self.head = end;
+
+            let res = if self.config.placeholders {
+                Token::Word(kind.placeholder())
+            } else {
+                self.peek_next()
+            };
+
+            self.state = LexingType::Punct;
+            self.start = self.head;
+
+            return Some(res);
+        }
+
         let chars = &mut self.from[self.head..].chars();
 
         loop {
+            if matches!(self.state, LexingType::Punct | LexingType::PostBegin) {
+                if let Some((kind, len)) = self.config.match_atom(&self.from[self.head..]) {
+                    let res = self.peek_next();
+
+                    self.state = LexingType::Atom(kind, self.head + len);
+                    self.start = self.head;
+
+                    return Some(res);
+                }
+            }
+
             let nextchar = chars.next();
-            let ctype = self.char_type(nextchar);
+            let ctype = self.char_type(nextchar, chars.clone().next());
 
             let final_ctype = if self.state == LexingType::PostBegin {
                 &LexingType::Punct
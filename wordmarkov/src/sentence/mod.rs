@@ -4,6 +4,7 @@
  */
 
 pub mod lex;
+pub mod split;
 pub mod token;
 
 pub mod prelude;
@@ -1,4 +1,5 @@
 //! Useful imports for sentence.
 
-pub use super::lex::Lexer;
+pub use super::lex::{AtomKind, Lexer, LexerConfig};
+pub use super::split::split_sentences;
 pub use super::token::Token;
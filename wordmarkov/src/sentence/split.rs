@@ -0,0 +1,57 @@
+/*!
+ * Code for splitting text into sentences.
+ */
+
+/// Whether a character ends a sentence, when followed by whitespace.
+fn is_terminal(char: char) -> bool {
+    matches!(char, '.' | '!' | '?')
+}
+
+/**
+ * Splits some text into sentences.
+ *
+ * A sentence ends at a newline, or at a run of terminal punctuation (`.`,
+ * `!` or `?`) followed by whitespace or by the end of the text; the
+ * punctuation stays with its sentence. Sentences are trimmed, and empty ones
+ * are skipped.
+ *
+ * Abbreviations such as "e.g." also end sentences, but numbers such as "3.14"
+ * do not.
+ */
+pub fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = vec![];
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, char)) = chars.next() {
+        let end = if char == '\n' {
+            Some(i)
+        } else if is_terminal(char) {
+            match chars.peek() {
+                None => Some(i + char.len_utf8()),
+                Some((_, next)) if next.is_whitespace() => Some(i + char.len_utf8()),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        if let Some(end) = end {
+            let sentence = text[start..end].trim();
+
+            if !sentence.is_empty() {
+                sentences.push(sentence);
+            }
+
+            start = end;
+        }
+    }
+
+    let rest = text[start..].trim();
+
+    if !rest.is_empty() {
+        sentences.push(rest);
+    }
+
+    sentences
+}
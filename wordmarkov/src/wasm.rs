@@ -0,0 +1,56 @@
+/*!
+ * WebAssembly bindings for the Markov chain.
+ *
+ * Only available with the `wasm` feature. Meant to be built for the
+ * `wasm32-unknown-unknown` target with `wasm-bindgen`, so that chatbots and
+ * demos can learn and generate sentences in the browser.
+ */
+use crate::prelude::*;
+use wasm_bindgen::prelude::*;
+
+/// A [MarkovChain], as exposed to JavaScript.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WasmMarkovChain {
+    chain: MarkovChain,
+}
+
+#[wasm_bindgen]
+impl WasmMarkovChain {
+    /// Makes a new empty chain.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmMarkovChain {
+        Self::default()
+    }
+
+    /// Parses a sentence into the chain.
+    pub fn parse_sentence(&mut self, sentence: &str) {
+        self.chain.parse_sentence(sentence);
+    }
+
+    /// Composes a sentence around a seed word, or a random word if none is
+    /// given.
+    pub fn compose(&self, seed: Option<String>, max_len: Option<usize>) -> Result<String, JsValue> {
+        let seed = match &seed {
+            Some(word) => MarkovSeed::Word(word),
+            None => MarkovSeed::Random,
+        };
+
+        self.chain
+            .compose_sentence(seed, &mut WeightedRandomSelector, max_len)
+            .map(|sentence| sentence.to_string())
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// The number of words in the chain.
+    #[wasm_bindgen(getter)]
+    pub fn num_words(&self) -> usize {
+        self.chain.num_words()
+    }
+
+    /// The number of edges in the chain.
+    #[wasm_bindgen(getter)]
+    pub fn num_edges(&self) -> usize {
+        self.chain.num_edges()
+    }
+}
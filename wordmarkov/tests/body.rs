@@ -1,7 +1,31 @@
 #![cfg(test)]
 
+use rand::{rngs::StdRng, SeedableRng};
+use std::io::Write;
 use wordmarkov::prelude::*;
 
+/// Records the hits of the last edge it weighed.
+struct HitsSelector(usize);
+
+impl MarkovSelector for HitsSelector {
+    fn reset(&mut self, _dir: MarkovTraverseDir) {}
+
+    fn weight<'a>(
+        &mut self,
+        _from: &MarkovToken<'a>,
+        _to: &MarkovToken<'a>,
+        _punct: &MarkovToken<'a>,
+        occurrences: usize,
+    ) -> f32 {
+        self.0 = occurrences;
+        1.0
+    }
+
+    fn selection_type(&mut self) -> SelectionType {
+        SelectionType::Highest
+    }
+}
+
 #[test]
 fn test_chain_parsing() {
     let mut chain: MarkovChain = MarkovChain::new();
@@ -20,6 +44,52 @@ fn test_chain_parsing() {
     assert_eq!(chain.try_get_textlet_index("."), None);
 }
 
+#[test]
+fn test_chain_edge_registration() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    for _ in 0..3 {
+        chain.parse_sentence("the cat sat");
+    }
+
+    assert_eq!(chain.num_edges(), 4);
+
+    for idx in 0..chain.num_edges() {
+        assert_eq!(chain.get_edge(idx).unwrap().hits, 3);
+    }
+
+    let the = chain.try_get_textlet_index("the").unwrap();
+    let cat = chain.try_get_textlet_index("cat").unwrap();
+    let space = chain.try_get_textlet_index(" ").unwrap();
+    let dash = chain.ensure_textlet_index("-");
+
+    let edge = chain.find_edge(the, cat, space).unwrap();
+
+    assert_eq!(chain.register_edge(the, cat, space), edge);
+    assert_eq!(chain.get_edge(edge).unwrap().hits, 4);
+
+    // other punctuation makes for another edge
+    let dashed = chain.register_edge(the, cat, dash);
+
+    assert_ne!(dashed, edge);
+    assert_eq!(chain.num_edges(), 5);
+    assert_eq!(chain.find_edge(the, cat, dash), Some(dashed));
+
+    // each edge is walked once in either direction
+    let forward = chain.transitions(the).count();
+    let reverse = chain
+        .suggest_next(MarkovSeed::Id(cat), 10, MarkovTraverseDir::Reverse)
+        .unwrap()
+        .len();
+
+    assert_eq!((forward, reverse), (2, 2));
+
+    assert!(chain.remove_edge(the, cat, dash));
+    assert!(!chain.remove_edge(the, cat, dash));
+    assert_eq!(chain.find_edge(the, cat, dash), None);
+    assert_eq!(chain.find_edge(the, cat, space), Some(edge));
+}
+
 #[test]
 fn test_chain_traversal() {
     let mut chain: MarkovChain = MarkovChain::new();
@@ -43,3 +113,1106 @@ fn test_chain_traversal() {
 
     println!("Composed sentence: {}", new_sentence);
 }
+
+#[test]
+fn test_chain_higher_order() {
+    let mut chain: MarkovChain = MarkovChain::with_order(2);
+
+    chain.parse_sentence("a b c");
+    chain.parse_sentence("d b e");
+
+    assert_eq!(chain.order(), 2);
+
+    for _ in 0..20 {
+        let sentence = chain
+            .compose_sentence(MarkovSeed::Word("a"), &mut WeightedRandomSelector, None)
+            .unwrap();
+        assert_eq!(sentence.to_string(), "a b c");
+
+        // "b" alone was never seen in a longer context, so the chain backs off
+        // to first order to find the word before it
+        let sentence = chain
+            .compose_sentence(MarkovSeed::Word("b"), &mut WeightedRandomSelector, None)
+            .unwrap()
+            .to_string();
+        assert!(sentence == "a b c" || sentence == "d b e", "{}", sentence);
+    }
+}
+
+#[test]
+fn test_chain_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<MarkovChain>();
+
+    let mut chain: MarkovChain = MarkovChain::new();
+    chain.parse_sentence("Mary had a little lamb");
+
+    let chain = std::sync::Arc::new(chain);
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let chain = chain.clone();
+
+            std::thread::spawn(move || {
+                chain
+                    .compose_sentence(MarkovSeed::Word("lamb"), &mut StaticBestSelector, None)
+                    .unwrap()
+                    .to_string()
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), "Mary had a little lamb");
+    }
+}
+
+#[test]
+fn test_chain_parse_reader() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    let parsed = chain
+        .parse_reader(&b"Mary had a little lamb\n\n  its fleece was white as snow  \n"[..])
+        .unwrap();
+
+    assert_eq!(parsed, 2);
+    assert!(chain.try_get_textlet_index("lamb").is_some());
+    assert!(chain.try_get_textlet_index("snow").is_some());
+
+    assert!(chain.parse_reader(&b"\xff\xfe\n"[..]).is_err());
+}
+
+#[test]
+fn test_chain_merge() {
+    let mut chain: MarkovChain = MarkovChain::new();
+    chain.parse_sentence("Mary had a little lamb");
+
+    let mut other: MarkovChain = MarkovChain::new();
+    other.parse_sentence("Mary had a little lamb");
+    other.parse_sentence("a goat");
+
+    chain.merge(&other);
+
+    assert_eq!(chain.num_textlets(), 10);
+    assert_eq!(chain.num_edges(), 9);
+
+    let mut selector = HitsSelector(0);
+    chain
+        .select_next_word(
+            MarkovSeed::Word("lamb"),
+            &mut selector,
+            MarkovTraverseDir::Forward,
+        )
+        .unwrap();
+    assert_eq!(selector.0, 2);
+
+    let sentence = chain
+        .compose_sentence(MarkovSeed::Word("goat"), &mut StaticBestSelector, None)
+        .unwrap();
+    assert!(sentence.to_string().ends_with(" a goat"));
+}
+
+#[test]
+fn test_chain_pruning() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("Mary had a little lamb");
+    chain.parse_sentence("Mary had a little lamb");
+    chain.parse_sentence("Mary had a little lamv");
+
+    assert_eq!(chain.num_edges(), 8);
+
+    assert_eq!(chain.prune_edges(2), 2);
+    assert_eq!(chain.num_edges(), 6);
+    assert_eq!(chain.num_words(), 7);
+    assert!(chain.try_get_textlet_index("lamv").is_some());
+
+    assert_eq!(chain.prune_orphan_textlets(), 1);
+    assert_eq!(chain.num_textlets(), 9);
+    assert_eq!(chain.try_get_textlet_index("lamv"), None);
+
+    let sentence = chain
+        .compose_sentence(MarkovSeed::Word("lamb"), &mut StaticBestSelector, None)
+        .unwrap();
+    assert_eq!(sentence.to_string(), "Mary had a little lamb");
+}
+
+#[test]
+fn test_chain_decay() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    for _ in 0..4 {
+        chain.parse_sentence("Mary had a little lamb");
+    }
+
+    assert_eq!(chain.decay_hits(1.0), Ok(0));
+    assert_eq!(chain.decay_hits(0.5), Ok(0));
+
+    let mut selector = HitsSelector(0);
+    chain
+        .select_next_word(
+            MarkovSeed::Word("little"),
+            &mut selector,
+            MarkovTraverseDir::Forward,
+        )
+        .unwrap();
+    assert_eq!(selector.0, 2);
+
+    assert_eq!(chain.decay_hits(0.0), Ok(6));
+    assert_eq!(chain.num_edges(), 0);
+    assert!(chain.is_empty());
+}
+
+#[test]
+fn test_chain_decay_rejects_bad_factors() {
+    let mut chain: MarkovChain = MarkovChain::new();
+    chain.parse_sentence("Mary had a little lamb");
+
+    for factor in [f32::INFINITY, f32::NEG_INFINITY, f32::NAN, -0.5] {
+        assert!(chain.decay_hits(factor).is_err());
+    }
+
+    assert_eq!(chain.num_edges(), 6);
+}
+
+#[test]
+fn test_chain_removal() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("Mary had a little lamb");
+    chain.parse_sentence("Mary had a big goat");
+
+    assert!(chain.remove_word("big"));
+    assert!(!chain.remove_word("big"));
+
+    assert_eq!(chain.try_get_textlet_index("big"), None);
+    assert_eq!(chain.num_edges(), 7);
+    assert_eq!(chain.num_textlets(), 10);
+
+    let sentence = chain
+        .compose_sentence(MarkovSeed::Word("lamb"), &mut StaticBestSelector, None)
+        .unwrap();
+    assert_eq!(sentence.to_string(), "Mary had a little lamb");
+
+    let goat = chain.try_get_textlet_index("goat").unwrap();
+    let space = chain.try_get_textlet_index(" ").unwrap();
+
+    assert!(!chain.remove_edge(goat, chain.end(), space));
+    assert!(chain.remove_edge(goat, chain.end(), chain.try_get_textlet_index("").unwrap()));
+    assert_eq!(chain.num_edges(), 6);
+}
+
+#[test]
+fn test_chain_probabilities() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("Mary had a little lamb");
+    chain.parse_sentence("Mary had a little lamb");
+    chain.parse_sentence("Mary had a big goat");
+    chain.parse_sentence("Mary had a big, big goat");
+
+    let a = chain.try_get_textlet_index("a").unwrap();
+    let big = chain.try_get_textlet_index("big").unwrap();
+    let little = chain.try_get_textlet_index("little").unwrap();
+
+    assert_eq!(chain.probability(a, little), 0.5);
+    assert_eq!(chain.probability(a, big), 0.5);
+    assert_eq!(chain.probability(little, big), 0.0);
+
+    let transitions: Vec<_> = chain.transitions(big).collect();
+
+    assert_eq!(transitions.len(), 2);
+    assert!(transitions.contains(&(
+        MarkovToken::Textlet("goat"),
+        MarkovToken::Textlet(" "),
+        2.0 / 3.0
+    )));
+    assert!(transitions.contains(&(
+        MarkovToken::Textlet("big"),
+        MarkovToken::Textlet(", "),
+        1.0 / 3.0
+    )));
+
+    let sum: f32 = chain.transitions(chain.begin()).map(|(_, _, p)| p).sum();
+    assert_eq!(sum, 1.0);
+}
+
+#[test]
+fn test_chain_suggestions() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("Mary had a little lamb");
+    chain.parse_sentence("Mary had a big goat");
+    chain.parse_sentence("Mary had a big cow");
+    chain.parse_sentence("Mary had a red hen");
+
+    let suggestions = chain
+        .suggest_next(MarkovSeed::Word("a"), 2, MarkovTraverseDir::Forward)
+        .unwrap();
+
+    assert_eq!(suggestions.len(), 2);
+    assert_eq!(
+        suggestions[0],
+        (MarkovToken::Textlet("big"), MarkovToken::Textlet(" "), 0.5)
+    );
+    assert_eq!(suggestions[1].2, 0.25);
+
+    let suggestions = chain
+        .suggest_next(MarkovSeed::Word("goat"), 5, MarkovTraverseDir::Reverse)
+        .unwrap();
+    assert_eq!(
+        suggestions,
+        vec![(MarkovToken::Textlet("big"), MarkovToken::Textlet(" "), 1.0)]
+    );
+
+    assert!(chain
+        .suggest_next(MarkovSeed::Word("sheep"), 5, MarkovTraverseDir::Forward)
+        .is_err());
+}
+
+#[test]
+fn test_chain_scoring() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("Mary had a little lamb");
+    chain.parse_sentence("Mary had a big goat");
+
+    let seen = chain.score_sentence("Mary had a little lamb");
+    assert!((seen - 0.5_f64.ln()).abs() < 1e-6);
+
+    let mixed = chain.score_sentence("Mary had a big lamb");
+    assert!((mixed - (0.5_f64.ln() + DEFAULT_UNSEEN_PENALTY)).abs() < 1e-6);
+
+    let unknown = chain.score_sentence_with_penalty("Mary had a little sheep", -5.0);
+    assert!((unknown - (0.5_f64.ln() - 10.0)).abs() < 1e-6);
+
+    assert!(seen > mixed);
+}
+
+#[test]
+fn test_chain_scoring_lexer_config() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain
+        .set_lexer_config(LexerConfig {
+            word_chars: vec!['_'],
+            ..LexerConfig::default()
+        })
+        .unwrap();
+
+    chain.parse_sentence("snake_case names");
+
+    // split as when parsed, "snake_case" is a single known word
+    assert!(chain.score_sentence("snake_case names").abs() < 1e-6);
+    assert!(chain.set_lexer_config(LexerConfig::default()).is_err());
+}
+
+#[test]
+fn test_chain_perplexity() {
+    let corpus = ["a b c", "d b e", "a b c", "d b e"];
+
+    let mut first_order: MarkovChain = MarkovChain::new();
+    let mut second_order: MarkovChain = MarkovChain::with_order(2);
+
+    for line in corpus {
+        first_order.parse_sentence(line);
+        second_order.parse_sentence(line);
+    }
+
+    // in a second order chain, "c" always follows "a b"
+    assert!((first_order.perplexity(corpus) - 2.0_f64.powf(0.5)).abs() < 1e-6);
+    assert!((second_order.perplexity(corpus) - 2.0_f64.powf(0.25)).abs() < 1e-6);
+
+    assert!(first_order.perplexity(["a b z"]) > first_order.perplexity(["a b c"]));
+    assert_eq!(first_order.perplexity(Vec::<String>::new()), f64::INFINITY);
+}
+
+#[test]
+fn test_chain_case_folding() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.set_case_folding(CaseFolding::MostFrequent).unwrap();
+    chain.parse_sentence("Hello world");
+    chain.parse_sentence("hello there");
+    chain.parse_sentence("HELLO World");
+
+    assert_eq!(chain.num_textlets(), 7);
+    assert_eq!(
+        chain.try_get_textlet_index("Hello"),
+        chain.try_get_textlet_index("hello")
+    );
+    assert_eq!(
+        chain.surface_forms("world"),
+        vec![("world", 1), ("World", 1)]
+    );
+    assert_eq!(chain.surface_forms("hello").len(), 3);
+
+    chain.parse_sentence("Big World");
+
+    assert_eq!(chain.surface_forms("WORLD")[0], ("World", 2));
+
+    let sentence = chain
+        .compose_sentence(MarkovSeed::Word("big"), &mut WeightedRandomSelector, None)
+        .unwrap();
+
+    assert_eq!(sentence.to_string(), "Big World");
+
+    // folding cannot change once words were parsed
+    assert!(chain.set_case_folding(CaseFolding::Off).is_err());
+}
+
+#[test]
+fn test_chain_case_capitalize_first() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain
+        .set_case_folding(CaseFolding::CapitalizeFirst)
+        .unwrap();
+    chain.parse_sentence("the CAT sat");
+
+    let sentence = chain
+        .compose_sentence(MarkovSeed::Word("cat"), &mut WeightedRandomSelector, None)
+        .unwrap();
+
+    assert_eq!(sentence.to_string(), "The cat sat");
+}
+
+#[test]
+fn test_chain_weighted_parsing() {
+    let mut weighted: MarkovChain = MarkovChain::with_order(2);
+    let mut repeated: MarkovChain = MarkovChain::with_order(2);
+
+    weighted.parse_sentence_weighted("the cat sat", 3);
+    weighted.parse_sentence_weighted("the dog ran", 1);
+    weighted.parse_sentence_weighted("the bird flew", 0);
+
+    for _ in 0..3 {
+        repeated.parse_sentence("the cat sat");
+    }
+
+    repeated.parse_sentence("the dog ran");
+
+    assert_eq!(weighted.num_edges(), repeated.num_edges());
+    assert!(weighted.try_get_textlet_index("bird").is_none());
+
+    let the = weighted.try_get_textlet_index("the").unwrap();
+    let cat = weighted.try_get_textlet_index("cat").unwrap();
+
+    assert_eq!(weighted.probability(the, cat), 0.75);
+    assert_eq!(
+        weighted.score_sentence("the cat sat"),
+        repeated.score_sentence("the cat sat")
+    );
+}
+
+#[test]
+fn test_chain_seed_fallback() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("they walked home");
+
+    let compose = |chain: &MarkovChain, seed| {
+        chain
+            .compose_sentence(MarkovSeed::Word(seed), &mut WeightedRandomSelector, None)
+            .map(|sentence| sentence.to_string())
+    };
+
+    assert!(compose(&chain, "Walking").is_err());
+
+    chain.set_seed_fallback(SeedFallback {
+        lowercase: true,
+        ..Default::default()
+    });
+
+    assert_eq!(compose(&chain, "HOME").unwrap(), "they walked home");
+    assert!(compose(&chain, "Walking").is_err());
+
+    chain.set_seed_fallback(SeedFallback {
+        stem: true,
+        ..Default::default()
+    });
+
+    assert_eq!(compose(&chain, "Walking").unwrap(), "they walked home");
+    assert!(compose(&chain, "nonsense").is_err());
+
+    chain.set_seed_fallback(SeedFallback::ALL);
+
+    assert!(compose(&chain, "nonsense").is_ok());
+    assert_eq!(stem("walks"), "walk");
+    assert_eq!(stem("is"), "is");
+}
+
+#[test]
+fn test_chain_resolve_seed_fallback() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("they walked home");
+
+    chain.set_seed_fallback(SeedFallback {
+        stem: true,
+        ..Default::default()
+    });
+
+    let walked = chain.try_get_textlet_index("walked");
+    let resolved = chain.resolve_seed(MarkovSeed::Word("Walking"), &mut rand::thread_rng());
+
+    assert!(walked.is_some());
+    assert_eq!(resolved.ok(), walked);
+}
+
+#[test]
+fn test_chain_directed_composition() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("the quick fox jumps over a lazy dog.");
+
+    let compose = |seed, direction| {
+        chain
+            .compose_sentence_directed(seed, direction, &mut HitsSelector(0), None)
+            .unwrap()
+            .to_string()
+    };
+
+    assert_eq!(
+        compose(MarkovSeed::Word("fox"), ComposeDirection::Forward),
+        "fox jumps over a lazy dog."
+    );
+    assert_eq!(
+        compose(MarkovSeed::Word("fox"), ComposeDirection::Backward),
+        "the quick fox"
+    );
+    assert_eq!(
+        compose(MarkovSeed::Word("dog"), ComposeDirection::Both),
+        "the quick fox jumps over a lazy dog."
+    );
+    assert_eq!(
+        compose(MarkovSeed::Begin, ComposeDirection::Both),
+        compose(MarkovSeed::Begin, ComposeDirection::Forward)
+    );
+    assert!(compose(MarkovSeed::Begin, ComposeDirection::Forward).starts_with("the "));
+    assert_eq!(compose(MarkovSeed::Begin, ComposeDirection::Backward), "");
+}
+
+#[test]
+fn test_chain_compose_limits() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("a b c d e f g");
+
+    let compose = |options: ComposeOptions| {
+        chain
+            .compose(MarkovSeed::Word("d"), &mut WeightedRandomSelector, &options)
+            .map(|sentence| sentence.to_string())
+    };
+
+    // the backward walk gets half of the words
+    assert_eq!(
+        compose(ComposeOptions {
+            max_words: Some(4),
+            ..Default::default()
+        })
+        .unwrap(),
+        "c d e f"
+    );
+
+    assert_eq!(
+        compose(ComposeOptions {
+            max_backward_words: Some(0),
+            max_forward_words: Some(2),
+            ..Default::default()
+        })
+        .unwrap(),
+        "d e f"
+    );
+
+    assert_eq!(
+        compose(ComposeOptions {
+            min_words: 7,
+            ..Default::default()
+        })
+        .unwrap(),
+        "a b c d e f g"
+    );
+
+    assert!(compose(ComposeOptions {
+        min_words: 8,
+        max_attempts: 3,
+        ..Default::default()
+    })
+    .is_err());
+}
+
+#[test]
+fn test_chain_required_banned_words() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("the cat sat");
+    chain.parse_sentence("the dog sat");
+    chain.parse_sentence("a bird flew");
+
+    let options = ComposeOptions {
+        banned_words: vec!["cat".to_owned()],
+        ..Default::default()
+    };
+
+    for _ in 0..20 {
+        let sentence = chain
+            .compose(
+                MarkovSeed::Word("sat"),
+                &mut WeightedRandomSelector,
+                &options,
+            )
+            .unwrap();
+
+        assert_eq!(sentence.to_string(), "the dog sat");
+    }
+
+    let options = ComposeOptions {
+        required_words: vec!["bird".to_owned()],
+        ..Default::default()
+    };
+
+    for _ in 0..20 {
+        let sentence = chain
+            .compose(MarkovSeed::Random, &mut WeightedRandomSelector, &options)
+            .unwrap();
+
+        assert_eq!(sentence.to_string(), "a bird flew");
+    }
+
+    let options = ComposeOptions {
+        required_words: vec!["bird".to_owned()],
+        max_attempts: 5,
+        ..Default::default()
+    };
+
+    assert!(chain
+        .compose(
+            MarkovSeed::Word("cat"),
+            &mut WeightedRandomSelector,
+            &options
+        )
+        .is_err());
+
+    let options = ComposeOptions {
+        required_words: vec!["unicorn".to_owned()],
+        ..Default::default()
+    };
+
+    assert!(chain
+        .compose(MarkovSeed::Random, &mut WeightedRandomSelector, &options)
+        .is_err());
+}
+
+#[test]
+fn test_chain_loop_guard() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("the cat and the dog");
+
+    let options = ComposeOptions {
+        direction: ComposeDirection::Forward,
+        loop_guard: LoopGuard::Forbid,
+        max_attempts: 100,
+        ..Default::default()
+    };
+
+    // "and the cat and" can only go on by walking "and the" again
+    for _ in 0..10 {
+        let sentence = chain
+            .compose(
+                MarkovSeed::Word("and"),
+                &mut WeightedRandomSelector,
+                &options,
+            )
+            .unwrap();
+
+        assert_eq!(sentence.to_string(), "and the dog");
+    }
+
+    assert_eq!(LoopGuard::Off.scale(3), 1.0);
+    assert_eq!(LoopGuard::Forbid.scale(0), 1.0);
+    assert_eq!(LoopGuard::Forbid.scale(1), 0.0);
+    assert_eq!(LoopGuard::Penalize(0.5).scale(2), 0.25);
+}
+
+#[test]
+fn test_chain_compose_best() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("we sat");
+    chain.parse_sentence("we sat down at the old wooden table");
+
+    let compose = |scorer: &mut dyn SentenceScorer| {
+        chain
+            .compose_best(
+                MarkovSeed::Word("we"),
+                &mut WeightedRandomSelector,
+                scorer,
+                50,
+            )
+            .unwrap()
+            .to_string()
+    };
+
+    assert_eq!(compose(&mut LengthScorer { target_words: 2 }), "we sat");
+    assert_eq!(
+        compose(&mut LengthScorer { target_words: 9 }),
+        "we sat down at the old wooden table"
+    );
+
+    // "we sat" is as likely to end as to go on, and then every word is sure
+    assert_eq!(
+        compose(&mut LikelihoodScorer),
+        "we sat down at the old wooden table"
+    );
+
+    let mut scorer = |_: &MarkovChain, sentence: &TokenList<'_>| -(sentence.len() as f64);
+
+    assert_eq!(compose(&mut scorer), "we sat");
+}
+
+#[test]
+fn test_chain_compose_paragraph() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("I like cats");
+    chain.parse_sentence("cats are fluffy!");
+
+    let options = ParagraphOptions {
+        sentences: 3,
+        chain_seeds: true,
+        compose: ComposeOptions {
+            direction: ComposeDirection::Forward,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let paragraph = chain
+        .compose_paragraph(MarkovSeed::Word("I"), &mut WeightedRandomSelector, &options)
+        .unwrap();
+
+    let sentences: Vec<&str> = paragraph.split_inclusive(['.', '!']).collect();
+
+    assert_eq!(sentences.len(), 3);
+    assert!(sentences[0].starts_with("I like cats"));
+
+    // each sentence starts with the last word of the one before it
+    for pair in sentences.windows(2) {
+        let last_word = pair[0]
+            .trim_end_matches(['.', '!'])
+            .rsplit(' ')
+            .next()
+            .unwrap();
+
+        assert!(pair[1].trim_start().starts_with(last_word));
+    }
+
+    let options = ParagraphOptions {
+        sentences: 4,
+        ..Default::default()
+    };
+
+    let paragraph = chain
+        .compose_paragraph(MarkovSeed::Random, &mut WeightedRandomSelector, &options)
+        .unwrap();
+
+    assert_eq!(paragraph.matches(['.', '!']).count(), 4);
+}
+
+#[test]
+fn test_compose_with_rng() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_text("the cat sat on the mat. a dog sat on a log. the dog ate the cat.");
+
+    let compose = |state: u64| -> Vec<String> {
+        let mut rng = StdRng::seed_from_u64(state);
+
+        (0..20)
+            .map(|_| {
+                chain
+                    .compose_sentence_with_rng(
+                        MarkovSeed::Random,
+                        &mut WeightedRandomSelector,
+                        Some(200),
+                        &mut rng,
+                    )
+                    .unwrap()
+                    .to_string()
+            })
+            .collect()
+    };
+
+    assert_eq!(compose(42), compose(42));
+    assert_ne!(compose(42), compose(43));
+}
+
+#[test]
+fn test_compose_errors() {
+    let mut chain: MarkovChain = MarkovChain::new();
+    let mut selector = WeightedRandomSelector;
+
+    assert_eq!(
+        chain
+            .compose_sentence(MarkovSeed::Random, &mut selector, None)
+            .unwrap_err(),
+        MarkovError::EmptyChain
+    );
+
+    chain.parse_sentence("a short sentence");
+
+    assert_eq!(
+        chain
+            .compose_sentence(MarkovSeed::Word("nonsense"), &mut selector, None)
+            .unwrap_err(),
+        MarkovError::UnknownSeedWord("nonsense".to_owned())
+    );
+
+    assert_eq!(
+        chain
+            .select_next_word(MarkovSeed::Begin, &mut selector, MarkovTraverseDir::Reverse)
+            .unwrap_err(),
+        MarkovError::DeadEnd(String::new())
+    );
+
+    assert_eq!(
+        chain
+            .select_next_word_in_context(&[], &mut selector, MarkovTraverseDir::Forward)
+            .unwrap_err(),
+        MarkovError::EmptyContext
+    );
+
+    let options = ComposeOptions {
+        min_words: 4,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        chain
+            .compose(MarkovSeed::Word("short"), &mut selector, &options)
+            .unwrap_err(),
+        MarkovError::LengthExhausted
+    );
+
+    let options = ComposeOptions {
+        required_words: vec!["long".to_owned()],
+        ..Default::default()
+    };
+
+    assert_eq!(
+        chain
+            .compose(MarkovSeed::Random, &mut selector, &options)
+            .unwrap_err(),
+        MarkovError::UnknownRequiredWord("long".to_owned())
+    );
+}
+
+#[test]
+fn test_chain_log() {
+    let path = std::env::temp_dir().join(format!("wordmarkov-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.open_log(&path).unwrap();
+    chain.parse_sentence("Mary had a little lamb");
+    chain.parse_sentence_weighted("its fleece was\twhite\\as\nsnow", 3);
+    chain.close_log();
+
+    // not logged
+    chain.parse_sentence("and everywhere that Mary went");
+
+    // cut short by a crash
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap()
+        .write_all(b"1\tthe lamb was")
+        .unwrap();
+
+    let mut replayed: MarkovChain = MarkovChain::new();
+
+    assert_eq!(replayed.replay(&path), Ok(2));
+    assert!(replayed.try_get_textlet_index("fleece").is_some());
+    assert!(replayed.try_get_textlet_index("everywhere").is_none());
+    assert!(replayed.try_get_textlet_index("was\t").is_none());
+
+    let fleece = replayed.try_get_textlet_index("fleece").unwrap();
+    let was = replayed.try_get_textlet_index("was").unwrap();
+    let space = replayed.try_get_textlet_index(" ").unwrap();
+    let edge = replayed.find_edge(fleece, was, space).unwrap();
+
+    assert_eq!(replayed.get_edge(edge).unwrap().hits, 3);
+    assert_eq!(replayed.num_edges(), chain.num_edges() - 6);
+
+    std::fs::write(&path, "1\tfine\nnot a sentence\n").unwrap();
+    assert!(replayed.replay(&path).is_err());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_chain_walk() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("one two, three four");
+
+    let mut selector = WeightedRandomSelector;
+
+    let steps: Vec<(usize, usize)> = chain
+        .walk(
+            MarkovSeed::Word("two"),
+            MarkovTraverseDir::Forward,
+            &mut selector,
+            None,
+        )
+        .unwrap()
+        .collect();
+
+    let words: Vec<&str> = steps
+        .iter()
+        .map(|(word, _)| match chain.get_textlet(*word).unwrap() {
+            MarkovToken::Textlet(text) => text,
+            _ => "",
+        })
+        .collect();
+
+    assert_eq!(words, vec!["three", "four", ""]);
+    assert_eq!(steps[2].0, chain.end());
+
+    for (word, edge) in &steps {
+        assert_eq!(chain.get_edge(*edge).unwrap().dst_idx, *word);
+    }
+
+    let comma = chain.try_get_textlet_index(", ").unwrap();
+    assert_eq!(chain.get_edge(steps[0].1).unwrap().pct_idx, comma);
+
+    let steps: Vec<usize> = chain
+        .walk(
+            MarkovSeed::Word("three"),
+            MarkovTraverseDir::Reverse,
+            &mut selector,
+            Some(2),
+        )
+        .unwrap()
+        .map(|(word, _)| word)
+        .collect();
+
+    assert_eq!(
+        steps,
+        vec![
+            chain.try_get_textlet_index("two").unwrap(),
+            chain.try_get_textlet_index("one").unwrap(),
+        ]
+    );
+
+    assert!(chain
+        .walk(
+            MarkovSeed::Word("five"),
+            MarkovTraverseDir::Forward,
+            &mut selector,
+            None
+        )
+        .is_err());
+}
+
+#[test]
+fn test_chain_random_seeding() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence_weighted("the cat sat on the mat", 50);
+    chain.parse_sentence("the dog ate");
+
+    let the = chain.try_get_textlet_index("the").unwrap();
+    let dog = chain.try_get_textlet_index("dog").unwrap();
+
+    assert_eq!(chain.frequency(the), 101);
+    assert_eq!(chain.frequency(dog), 1);
+    assert_eq!(chain.frequency(chain.end()), 51);
+
+    let seeds = |chain: &MarkovChain| -> Vec<String> {
+        let mut rng = StdRng::seed_from_u64(1);
+
+        (0..300)
+            .map(|_| {
+                // too short to walk anywhere from the seed
+                chain
+                    .compose_sentence_with_rng(
+                        MarkovSeed::Random,
+                        &mut StaticBestSelector,
+                        Some(0),
+                        &mut rng,
+                    )
+                    .unwrap()
+                    .to_string()
+            })
+            .collect()
+    };
+
+    chain.set_random_seeding(RandomSeeding {
+        stop_words: ENGLISH_STOP_WORDS.iter().map(|w| w.to_string()).collect(),
+        ..Default::default()
+    });
+
+    let picked = seeds(&chain);
+
+    assert!(picked
+        .iter()
+        .all(|seed| !["the", "on", ""].contains(&seed.as_str())));
+    assert!(picked.iter().any(|seed| seed == "dog"));
+
+    chain.set_random_seeding(RandomSeeding {
+        weighting: SeedWeighting::InverseFrequency,
+        ..Default::default()
+    });
+
+    // the words of the rare sentence are 50 times as likely
+    let picked = seeds(&chain);
+    let rare = picked
+        .iter()
+        .filter(|seed| ["dog", "ate"].contains(&seed.as_str()))
+        .count();
+
+    assert!(rare > picked.len() / 2);
+}
+
+#[test]
+fn test_chain_sampling_cache() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("x y");
+    chain.set_sampling_cache(true);
+
+    assert!(chain.has_sampling_cache());
+
+    let mut rng = StdRng::seed_from_u64(42);
+
+    let mut next = |chain: &MarkovChain, seed: &str, direction| -> Vec<usize> {
+        (0..20)
+            .map(|_| {
+                chain
+                    .select_next_word_with_rng(
+                        MarkovSeed::Word(seed),
+                        &mut WeightedRandomSelector,
+                        direction,
+                        &mut rng,
+                    )
+                    .unwrap()
+                    .2
+            })
+            .collect()
+    };
+
+    let (x, y) = (
+        chain.try_get_textlet_index("x").unwrap(),
+        chain.try_get_textlet_index("y").unwrap(),
+    );
+
+    assert!(next(&chain, "x", MarkovTraverseDir::Forward)
+        .iter()
+        .all(|word| *word == y));
+
+    // the edges from x changed since they were cached
+    let z = chain.ensure_textlet_index("z");
+    let space = chain.try_get_textlet_index(" ").unwrap();
+
+    chain.register_edge_hits(x, z, space, 1_000_000);
+    chain.register_edge(z, chain.end(), space);
+
+    assert!(next(&chain, "x", MarkovTraverseDir::Forward).contains(&z));
+    assert_eq!(next(&chain, "z", MarkovTraverseDir::Reverse), vec![x; 20]);
+
+    // and compositions still end
+    let sentence = chain
+        .compose_sentence_with_rng(
+            MarkovSeed::Word("x"),
+            &mut WeightedRandomSelector,
+            Some(50),
+            &mut rng,
+        )
+        .unwrap()
+        .to_string();
+
+    assert!(sentence.starts_with('x'));
+
+    chain.set_sampling_cache(false);
+
+    assert!(!chain.has_sampling_cache());
+}
+
+#[test]
+fn test_shared_chain() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SharedMarkovChain>();
+
+    let chain = SharedMarkovChain::default();
+    chain.parse_sentence("Mary had a little lamb");
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let chain = chain.clone();
+
+            std::thread::spawn(move || {
+                (0..50)
+                    .map(|_| {
+                        chain
+                            .compose_sentence(
+                                MarkovSeed::Word("lamb"),
+                                &mut StaticBestSelector,
+                                None,
+                            )
+                            .unwrap()
+                    })
+                    .collect::<Vec<String>>()
+            })
+        })
+        .collect();
+
+    let writer = {
+        let chain = chain.clone();
+
+        std::thread::spawn(move || {
+            for i in 0..100 {
+                chain.parse_text(&format!("sheep{} grazed. sheep{} slept.", i, i));
+            }
+        })
+    };
+
+    writer.join().unwrap();
+
+    for reader in readers {
+        for sentence in reader.join().unwrap() {
+            assert_eq!(sentence, "Mary had a little lamb");
+        }
+    }
+
+    assert_eq!(chain.read().num_words(), 2 + 5 + 100 + 2);
+
+    let chain = chain.try_into_inner().ok().unwrap();
+
+    assert!(chain.try_get_textlet_index("sheep99").is_some());
+}
+
+#[test]
+fn test_chain_memory_usage() {
+    let mut chain: MarkovChain = MarkovChain::new();
+    let empty = chain.memory_usage();
+
+    chain.parse_text("the cat sat on the mat. a dog sat on a log.");
+    let parsed = chain.memory_usage();
+
+    assert!(parsed > empty);
+
+    chain.set_sampling_cache(true);
+    chain
+        .compose_sentence(
+            MarkovSeed::Word("sat"),
+            &mut WeightedRandomSelector,
+            Some(50),
+        )
+        .unwrap();
+
+    assert!(chain.memory_usage() > parsed);
+}
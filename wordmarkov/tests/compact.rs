@@ -0,0 +1,84 @@
+#![cfg(test)]
+
+use wordmarkov::prelude::*;
+
+fn sample_chain() -> MarkovChain {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("Mary had a little lamb");
+    chain.parse_sentence("Mary had a little goat, too.");
+
+    chain
+}
+
+#[test]
+fn test_compact_roundtrip() {
+    let chain = sample_chain();
+
+    let mut bytes: Vec<u8> = vec![];
+    chain.write_compact(&mut bytes).unwrap();
+
+    let compact = CompactChain::from_bytes(&bytes).unwrap();
+
+    assert_eq!(compact.num_words(), chain.num_words());
+    assert_eq!(compact.num_edges(), chain.num_edges());
+    assert_eq!(compact.num_textlets(), chain.num_textlets());
+
+    for word in ["Mary", "goat", "lamb", ", "] {
+        let index = compact.try_get_textlet_index(word);
+
+        assert_eq!(index, chain.try_get_textlet_index(word));
+        assert_eq!(
+            compact.get_textlet(index.unwrap()),
+            Some(MarkovToken::Textlet(word))
+        );
+    }
+
+    assert_eq!(compact.try_get_textlet_index("sheep"), None);
+
+    let sentence = compact
+        .compose_sentence(MarkovSeed::Word("Mary"), &mut StaticBestSelector, None)
+        .unwrap();
+    assert!(sentence.to_string().starts_with("Mary had a little"));
+
+    let sentence = compact
+        .compose_sentence(MarkovSeed::Word("goat"), &mut StaticBestSelector, None)
+        .unwrap();
+    assert_eq!(sentence.to_string(), "Mary had a little goat, too.");
+
+    let restored = compact.to_chain().unwrap();
+
+    assert_eq!(restored.num_edges(), chain.num_edges());
+    assert_eq!(
+        restored.try_get_textlet_index("goat"),
+        chain.try_get_textlet_index("goat")
+    );
+}
+
+#[test]
+fn test_compact_bad_data() {
+    let mut bytes: Vec<u8> = vec![];
+    sample_chain().write_compact(&mut bytes).unwrap();
+
+    assert!(CompactChain::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    assert!(CompactChain::from_bytes(b"WMKC\x01\x02\x03").is_err());
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_mapped_chain() {
+    let path = std::env::temp_dir().join(format!("wordmarkov-{}.wmkc", std::process::id()));
+
+    sample_chain().save_compact_file(&path).unwrap();
+
+    let mapped = MappedChain::open(&path).unwrap();
+    let sentence = mapped
+        .chain()
+        .compose_sentence(MarkovSeed::Word("lamb"), &mut StaticBestSelector, None)
+        .unwrap();
+
+    assert_eq!(sentence.to_string(), "Mary had a little lamb");
+
+    drop(mapped);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,38 @@
+#![cfg(test)]
+
+use wordmarkov::prelude::*;
+
+#[test]
+fn test_dot_export() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("Mary had a \"little\" lamb");
+    chain.parse_sentence("Mary had a little goat");
+
+    let dot = chain.to_dot(&DotOptions::default()).unwrap();
+
+    assert!(dot.starts_with("digraph markov {\n"));
+    assert!(dot.contains("[label=\"BEGIN\", shape=box];"));
+    assert!(dot.contains("[label=\"Mary\"];"));
+    assert!(dot.contains("[label=\"\\\" \\\\\\\"\\\" (1)\"];"));
+
+    let dot = chain
+        .to_dot(&DotOptions {
+            min_hits: 2,
+            neighborhood: Some(("had".to_owned(), 1)),
+        })
+        .unwrap();
+
+    assert!(dot.contains("[label=\"Mary\"];"));
+    assert!(dot.contains("[label=\"a\"];"));
+    assert!(!dot.contains("BEGIN"));
+    assert!(!dot.contains("lamb"));
+    assert_eq!(dot.matches("->").count(), 2);
+
+    assert!(chain
+        .to_dot(&DotOptions {
+            min_hits: 0,
+            neighborhood: Some(("sheep".to_owned(), 1)),
+        })
+        .is_err());
+}
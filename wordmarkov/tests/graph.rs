@@ -0,0 +1,76 @@
+#![cfg(test)]
+
+use wordmarkov::prelude::*;
+
+#[test]
+fn test_graph_roundtrip() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("Mary had a little lamb");
+    chain.parse_sentence("Mary had a little lamb");
+
+    let mut graph = chain.to_graph();
+
+    assert_eq!(graph.nodes.len(), 7);
+    assert_eq!(graph.edges.len(), 6);
+    assert!(graph.edges.iter().all(|edge| edge.hits == 2));
+
+    let lamb = graph.nodes.iter().find(|n| n.text == "lamb").unwrap().id;
+    let end = graph
+        .nodes
+        .iter()
+        .find(|n| n.kind == GraphNodeKind::End)
+        .unwrap()
+        .id;
+
+    graph.nodes.push(GraphNode {
+        id: 100,
+        kind: GraphNodeKind::Word,
+        text: "sheep".to_owned(),
+    });
+
+    graph.edges.push(GraphEdge {
+        source: lamb,
+        target: 100,
+        punct: " and ".to_owned(),
+        hits: 5,
+    });
+
+    graph.edges.push(GraphEdge {
+        source: 100,
+        target: end,
+        punct: ".".to_owned(),
+        hits: 5,
+    });
+
+    let edited = MarkovChain::from_graph(&graph).unwrap();
+
+    assert_eq!(edited.num_edges(), 8);
+
+    let sentence = edited
+        .compose_sentence(MarkovSeed::Word("sheep"), &mut StaticBestSelector, None)
+        .unwrap();
+    assert_eq!(sentence.to_string(), "Mary had a little lamb and sheep.");
+
+    graph.nodes.push(GraphNode {
+        id: 101,
+        kind: GraphNodeKind::Word,
+        text: "sheep".to_owned(),
+    });
+    assert!(MarkovChain::from_graph(&graph).is_err());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_graph_json() {
+    let mut chain: MarkovChain = MarkovChain::new();
+    chain.parse_sentence("Mary had a little lamb");
+
+    let json = chain.to_json().unwrap();
+    assert!(json.contains("\"kind\":\"begin\""));
+
+    let loaded = MarkovChain::from_json(&json).unwrap();
+    assert_eq!(loaded.num_edges(), chain.num_edges());
+
+    assert!(MarkovChain::from_json("{\"nodes\": 3}").is_err());
+}
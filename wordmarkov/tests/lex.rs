@@ -39,3 +39,114 @@ fn test_split_sentence_2() {
     assert_eq!(lexstate.next(), None);
     assert_eq!(lexstate.next(), None);
 }
+
+#[test]
+fn test_split_sentence_config() {
+    let sentence = "don't fly-by snake_case - 'quoted'";
+
+    let config = LexerConfig {
+        word_chars: vec!['_'],
+        word_internal_chars: vec!['\'', '-'],
+        ..Default::default()
+    };
+
+    let tokens: Vec<Token> = Lexer::new_with_config(sentence, &config).collect();
+
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Begin,
+            Token::Punct(""),
+            Token::Word("don't"),
+            Token::Punct(" "),
+            Token::Word("fly-by"),
+            Token::Punct(" "),
+            Token::Word("snake_case"),
+            Token::Punct(" - '"),
+            Token::Word("quoted"),
+            Token::Punct("'"),
+            Token::End,
+        ]
+    );
+
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence_with_config(sentence, &config);
+
+    assert!(chain.try_get_textlet_index("don't").is_some());
+    assert!(chain.try_get_textlet_index("don").is_none());
+}
+
+#[test]
+fn test_split_sentence_atoms() {
+    let sentence = "@bob see https://example.com/a?b=c, #rust2024 :smile: #1";
+
+    let mut config = LexerConfig {
+        atoms: AtomKind::ALL.to_vec(),
+        ..Default::default()
+    };
+
+    let words = |config: &LexerConfig| -> Vec<String> {
+        Lexer::new_with_config(sentence, config)
+            .filter_map(|token| match token {
+                Token::Word(word) => Some(word.to_owned()),
+                _ => None,
+            })
+            .collect()
+    };
+
+    assert_eq!(
+        words(&config),
+        vec![
+            "@bob",
+            "see",
+            "https://example.com/a?b=c",
+            "#rust2024",
+            ":smile:",
+            "1"
+        ]
+    );
+
+    config.placeholders = true;
+
+    assert_eq!(
+        words(&config),
+        vec!["<mention>", "see", "<url>", "<hashtag>", "<emoji>", "1"]
+    );
+
+    let tokens: Vec<Token> = Lexer::new_with_config("hi @bob", &config).collect();
+
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Begin,
+            Token::Punct(""),
+            Token::Word("hi"),
+            Token::Punct(" "),
+            Token::Word("<mention>"),
+            Token::Punct(""),
+            Token::End,
+        ]
+    );
+}
+
+#[test]
+fn test_split_sentences() {
+    let text = "Hello there! Pi is 3.14, right?\nYes...  indeed\n\n";
+
+    assert_eq!(
+        split_sentences(text),
+        vec!["Hello there!", "Pi is 3.14, right?", "Yes...", "indeed"]
+    );
+
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    assert_eq!(chain.parse_text(text), 4);
+
+    // "there" ends a sentence, rather than leading to "Pi"
+    let there = chain.try_get_textlet_index("there").unwrap();
+    let pi = chain.try_get_textlet_index("Pi").unwrap();
+
+    assert_eq!(chain.probability(there, pi), 0.0);
+    assert_eq!(chain.probability(there, chain.end()), 1.0);
+}
@@ -0,0 +1,52 @@
+#![cfg(test)]
+
+use wordmarkov::prelude::*;
+
+#[test]
+fn test_limits_lowest_hits() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.set_limits(Some(ChainLimits {
+        max_textlets: None,
+        max_edges: Some(10),
+        policy: EvictionPolicy::LowestHits,
+    }));
+
+    for _ in 0..5 {
+        chain.parse_sentence("Mary had a little lamb");
+    }
+
+    for i in 0..50 {
+        chain.parse_sentence(&format!("typo{} here", i));
+        assert!(chain.num_edges() <= 10);
+    }
+
+    let sentence = chain
+        .compose_sentence(MarkovSeed::Word("lamb"), &mut StaticBestSelector, None)
+        .unwrap();
+    assert_eq!(sentence.to_string(), "Mary had a little lamb");
+}
+
+#[test]
+fn test_limits_least_recently_seen() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.set_limits(Some(ChainLimits {
+        max_textlets: Some(20),
+        max_edges: None,
+        policy: EvictionPolicy::LeastRecentlySeen,
+    }));
+
+    for i in 0..50 {
+        chain.parse_sentence(&format!("first{} second{}", i, i));
+        assert!(chain.num_textlets() <= 20);
+    }
+
+    assert!(chain.try_get_textlet_index("first0").is_none());
+    assert!(chain.try_get_textlet_index("second49").is_some());
+
+    let sentence = chain
+        .compose_sentence(MarkovSeed::Word("first49"), &mut StaticBestSelector, None)
+        .unwrap();
+    assert_eq!(sentence.to_string(), "first49 second49");
+}
@@ -0,0 +1,31 @@
+#![cfg(all(test, feature = "parallel"))]
+
+use wordmarkov::prelude::*;
+
+#[test]
+fn test_parse_corpus_parallel() {
+    let lines: Vec<String> = (0..200)
+        .map(|i| match i % 3 {
+            0 => "Mary had a little lamb".to_owned(),
+            1 => "Mary had a little goat, too.".to_owned(),
+            _ => format!("Mary counted to {}.", i),
+        })
+        .collect();
+
+    let mut sequential = MarkovChain::new();
+
+    for line in &lines {
+        sequential.parse_sentence(line);
+    }
+
+    let mut parallel = MarkovChain::new();
+    parallel.parse_corpus_parallel(&lines);
+
+    assert_eq!(parallel.num_words(), sequential.num_words());
+    assert_eq!(parallel.num_textlets(), sequential.num_textlets());
+
+    let sentence = parallel
+        .compose_sentence(MarkovSeed::Word("goat"), &mut StaticBestSelector, None)
+        .unwrap();
+    assert!(sentence.to_string().starts_with("Mary had a little goat"));
+}
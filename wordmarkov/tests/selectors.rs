@@ -0,0 +1,382 @@
+#![cfg(test)]
+
+use std::collections::HashSet;
+use wordmarkov::prelude::*;
+
+fn next_words(chain: &MarkovChain, selector: &mut dyn MarkovSelector) -> HashSet<String> {
+    (0..200)
+        .map(|_| {
+            let (word, ..) = chain
+                .select_next_word(MarkovSeed::Word("a"), selector, MarkovTraverseDir::Forward)
+                .unwrap();
+
+            <&str>::from(&word).to_owned()
+        })
+        .collect()
+}
+
+#[test]
+fn test_selectors_top_k_top_p() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence_weighted("a b", 5);
+    chain.parse_sentence_weighted("a c", 3);
+    chain.parse_sentence_weighted("a d", 1);
+
+    let set = |words: &[&str]| words.iter().map(|w| w.to_string()).collect();
+
+    assert_eq!(next_words(&chain, &mut TopKSelector { k: 1 }), set(&["b"]));
+    assert_eq!(
+        next_words(&chain, &mut TopKSelector { k: 2 }),
+        set(&["b", "c"])
+    );
+    assert_eq!(
+        next_words(&chain, &mut TopPSelector { p: 0.5 }),
+        set(&["b"])
+    );
+    assert_eq!(
+        next_words(&chain, &mut TopPSelector { p: 0.8 }),
+        set(&["b", "c"])
+    );
+    assert_eq!(
+        next_words(&chain, &mut TopPSelector { p: 1.0 }),
+        set(&["b", "c", "d"])
+    );
+
+    let mut weights = vec![1.0, 4.0, 2.0, 3.0];
+    TopKSelector { k: 2 }.filter_weights(&mut weights);
+
+    assert_eq!(weights, vec![0.0, 4.0, 0.0, 3.0]);
+}
+
+#[test]
+fn test_selectors_anti_repetition() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("one two one three");
+
+    let options = ComposeOptions {
+        direction: ComposeDirection::Forward,
+        max_words: Some(40),
+        ..Default::default()
+    };
+
+    let most_repeats = |selector: &mut dyn MarkovSelector| {
+        (0..50)
+            .map(|_| {
+                let sentence = chain
+                    .compose(MarkovSeed::Word("one"), selector, &options)
+                    .unwrap()
+                    .to_string();
+
+                sentence.matches("two").count()
+            })
+            .max()
+            .unwrap()
+    };
+
+    // once "two" was walked, "one" must be walked again, but then "three"
+    // is picked rather than "two"
+    assert_eq!(most_repeats(&mut AntiRepetitionSelector::new(0.0)), 1);
+    assert!(most_repeats(&mut WeightedRandomSelector) > 1);
+}
+
+/// Records what it is told of each sentence before each step.
+struct ContextRecorder {
+    steps: Vec<(MarkovTraverseDir, usize, Option<usize>)>,
+}
+
+impl MarkovSelector for ContextRecorder {
+    fn reset(&mut self, _dir: MarkovTraverseDir) {
+        self.steps.clear();
+    }
+
+    fn prepare(&mut self, context: &SelectionContext<'_, '_>) {
+        self.steps.push((
+            context.direction,
+            context.sentence.num_words(),
+            context.remaining_words,
+        ));
+    }
+
+    fn weight<'a>(
+        &mut self,
+        _from: &MarkovToken<'a>,
+        _to: &MarkovToken<'a>,
+        _punct: &MarkovToken<'a>,
+        hits: usize,
+    ) -> f32 {
+        hits as f32
+    }
+
+    fn selection_type(&mut self) -> SelectionType {
+        SelectionType::Highest
+    }
+}
+
+#[test]
+fn test_selectors_context() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("one two three four five");
+
+    let mut recorder = ContextRecorder { steps: vec![] };
+
+    let options = ComposeOptions {
+        max_backward_words: Some(5),
+        max_forward_words: Some(5),
+        ..Default::default()
+    };
+
+    let sentence = chain
+        .compose(MarkovSeed::Word("three"), &mut recorder, &options)
+        .unwrap();
+
+    assert_eq!(sentence.to_string(), "one two three four five");
+
+    use MarkovTraverseDir::*;
+
+    assert_eq!(
+        recorder.steps,
+        vec![
+            (Reverse, 1, Some(5)),
+            (Reverse, 2, Some(4)),
+            (Reverse, 3, Some(3)),
+            (Forward, 3, Some(5)),
+            (Forward, 4, Some(4)),
+            (Forward, 5, Some(3)),
+        ]
+    );
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pos {
+    Article,
+    Noun,
+    Verb,
+}
+
+/// Prefers nouns after articles, by their metadata.
+struct GrammarSelector;
+
+impl MarkovSelector for GrammarSelector {
+    fn reset(&mut self, _dir: MarkovTraverseDir) {}
+
+    fn weight<'a>(
+        &mut self,
+        _from: &MarkovToken<'a>,
+        _to: &MarkovToken<'a>,
+        _punct: &MarkovToken<'a>,
+        hits: usize,
+    ) -> f32 {
+        hits as f32
+    }
+
+    fn weight_edge(&mut self, chain: &MarkovChain, edge: &Edge, hits: usize) -> f32 {
+        let from = chain.metadata::<Pos>(edge.src_idx);
+        let to = chain.metadata::<Pos>(edge.dst_idx);
+
+        if from == Some(&Pos::Article) && to != Some(&Pos::Noun) {
+            0.0
+        } else {
+            hits as f32
+        }
+    }
+
+    fn selection_type(&mut self) -> SelectionType {
+        SelectionType::WeightedRandom
+    }
+}
+
+#[test]
+fn test_selectors_metadata() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence_weighted("the runs", 20);
+    chain.parse_sentence("the dog runs");
+
+    for (word, pos) in [
+        ("the", Pos::Article),
+        ("dog", Pos::Noun),
+        ("runs", Pos::Verb),
+    ] {
+        let idx = chain.try_get_textlet_index(word).unwrap();
+        assert!(chain.set_metadata(idx, pos).is_none());
+    }
+
+    let runs = chain.try_get_textlet_index("runs").unwrap();
+
+    assert!(chain.metadata::<Pos>(runs) == Some(&Pos::Verb));
+    assert!(chain.metadata::<u32>(runs).is_none());
+
+    for _ in 0..50 {
+        let (word, ..) = chain
+            .select_next_word(
+                MarkovSeed::Word("the"),
+                &mut GrammarSelector,
+                MarkovTraverseDir::Forward,
+            )
+            .unwrap();
+
+        assert_eq!(word, MarkovToken::Textlet("dog"));
+    }
+
+    // metadata follows its textlet when textlets are renumbered
+    chain.set_metadata(runs, 1996_u32);
+    chain.remove_word("the");
+
+    let runs = chain.try_get_textlet_index("runs").unwrap();
+
+    assert!(chain.metadata::<Pos>(runs) == Some(&Pos::Verb));
+    assert_eq!(chain.metadata::<u32>(runs), Some(&1996));
+}
+
+#[test]
+fn test_selectors_inverse_frequency() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    // "the" is common everywhere, "hat" only after "a"
+    chain.parse_sentence_weighted("a the", 4);
+    chain.parse_sentence("a hat");
+
+    for word in ["x", "y", "z", "w"] {
+        chain.parse_sentence_weighted(&format!("{} the", word), 100);
+    }
+
+    let hat_share = |selector: &mut dyn MarkovSelector| {
+        let hats = (0..1000)
+            .filter(|_| {
+                let (word, ..) = chain
+                    .select_next_word(MarkovSeed::Word("a"), selector, MarkovTraverseDir::Forward)
+                    .unwrap();
+
+                word == MarkovToken::Textlet("hat")
+            })
+            .count();
+
+        hats as f32 / 1000.0
+    };
+
+    assert!(hat_share(&mut InverseFrequencySelector::new(0.0)) < 0.4);
+    assert!(hat_share(&mut InverseFrequencySelector::new(1.0)) > 0.9);
+}
+
+#[test]
+fn test_selectors_temperature() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence_weighted("a b", 1000);
+    chain.parse_sentence("a c");
+
+    let set = |words: &[&str]| words.iter().map(|w| w.to_string()).collect();
+
+    assert_eq!(
+        next_words(&chain, &mut TemperatureSelector { temperature: 0.0 }),
+        set(&["b"])
+    );
+    assert_eq!(
+        next_words(&chain, &mut TemperatureSelector { temperature: 0.1 }),
+        set(&["b"])
+    );
+    assert_eq!(
+        next_words(&chain, &mut TemperatureSelector { temperature: 100.0 }),
+        set(&["b", "c"])
+    );
+
+    let mut weights = vec![4.0, 1.0, 0.0];
+    TemperatureSelector { temperature: 0.5 }.filter_weights(&mut weights);
+
+    assert_eq!(weights, vec![1.0, 1.0 / 16.0, 0.0]);
+}
+
+/// Weighs edges by their hits, and filters out all but the edges found
+/// `keep_hits` times.
+struct FilterSelector {
+    sel_type: fn() -> SelectionType,
+    keep_hits: f32,
+}
+
+impl MarkovSelector for FilterSelector {
+    fn reset(&mut self, _dir: MarkovTraverseDir) {}
+
+    fn weight<'a>(
+        &mut self,
+        _from: &MarkovToken<'a>,
+        _to: &MarkovToken<'a>,
+        _punct: &MarkovToken<'a>,
+        hits: usize,
+    ) -> f32 {
+        hits as f32
+    }
+
+    fn selection_type(&mut self) -> SelectionType {
+        (self.sel_type)()
+    }
+
+    fn filter_weights(&mut self, weights: &mut [f32]) {
+        for weight in weights {
+            if *weight != self.keep_hits {
+                *weight = 0.0;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_selectors_filtered_out() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence_weighted("a b", 5);
+    chain.parse_sentence_weighted("a c", 3);
+    chain.parse_sentence_weighted("a d", 1);
+
+    let mut bytes: Vec<u8> = vec![];
+    chain.write_compact(&mut bytes).unwrap();
+    let compact = CompactChain::from_bytes(&bytes).unwrap();
+
+    let sel_types: [fn() -> SelectionType; 3] = [
+        || SelectionType::Lowest,
+        || SelectionType::Highest,
+        || SelectionType::WeightedRandom,
+    ];
+
+    for sel_type in sel_types {
+        // filtered edges are never picked, not even by weighing zero
+        let mut selector = FilterSelector {
+            sel_type,
+            keep_hits: 3.0,
+        };
+
+        assert_eq!(
+            next_words(&chain, &mut selector),
+            HashSet::from(["c".to_owned()])
+        );
+
+        let (word, ..) = compact
+            .select_next_word(
+                MarkovSeed::Word("a"),
+                &mut selector,
+                MarkovTraverseDir::Forward,
+            )
+            .unwrap();
+        assert_eq!(word, MarkovToken::Textlet("c"));
+
+        // with every edge filtered out, there is nothing to pick
+        selector.keep_hits = 2.0;
+
+        for result in [
+            chain.select_next_word(
+                MarkovSeed::Word("a"),
+                &mut selector,
+                MarkovTraverseDir::Forward,
+            ),
+            compact.select_next_word(
+                MarkovSeed::Word("a"),
+                &mut selector,
+                MarkovTraverseDir::Forward,
+            ),
+        ] {
+            assert!(matches!(result, Err(MarkovError::DeadEnd(_))));
+        }
+    }
+}
@@ -0,0 +1,71 @@
+#![cfg(all(test, feature = "serde"))]
+
+use wordmarkov::prelude::*;
+
+#[test]
+fn test_chain_save_load() {
+    let mut chain: MarkovChain = MarkovChain::new();
+
+    chain.parse_sentence("Mary had a little lamb");
+    chain.parse_sentence("Mary had a little goat, too.");
+
+    let mut bytes: Vec<u8> = vec![];
+    chain.save_to(&mut bytes).unwrap();
+
+    let loaded = MarkovChain::load_from(bytes.as_slice()).unwrap();
+
+    assert_eq!(loaded.num_words(), chain.num_words());
+    assert_eq!(loaded.num_edges(), chain.num_edges());
+    assert_eq!(loaded.num_textlets(), chain.num_textlets());
+    assert_eq!(
+        loaded.try_get_textlet_index("goat"),
+        chain.try_get_textlet_index("goat")
+    );
+
+    let sentence = loaded
+        .compose_sentence(MarkovSeed::Word("Mary"), &mut StaticBestSelector, None)
+        .unwrap();
+    assert!(sentence.to_string().starts_with("Mary had a little"));
+}
+
+#[test]
+fn test_chain_load_garbage() {
+    assert!(MarkovChain::load_from(&b"\x01\x02\x03"[..]).is_err());
+}
+
+#[test]
+fn test_chain_save_load_order() {
+    let mut chain: MarkovChain = MarkovChain::with_order(3);
+
+    chain.parse_sentence("a b c");
+    chain.parse_sentence("d b e");
+
+    let mut bytes: Vec<u8> = vec![];
+    chain.save_to(&mut bytes).unwrap();
+
+    let loaded = MarkovChain::load_from(bytes.as_slice()).unwrap();
+    assert_eq!(loaded.order(), 3);
+
+    let sentence = loaded
+        .compose_sentence(MarkovSeed::Word("d"), &mut WeightedRandomSelector, None)
+        .unwrap();
+    assert_eq!(sentence.to_string(), "d b e");
+}
+
+#[test]
+fn test_chain_save_load_lexer_config() {
+    let config = LexerConfig {
+        word_chars: vec!['_'],
+        atoms: vec![AtomKind::Url],
+        ..LexerConfig::default()
+    };
+
+    let mut chain: MarkovChain = MarkovChain::new();
+    chain.set_lexer_config(config.clone()).unwrap();
+
+    let mut bytes: Vec<u8> = vec![];
+    chain.save_to(&mut bytes).unwrap();
+
+    let loaded = MarkovChain::load_from(bytes.as_slice()).unwrap();
+    assert_eq!(loaded.lexer_config(), &config);
+}